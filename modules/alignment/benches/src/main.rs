@@ -0,0 +1,110 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use biobit_alignment_rs::pairwise::sw::algo::FullScan;
+use biobit_alignment_rs::pairwise::sw::storage::{Best, Storage};
+use biobit_alignment_rs::pairwise::{scoring, sw};
+
+#[path = "../../rs/src/random.rs"]
+mod random;
+
+use random::Random;
+
+const REPEATS: usize = 5;
+
+type Scheme = scoring::Delegate<
+    i32,
+    u8,
+    scoring::symbols::Equality<i32, u8>,
+    scoring::gaps::Affine<i32>,
+    scoring::equiv::Equality,
+>;
+
+fn scheme() -> Scheme {
+    scoring::compose(
+        scoring::symbols::Equality::new(2, -3),
+        scoring::gaps::Affine {
+            open: -5,
+            extend: -2,
+        },
+        scoring::equiv::Equality {},
+    )
+}
+
+// Random queries paired with targets that contain a mutated copy of the query in the middle
+fn workload(pairs: usize, query: usize, target: usize) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let mut random = Random::new(0x9E3779B97F4A7C15);
+    (0..pairs)
+        .map(|_| {
+            let seq1: Vec<u8> = (0..query).map(|_| random.symbol()).collect();
+
+            let flank = target.saturating_sub(query) / 2;
+            let mut seq2: Vec<u8> = (0..flank).map(|_| random.symbol()).collect();
+            for &symbol in &seq1 {
+                match random.next_u64() % 20 {
+                    0 => seq2.push(random.symbol()),
+                    1 => {}
+                    2 => seq2.extend([symbol, random.symbol()]),
+                    _ => seq2.push(symbol),
+                }
+            }
+            seq2.extend((0..flank).map(|_| random.symbol()));
+            (seq1, seq2)
+        })
+        .collect()
+}
+
+// Best timing and the total score over all pairs
+fn run(pairs: &[(Vec<u8>, Vec<u8>)], mut scan: impl FnMut(&[u8], &[u8]) -> i32) -> (Duration, i64) {
+    let (mut elapsed, mut total) = (Duration::MAX, 0);
+    for _ in 0..REPEATS {
+        let start = Instant::now();
+        total = 0;
+        for (seq1, seq2) in pairs {
+            total += black_box(scan(seq1, seq2)) as i64;
+        }
+        elapsed = elapsed.min(start.elapsed());
+    }
+    (elapsed, total)
+}
+
+fn report(count: usize, query: usize, target: usize) {
+    let pairs = workload(count, query, target);
+    let cells = pairs
+        .iter()
+        .map(|(seq1, seq2)| seq1.len() * seq2.len())
+        .sum::<usize>() as f64;
+
+    let mut striped = sw::Striped::new(scheme());
+    let striped = run(&pairs, |seq1, seq2| {
+        striped.with_query(&seq1);
+        striped.scan(&seq2).map_or(0, |x| x.score)
+    });
+
+    let (mut algo, mut scorer, mut best) = (FullScan::new(query), scheme(), Best::new());
+    let fullscan = run(&pairs, |seq1, seq2| {
+        best.reset(seq1.len(), seq2.len());
+        algo.scan_all(&seq1, &seq2, &mut scorer, &mut best);
+        best.finalize().pop().map_or(0, |x| x.score)
+    });
+    assert_eq!(
+        striped.1, fullscan.1,
+        "Algorithms reported different scores"
+    );
+
+    println!("{count} x {query}bp queries vs {target}bp targets:");
+    for (name, (elapsed, score)) in [("Striped", striped), ("FullScan", fullscan)] {
+        let gcups = cells / elapsed.as_secs_f64() / 1e9;
+        println!("\t{name:<10} time: {elapsed:>12.3?}\tGCUPS: {gcups:>6.3}\ttotal score: {score}");
+    }
+}
+
+fn main() {
+    // Primers and adapters against reads, i8 lanes are enough
+    report(20_000, 40, 500);
+    // Scores of longer queries overflow i8 lanes and are recomputed in i16 lanes
+    report(2_000, 150, 500);
+    report(100, 1_000, 2_000);
+    // Scores above i16::MAX fall back to the scalar FullScan
+    report(2, 30_000, 31_000);
+}
@@ -5,3 +5,6 @@ pub mod pairwise;
 
 pub mod alignable;
 mod score;
+
+#[cfg(test)]
+pub(crate) mod random;
@@ -1,11 +1,14 @@
 pub use alignment::Alignment;
 pub use offset::Offset;
 pub use op::Op;
+pub use stats::{Identity, KarlinAltschul, Stats};
 pub use step::Step;
 
 #[allow(clippy::module_inception)]
 pub mod alignment;
 mod offset;
 mod op;
+mod sam;
+mod stats;
 pub mod step;
 pub mod utils;
@@ -0,0 +1,456 @@
+use eyre::{Result, ensure, eyre};
+
+use biobit_core_rs::num::{Num, PrimUInt};
+
+use crate::Alignable;
+
+use super::alignment::Alignment;
+use super::offset::Offset;
+use super::op::Op;
+use super::step::Step;
+use super::utils;
+
+// SAM conventions: seq1 is the query (read) and seq2 is the reference. Query symbols absent from
+// the reference (GapFirst) are insertions, reference symbols absent from the query (GapSecond)
+// are deletions.
+
+impl<Score, StepLen, Seq1Idx, Seq2Idx> Alignment<Score, StepLen, Seq1Idx, Seq2Idx>
+where
+    Score: Num,
+    StepLen: PrimUInt + Into<Seq1Idx> + Into<Seq2Idx>,
+    Seq1Idx: PrimUInt,
+    Seq2Idx: PrimUInt,
+{
+    /// SAM CIGAR string of the aligned region. Extended CIGAR reports identical symbols as `=`
+    /// and both mismatches and equivalences as `X`, otherwise all of them are `M`.
+    pub fn cigar(&self, extended: bool) -> String {
+        render(&self.cigar_ops(extended, 0, 0))
+    }
+
+    /// SAM CIGAR string of the whole query (seq1) of the given length, where unaligned query
+    /// flanks are soft-clipped. Fails if the alignment ends past the end of the query.
+    pub fn clipped_cigar(&self, extended: bool, seq1len: Seq1Idx) -> Result<String> {
+        let (head, tail) = self.clips(seq1len)?;
+        Ok(render(&self.cigar_ops(extended, head, tail)))
+    }
+
+    /// Alignment from the SAM CIGAR string starting at the given reference (seq2) position.
+    /// The query (seq1) starts after the leading soft clip, hard clips and paddings are ignored.
+    ///
+    /// The `M` operation doesn't tell matches from mismatches and is stored as [`Op::Equivalent`],
+    /// see [`utils::disambiguate`] to resolve it with the actual sequences.
+    pub fn from_cigar(cigar: &str, score: Score, seq2start: Seq2Idx) -> Result<Self> {
+        Self::from_cigar_ops(parse(cigar)?, score, seq2start)
+    }
+
+    /// MD tag and edit distance to the reference (NM tag) of the alignment between the query
+    /// (seq1) and the reference (seq2). Only identical symbols are counted as matches, ignoring
+    /// their case as samtools does. Reference symbols are reported in upper case.
+    pub fn md_nm<Seq1, Seq2>(&self, seq1: &Seq1, seq2: &Seq2) -> (String, usize)
+    where
+        Seq1: Alignable<Symbol = u8>,
+        Seq2: Alignable<Symbol = u8>,
+    {
+        let (mut md, mut nm) = (String::new(), 0);
+        // Matches since the last mismatch or deletion, true while inside a deletion
+        let (mut matches, mut deletion) = (0, false);
+        for step in self.tracked_steps() {
+            let (start1, start2) = (
+                step.start.seq1.to_usize().unwrap(),
+                step.start.seq2.to_usize().unwrap(),
+            );
+            let len = step.step.len().to_usize().unwrap();
+            match step.step.op() {
+                Op::GapFirst => nm += len,
+                Op::GapSecond => {
+                    if !deletion {
+                        md.push_str(&matches.to_string());
+                        md.push('^');
+                        matches = 0;
+                        deletion = true;
+                    }
+                    md.extend(
+                        (start2..start2 + len).map(|pos| seq2.at(pos).to_ascii_uppercase() as char),
+                    );
+                    nm += len;
+                }
+                Op::Match | Op::Mismatch | Op::Equivalent => {
+                    for offset in 0..len {
+                        let (s1, s2) = (seq1.at(start1 + offset), seq2.at(start2 + offset));
+                        if s1.eq_ignore_ascii_case(s2) {
+                            matches += 1;
+                        } else {
+                            md.push_str(&matches.to_string());
+                            md.push(s2.to_ascii_uppercase() as char);
+                            matches = 0;
+                            nm += 1;
+                        }
+                    }
+                    deletion = false;
+                }
+            }
+        }
+        md.push_str(&matches.to_string());
+        (md, nm)
+    }
+
+    /// Human-readable alignment: seq1, the alignment operations and seq2, wrapped into blocks of
+    /// the given width (0 - no wrapping). Matches are shown as `|`, equivalences as `~`,
+    /// mismatches as `*`, and gaps as `-`.
+    pub fn pretty<Seq1, Seq2>(&self, seq1: &Seq1, seq2: &Seq2, width: usize) -> String
+    where
+        Seq1: Alignable<Symbol = u8>,
+        Seq2: Alignable<Symbol = u8>,
+    {
+        let lines = utils::prettify(
+            seq1,
+            self.seq1().start.to_usize().unwrap(),
+            seq2,
+            self.seq2().start.to_usize().unwrap(),
+            self.steps(),
+        );
+
+        let lines = lines.map(|x| x.chars().collect::<Vec<_>>());
+        let total = lines[0].len();
+        let width = if width == 0 { total.max(1) } else { width };
+        let mut result = String::with_capacity(total * 3 + (total / width + 1) * 4);
+        for start in (0..total).step_by(width) {
+            if start > 0 {
+                result.push('\n');
+            }
+            let end = (start + width).min(total);
+            for line in &lines {
+                result.extend(&line[start..end]);
+                result.push('\n');
+            }
+        }
+        result
+    }
+
+    // Soft clips of the query (seq1) flanks
+    fn clips(&self, seq1len: Seq1Idx) -> Result<(usize, usize)> {
+        ensure!(
+            self.seq1().end <= seq1len,
+            "Alignment ends at {} past the end of the query of length {}",
+            self.seq1().end.to_usize().unwrap(),
+            seq1len.to_usize().unwrap()
+        );
+        Ok((
+            self.seq1().start.to_usize().unwrap(),
+            (seq1len - self.seq1().end).to_usize().unwrap(),
+        ))
+    }
+
+    // CIGAR operations with adjacent identical operations merged
+    fn cigar_ops(&self, extended: bool, head: usize, tail: usize) -> Vec<(u8, usize)> {
+        let mut ops: Vec<(u8, usize)> = Vec::with_capacity(self.steps().len() + 2);
+        let mut push = |op: u8, len: usize| match ops.last_mut() {
+            Some((last, total)) if *last == op => *total += len,
+            _ if len > 0 => ops.push((op, len)),
+            _ => {}
+        };
+
+        push(b'S', head);
+        for step in self.steps() {
+            let op = match (step.op(), extended) {
+                (Op::GapFirst, _) => b'I',
+                (Op::GapSecond, _) => b'D',
+                (Op::Match, true) => b'=',
+                (Op::Mismatch | Op::Equivalent, true) => b'X',
+                (Op::Match | Op::Mismatch | Op::Equivalent, false) => b'M',
+            };
+            push(op, step.len().to_usize().unwrap());
+        }
+        push(b'S', tail);
+        ops
+    }
+
+    fn from_cigar_ops(
+        ops: impl IntoIterator<Item = (u8, usize)>,
+        score: Score,
+        seq2start: Seq2Idx,
+    ) -> Result<Self> {
+        let ops = ops.into_iter().collect::<Vec<_>>();
+
+        // Hard clips are the outermost operations followed by soft clips
+        let mut core = ops.as_slice();
+        while let Some((b'H', _)) = core.first() {
+            core = &core[1..];
+        }
+        while let Some((b'H', _)) = core.last() {
+            core = &core[..core.len() - 1];
+        }
+        let mut seq1start = 0;
+        if let Some(&(b'S', len)) = core.first() {
+            seq1start = len;
+            core = &core[1..];
+        }
+        if let Some((b'S', _)) = core.last() {
+            core = &core[..core.len() - 1];
+        }
+
+        let chunk = StepLen::max_value().to_usize().unwrap();
+        let mut steps = Vec::with_capacity(core.len());
+        for &(op, mut len) in core {
+            let op = match op {
+                b'M' => Op::Equivalent,
+                b'=' => Op::Match,
+                b'X' => Op::Mismatch,
+                b'I' => Op::GapFirst,
+                b'D' | b'N' => Op::GapSecond,
+                b'P' => continue,
+                b'S' | b'H' => return Err(eyre!("Clips must be the outermost CIGAR operations")),
+                _ => return Err(eyre!("Unknown CIGAR operation: {}", op as char)),
+            };
+            while len > 0 {
+                let step = len.min(chunk);
+                steps.push(Step::new(op, StepLen::from(step).unwrap())?);
+                len -= step;
+            }
+        }
+        Step::collapse(&mut steps);
+
+        let seq1start = Seq1Idx::from(seq1start).ok_or_else(|| eyre!("Query clip overflow"))?;
+        let end = steps
+            .iter()
+            .fold(Offset::new(seq1start, seq2start), |offset, step| {
+                offset.apply(step)
+            });
+        Ok(Self::new(
+            score,
+            steps,
+            seq1start..end.seq1,
+            seq2start..end.seq2,
+        ))
+    }
+}
+
+#[cfg(feature = "noodles")]
+mod noodles_cigar {
+    use eyre::Result;
+    use noodles::sam::alignment::record::cigar::Op as CigarOp;
+    use noodles::sam::alignment::record::cigar::op::Kind;
+    use noodles::sam::alignment::record_buf::Cigar;
+
+    use biobit_core_rs::num::{Num, PrimUInt};
+
+    use super::Alignment;
+
+    impl<Score, StepLen, Seq1Idx, Seq2Idx> Alignment<Score, StepLen, Seq1Idx, Seq2Idx>
+    where
+        Score: Num,
+        StepLen: PrimUInt + Into<Seq1Idx> + Into<Seq2Idx>,
+        Seq1Idx: PrimUInt,
+        Seq2Idx: PrimUInt,
+    {
+        /// Same as [`Alignment::clipped_cigar`], but as the noodles CIGAR.
+        pub fn noodles_cigar(&self, extended: bool, seq1len: Seq1Idx) -> Result<Cigar> {
+            let (head, tail) = self.clips(seq1len)?;
+            let cigar = self
+                .cigar_ops(extended, head, tail)
+                .into_iter()
+                .map(|(op, len)| {
+                    let kind = match op {
+                        b'M' => Kind::Match,
+                        b'I' => Kind::Insertion,
+                        b'D' => Kind::Deletion,
+                        b'S' => Kind::SoftClip,
+                        b'=' => Kind::SequenceMatch,
+                        b'X' => Kind::SequenceMismatch,
+                        _ => unreachable!(),
+                    };
+                    CigarOp::new(kind, len)
+                })
+                .collect();
+            Ok(cigar)
+        }
+
+        /// Same as [`Alignment::from_cigar`], but for the noodles CIGAR.
+        pub fn from_noodles_cigar(cigar: &Cigar, score: Score, seq2start: Seq2Idx) -> Result<Self> {
+            let ops = cigar.as_ref().iter().map(|op| {
+                let kind = match op.kind() {
+                    Kind::Match => b'M',
+                    Kind::Insertion => b'I',
+                    Kind::Deletion => b'D',
+                    Kind::Skip => b'N',
+                    Kind::SoftClip => b'S',
+                    Kind::HardClip => b'H',
+                    Kind::Pad => b'P',
+                    Kind::SequenceMatch => b'=',
+                    Kind::SequenceMismatch => b'X',
+                };
+                (kind, op.len())
+            });
+            Self::from_cigar_ops(ops, score, seq2start)
+        }
+    }
+}
+
+fn render(ops: &[(u8, usize)]) -> String {
+    let mut result = String::with_capacity(ops.len() * 3);
+    for &(op, len) in ops {
+        result.push_str(&len.to_string());
+        result.push(op as char);
+    }
+    result
+}
+
+fn parse(cigar: &str) -> Result<Vec<(u8, usize)>> {
+    let mut ops = Vec::new();
+    let mut len: Option<usize> = None;
+    for symbol in cigar.bytes() {
+        if symbol.is_ascii_digit() {
+            len = len
+                .unwrap_or(0)
+                .checked_mul(10)
+                .and_then(|x| x.checked_add((symbol - b'0') as usize));
+            ensure!(len.is_some(), "CIGAR operation length overflow: {cigar}");
+        } else {
+            match len.take() {
+                Some(0) | None => return Err(eyre!("Invalid CIGAR string: {cigar}")),
+                Some(len) => ops.push((symbol, len)),
+            }
+        }
+    }
+    ensure!(len.is_none(), "CIGAR ends with a dangling length: {cigar}");
+    Ok(ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Aln = Alignment<i32, u8, usize, usize>;
+
+    fn alignment(steps: &[(Op, u8)], seq1: usize, seq2: usize) -> Aln {
+        let steps = steps
+            .iter()
+            .map(|(op, len)| Step::new(*op, *len).unwrap())
+            .collect::<Vec<_>>();
+        let end = steps
+            .iter()
+            .fold(Offset::new(seq1, seq2), |x, step| x.apply(step));
+        Aln::new(0, steps, seq1..end.seq1, seq2..end.seq2)
+    }
+
+    #[test]
+    fn test_cigar() -> Result<()> {
+        let aln = alignment(
+            &[
+                (Op::Match, 3),
+                (Op::Mismatch, 1),
+                (Op::Equivalent, 2),
+                (Op::GapFirst, 2),
+                (Op::Match, 1),
+                (Op::GapSecond, 3),
+                (Op::Match, 2),
+            ],
+            2,
+            10,
+        );
+        assert_eq!(aln.cigar(false), "6M2I1M3D2M");
+        assert_eq!(aln.cigar(true), "3=3X2I1=3D2=");
+        assert_eq!(aln.clipped_cigar(false, 16)?, "2S6M2I1M3D2M3S");
+        assert_eq!(aln.clipped_cigar(true, 13)?, "2S3=3X2I1=3D2=");
+        assert!(aln.clipped_cigar(true, 12).is_err());
+        assert_eq!(alignment(&[], 0, 0).cigar(true), "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_cigar() -> Result<()> {
+        let aln = Aln::from_cigar("5H2S3=3X2I1=3D2=3S", 7, 10)?;
+        assert_eq!(*aln.score(), 7);
+        assert_eq!(*aln.seq1(), 2..13);
+        assert_eq!(*aln.seq2(), 10..22);
+        assert_eq!(aln.rle(), "3=3X2v1=3^2=");
+        assert_eq!(aln.clipped_cigar(true, 16)?, "2S3=3X2I1=3D2=3S");
+
+        // M is ambiguous, long operations are split, skips are deletions, paddings are ignored
+        let aln = Aln::from_cigar("300M2P4N1M", 0, 0)?;
+        assert_eq!(*aln.seq1(), 0..301);
+        assert_eq!(*aln.seq2(), 0..305);
+        assert_eq!(aln.rle(), "255~45~4^1~");
+        assert_eq!(aln.cigar(false), "300M4D1M");
+
+        assert!(Aln::from_cigar("", 0, 0)?.is_empty());
+        for invalid in [
+            "M", "3", "0M", "3M2", "3Q", "2M3S2M", "2H2M2H2M", "2M2H2S", "3M-2I",
+        ] {
+            assert!(Aln::from_cigar(invalid, 0, 0).is_err(), "{invalid}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_md_nm() {
+        let seq1 = b"NNACGTTTACGGT".as_slice();
+        let seq2 = b"ACCTACGGTT".as_slice();
+
+        for (steps, md, nm) in [
+            (vec![(Op::Match, 4)], "2C1", 1),
+            (
+                vec![
+                    (Op::Match, 2),
+                    (Op::Mismatch, 1),
+                    (Op::GapSecond, 2),
+                    (Op::Match, 1),
+                ],
+                "2C0^TA0C0",
+                4,
+            ),
+            (
+                vec![(Op::Match, 2), (Op::GapFirst, 2), (Op::Match, 4)],
+                "2C3",
+                3,
+            ),
+            // Split deletions are reported as one
+            (
+                vec![
+                    (Op::Match, 1),
+                    (Op::GapSecond, 1),
+                    (Op::GapSecond, 2),
+                    (Op::Match, 2),
+                ],
+                "1^CCT0A0C0",
+                5,
+            ),
+        ] {
+            let aln = alignment(&steps, 2, 0);
+            assert_eq!(aln.md_nm(&seq1, &seq2), (md.to_string(), nm), "{steps:?}");
+        }
+
+        // Soft-masked symbols are compared ignoring the case
+        let aln = alignment(&[(Op::Match, 1), (Op::GapSecond, 2), (Op::Match, 2)], 2, 0);
+        let (seq1, seq2) = (b"NNaCt".as_slice(), b"AccgT".as_slice());
+        assert_eq!(aln.md_nm(&seq1, &seq2), ("1^CC0G1".to_string(), 3));
+    }
+
+    #[test]
+    fn test_pretty() {
+        let seq1 = b"GGACGUACGGUUA".as_slice();
+        let seq2 = b"ACGCCACGUA".as_slice();
+        let aln = alignment(
+            &[
+                (Op::Match, 3),
+                (Op::GapSecond, 1),
+                (Op::Mismatch, 1),
+                (Op::Match, 2),
+                (Op::GapFirst, 2),
+                (Op::Equivalent, 1),
+                (Op::Match, 2),
+            ],
+            2,
+            0,
+        );
+        assert_eq!(
+            aln.pretty(&seq1, &seq2, 0),
+            "ACG-UACGGUUA\n||| *||  ~||\nACGCCAC--GUA\n"
+        );
+        assert_eq!(
+            aln.pretty(&seq1, &seq2, 5),
+            "ACG-U\n||| *\nACGCC\n\nACGGU\n||  ~\nAC--G\n\nUA\n||\nUA\n"
+        );
+        assert_eq!(alignment(&[], 0, 0).pretty(&seq1, &seq2, 5), "");
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+
+use derive_getters::{Dissolve, Getters};
+use eyre::{Result, ensure, eyre};
+
+use biobit_core_rs::num::{Num, PrimUInt};
+
+use crate::pairwise::scoring::{gaps, symbols};
+
+use super::alignment::Alignment;
+use super::op::Op;
+
+/// Summary of alignment columns
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default, Getters, Dissolve)]
+pub struct Stats {
+    /// Number of identical symbols
+    matches: usize,
+    /// Number of different symbols
+    mismatches: usize,
+    /// Number of equivalent symbols, see [`Op::Equivalent`]
+    equivalences: usize,
+    /// Number of ([`Op::GapFirst`], [`Op::GapSecond`]) gaps, adjacent gap columns of the same kind
+    /// are a single gap
+    gap_openings: (usize, usize),
+    /// Total length of ([`Op::GapFirst`], [`Op::GapSecond`]) gaps
+    gap_length: (usize, usize),
+}
+
+/// Definitions of the sequence identity: https://lh3.github.io/2018/11/25/on-the-definition-of-sequence-identity
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Identity {
+    /// Matches over all alignment columns, gaps included (BLAST)
+    Blast,
+    /// Matches over aligned columns, each gap counts as a single column
+    GapCompressed,
+    /// Matches over aligned columns, gaps are ignored
+    Ungapped,
+}
+
+impl Stats {
+    /// Total number of alignment columns
+    pub fn columns(&self) -> usize {
+        self.aligned() + self.gap_length.0 + self.gap_length.1
+    }
+
+    /// Number of columns where both sequences have a symbol
+    pub fn aligned(&self) -> usize {
+        self.matches + self.mismatches + self.equivalences
+    }
+
+    /// Fraction of identical symbols in [0, 1], 0 for empty alignments
+    pub fn identity(&self, definition: Identity) -> f64 {
+        let total = match definition {
+            Identity::Blast => self.columns(),
+            Identity::GapCompressed => self.aligned() + self.gap_openings.0 + self.gap_openings.1,
+            Identity::Ungapped => self.aligned(),
+        };
+        if total == 0 {
+            0.0
+        } else {
+            self.matches as f64 / total as f64
+        }
+    }
+}
+
+impl<Score, StepLen, Seq1Idx, Seq2Idx> Alignment<Score, StepLen, Seq1Idx, Seq2Idx>
+where
+    Score: Num,
+    StepLen: PrimUInt + Into<Seq1Idx> + Into<Seq2Idx>,
+    Seq1Idx: PrimUInt,
+    Seq2Idx: PrimUInt,
+{
+    /// Count matches, mismatches, equivalences and gaps in the alignment.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        let mut previous = None;
+        for step in self.steps() {
+            let len = step.len().to_usize().unwrap();
+            match step.op() {
+                Op::Match => stats.matches += len,
+                Op::Mismatch => stats.mismatches += len,
+                Op::Equivalent => stats.equivalences += len,
+                Op::GapFirst => {
+                    stats.gap_openings.0 += (previous != Some(Op::GapFirst)) as usize;
+                    stats.gap_length.0 += len;
+                }
+                Op::GapSecond => {
+                    stats.gap_openings.1 += (previous != Some(Op::GapSecond)) as usize;
+                    stats.gap_length.1 += len;
+                }
+            }
+            previous = Some(*step.op());
+        }
+        stats
+    }
+
+    /// Fraction of seq1 covered by the alignment
+    pub fn seq1_coverage(&self, seq1len: Seq1Idx) -> f64 {
+        coverage(self.seq1().end - self.seq1().start, seq1len)
+    }
+
+    /// Fraction of seq2 covered by the alignment
+    pub fn seq2_coverage(&self, seq2len: Seq2Idx) -> f64 {
+        coverage(self.seq2().end - self.seq2().start, seq2len)
+    }
+
+    /// Normalized alignment score in bits
+    pub fn bit_score(&self, params: &KarlinAltschul) -> f64
+    where
+        Score: crate::Score,
+    {
+        params.bit_score(self.score().to_f64().unwrap())
+    }
+
+    /// Expected number of alignments with the same or higher score between random sequences of
+    /// the query (seq1) and the database lengths.
+    pub fn evalue(&self, params: &KarlinAltschul, query: usize, database: usize) -> f64
+    where
+        Score: crate::Score,
+    {
+        params.evalue(self.score().to_f64().unwrap(), query, database)
+    }
+}
+
+fn coverage<T: PrimUInt>(covered: T, total: T) -> f64 {
+    if total.is_zero() {
+        0.0
+    } else {
+        covered.to_f64().unwrap() / total.to_f64().unwrap()
+    }
+}
+
+/// Karlin-Altschul statistics of local alignment scores: https://doi.org/10.1073/pnas.87.6.2264
+///
+/// Gapped parameters can't be derived analytically, use published values, e.g.
+/// [`KarlinAltschul::blosum62`], or estimate them by simulation.
+/// E-values are computed without edge-effect corrections of the sequence lengths.
+#[derive(Copy, Clone, PartialEq, Debug, Getters, Dissolve)]
+pub struct KarlinAltschul {
+    lambda: f64,
+    k: f64,
+}
+
+impl KarlinAltschul {
+    pub fn new(lambda: f64, k: f64) -> Result<Self> {
+        ensure!(
+            lambda > 0.0 && k > 0.0,
+            "Karlin-Altschul parameters must be positive, got lambda = {lambda} and K = {k}"
+        );
+        Ok(Self { lambda, k })
+    }
+
+    /// Parameters of ungapped alignments with the given symbol scores and background symbol
+    /// frequencies (normalized to sum to 1). The expected score must be negative and at least
+    /// one score must be positive.
+    pub fn ungapped<S, Scorer>(scorer: &Scorer, background: &[(u8, f64)]) -> Result<Self>
+    where
+        S: crate::Score,
+        Scorer: symbols::PosInvariantScorer<Symbol = u8, SymScore = S>,
+    {
+        let total = background.iter().map(|x| x.1).sum::<f64>();
+        ensure!(
+            total > 0.0 && background.iter().all(|x| x.1 >= 0.0),
+            "Background frequencies must be non-negative with a positive sum"
+        );
+
+        let mut distribution = BTreeMap::new();
+        for (s1, f1) in background {
+            for (s2, f2) in background {
+                let score = scorer.score(s1, s2).to_i64().unwrap();
+                *distribution.entry(score).or_insert(0.0) += f1 * f2 / (total * total);
+            }
+        }
+        distribution.retain(|_, p| *p > 0.0);
+        let distribution = distribution.into_iter().collect::<Vec<_>>();
+
+        let expected = distribution.iter().map(|(s, p)| *s as f64 * p).sum::<f64>();
+        ensure!(
+            expected < 0.0 && distribution.last().is_some_and(|x| x.0 > 0),
+            "Expected score must be negative and some scores must be positive"
+        );
+
+        let lambda = lambda(&distribution);
+        let k = k(&distribution, lambda, expected);
+        Self::new(lambda, k)
+    }
+
+    /// Published gapped parameters for BLOSUM62 (NCBI BLAST). Gap costs are in the crate
+    /// convention: BLAST costs `-gapopen 11 -gapextend 1` are `Affine { open: -12, extend: -1 }`.
+    pub fn blosum62<S: crate::Score>(gaps: &gaps::Affine<S>) -> Result<Self> {
+        let (open, extend) = (gaps.open.to_i64().unwrap(), gaps.extend.to_i64().unwrap());
+        let (lambda, k) = match (-(open - extend), -extend) {
+            (11, 2) => (0.297, 0.082),
+            (10, 2) => (0.291, 0.075),
+            (9, 2) => (0.279, 0.058),
+            (8, 2) => (0.264, 0.045),
+            (7, 2) => (0.239, 0.027),
+            (6, 2) => (0.201, 0.012),
+            (13, 1) => (0.292, 0.071),
+            (12, 1) => (0.283, 0.059),
+            (11, 1) => (0.267, 0.041),
+            (10, 1) => (0.243, 0.024),
+            (9, 1) => (0.206, 0.010),
+            _ => {
+                return Err(eyre!(
+                    "No BLOSUM62 parameters for gap open {open} and gap extend {extend}"
+                ));
+            }
+        };
+        Self::new(lambda, k)
+    }
+
+    pub fn bit_score(&self, score: f64) -> f64 {
+        (self.lambda * score - self.k.ln()) / std::f64::consts::LN_2
+    }
+
+    pub fn evalue(&self, score: f64, query: usize, database: usize) -> f64 {
+        self.k * query as f64 * database as f64 * (-self.lambda * score).exp()
+    }
+}
+
+// The unique positive root of sum(p * exp(lambda * s)) = 1
+fn lambda(distribution: &[(i64, f64)]) -> f64 {
+    let f = |lambda: f64| {
+        distribution
+            .iter()
+            .map(|(s, p)| p * (lambda * *s as f64).exp())
+            .sum::<f64>()
+            - 1.0
+    };
+
+    let mut high = 0.5;
+    while f(high) <= 0.0 {
+        high *= 2.0;
+    }
+    let mut low = 0.0;
+    for _ in 0..128 {
+        let mid = (low + high) / 2.0;
+        if f(mid) > 0.0 {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+// Karlin & Altschul (1990), appendix. Follows the NCBI BLAST implementation (BlastKarlinLHtoK).
+fn k(distribution: &[(i64, f64)], lambda: f64, expected: f64) -> f64 {
+    let entropy = distribution
+        .iter()
+        .map(|(s, p)| lambda * *s as f64 * p * (lambda * *s as f64).exp())
+        .sum::<f64>();
+
+    // Scores are multiples of delta, work in the reduced units
+    let delta = distribution
+        .iter()
+        .fold(0, |result, (s, _)| gcd(result, s.unsigned_abs()));
+    let (low, high) = (
+        distribution[0].0 / delta as i64,
+        distribution[distribution.len() - 1].0 / delta as i64,
+    );
+    let lambda = lambda * delta as f64;
+    let ratio = entropy / lambda;
+
+    if low == -1 && high == 1 {
+        let (pl, ph) = (distribution[0].1, distribution[distribution.len() - 1].1);
+        return (pl - ph) * (pl - ph) / pl;
+    }
+    if low == -1 {
+        let expected = expected / delta as f64;
+        return expected * expected / ratio * (1.0 - (-lambda).exp());
+    }
+    if high == 1 {
+        return ratio * (1.0 - (-lambda).exp());
+    }
+
+    let range = (high - low) as usize;
+    let mut probs = vec![0.0; range + 1];
+    for (s, p) in distribution {
+        probs[(s / delta as i64 - low) as usize] = *p;
+    }
+
+    // sigma = sum over j of (P(S_j >= 0) + E[exp(lambda * S_j); S_j < 0]) / j,
+    // where S_j is the sum of j scores
+    const ITERATIONS: usize = 100;
+    const LIMIT: f64 = 1e-4;
+    let mut current = vec![1.0];
+    let mut sigma = 0.0;
+    for j in 1..=ITERATIONS {
+        let mut next = vec![0.0; current.len() + range];
+        for (x, px) in current.iter().enumerate() {
+            for (y, py) in probs.iter().enumerate() {
+                next[x + y] += px * py;
+            }
+        }
+        current = next;
+
+        // Scores of S_j start at j * low
+        let start = j as i64 * low;
+        let term = current
+            .iter()
+            .enumerate()
+            .map(|(ind, p)| {
+                let score = start + ind as i64;
+                if score < 0 {
+                    p * (lambda * score as f64).exp()
+                } else {
+                    *p
+                }
+            })
+            .sum::<f64>()
+            / j as f64;
+        sigma += term;
+        if term <= LIMIT {
+            break;
+        }
+    }
+
+    (-2.0 * sigma).exp() / (ratio * (1.0 - (-lambda).exp()))
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
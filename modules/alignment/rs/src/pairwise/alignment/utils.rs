@@ -7,68 +7,48 @@ use crate::pairwise::{Op, Step, scoring};
 
 use super::step::StepWithOffset;
 
-// pub fn prettify<L: PrimUInt + Into<usize>, Symbol: Display>(
-//     seq1: &[Symbol],
-//     seq2: &[Symbol],
-//     steps: &[Step<L>],
-// ) -> Result<[String; 3]> {
-//     let total = steps.iter().map(|x| x.len.into()).sum::<usize>();
-//     let mut lines = [
-//         String::with_capacity(total + 1),
-//         String::with_capacity(total + 1),
-//         String::with_capacity(total + 1),
-//     ];
-//
-//     let seq1_length = seq1.len();
-//     let seq1_string = seq1.iter().map(|x| x.to_string()).collect::<String>();
-//     if seq1_length != seq1_string.len() {
-//         return Err(eyre::eyre!("seq1 string representation is non trivial"));
-//     }
-//     let mut seq1 = seq1_string.as_str();
-//
-//     let seq2_length = seq2.len();
-//     let seq2_string = seq2.iter().map(|x| x.to_string()).collect::<String>();
-//     if seq2_length != seq2_string.len() {
-//         return Err(eyre::eyre!("seq2 string representation is non trivial"));
-//     }
-//     let mut seq2 = seq2_string.as_str();
-//
-//     for step in steps {
-//         let len = step.len.to_usize().unwrap();
-//
-//         let symbol = match step.op {
-//             Op::GapFirst | Op::GapSecond => " ",
-//             Op::Equivalent => "~",
-//             Op::Match => "|",
-//             Op::Mismatch => "*",
-//         }
-//         .repeat(len);
-//         lines[1].push_str(&symbol);
-//
-//         match step.op {
-//             Op::GapFirst => {
-//                 lines[0].push_str(&"-".repeat(len));
-//                 lines[2].push_str(&seq2[..len]);
-//
-//                 seq2 = &seq2[len..];
-//             }
-//             Op::GapSecond => {
-//                 lines[0].push_str(&seq1[len..]);
-//                 lines[2].push_str(&"-".repeat(len));
-//
-//                 seq1 = &seq1[len..];
-//             }
-//             Op::Equivalent | Op::Mismatch | Op::Match => {
-//                 lines[0].push_str(&seq1[len..]);
-//                 lines[2].push_str(&seq2[len..]);
-//
-//                 seq1 = &seq1[len..];
-//                 seq2 = &seq2[len..];
-//             }
-//         };
-//     }
-//     Ok(lines)
-// }
+/// Three aligned lines (seq1, operations, seq2) for the steps starting at the given positions.
+pub fn prettify<Seq1, Seq2, L>(
+    seq1: &Seq1,
+    mut s1: usize,
+    seq2: &Seq2,
+    mut s2: usize,
+    steps: &[Step<L>],
+) -> [String; 3]
+where
+    Seq1: Alignable<Symbol = u8>,
+    Seq2: Alignable<Symbol = u8>,
+    L: PrimUInt,
+{
+    let total = steps
+        .iter()
+        .map(|x| x.len().to_usize().unwrap())
+        .sum::<usize>();
+    let mut lines = [
+        String::with_capacity(total),
+        String::with_capacity(total),
+        String::with_capacity(total),
+    ];
+
+    for step in steps {
+        let len = step.len().to_usize().unwrap();
+        for _ in 0..len {
+            let (symbol1, symbol, symbol2) = match step.op() {
+                Op::GapFirst => (*seq1.at(s1) as char, ' ', '-'),
+                Op::GapSecond => ('-', ' ', *seq2.at(s2) as char),
+                Op::Equivalent => (*seq1.at(s1) as char, '~', *seq2.at(s2) as char),
+                Op::Match => (*seq1.at(s1) as char, '|', *seq2.at(s2) as char),
+                Op::Mismatch => (*seq1.at(s1) as char, '*', *seq2.at(s2) as char),
+            };
+            lines[0].push(symbol1);
+            lines[1].push(symbol);
+            lines[2].push(symbol2);
+
+            step.op().apply(&mut s1, &mut s2, 1usize);
+        }
+    }
+    lines
+}
 
 pub fn disambiguate<Scheme, Seq1, Seq2>(
     ops: Vec<Step<u8>>,
@@ -1,5 +1,7 @@
 pub use self::alignment::{Op, Step};
 
 pub mod alignment;
+pub mod myers;
 pub mod scoring;
 pub mod sw;
+pub mod wfa;
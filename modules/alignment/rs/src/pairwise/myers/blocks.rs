@@ -0,0 +1,57 @@
+// Bit-vector columns of the edit distance matrix: https://doi.org/10.1145/316542.316550
+// Multi-word (block) extension follows Hyyro (2003): https://doi.org/10.1142/S012905410300204X
+
+// Each pattern row is a bit in one of the 64-bit blocks. For every column we keep vertical deltas
+// D[i][j] - D[i - 1][j] as two bit-vectors: positive (pv) and negative (mv) ones.
+// All operations propagate information from lower to higher bits only (shifts and carries),
+// so the padding bits of the last block never affect the real pattern rows.
+
+pub const WORD: usize = u64::BITS as usize;
+
+// Advance the block to the next column given the matching rows (eq) and the horizontal delta of
+// the row right above the block (hin). Returns the horizontal delta of the given (high) row.
+#[inline(always)]
+pub fn advance(pv: &mut u64, mv: &mut u64, mut eq: u64, hin: i8, high: u64) -> i8 {
+    let xv = eq | *mv;
+    if hin < 0 {
+        eq |= 1;
+    }
+    let xh = ((eq & *pv).wrapping_add(*pv) ^ *pv) | eq;
+    let mut ph = *mv | !(xh | *pv);
+    let mut mh = *pv & xh;
+
+    let hout = if ph & high != 0 {
+        1
+    } else if mh & high != 0 {
+        -1
+    } else {
+        0
+    };
+
+    ph <<= 1;
+    mh <<= 1;
+    match hin {
+        1 => ph |= 1,
+        -1 => mh |= 1,
+        _ => {}
+    }
+
+    *pv = mh | !(xv | ph);
+    *mv = ph & xv;
+    hout
+}
+
+// Sum of vertical deltas in the first `rows` rows of the column
+#[inline(always)]
+pub fn prefix(pv: &[u64], mv: &[u64], rows: usize) -> isize {
+    let (full, tail) = (rows / WORD, rows % WORD);
+    let mut sum = 0;
+    for block in 0..full {
+        sum += pv[block].count_ones() as isize - mv[block].count_ones() as isize;
+    }
+    if tail > 0 {
+        let mask = (1u64 << tail) - 1;
+        sum += (pv[full] & mask).count_ones() as isize - (mv[full] & mask).count_ones() as isize;
+    }
+    sum
+}
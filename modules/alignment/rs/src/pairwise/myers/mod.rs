@@ -0,0 +1,230 @@
+use crate::Alignable;
+use crate::pairwise::alignment::Alignment;
+use crate::pairwise::{Op, Step};
+
+use blocks::WORD;
+
+mod blocks;
+
+/// Approximate pattern search reported by [`Myers::search`]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
+pub struct Hit {
+    /// End of the matched text region (exclusive)
+    pub end: usize,
+    /// Edit distance between the pattern and the best text region ending at `end`
+    pub distance: usize,
+}
+
+/// Unit-cost edit distance (Levenshtein) with Myers' bit-parallel algorithm.
+///
+/// Processes 64 pattern symbols per machine word, which makes it much faster than the
+/// [`Engine`](crate::pairwise::sw::Engine) for short patterns (adapters, primers, barcodes).
+/// Patterns of any length are supported, each column costs O(pattern length / 64).
+///
+/// Alignments are reported with the pattern as seq1 and the text as seq2, the alignment score is
+/// the edit distance. Identical symbols are matches, other symbols accepted by the matcher are
+/// equivalences.
+pub struct Myers {
+    pattern: Vec<u8>,
+    blocks: usize,
+    // Pattern rows matching each text symbol, `blocks` words per symbol
+    peq: Vec<u64>,
+    // Vertical deltas of the current column
+    pv: Vec<u64>,
+    mv: Vec<u64>,
+    // Vertical deltas of all processed columns, recorded for the traceback
+    history: Vec<u64>,
+}
+
+impl Myers {
+    pub fn new<Pattern: Alignable<Symbol = u8>>(pattern: &Pattern) -> Self {
+        Self::with_matcher(pattern, |p, t| p == t)
+    }
+
+    /// Use a custom symbol matcher, e.g. to accept IUPAC ambiguity codes in the pattern.
+    /// The matcher receives the pattern symbol first and the text symbol second.
+    pub fn with_matcher<Pattern, Matcher>(pattern: &Pattern, matcher: Matcher) -> Self
+    where
+        Pattern: Alignable<Symbol = u8>,
+        Matcher: Fn(u8, u8) -> bool,
+    {
+        let pattern = (0..pattern.len())
+            .map(|pos| *pattern.at(pos))
+            .collect::<Vec<_>>();
+        let blocks = pattern.len().div_ceil(WORD);
+
+        let mut peq = vec![0; 256 * blocks];
+        for symbol in 0..=u8::MAX {
+            let masks = &mut peq[symbol as usize * blocks..(symbol as usize + 1) * blocks];
+            for (row, &p) in pattern.iter().enumerate() {
+                if matcher(p, symbol) {
+                    masks[row / WORD] |= 1 << (row % WORD);
+                }
+            }
+        }
+
+        Self {
+            pattern,
+            blocks,
+            peq,
+            pv: vec![0; blocks],
+            mv: vec![0; blocks],
+            history: Vec::new(),
+        }
+    }
+
+    pub fn pattern(&self) -> &[u8] {
+        &self.pattern
+    }
+
+    /// Edit distance between the pattern and the whole text.
+    pub fn distance<Text: Alignable<Symbol = u8>>(&mut self, text: &Text) -> usize {
+        let mut distance = self.pattern.len();
+        self.run(text, 0, text.len(), true, false, |_, score| {
+            distance = score
+        });
+        distance
+    }
+
+    /// All text positions where the pattern ends with at most `k` errors (semi-global alignment
+    /// with the free text flanks). Hits are sorted by the end position.
+    pub fn search<Text: Alignable<Symbol = u8>>(&mut self, text: &Text, k: usize) -> Vec<Hit> {
+        let mut hits = Vec::new();
+        if self.pattern.is_empty() {
+            return hits;
+        }
+        self.run(text, 0, text.len(), false, false, |end, distance| {
+            if distance <= k {
+                hits.push(Hit { end, distance });
+            }
+        });
+        hits
+    }
+
+    /// Optimal global alignment of the pattern and the whole text.
+    pub fn align<Text: Alignable<Symbol = u8>>(
+        &mut self,
+        text: &Text,
+    ) -> Alignment<usize, u8, usize, usize> {
+        let mut distance = self.pattern.len();
+        self.run(text, 0, text.len(), true, true, |_, score| distance = score);
+        self.traceback(text, 0, text.len(), distance, true)
+    }
+
+    /// Optimal alignment of the pattern and the text region ending at the hit.
+    /// Panics if the hit wasn't reported by [`Myers::search`] for the same text.
+    pub fn align_hit<Text: Alignable<Symbol = u8>>(
+        &mut self,
+        text: &Text,
+        hit: &Hit,
+    ) -> Alignment<usize, u8, usize, usize> {
+        // The optimal path spans at most `pattern + distance` text symbols
+        let start = hit.end.saturating_sub(self.pattern.len() + hit.distance);
+        let mut distance = usize::MAX;
+        self.run(text, start, hit.end, false, true, |_, score| {
+            distance = score
+        });
+        assert_eq!(distance, hit.distance, "Invalid hit: {hit:?}");
+        self.traceback(text, start, hit.end, distance, false)
+    }
+
+    // Process text[start..end] column by column, report the distance for each column end
+    fn run<Text, Callback>(
+        &mut self,
+        text: &Text,
+        start: usize,
+        end: usize,
+        global: bool,
+        record: bool,
+        mut callback: Callback,
+    ) where
+        Text: Alignable<Symbol = u8>,
+        Callback: FnMut(usize, usize),
+    {
+        let (m, blocks) = (self.pattern.len(), self.blocks);
+        self.pv.fill(u64::MAX);
+        self.mv.fill(0);
+        self.history.clear();
+
+        // Global alignments pay for each leading text symbol, search starts anywhere for free
+        let hin = if global { 1 } else { 0 };
+        let last = if m == 0 { 0 } else { 1 << ((m - 1) % WORD) };
+        let mut score = m as isize;
+        for col in start..end {
+            let masks = &self.peq[*text.at(col) as usize * blocks..][..blocks];
+            let mut delta = hin;
+            for (block, &eq) in masks.iter().enumerate() {
+                let high = if block + 1 == blocks {
+                    last
+                } else {
+                    1 << (WORD - 1)
+                };
+                delta = blocks::advance(&mut self.pv[block], &mut self.mv[block], eq, delta, high);
+            }
+            score += delta as isize;
+            if record {
+                self.history.extend_from_slice(&self.pv);
+                self.history.extend_from_slice(&self.mv);
+            }
+            callback(col + 1, score as usize);
+        }
+    }
+
+    // Distance between the first `row` pattern symbols and the text up to the recorded column
+    fn cell(&self, row: usize, col: usize, global: bool) -> usize {
+        if col == 0 {
+            return row;
+        }
+        let column = &self.history[(col - 1) * 2 * self.blocks..][..2 * self.blocks];
+        let (pv, mv) = column.split_at(self.blocks);
+        let top = if global { col } else { 0 };
+        (top as isize + blocks::prefix(pv, mv, row)) as usize
+    }
+
+    fn traceback<Text: Alignable<Symbol = u8>>(
+        &self,
+        text: &Text,
+        start: usize,
+        end: usize,
+        distance: usize,
+        global: bool,
+    ) -> Alignment<usize, u8, usize, usize> {
+        let (mut row, mut col) = (self.pattern.len(), end - start);
+        let mut ops = Vec::with_capacity(row + col);
+        while row > 0 || (global && col > 0) {
+            let score = self.cell(row, col, global);
+            if row > 0 && col > 0 {
+                let (p, t) = (self.pattern[row - 1], *text.at(start + col - 1));
+                let matched = self.peq[t as usize * self.blocks + (row - 1) / WORD]
+                    & (1 << ((row - 1) % WORD))
+                    != 0;
+                if self.cell(row - 1, col - 1, global) + !matched as usize == score {
+                    ops.push(match (matched, p == t) {
+                        (true, true) => Op::Match,
+                        (true, false) => Op::Equivalent,
+                        (false, _) => Op::Mismatch,
+                    });
+                    row -= 1;
+                    col -= 1;
+                    continue;
+                }
+            }
+            if row > 0 && self.cell(row - 1, col, global) + 1 == score {
+                ops.push(Op::GapFirst);
+                row -= 1;
+            } else {
+                debug_assert!(col > 0 && self.cell(row, col - 1, global) + 1 == score);
+                ops.push(Op::GapSecond);
+                col -= 1;
+            }
+        }
+
+        let mut steps = ops
+            .into_iter()
+            .rev()
+            .map(|op| Step::new(op, 1).unwrap())
+            .collect::<Vec<_>>();
+        Step::collapse(&mut steps);
+        Alignment::new(distance, steps, 0..self.pattern.len(), start + col..end)
+    }
+}
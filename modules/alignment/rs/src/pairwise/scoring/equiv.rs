@@ -1,4 +1,5 @@
 use crate::pairwise;
+use crate::pairwise::scoring::{Matrix, Score, iupac};
 
 #[repr(u8)]
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
@@ -47,6 +48,57 @@ impl Classifier for Equality {
     }
 }
 
+// Identical symbols are matches, other pairs with positive substitution scores are equivalent
+pub struct Positives {
+    table: Box<[Type]>,
+}
+
+impl Positives {
+    pub fn new<S: Score>(matrix: &Matrix<S>) -> Self {
+        let table = (0..=u8::MAX)
+            .flat_map(|s1| (0..=u8::MAX).map(move |s2| (s1, s2)))
+            .map(|(s1, s2)| {
+                if s1.eq_ignore_ascii_case(&s2) {
+                    Type::Match
+                } else if matrix.get(s1, s2) > S::zero() {
+                    Type::Equivalent
+                } else {
+                    Type::Mismatch
+                }
+            })
+            .collect();
+        Self { table }
+    }
+}
+
+impl Classifier for Positives {
+    type Symbol = u8;
+
+    #[inline(always)]
+    fn classify(&self, s1: &Self::Symbol, s2: &Self::Symbol) -> Type {
+        self.table[((*s1 as usize) << 8) | *s2 as usize]
+    }
+}
+
+// Identical unambiguous nucleotides are matches, overlapping IUPAC codes are equivalent
+pub struct IUPAC {}
+
+impl Classifier for IUPAC {
+    type Symbol = u8;
+
+    #[inline(always)]
+    fn classify(&self, s1: &Self::Symbol, s2: &Self::Symbol) -> Type {
+        let (s1, s2) = (iupac::bases(*s1), iupac::bases(*s2));
+        if s1 == s2 && s1.count_ones() == 1 {
+            Type::Match
+        } else if s1 & s2 != 0 {
+            Type::Equivalent
+        } else {
+            Type::Mismatch
+        }
+    }
+}
+
 impl From<Type> for pairwise::Op {
     fn from(value: Type) -> Self {
         match value {
@@ -0,0 +1,30 @@
+// IUPAC nucleotide codes: https://www.bioinformatics.org/sms/iupac.html
+
+pub const A: u8 = 0b0001;
+pub const C: u8 = 0b0010;
+pub const G: u8 = 0b0100;
+pub const T: u8 = 0b1000;
+
+/// Set of nucleotides encoded by the IUPAC symbol as a bit mask of [A], [C], [G] and [T] (or U).
+/// Symbols are case-insensitive, non-IUPAC symbols encode the empty set.
+#[inline(always)]
+pub const fn bases(symbol: u8) -> u8 {
+    match symbol.to_ascii_uppercase() {
+        b'A' => A,
+        b'C' => C,
+        b'G' => G,
+        b'T' | b'U' => T,
+        b'R' => A | G,
+        b'Y' => C | T,
+        b'S' => C | G,
+        b'W' => A | T,
+        b'K' => G | T,
+        b'M' => A | C,
+        b'B' => C | G | T,
+        b'D' => A | G | T,
+        b'H' => A | C | T,
+        b'V' => A | C | G,
+        b'N' => A | C | G | T,
+        _ => 0,
+    }
+}
@@ -0,0 +1,192 @@
+use eyre::{Result, ensure, eyre};
+
+use crate::pairwise::scoring::Score;
+
+const UNKNOWN: u8 = u8::MAX;
+
+/// Substitution matrix over single-byte symbols (e.g. BLOSUM or PAM matrices for proteins).
+///
+/// Symbols are case-insensitive. Symbols absent from the matrix score as the lowest matrix value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Matrix<S: Score> {
+    // Index of each byte in the matrix alphabet or UNKNOWN
+    index: [u8; 256],
+    symbols: Vec<u8>,
+    scores: Vec<S>,
+    lowest: S,
+}
+
+impl<S: Score> Matrix<S> {
+    /// Parse a matrix in the NCBI format: `#` comments, a header with column symbols and one row
+    /// per symbol starting with the row symbol. Rows may follow in any order.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|x| !x.is_empty() && !x.starts_with('#'));
+
+        let header = lines
+            .next()
+            .ok_or_else(|| eyre!("Matrix header is missing"))?;
+        let symbols = header
+            .split_whitespace()
+            .map(|x| symbol(x).map(|x| x.to_ascii_uppercase()))
+            .collect::<Result<Vec<_>>>()?;
+        ensure!(
+            !symbols.is_empty() && symbols.len() < UNKNOWN as usize,
+            "Matrix must have between 1 and {} symbols, got {}",
+            UNKNOWN - 1,
+            symbols.len()
+        );
+
+        let mut index = [UNKNOWN; 256];
+        for (ind, &s) in symbols.iter().enumerate() {
+            ensure!(
+                index[s as usize] == UNKNOWN,
+                "Duplicated matrix symbol: {}",
+                s as char
+            );
+            index[s as usize] = ind as u8;
+            index[s.to_ascii_lowercase() as usize] = ind as u8;
+        }
+
+        let n = symbols.len();
+        let mut scores: Vec<Option<S>> = vec![None; n * n];
+        let mut rows = 0;
+        for line in lines {
+            let mut fields = line.split_whitespace();
+            let row = symbol(fields.next().unwrap())?.to_ascii_uppercase();
+            let row = match index[row as usize] {
+                UNKNOWN => {
+                    return Err(eyre!("Row symbol is absent in the header: {}", row as char));
+                }
+                row => row as usize,
+            };
+            ensure!(
+                scores[row * n].is_none(),
+                "Duplicated matrix row: {}",
+                symbols[row] as char
+            );
+
+            let values = fields
+                .map(|x| {
+                    x.parse::<i64>()
+                        .ok()
+                        .and_then(S::from)
+                        .ok_or_else(|| eyre!("Invalid matrix score: {x}"))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            ensure!(
+                values.len() == n,
+                "Matrix row {} has {} scores, expected {}",
+                symbols[row] as char,
+                values.len(),
+                n
+            );
+            for (col, value) in values.into_iter().enumerate() {
+                scores[row * n + col] = Some(value);
+            }
+            rows += 1;
+        }
+        ensure!(rows == n, "Matrix has {rows} rows, expected {n}");
+
+        let scores = scores.into_iter().map(Option::unwrap).collect::<Vec<_>>();
+        let lowest = scores.iter().copied().fold(S::max_value(), S::min);
+        Ok(Self {
+            index,
+            symbols,
+            scores,
+            lowest,
+        })
+    }
+
+    pub fn blosum62() -> Self {
+        Self::parse(BLOSUM62).expect("Invalid built-in BLOSUM62 matrix")
+    }
+
+    pub fn pam250() -> Self {
+        Self::parse(PAM250).expect("Invalid built-in PAM250 matrix")
+    }
+
+    /// Matrix alphabet (upper case) in the header order
+    pub fn symbols(&self) -> &[u8] {
+        &self.symbols
+    }
+
+    #[inline(always)]
+    pub fn get(&self, s1: u8, s2: u8) -> S {
+        match (self.index[s1 as usize], self.index[s2 as usize]) {
+            (UNKNOWN, _) | (_, UNKNOWN) => self.lowest,
+            (row, col) => self.scores[row as usize * self.symbols.len() + col as usize],
+        }
+    }
+}
+
+fn symbol(field: &str) -> Result<u8> {
+    match field.as_bytes() {
+        &[s] if s.is_ascii_graphic() => Ok(s),
+        _ => Err(eyre!(
+            "Matrix symbol must be a single ASCII character: {field}"
+        )),
+    }
+}
+
+// https://ftp.ncbi.nlm.nih.gov/blast/matrices/BLOSUM62
+const BLOSUM62: &str = "
+#  Matrix made by matblas from blosum62.iij
+   A  R  N  D  C  Q  E  G  H  I  L  K  M  F  P  S  T  W  Y  V  B  Z  X  *
+A  4 -1 -2 -2  0 -1 -1  0 -2 -1 -1 -1 -1 -2 -1  1  0 -3 -2  0 -2 -1  0 -4
+R -1  5  0 -2 -3  1  0 -2  0 -3 -2  2 -1 -3 -2 -1 -1 -3 -2 -3 -1  0 -1 -4
+N -2  0  6  1 -3  0  0  0  1 -3 -3  0 -2 -3 -2  1  0 -4 -2 -3  3  0 -1 -4
+D -2 -2  1  6 -3  0  2 -1 -1 -3 -4 -1 -3 -3 -1  0 -1 -4 -3 -3  4  1 -1 -4
+C  0 -3 -3 -3  9 -3 -4 -3 -3 -1 -1 -3 -1 -2 -3 -1 -1 -2 -2 -1 -3 -3 -2 -4
+Q -1  1  0  0 -3  5  2 -2  0 -3 -2  1  0 -3 -1  0 -1 -2 -1 -2  0  3 -1 -4
+E -1  0  0  2 -4  2  5 -2  0 -3 -3  1 -2 -3 -1  0 -1 -3 -2 -2  1  4 -1 -4
+G  0 -2  0 -1 -3 -2 -2  6 -2 -4 -4 -2 -3 -3 -2  0 -2 -2 -3 -3 -1 -2 -1 -4
+H -2  0  1 -1 -3  0  0 -2  8 -3 -3 -1 -2 -1 -2 -1 -2 -2  2 -3  0  0 -1 -4
+I -1 -3 -3 -3 -1 -3 -3 -4 -3  4  2 -3  1  0 -3 -2 -1 -3 -1  3 -3 -3 -1 -4
+L -1 -2 -3 -4 -1 -2 -3 -4 -3  2  4 -2  2  0 -3 -2 -1 -2 -1  1 -4 -3 -1 -4
+K -1  2  0 -1 -3  1  1 -2 -1 -3 -2  5 -1 -3 -1  0 -1 -3 -2 -2  0  1 -1 -4
+M -1 -1 -2 -3 -1  0 -2 -3 -2  1  2 -1  5  0 -2 -1 -1 -1 -1  1 -3 -1 -1 -4
+F -2 -3 -3 -3 -2 -3 -3 -3 -1  0  0 -3  0  6 -4 -2 -2  1  3 -1 -3 -3 -1 -4
+P -1 -2 -2 -1 -3 -1 -1 -2 -2 -3 -3 -1 -2 -4  7 -1 -1 -4 -3 -2 -2 -1 -2 -4
+S  1 -1  1  0 -1  0  0  0 -1 -2 -2  0 -1 -2 -1  4  1 -3 -2 -2  0  0  0 -4
+T  0 -1  0 -1 -1 -1 -1 -2 -2 -1 -1 -1 -1 -2 -1  1  5 -2 -2  0 -1 -1  0 -4
+W -3 -3 -4 -4 -2 -2 -3 -2 -2 -3 -2 -3 -1  1 -4 -3 -2 11  2 -3 -4 -3 -2 -4
+Y -2 -2 -2 -3 -2 -1 -2 -3  2 -1 -1 -2 -1  3 -3 -2 -2  2  7 -1 -3 -2 -1 -4
+V  0 -3 -3 -3 -1 -2 -2 -3 -3  3  1 -2  1 -1 -2 -2  0 -3 -1  4 -3 -2 -1 -4
+B -2 -1  3  4 -3  0  1 -1  0 -3 -4  0 -3 -3 -2  0 -1 -4 -3 -3  4  1 -1 -4
+Z -1  0  0  1 -3  3  4 -2  0 -3 -3  1 -1 -3 -1  0 -1 -3 -2 -2  1  4 -1 -4
+X  0 -1 -1 -1 -2 -1 -1 -1 -1 -1 -1 -1 -1 -1 -2  0  0 -2 -1 -1 -1 -1 -1 -4
+* -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4  1
+";
+
+// https://ftp.ncbi.nlm.nih.gov/blast/matrices/PAM250
+const PAM250: &str = "
+#  PAM 250 substitution matrix, scale = ln(2)/3 = 0.231049
+   A  R  N  D  C  Q  E  G  H  I  L  K  M  F  P  S  T  W  Y  V  B  Z  X  *
+A  2 -2  0  0 -2  0  0  1 -1 -1 -2 -1 -1 -3  1  1  1 -6 -3  0  0  0  0 -8
+R -2  6  0 -1 -4  1 -1 -3  2 -2 -3  3  0 -4  0  0 -1  2 -4 -2 -1  0 -1 -8
+N  0  0  2  2 -4  1  1  0  2 -2 -3  1 -2 -3  0  1  0 -4 -2 -2  2  1  0 -8
+D  0 -1  2  4 -5  2  3  1  1 -2 -4  0 -3 -6 -1  0  0 -7 -4 -2  3  3 -1 -8
+C -2 -4 -4 -5 12 -5 -5 -3 -3 -2 -6 -5 -5 -4 -3  0 -2 -8  0 -2 -4 -5 -3 -8
+Q  0  1  1  2 -5  4  2 -1  3 -2 -2  1 -1 -5  0 -1 -1 -5 -4 -2  1  3 -1 -8
+E  0 -1  1  3 -5  2  4  0  1 -2 -3  0 -2 -5 -1  0  0 -7 -4 -2  3  3 -1 -8
+G  1 -3  0  1 -3 -1  0  5 -2 -3 -4 -2 -3 -5  0  1  0 -7 -5 -1  0  0 -1 -8
+H -1  2  2  1 -3  3  1 -2  6 -2 -2  0 -2 -2  0 -1 -1 -3  0 -2  1  2 -1 -8
+I -1 -2 -2 -2 -2 -2 -2 -3 -2  5  2 -2  2  1 -2 -1  0 -5 -1  4 -2 -2 -1 -8
+L -2 -3 -3 -4 -6 -2 -3 -4 -2  2  6 -3  4  2 -3 -3 -2 -2 -1  2 -3 -3 -1 -8
+K -1  3  1  0 -5  1  0 -2  0 -2 -3  5  0 -5 -1  0  0 -3 -4 -2  1  0 -1 -8
+M -1  0 -2 -3 -5 -1 -2 -3 -2  2  4  0  6  0 -2 -2 -1 -4 -2  2 -2 -2 -1 -8
+F -3 -4 -3 -6 -4 -5 -5 -5 -2  1  2 -5  0  9 -5 -3 -3  0  7 -1 -4 -5 -2 -8
+P  1  0  0 -1 -3  0 -1  0  0 -2 -3 -1 -2 -5  6  1  0 -6 -5 -1 -1  0 -1 -8
+S  1  0  1  0  0 -1  0  1 -1 -1 -3  0 -2 -3  1  2  1 -2 -3 -1  0  0  0 -8
+T  1 -1  0  0 -2 -1  0  0 -1  0 -2  0 -1 -3  0  1  3 -5 -3  0  0 -1  0 -8
+W -6  2 -4 -7 -8 -5 -7 -7 -3 -5 -2 -3 -4  0 -6 -2 -5 17  0 -6 -5 -6 -4 -8
+Y -3 -4 -2 -4  0 -4 -4 -5  0 -1 -1 -4 -2  7 -5 -3 -3  0 10 -2 -3 -4 -2 -8
+V  0 -2 -2 -2 -2 -2 -2 -1 -2  4  2 -2  2 -1 -1 -1  0 -6 -2  4 -2 -2 -1 -8
+B  0 -1  2  3 -4  1  3  0  1 -2 -3  1 -2 -4 -1  0  0 -5 -3 -2  3  2 -1 -8
+Z  0  0  1  3 -5  3  3  0  2 -2 -3  0 -2 -5  0  0 -1 -6 -4 -2  2  3 -1 -8
+X  0 -1  0 -1 -3 -1 -1 -1 -1 -1 -1 -1 -1 -2 -1  0  0 -4 -2 -1 -1 -1 -1 -8
+* -8 -8 -8 -8 -8 -8 -8 -8 -8 -8 -8 -8 -8 -8 -8 -8 -8 -8 -8 -8 -8 -8 -8  1
+";
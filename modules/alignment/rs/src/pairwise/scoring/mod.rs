@@ -1,10 +1,13 @@
 pub use delegate::Delegate;
+pub use matrix::Matrix;
 
 pub use crate::Score;
 
 mod delegate;
 pub mod equiv;
 pub mod gaps;
+pub mod iupac;
+mod matrix;
 pub mod symbols;
 
 pub trait Scheme:
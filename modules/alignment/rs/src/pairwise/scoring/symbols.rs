@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use crate::pairwise::scoring::Score;
+use crate::pairwise::scoring::{Matrix, Score, iupac};
 
 pub trait Scorer {
     type Score: Score;
@@ -89,3 +89,50 @@ impl<S: Score> RNAComplementarity<S> {
         }
     }
 }
+
+impl<S: Score> PosInvariantScorer for Matrix<S> {
+    type SymScore = S;
+    type Symbol = u8;
+
+    #[inline(always)]
+    fn score(&self, a: &Self::Symbol, b: &Self::Symbol) -> Self::SymScore {
+        self.get(*a, *b)
+    }
+}
+
+/// Nucleotide scoring with IUPAC ambiguity codes (case-insensitive).
+///
+/// Ambiguous symbols score as the expected score over all pairs of encoded nucleotides, rounded
+/// to the nearest integer. E.g. A vs R (A or G) is `(equal + different) / 2` and N vs N is
+/// `(equal + 3 * different) / 4`. Non-IUPAC symbols are always `different`.
+pub struct IUPAC<S: Score> {
+    // Scores for all pairs of nucleotide sets
+    table: [[S; 16]; 16],
+}
+
+impl<S: Score> PosInvariantScorer for IUPAC<S> {
+    type SymScore = S;
+    type Symbol = u8;
+
+    #[inline(always)]
+    fn score(&self, a: &Self::Symbol, b: &Self::Symbol) -> Self::SymScore {
+        self.table[iupac::bases(*a) as usize][iupac::bases(*b) as usize]
+    }
+}
+
+impl<S: Score> IUPAC<S> {
+    pub fn new(equal: S, different: S) -> Self {
+        let (e, d) = (equal.to_f64().unwrap(), different.to_f64().unwrap());
+        let table = std::array::from_fn(|x: usize| {
+            std::array::from_fn(|y: usize| {
+                let total = (x.count_ones() * y.count_ones()) as f64;
+                if total == 0.0 {
+                    return different;
+                }
+                let same = (x & y).count_ones() as f64;
+                S::from(((same * e + (total - same) * d) / total).round()).unwrap()
+            })
+        });
+        Self { table }
+    }
+}
@@ -0,0 +1,239 @@
+use std::ops::Range;
+
+use crate::pairwise::scoring;
+use crate::pairwise::sw::storage::AlignmentSeed;
+use crate::{Alignable, Score};
+
+use super::{EndGaps, FullScan, Tracer};
+
+// Same recursions as the local/global scans restricted to a band of diagonals.
+// Cells outside the band are never computed and behave as unreachable: 0 in the local mode
+// (identical to a restart) and S::min_value() in the global mode (saturating arithmetic).
+// The band is a contiguous strip, hence every cell inside it is reachable in the global mode
+// by following its diagonal back to the boundary.
+
+/// A band of diagonals `[min_diagonal, max_diagonal]` of the alignment matrix.
+///
+/// Cell (row, col) belongs to the diagonal `col - row`: the main diagonal is 0, diagonals above
+/// it (seq2 is ahead of seq1) are positive, diagonals below it are negative.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Band {
+    pub min_diagonal: isize,
+    pub max_diagonal: isize,
+}
+
+impl Band {
+    pub fn new(min_diagonal: isize, max_diagonal: isize) -> Self {
+        debug_assert!(min_diagonal <= max_diagonal);
+        Self {
+            min_diagonal,
+            max_diagonal,
+        }
+    }
+
+    /// Band of `2 * radius + 1` diagonals centered at the given one.
+    pub fn around(diagonal: isize, radius: usize) -> Self {
+        Self::new(diagonal - radius as isize, diagonal + radius as isize)
+    }
+
+    /// Rows of the given column inside the band, clipped to the matrix with `rows` rows.
+    pub fn rows(&self, col: usize, rows: usize) -> Range<usize> {
+        let col = col as isize;
+        let start = (col - self.max_diagonal).clamp(0, rows as isize) as usize;
+        let end = (col - self.min_diagonal + 1).clamp(0, rows as isize) as usize;
+        start..end.max(start)
+    }
+
+    /// Returns true if the cell is inside the band.
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        let diagonal = col as isize - row as isize;
+        self.min_diagonal <= diagonal && diagonal <= self.max_diagonal
+    }
+}
+
+impl<S: Score> FullScan<S> {
+    /// Banded version of [`scan_all`](Self::scan_all) (`ends` is None) or
+    /// [`scan_global`](Self::scan_global) (`ends` is Some). The tracer is notified only about
+    /// cells inside the band. Returns the global alignment seed, if any end cell is inside the band.
+    pub fn scan_band<Smb, Scheme, Seq1, Seq2, Trace>(
+        &mut self,
+        seq1: &Seq1,
+        seq2: &Seq2,
+        band: &Band,
+        ends: Option<&EndGaps>,
+        scorer: &mut Scheme,
+        tracer: &mut Trace,
+    ) -> Option<AlignmentSeed<S>>
+    where
+        Scheme: scoring::Scheme<Symbol = Smb, Score = S>,
+        Seq1: Alignable<Symbol = Smb>,
+        Seq2: Alignable<Symbol = Smb>,
+        Trace: Tracer<Score = S>,
+    {
+        if seq1.len() == 0 || seq2.len() == 0 {
+            return None;
+        }
+        let (rows, cols) = (seq1.len(), seq2.len());
+        let floor = if ends.is_some() {
+            S::min_value()
+        } else {
+            S::zero()
+        };
+        let (lead1, lead2) = match ends {
+            None => (false, false),
+            Some(ends) => (!ends.seq1_leading, !ends.seq2_leading),
+        };
+
+        // The column before the first one is the left boundary, it's only read inside the band
+        let mut prev = 0..band.rows(0, rows).end;
+        self.scores.clear();
+        self.scores.resize(rows, floor);
+        self.gapcol.clear();
+        self.gapcol.resize(rows, floor);
+        if ends.is_some() {
+            let mut lead = S::zero();
+            for row in prev.clone() {
+                if lead1 {
+                    lead = lead
+                        + if row == 0 {
+                            scorer.seq1_gap_open(row)
+                        } else {
+                            scorer.seq1_gap_extend(row)
+                        };
+                }
+                self.scores[row] = lead;
+            }
+        }
+
+        // Best cell in the last row (only tracked if the seq2 suffix is free)
+        let mut last_row: Option<(usize, S)> = None;
+
+        // Top boundary: cost of the leading seq2 gap before the current column
+        let mut top = if ends.is_some() { S::zero() } else { floor };
+        for col in 0..cols {
+            if col == 0 {
+                tracer.first_col_start();
+            } else {
+                tracer.col_start(col);
+            }
+
+            let window = band.rows(col, rows);
+            let s2 = seq2.at(col);
+            self.diagonal = match window.start {
+                0 => top,
+                start => self.scores[start - 1],
+            };
+            if lead2 {
+                top = top
+                    + if col == 0 {
+                        scorer.seq2_gap_open(col)
+                    } else {
+                        scorer.seq2_gap_extend(col)
+                    };
+            }
+
+            self.gaprow = floor;
+            for row in window.clone() {
+                self.left = self.scores[row];
+
+                // Vertical/row gaps
+                let above = if row == window.start {
+                    if row == 0 { top } else { floor }
+                } else {
+                    self.scores[row - 1]
+                };
+                let opened = above.saturating_add(scorer.seq1_gap_open(row));
+                let extended = self.gaprow.saturating_add(scorer.seq1_gap_extend(row));
+                self.gaprow = if opened > extended && opened > floor {
+                    tracer.row_gap_open(row, col, opened);
+                    opened
+                } else if extended > floor {
+                    tracer.row_gap_extend(row, col, extended);
+                    extended
+                } else {
+                    floor
+                };
+
+                // Horizontal/column gaps
+                let opened = self.left.saturating_add(scorer.seq2_gap_open(col));
+                let extended = self.gapcol[row].saturating_add(scorer.seq2_gap_extend(col));
+                self.gapcol[row] = if opened > extended && opened > floor {
+                    tracer.col_gap_open(row, col, opened);
+                    opened
+                } else if extended > floor {
+                    tracer.col_gap_extend(row, col, extended);
+                    extended
+                } else {
+                    floor
+                };
+
+                // Best scores
+                let equiv = self
+                    .diagonal
+                    .saturating_add(scorer.score(row, seq1.at(row), col, s2));
+                self.scores[row] =
+                    if equiv > self.gaprow && equiv > self.gapcol[row] && equiv > floor {
+                        tracer.equivalent(row, col, equiv);
+                        equiv
+                    } else if self.gapcol[row] > self.gaprow && self.gapcol[row] > floor {
+                        tracer.gap_col(row, col, self.gapcol[row]);
+                        self.gapcol[row]
+                    } else if self.gaprow > floor {
+                        tracer.gap_row(row, col, self.gaprow);
+                        self.gaprow
+                    } else {
+                        tracer.none(row, col);
+                        floor
+                    };
+
+                self.diagonal = self.left;
+            }
+
+            // Cells that left the band become unreachable
+            for row in prev.start..prev.end.min(window.start) {
+                self.scores[row] = floor;
+                self.gapcol[row] = floor;
+            }
+            for row in prev.start.max(window.end)..prev.end {
+                self.scores[row] = floor;
+                self.gapcol[row] = floor;
+            }
+
+            if let Some(ends) = ends
+                && ends.seq2_trailing
+                && window.contains(&(rows - 1))
+                && last_row.is_none_or(|(_, s)| self.scores[rows - 1] > s)
+            {
+                last_row = Some((col, self.scores[rows - 1]));
+            }
+            prev = window;
+
+            if col == 0 {
+                tracer.first_col_end();
+            } else {
+                tracer.col_end(col);
+            }
+        }
+
+        // Same priorities as in the full global scan
+        let ends = ends?;
+        let mut seed: Option<AlignmentSeed<S>> = None;
+        let mut consider = |row: usize, col: usize, score: S| {
+            if seed.is_none_or(|x| score > x.score) {
+                seed = Some(AlignmentSeed { row, col, score });
+            }
+        };
+        if prev.contains(&(rows - 1)) {
+            consider(rows - 1, cols - 1, self.scores[rows - 1]);
+        }
+        if let Some((col, score)) = last_row {
+            consider(rows - 1, col, score);
+        }
+        if ends.seq1_trailing {
+            for row in prev {
+                consider(row, cols - 1, self.scores[row]);
+            }
+        }
+        seed
+    }
+}
@@ -0,0 +1,240 @@
+use crate::pairwise::scoring;
+use crate::pairwise::sw::storage::AlignmentSeed;
+use crate::{Alignable, Score};
+
+use super::{FullScan, Tracer};
+
+// Global alignment with affine gaps: https://doi.org/10.1016/0022-2836(82)90398-9
+
+// Same recursion as the local scan, but without the zero floor:
+// C(i,j) = max {
+//      D(i,j)
+//      I(i,j)
+//      C(i-1,j-1) + equiv(ai, bj)
+// }
+// Boundary conditions (virtual row/column before the first symbol):
+// C(i,-1) = cost of the leading seq1 gap of length i + 1 (0 if seq1 leading gaps are free)
+// C(-1,j) = cost of the leading seq2 gap of length j + 1 (0 if seq2 leading gaps are free)
+// Gaps can't be extended from the boundary, they are only opened there.
+
+/// End gaps that are not penalized in the global alignment.
+///
+/// Leading/trailing refer to the unaligned prefix/suffix of the corresponding sequence. Free end
+/// gaps are clipped from the reported alignment, while penalized ones are reported as gap steps.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct EndGaps {
+    pub seq1_leading: bool,
+    pub seq1_trailing: bool,
+    pub seq2_leading: bool,
+    pub seq2_trailing: bool,
+}
+
+impl EndGaps {
+    /// All end gaps are penalized (Needleman-Wunsch), both sequences are aligned end-to-end.
+    pub const fn penalized() -> Self {
+        Self {
+            seq1_leading: false,
+            seq1_trailing: false,
+            seq2_leading: false,
+            seq2_trailing: false,
+        }
+    }
+
+    /// Seq1 is aligned end-to-end somewhere inside seq2 (read-to-reference, primer-to-amplicon).
+    pub const fn free_seq2() -> Self {
+        Self {
+            seq2_leading: true,
+            seq2_trailing: true,
+            ..Self::penalized()
+        }
+    }
+
+    /// Seq2 is aligned end-to-end somewhere inside seq1.
+    pub const fn free_seq1() -> Self {
+        Self {
+            seq1_leading: true,
+            seq1_trailing: true,
+            ..Self::penalized()
+        }
+    }
+
+    /// Suffix of seq1 overlaps the prefix of seq2 (dovetail overlap).
+    pub const fn overlap() -> Self {
+        Self {
+            seq1_leading: true,
+            seq2_trailing: true,
+            ..Self::penalized()
+        }
+    }
+
+    /// None of the end gaps are penalized.
+    pub const fn free() -> Self {
+        Self {
+            seq1_leading: true,
+            seq1_trailing: true,
+            seq2_leading: true,
+            seq2_trailing: true,
+        }
+    }
+}
+
+impl<S: Score> FullScan<S> {
+    /// Fill the whole matrix without the zero floor and return the best admissible end cell.
+    ///
+    /// The end cell is the last cell of the matrix unless trailing end gaps are free, in which
+    /// case the alignment may end anywhere in the last row (free seq2 suffix) or in the last
+    /// column (free seq1 suffix). Unlike the local scan, the tracer is notified about every cell
+    /// and row/column gaps might be opened at row/column 0 (from the virtual boundary).
+    pub fn scan_global<Smb, Scheme, Seq1, Seq2, Trace>(
+        &mut self,
+        seq1: &Seq1,
+        seq2: &Seq2,
+        ends: &EndGaps,
+        scorer: &mut Scheme,
+        tracer: &mut Trace,
+    ) -> Option<AlignmentSeed<S>>
+    where
+        Scheme: scoring::Scheme<Symbol = Smb, Score = S>,
+        Seq1: Alignable<Symbol = Smb>,
+        Seq2: Alignable<Symbol = Smb>,
+        Trace: Tracer<Score = S>,
+    {
+        if seq1.len() == 0 || seq2.len() == 0 {
+            return None;
+        }
+        let (rows, cols) = (seq1.len(), seq2.len());
+
+        // The column before the first one is the left boundary
+        let mut lead = S::zero();
+        self.scores.clear();
+        self.scores.extend((0..rows).map(|row| {
+            if !ends.seq1_leading {
+                lead = lead
+                    + if row == 0 {
+                        scorer.seq1_gap_open(row)
+                    } else {
+                        scorer.seq1_gap_extend(row)
+                    };
+            }
+            lead
+        }));
+
+        // Not used in the first column, column gaps can only be opened there
+        self.gapcol.clear();
+        self.gapcol.resize(rows, S::zero());
+
+        // Best cell in the last row (only tracked if the seq2 suffix is free)
+        let mut last_row: Option<(usize, S)> = None;
+
+        // Top boundary: cost of the leading seq2 gap before the current column
+        let mut top = S::zero();
+        for col in 0..cols {
+            if col == 0 {
+                tracer.first_col_start();
+            } else {
+                tracer.col_start(col);
+            }
+
+            let s2 = seq2.at(col);
+            self.diagonal = top;
+            if !ends.seq2_leading {
+                top = top
+                    + if col == 0 {
+                        scorer.seq2_gap_open(col)
+                    } else {
+                        scorer.seq2_gap_extend(col)
+                    };
+            }
+
+            for row in 0..rows {
+                self.left = self.scores[row];
+
+                // Vertical/row gaps
+                let above = if row == 0 { top } else { self.scores[row - 1] };
+                let opened = above + scorer.seq1_gap_open(row);
+                self.gaprow = if row == 0 {
+                    tracer.row_gap_open(row, col, opened);
+                    opened
+                } else {
+                    let extended = self.gaprow + scorer.seq1_gap_extend(row);
+                    if opened > extended {
+                        tracer.row_gap_open(row, col, opened);
+                        opened
+                    } else {
+                        tracer.row_gap_extend(row, col, extended);
+                        extended
+                    }
+                };
+
+                // Horizontal/column gaps
+                let opened = self.left + scorer.seq2_gap_open(col);
+                self.gapcol[row] = if col == 0 {
+                    tracer.col_gap_open(row, col, opened);
+                    opened
+                } else {
+                    let extended = self.gapcol[row] + scorer.seq2_gap_extend(col);
+                    if opened > extended {
+                        tracer.col_gap_open(row, col, opened);
+                        opened
+                    } else {
+                        tracer.col_gap_extend(row, col, extended);
+                        extended
+                    }
+                };
+
+                // Best scores
+                let equiv = self.diagonal + scorer.score(row, seq1.at(row), col, s2);
+                self.scores[row] = if equiv > self.gaprow && equiv > self.gapcol[row] {
+                    tracer.equivalent(row, col, equiv);
+                    equiv
+                } else if self.gapcol[row] > self.gaprow {
+                    tracer.gap_col(row, col, self.gapcol[row]);
+                    self.gapcol[row]
+                } else {
+                    tracer.gap_row(row, col, self.gaprow);
+                    self.gaprow
+                };
+
+                self.diagonal = self.left;
+            }
+
+            if ends.seq2_trailing && last_row.is_none_or(|(_, s)| self.scores[rows - 1] > s) {
+                last_row = Some((col, self.scores[rows - 1]));
+            }
+
+            if col == 0 {
+                tracer.first_col_end();
+            } else {
+                tracer.col_end(col);
+            }
+        }
+
+        // Ties are resolved in favor of the full-length alignment
+        let mut seed = AlignmentSeed {
+            row: rows - 1,
+            col: cols - 1,
+            score: self.scores[rows - 1],
+        };
+        if let Some((col, score)) = last_row
+            && score > seed.score
+        {
+            seed = AlignmentSeed {
+                row: rows - 1,
+                col,
+                score,
+            };
+        }
+        if ends.seq1_trailing {
+            for (row, &score) in self.scores.iter().enumerate() {
+                if score > seed.score {
+                    seed = AlignmentSeed {
+                        row,
+                        col: cols - 1,
+                        score,
+                    };
+                }
+            }
+        }
+        Some(seed)
+    }
+}
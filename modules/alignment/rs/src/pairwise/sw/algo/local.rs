@@ -37,11 +37,11 @@ use super::Tracer;
 //      gaprow = current D(i-1) value (vertical seq1 gaps)
 
 pub struct FullScan<S: Score> {
-    left: S,
-    diagonal: S,
-    scores: Vec<S>,
-    gapcol: Vec<S>,
-    gaprow: S,
+    pub(super) left: S,
+    pub(super) diagonal: S,
+    pub(super) scores: Vec<S>,
+    pub(super) gapcol: Vec<S>,
+    pub(super) gaprow: S,
 }
 
 impl<S: Score> FullScan<S> {
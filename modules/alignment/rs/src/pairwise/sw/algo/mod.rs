@@ -1,12 +1,28 @@
+pub use banded::Band;
+pub use global::EndGaps;
 pub use local::FullScan;
 
 use crate::pairwise::scoring;
 
+mod banded;
+mod global;
 mod local;
+mod xdrop;
 
 // All smith-waterman (sw) algorithms run column-by-column and
 // notify other pieces of the algorithm about each step
 
+/// The scan that produced an alignment. Required by the traceback to repeat the scan on demand.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Scan<S: scoring::Score> {
+    /// [`FullScan::scan_all`] or [`FullScan::scan_band`] without end gaps.
+    Local(Option<Band>),
+    /// [`FullScan::scan_global`] or [`FullScan::scan_band`] with end gaps.
+    Global(EndGaps, Option<Band>),
+    /// [`FullScan::extend`] from the given origin.
+    XDrop { from: (usize, usize), xdrop: S },
+}
+
 #[allow(unused_variables)]
 pub trait BestOrientationTracer {
     type Score: scoring::Score;
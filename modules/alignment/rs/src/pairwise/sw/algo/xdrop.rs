@@ -0,0 +1,202 @@
+use crate::pairwise::scoring;
+use crate::pairwise::sw::storage::AlignmentSeed;
+use crate::{Alignable, Score};
+
+use super::{FullScan, Tracer};
+
+// Gapped X-drop extension: https://doi.org/10.1093/nar/25.17.3389
+
+// The global recursion anchored at the given cell (the origin), that is the matrix boundary is
+// moved to the row/column right before the origin. Cells are computed column-by-column, but only
+// around the cells that are "alive": C(i,j) >= best - X, where best is the best score seen so far
+// (0 for the empty extension). Each column:
+// * starts at the first alive row of the previous column;
+// * covers all rows computed in the previous column (+1 for the diagonal);
+// * continues downwards while the computed cells are alive.
+// Cells that are not computed are unreachable (S::min_value(), saturating arithmetic).
+// The scan stops once there are no alive cells in a column.
+
+impl<S: Score> FullScan<S> {
+    /// Extend the alignment from the origin `from` (row, col) towards the ends of sequences using
+    /// the X-drop heuristic. The alignment starts exactly at the origin (leading gaps are
+    /// penalized) and ends at the best scoring computed cell. Returns None if no extension
+    /// scores above 0.
+    ///
+    /// The tracer is notified only about computed cells, their rows are contiguous in each column.
+    pub fn extend<Smb, Scheme, Seq1, Seq2, Trace>(
+        &mut self,
+        seq1: &Seq1,
+        seq2: &Seq2,
+        from: (usize, usize),
+        xdrop: S,
+        scorer: &mut Scheme,
+        tracer: &mut Trace,
+    ) -> Option<AlignmentSeed<S>>
+    where
+        Scheme: scoring::Scheme<Symbol = Smb, Score = S>,
+        Seq1: Alignable<Symbol = Smb>,
+        Seq2: Alignable<Symbol = Smb>,
+        Trace: Tracer<Score = S>,
+    {
+        let (rows, cols) = (seq1.len(), seq2.len());
+        if from.0 >= rows || from.1 >= cols {
+            return None;
+        }
+        let none = S::min_value();
+        let mut best = S::zero();
+        let mut seed: Option<AlignmentSeed<S>> = None;
+
+        self.scores.clear();
+        self.scores.resize(rows, none);
+        self.gapcol.clear();
+        self.gapcol.resize(rows, none);
+
+        // The column before the origin is the left boundary, only alive cells are kept
+        let mut prev = from.0..from.0;
+        let mut lead = S::zero();
+        for row in from.0..rows {
+            lead = lead.saturating_add(if row == from.0 {
+                scorer.seq1_gap_open(row)
+            } else {
+                scorer.seq1_gap_extend(row)
+            });
+            if lead < best.saturating_sub(xdrop) {
+                break;
+            }
+            self.scores[row] = lead;
+            prev.end = row + 1;
+        }
+
+        // Rows computed in the previous column and the first row to compute in the current one
+        let mut next = prev.start;
+
+        // Top boundary: cost of the leading seq2 gap before the current column
+        let mut top = S::zero();
+        for col in from.1..cols {
+            if col == from.1 {
+                tracer.first_col_start();
+            } else {
+                tracer.col_start(col);
+            }
+
+            let threshold = best.saturating_sub(xdrop);
+            let s2 = seq2.at(col);
+            let start = next;
+            self.diagonal = if start == from.0 {
+                top
+            } else {
+                self.scores[start - 1]
+            };
+            top = top.saturating_add(if col == from.1 {
+                scorer.seq2_gap_open(col)
+            } else {
+                scorer.seq2_gap_extend(col)
+            });
+            if top < threshold {
+                top = none;
+            }
+
+            self.gaprow = none;
+            let mut alive = start..start;
+            let mut row = start;
+            while row < rows {
+                self.left = self.scores[row];
+
+                // Vertical/row gaps
+                let above = if row == start {
+                    if row == from.0 { top } else { none }
+                } else {
+                    self.scores[row - 1]
+                };
+                let opened = above.saturating_add(scorer.seq1_gap_open(row));
+                let extended = self.gaprow.saturating_add(scorer.seq1_gap_extend(row));
+                self.gaprow = if opened > extended && opened > none {
+                    tracer.row_gap_open(row, col, opened);
+                    opened
+                } else if extended > none {
+                    tracer.row_gap_extend(row, col, extended);
+                    extended
+                } else {
+                    none
+                };
+
+                // Horizontal/column gaps
+                let opened = self.left.saturating_add(scorer.seq2_gap_open(col));
+                let extended = self.gapcol[row].saturating_add(scorer.seq2_gap_extend(col));
+                self.gapcol[row] = if opened > extended && opened > none {
+                    tracer.col_gap_open(row, col, opened);
+                    opened
+                } else if extended > none {
+                    tracer.col_gap_extend(row, col, extended);
+                    extended
+                } else {
+                    none
+                };
+
+                // Best scores
+                let equiv = self
+                    .diagonal
+                    .saturating_add(scorer.score(row, seq1.at(row), col, s2));
+                self.scores[row] =
+                    if equiv > self.gaprow && equiv > self.gapcol[row] && equiv > none {
+                        tracer.equivalent(row, col, equiv);
+                        equiv
+                    } else if self.gapcol[row] > self.gaprow && self.gapcol[row] > none {
+                        tracer.gap_col(row, col, self.gapcol[row]);
+                        self.gapcol[row]
+                    } else if self.gaprow > none {
+                        tracer.gap_row(row, col, self.gaprow);
+                        self.gaprow
+                    } else {
+                        tracer.none(row, col);
+                        none
+                    };
+                self.diagonal = self.left;
+
+                let score = self.scores[row];
+                if score >= threshold && score > none {
+                    if alive.is_empty() {
+                        alive.start = row;
+                    }
+                    alive.end = row + 1;
+                }
+                if score > seed.map_or(best, |x| x.score) {
+                    seed = Some(AlignmentSeed { row, col, score });
+                }
+
+                row += 1;
+                // Below the previous column only vertical gaps are possible
+                if row > prev.end && alive.end != row {
+                    break;
+                }
+            }
+
+            // Cells that are not computed in this column become unreachable
+            for x in prev.start..start.min(prev.end) {
+                self.scores[x] = none;
+                self.gapcol[x] = none;
+            }
+            for x in prev.start.max(row)..prev.end {
+                self.scores[x] = none;
+                self.gapcol[x] = none;
+            }
+
+            if col == from.1 {
+                tracer.first_col_end();
+            } else {
+                tracer.col_end(col);
+            }
+
+            if alive.is_empty() {
+                break;
+            }
+            if let Some(x) = seed {
+                best = x.score;
+            }
+            // Dead rows above the first alive one are not computed in the next column
+            prev = start..row;
+            next = alive.start;
+        }
+        seed
+    }
+}
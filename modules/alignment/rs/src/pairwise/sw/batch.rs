@@ -0,0 +1,135 @@
+use std::cell::RefCell;
+use std::cmp::Reverse;
+
+use rayon::ThreadPool;
+use rayon::prelude::*;
+use thread_local::ThreadLocal;
+
+use crate::Alignable;
+use crate::pairwise::sw::{Engine, algo, storage, traceback};
+use crate::pairwise::{alignment, scoring};
+
+// Lazily created engine of each pool thread
+type Engines<S, Smb, Storage, TraceMat, Scheme> =
+    ThreadLocal<RefCell<Engine<S, Smb, Storage, TraceMat, Scheme>>>;
+
+/// Alignments of a single pair reported by the [`Batch`]
+#[derive(Clone, Debug)]
+pub struct Hits<S: scoring::Score> {
+    /// Index of the pair (or target) in the input
+    pub index: usize,
+    pub alignments: Vec<alignment::Alignment<S, u8, usize, usize>>,
+}
+
+impl<S: scoring::Score> Hits<S> {
+    /// Best alignment score, None if there are no alignments
+    pub fn score(&self) -> Option<S> {
+        self.alignments.iter().map(|x| *x.score()).max()
+    }
+}
+
+/// Align many sequence pairs in parallel on the thread pool.
+///
+/// Each pool thread lazily creates its own [`Engine`] with the factory and reuses it (including
+/// the DP and traceback buffers) for all pairs processed by the thread, also across calls.
+/// Results are always reported in the input order.
+pub struct Batch<S, Smb, Storage, TraceMat, Scheme, Factory>
+where
+    S: scoring::Score,
+    Scheme: scoring::Scheme<Score = S, Symbol = Smb>,
+    Storage: storage::Storage + algo::Tracer<Score = S>,
+    TraceMat: traceback::TraceMat + algo::Tracer<Score = S>,
+    Engine<S, Smb, Storage, TraceMat, Scheme>: Send,
+    Factory: Fn() -> Engine<S, Smb, Storage, TraceMat, Scheme> + Sync,
+{
+    pool: ThreadPool,
+    factory: Factory,
+    engines: Engines<S, Smb, Storage, TraceMat, Scheme>,
+    top_k: Option<usize>,
+}
+
+impl<S, Smb, Storage, TraceMat, Scheme, Factory> Batch<S, Smb, Storage, TraceMat, Scheme, Factory>
+where
+    S: scoring::Score + Send,
+    Scheme: scoring::Scheme<Score = S, Symbol = Smb>,
+    Storage: storage::Storage + algo::Tracer<Score = S>,
+    TraceMat: traceback::TraceMat + algo::Tracer<Score = S>,
+    Engine<S, Smb, Storage, TraceMat, Scheme>: Send,
+    Factory: Fn() -> Engine<S, Smb, Storage, TraceMat, Scheme> + Sync,
+{
+    pub fn new(pool: ThreadPool, factory: Factory) -> Self {
+        Self {
+            pool,
+            factory,
+            engines: ThreadLocal::new(),
+            top_k: None,
+        }
+    }
+
+    /// Report only the k pairs with the best alignment scores (None to report all pairs).
+    /// Pairs without alignments are never reported, ties are resolved in favor of earlier pairs.
+    pub fn with_top_k(&mut self, top_k: Option<usize>) {
+        self.top_k = top_k;
+    }
+
+    pub fn top_k(&self) -> Option<usize> {
+        self.top_k
+    }
+
+    /// Align each (seq1, seq2) pair with [`Engine::scan_all`].
+    pub fn scan_pairs<S1, S2, Pairs>(&self, pairs: Pairs) -> Vec<Hits<S>>
+    where
+        S1: Alignable<Symbol = Smb> + Sync,
+        S2: Alignable<Symbol = Smb> + Sync,
+        Pairs: IntoIterator<Item = (S1, S2)>,
+    {
+        let pairs = pairs.into_iter().collect::<Vec<_>>();
+        self._run(&pairs, |engine, (seq1, seq2)| engine.scan_all(seq1, seq2))
+    }
+
+    /// Align the query (seq1) against each target (seq2) with [`Engine::scan_all`].
+    pub fn scan_targets<S1, S2, Targets>(&self, query: &S1, targets: Targets) -> Vec<Hits<S>>
+    where
+        S1: Alignable<Symbol = Smb> + Sync,
+        S2: Alignable<Symbol = Smb> + Sync,
+        Targets: IntoIterator<Item = S2>,
+    {
+        let targets = targets.into_iter().collect::<Vec<_>>();
+        self._run(&targets, |engine, target| engine.scan_all(query, target))
+    }
+
+    fn _run<Item, Align>(&self, items: &[Item], align: Align) -> Vec<Hits<S>>
+    where
+        Item: Sync,
+        Align: Fn(
+                &mut Engine<S, Smb, Storage, TraceMat, Scheme>,
+                &Item,
+            ) -> Vec<alignment::Alignment<S, u8, usize, usize>>
+            + Sync,
+    {
+        let mut hits = self.pool.install(|| {
+            items
+                .par_iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    let mut engine = self
+                        .engines
+                        .get_or(|| RefCell::new((self.factory)()))
+                        .borrow_mut();
+                    Hits {
+                        index,
+                        alignments: align(&mut engine, item),
+                    }
+                })
+                .collect::<Vec<_>>()
+        });
+
+        if let Some(k) = self.top_k {
+            hits.retain(|x| !x.alignments.is_empty());
+            hits.sort_by_key(|x| (Reverse(x.score()), x.index));
+            hits.truncate(k);
+            hits.sort_by_key(|x| x.index);
+        }
+        hits
+    }
+}
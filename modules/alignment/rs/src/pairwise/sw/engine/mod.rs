@@ -4,10 +4,21 @@ use tracers::Tracers;
 
 use crate::Alignable;
 use crate::pairwise::sw::{algo, storage, traceback};
-use crate::pairwise::{alignment, scoring};
+use crate::pairwise::{Op, Step, alignment, scoring};
 
 mod tracers;
 
+/// Alignment mode of the [`Engine`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Mode {
+    /// Local (Smith-Waterman) alignment, reported alignments are selected by the storage.
+    #[default]
+    Local,
+    /// Global (Needleman-Wunsch) alignment, semi-global if some end gaps are free.
+    /// Exactly one optimal alignment is reported and the storage is not used.
+    Global(algo::EndGaps),
+}
+
 #[derive(Dissolve)]
 pub struct Engine<S, Smb, Storage, TraceMat, Scheme>
 where
@@ -19,6 +30,8 @@ where
     algo: algo::FullScan<S>,
     scoring: Scheme,
     tracers: Tracers<S, Storage, TraceMat>,
+    mode: Mode,
+    band: Option<algo::Band>,
 }
 
 impl<S, Smb, Storage, TraceMat, Scheme> Engine<S, Smb, Storage, TraceMat, Scheme>
@@ -39,6 +52,8 @@ where
             algo,
             scoring,
             tracers,
+            mode: Mode::Local,
+            band: None,
         }
     }
 
@@ -46,10 +61,33 @@ where
         self.scoring = scoring;
     }
 
+    pub fn with_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Restrict [`scan_all`](Self::scan_all) to the band of diagonals (None for the full matrix).
+    /// Only alignments that lie entirely inside the band are reported.
+    pub fn with_band(&mut self, band: Option<algo::Band>) {
+        self.band = band;
+    }
+
+    pub fn band(&self) -> Option<algo::Band> {
+        self.band
+    }
+
     pub fn storage(&mut self) -> &mut Storage {
         &mut self.tracers.storage
     }
 
+    /// Consume the engine and return its storage.
+    pub fn into_storage(self) -> Storage {
+        self.tracers.storage
+    }
+
     pub fn scan_all<S1, S2>(
         &mut self,
         seq1: &S1,
@@ -59,15 +97,85 @@ where
         S1: Alignable<Symbol = Smb>,
         S2: Alignable<Symbol = Smb>,
     {
-        if seq1.len() == 0 || seq2.len() == 0 {
-            return vec![];
+        match self.mode {
+            Mode::Local => {
+                if seq1.len() == 0 || seq2.len() == 0 {
+                    return vec![];
+                }
+                self.tracers.reset(seq1.len(), seq2.len());
+                match &self.band {
+                    None => self
+                        .algo
+                        .scan_all(seq1, seq2, &mut self.scoring, &mut self.tracers),
+                    Some(band) => {
+                        self.algo.scan_band(
+                            seq1,
+                            seq2,
+                            band,
+                            None,
+                            &mut self.scoring,
+                            &mut self.tracers,
+                        );
+                    }
+                }
+                self._finalize(seq1, seq2, &algo::Scan::Local(self.band))
+            }
+            Mode::Global(ends) => {
+                if seq1.len() == 0 || seq2.len() == 0 {
+                    return self
+                        ._gaps_only(seq1.len(), seq2.len(), &ends)
+                        .into_iter()
+                        .collect();
+                }
+                let tracemat = &mut self.tracers.tracemat;
+                tracemat.reset(seq1.len(), seq2.len());
+                let seed = match &self.band {
+                    None => self
+                        .algo
+                        .scan_global(seq1, seq2, &ends, &mut self.scoring, tracemat),
+                    Some(band) => self.algo.scan_band(
+                        seq1,
+                        seq2,
+                        band,
+                        Some(&ends),
+                        &mut self.scoring,
+                        tracemat,
+                    ),
+                };
+                let scan = algo::Scan::Global(ends, self.band);
+                self._single(seq1, seq2, seed, &scan).into_iter().collect()
+            }
         }
+    }
 
-        self.tracers.reset(seq1.len(), seq2.len());
-        self.algo
-            .scan_all(seq1, seq2, &mut self.scoring, &mut self.tracers);
-        self._finalize(seq1, seq2)
+    /// Extend the alignment from the origin (row, col) towards the ends of sequences until the
+    /// score drops more than `xdrop` below the best one (gapped X-drop). The alignment starts at
+    /// the origin and ends at the best scoring cell, the mode, band and storage are not used.
+    pub fn extend<S1, S2>(
+        &mut self,
+        seq1: &S1,
+        seq2: &S2,
+        from: (usize, usize),
+        xdrop: S,
+    ) -> Option<alignment::Alignment<S, u8, usize, usize>>
+    where
+        S1: Alignable<Symbol = Smb>,
+        S2: Alignable<Symbol = Smb>,
+    {
+        if from.0 >= seq1.len() || from.1 >= seq2.len() {
+            return None;
+        }
+
+        let tracemat = &mut self.tracers.tracemat;
+        tracemat.reset(seq1.len(), seq2.len());
+        let seed = self
+            .algo
+            .extend(seq1, seq2, from, xdrop, &mut self.scoring, tracemat);
+        self._single(seq1, seq2, seed, &algo::Scan::XDrop { from, xdrop })
     }
+
+    // Always runs the local alignment over the whole triangle, global alignment of the triangle is
+    // meaningless and the band is ignored
     pub fn scan_up_triangle<S1, S2>(
         &mut self,
         seq1: &S1,
@@ -85,38 +193,157 @@ where
         self.tracers.reset(seq1.len(), seq2.len());
         self.algo
             .scan_up_triangle(seq1, seq2, offset, &mut self.scoring, &mut self.tracers);
-        self._finalize(seq1, seq2)
+        self._finalize(seq1, seq2, &algo::Scan::Local(None))
+    }
+
+    // Global alignment with an empty sequence: the other one is covered by a single gap, which is
+    // left out of the alignment if any of its ends is free
+    fn _gaps_only(
+        &mut self,
+        len1: usize,
+        len2: usize,
+        ends: &algo::EndGaps,
+    ) -> Option<alignment::Alignment<S, u8, usize, usize>> {
+        let (op, len, leading, trailing) = if len1 > 0 {
+            (Op::GapFirst, len1, ends.seq1_leading, ends.seq1_trailing)
+        } else {
+            (Op::GapSecond, len2, ends.seq2_leading, ends.seq2_trailing)
+        };
+        if len == 0 || trailing {
+            return Some(alignment::Alignment::new(S::zero(), vec![], 0..0, 0..0));
+        } else if leading {
+            let (end1, end2) = (len1..len1, len2..len2);
+            return Some(alignment::Alignment::new(S::zero(), vec![], end1, end2));
+        }
+
+        // The gap runs along the matrix boundary, all of its diagonals must be inside the band
+        let diagonals = match op {
+            Op::GapFirst => -(len as isize)..=-1,
+            _ => 1..=len as isize,
+        };
+        if let Some(band) = &self.band
+            && (*diagonals.start() < band.min_diagonal || *diagonals.end() > band.max_diagonal)
+        {
+            return None;
+        }
+
+        let mut score = S::zero();
+        for pos in 0..len {
+            score = score
+                + match (op, pos) {
+                    (Op::GapFirst, 0) => self.scoring.seq1_gap_open(pos),
+                    (Op::GapFirst, _) => self.scoring.seq1_gap_extend(pos),
+                    (_, 0) => self.scoring.seq2_gap_open(pos),
+                    _ => self.scoring.seq2_gap_extend(pos),
+                };
+        }
+
+        let mut ops = Vec::with_capacity(len.div_ceil(u8::MAX as usize));
+        let mut remaining = len;
+        while remaining > 0 {
+            let step = remaining.min(u8::MAX as usize);
+            ops.push(Step::new(op, step as u8).unwrap());
+            remaining -= step;
+        }
+        Some(alignment::Alignment::new(score, ops, 0..len1, 0..len2))
+    }
+
+    fn _single<S1, S2>(
+        &mut self,
+        seq1: &S1,
+        seq2: &S2,
+        seed: Option<storage::AlignmentSeed<S>>,
+        scan: &algo::Scan<S>,
+    ) -> Option<alignment::Alignment<S, u8, usize, usize>>
+    where
+        S1: Alignable<Symbol = Smb>,
+        S2: Alignable<Symbol = Smb>,
+    {
+        let seed = seed?;
+        let trace = self
+            .tracers
+            .tracemat
+            .trace(seed.row, seed.col, seq1, seq2, &mut self.scoring, scan)
+            .unwrap();
+
+        // The trace stops at the matrix boundary, leading end gaps must be restored manually
+        let (mut start1, mut start2) = (seed.row + 1, seed.col + 1);
+        for step in &trace.ops {
+            let len = *step.len() as usize;
+            match step.op() {
+                Op::GapFirst => start1 -= len,
+                Op::GapSecond => start2 -= len,
+                _ => {
+                    start1 -= len;
+                    start2 -= len;
+                }
+            }
+        }
+
+        let (origin, free1, free2) = match scan {
+            algo::Scan::Global(ends, _) => ((0, 0), ends.seq1_leading, ends.seq2_leading),
+            algo::Scan::XDrop { from, .. } => (*from, false, false),
+            algo::Scan::Local(_) => unreachable!("Local alignments are reported via the storage"),
+        };
+        let mut ops = Vec::with_capacity(trace.ops.len() + 2);
+        for (op, start, origin, free) in [
+            (Op::GapFirst, &mut start1, origin.0, free1),
+            (Op::GapSecond, &mut start2, origin.1, free2),
+        ] {
+            if free {
+                continue;
+            }
+            while *start > origin {
+                let len = (*start - origin).min(u8::MAX as usize);
+                ops.push(Step::new(op, len as u8).unwrap());
+                *start -= len;
+            }
+        }
+        ops.extend(trace.ops);
+
+        let ops = alignment::utils::disambiguate(ops, &self.scoring, seq1, start1, seq2, start2);
+        Some(alignment::Alignment::new(
+            seed.score,
+            ops,
+            start1..seed.row + 1,
+            start2..seed.col + 1,
+        ))
     }
 
     fn _finalize<S1, S2>(
         &mut self,
         seq1: &S1,
         seq2: &S2,
+        scan: &algo::Scan<S>,
     ) -> Vec<alignment::Alignment<S, u8, usize, usize>>
     where
         S1: Alignable<Symbol = Smb>,
         S2: Alignable<Symbol = Smb>,
     {
-        self.tracers
-            .storage
-            .finalize()
-            .into_iter()
-            .map(|x| {
-                let trace = self.tracers.tracemat.trace(x.row, x.col).unwrap();
-                debug_assert_eq!(trace.seq1.end, x.row + 1);
-                debug_assert_eq!(trace.seq2.end, x.col + 1);
-                let ops = alignment::utils::disambiguate(
-                    trace.ops,
-                    &self.scoring,
-                    seq1,
-                    trace.seq1.start,
-                    seq2,
-                    trace.seq2.start,
-                );
-
-                alignment::Alignment::new(x.score, ops, trace.seq1, trace.seq2)
-            })
-            .collect()
+        let seeds = self.tracers.storage.finalize();
+        let mut result = Vec::with_capacity(seeds.len());
+        for x in seeds {
+            let trace = self
+                .tracers
+                .tracemat
+                .trace(x.row, x.col, seq1, seq2, &mut self.scoring, scan)
+                .unwrap();
+            debug_assert_eq!(trace.seq1.end, x.row + 1);
+            debug_assert_eq!(trace.seq2.end, x.col + 1);
+            let ops = alignment::utils::disambiguate(
+                trace.ops,
+                &self.scoring,
+                seq1,
+                trace.seq1.start,
+                seq2,
+                trace.seq2.start,
+            );
+
+            result.push(alignment::Alignment::new(
+                x.score, ops, trace.seq1, trace.seq2,
+            ));
+        }
+        result
         // Collapse overlapping paths & save results
         // let result = Vec::with_capacity(128);
         // for item in results.into_iter().rev() {
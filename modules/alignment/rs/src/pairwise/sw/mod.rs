@@ -1,6 +1,10 @@
-pub use engine::Engine;
+pub use batch::{Batch, Hits};
+pub use engine::{Engine, Mode};
+pub use striped::Striped;
 
 pub mod algo;
+mod batch;
 mod engine;
 pub mod storage;
+mod striped;
 pub mod traceback;
@@ -0,0 +1,210 @@
+use biobit_core_rs::num::PrimInt;
+
+use crate::Score;
+use crate::pairwise::scoring;
+use crate::pairwise::sw::storage::AlignmentSeed;
+
+use super::lanes::{Lanes, Simd};
+
+// Striped Smith-Waterman: https://doi.org/10.1093/bioinformatics/btl582
+
+// Query (seq1) rows are split into N lanes of `segments` consecutive rows. Vector k holds rows
+// {k, k + segments, k + 2 * segments, ...}, which makes all dependencies inside a column
+// (except the vertical gaps crossing the lane boundary) independent across lanes.
+// Vertical gaps crossing the lane boundary are resolved by the "lazy F" loop after each column.
+// Rows past the end of the query (padding) are unreachable and stay at 0.
+
+// Query profile for the given lane type
+pub struct Profile<T: PrimInt, const N: usize> {
+    pub segments: usize,
+    // False if some symbol or gap score doesn't fit into the lane type
+    pub fits: bool,
+    // The highest symbol score, used to detect saturation
+    pub highest: T,
+    // Striped symbol scores, `segments` vectors per each profiled seq2 symbol
+    pub scores: Vec<Lanes<T, N>>,
+    // Cost of the vertical gap transition from the row in the vector k to the next row
+    pub open_next: Vec<Lanes<T, N>>,
+    pub extend_next: Vec<Lanes<T, N>>,
+    // Best scores for the previous and the current columns, horizontal gap scores
+    hprev: Vec<Lanes<T, N>>,
+    hcur: Vec<Lanes<T, N>>,
+    gapcol: Vec<Lanes<T, N>>,
+}
+
+pub enum Outcome<S: Score> {
+    Done(Option<AlignmentSeed<S>>),
+    // Scores don't fit into the lane type
+    Overflow,
+}
+
+impl<T: Simd<N>, const N: usize> Profile<T, N> {
+    pub fn new() -> Self {
+        Self {
+            segments: 0,
+            fits: true,
+            highest: T::zero(),
+            scores: Vec::new(),
+            open_next: Vec::new(),
+            extend_next: Vec::new(),
+            hprev: Vec::new(),
+            hcur: Vec::new(),
+            gapcol: Vec::new(),
+        }
+    }
+
+    // Reset the profile for a new query, symbol scores must be added separately
+    pub fn reset<Scheme: scoring::Scheme>(&mut self, rows: usize, scorer: &Scheme) {
+        let segments = rows.div_ceil(N).max(1);
+        self.segments = segments;
+        self.fits = true;
+        self.highest = T::zero();
+        self.scores.clear();
+        self.open_next.clear();
+        self.extend_next.clear();
+
+        // Row that follows the given one in the vector k. Gaps into the padding are prohibited.
+        let next = |k: usize, lane: usize| {
+            let row = if k + 1 < segments {
+                lane * segments + k + 1
+            } else {
+                (lane + 1) * segments
+            };
+            (row < rows).then_some(row)
+        };
+        for k in 0..segments {
+            let open = Lanes::from_fn(|lane| match next(k, lane) {
+                None => T::min_value(),
+                Some(row) => cast(scorer.seq1_gap_open(row), &mut self.fits),
+            });
+            let extend = Lanes::from_fn(|lane| match next(k, lane) {
+                None => T::min_value(),
+                Some(row) => cast(scorer.seq1_gap_extend(row), &mut self.fits),
+            });
+            self.open_next.push(open);
+            self.extend_next.push(extend);
+        }
+    }
+
+    // Append striped scores of the seq2 symbol against all query symbols
+    pub fn push<Smb, Scheme>(&mut self, query: &[Smb], s2: &Smb, scorer: &Scheme)
+    where
+        Scheme: scoring::Scheme<Symbol = Smb>,
+    {
+        for k in 0..self.segments {
+            let vector = Lanes::from_fn(|lane| {
+                let row = lane * self.segments + k;
+                if row >= query.len() {
+                    return T::min_value();
+                }
+                let score = cast(scorer.score(row, &query[row], 0, s2), &mut self.fits);
+                self.highest = self.highest.max(score);
+                score
+            });
+            self.scores.push(vector);
+        }
+    }
+
+    // Score-only local alignment of the profiled query against the sequence of profile indices
+    pub fn scan<S, Scheme>(&mut self, rows: usize, seq2: &[usize], scorer: &Scheme) -> Outcome<S>
+    where
+        S: Score,
+        Scheme: scoring::Scheme<Score = S>,
+    {
+        if !self.fits {
+            return Outcome::Overflow;
+        }
+        // No add can saturate while the best score is below the limit
+        let limit = T::max_value() - self.highest;
+
+        let n = self.segments;
+        let (zero, none) = (Lanes::splat(T::zero()), Lanes::splat(T::min_value()));
+        for x in [&mut self.hprev, &mut self.hcur] {
+            x.clear();
+            x.resize(n, zero);
+        }
+        self.gapcol.clear();
+        self.gapcol.resize(n, none);
+
+        let mut best: Option<AlignmentSeed<T>> = None;
+        for (col, &symbol) in seq2.iter().enumerate() {
+            let mut fits = true;
+            let open = Lanes::splat(cast(scorer.seq2_gap_open(col), &mut fits));
+            let extend = Lanes::splat(cast(scorer.seq2_gap_extend(col), &mut fits));
+            if !fits {
+                return Outcome::Overflow;
+            }
+            let profile = &self.scores[symbol * n..(symbol + 1) * n];
+
+            // The first row of each lane continues the last row of the previous lane
+            let mut diagonal = self.hprev[n - 1].shift(T::zero());
+            let mut gaprow = none;
+            let mut colmax = zero;
+            for (k, &scores) in profile.iter().enumerate() {
+                let gapcol = self.gapcol[k].adds(extend).max(self.hprev[k].adds(open));
+                self.gapcol[k] = gapcol;
+
+                let h = diagonal.adds(scores).max(gapcol).max(gaprow).max(zero);
+                self.hcur[k] = h;
+                colmax = colmax.max(h);
+                diagonal = self.hprev[k];
+
+                gaprow = gaprow
+                    .adds(self.extend_next[k])
+                    .max(h.adds(self.open_next[k]));
+            }
+
+            // Lazy F loop: propagate vertical gaps across lanes until they can't change anything
+            gaprow = gaprow.shift(T::min_value());
+            'lazy: loop {
+                for k in 0..n {
+                    let h = self.hcur[k];
+                    let extended = gaprow.adds(self.extend_next[k]);
+                    if !gaprow.any_gt(h) && !extended.any_gt(h.adds(self.open_next[k])) {
+                        break 'lazy;
+                    }
+                    // Opening from the updated cell is never better than extending the gap
+                    self.hcur[k] = h.max(gaprow);
+                    colmax = colmax.max(self.hcur[k]);
+                    gaprow = extended;
+                }
+                gaprow = gaprow.shift(T::min_value());
+            }
+
+            let colmax = colmax.hmax();
+            if colmax >= limit {
+                return Outcome::Overflow;
+            }
+            std::mem::swap(&mut self.hprev, &mut self.hcur);
+
+            // The first row with the best score in the column, same as the full scan + Best storage
+            if colmax > best.map_or(T::zero(), |x| x.score) {
+                let row = (0..rows)
+                    .find(|&row| self.hprev[row % n].lane(row / n) == colmax)
+                    .unwrap();
+                best = Some(AlignmentSeed {
+                    row,
+                    col,
+                    score: colmax,
+                });
+            }
+        }
+
+        Outcome::Done(best.map(|x| AlignmentSeed {
+            row: x.row,
+            col: x.col,
+            score: S::from(x.score).unwrap(),
+        }))
+    }
+}
+
+// Cast the score to the lane type, saturated values are marked as not fitting
+fn cast<S: Score, T: PrimInt>(score: S, fits: &mut bool) -> T {
+    match T::from(score) {
+        Some(x) if x != T::min_value() && x != T::max_value() => x,
+        _ => {
+            *fits = false;
+            T::min_value()
+        }
+    }
+}
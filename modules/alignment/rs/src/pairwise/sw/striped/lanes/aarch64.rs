@@ -0,0 +1,90 @@
+use std::arch::aarch64::*;
+use std::mem::transmute;
+
+use super::{Lanes, Simd};
+
+// NEON implementation. Lanes are 16-byte aligned arrays of 128 bits, i.e. they share the layout
+// of the NEON vector types, and lane 0 is the lowest element of the register. Intrinsics are only
+// safe to call from functions with a matching #[target_feature], which trait methods can't have,
+// hence the unsafe blocks. They are sound because NEON is always available on aarch64.
+
+impl Simd<16> for i8 {
+    #[inline(always)]
+    fn adds(a: Lanes<i8, 16>, b: Lanes<i8, 16>) -> Lanes<i8, 16> {
+        unsafe { store8(vqaddq_s8(load8(a), load8(b))) }
+    }
+
+    #[inline(always)]
+    fn max(a: Lanes<i8, 16>, b: Lanes<i8, 16>) -> Lanes<i8, 16> {
+        unsafe { store8(vmaxq_s8(load8(a), load8(b))) }
+    }
+
+    #[inline(always)]
+    fn any_gt(a: Lanes<i8, 16>, b: Lanes<i8, 16>) -> bool {
+        unsafe { vmaxvq_u8(vcgtq_s8(load8(a), load8(b))) != 0 }
+    }
+
+    #[inline(always)]
+    fn shift(a: Lanes<i8, 16>, fill: i8) -> Lanes<i8, 16> {
+        unsafe {
+            // Concatenate [fill; 16] with a and take 16 lanes starting from the last fill lane
+            store8(vextq_s8::<15>(vdupq_n_s8(fill), load8(a)))
+        }
+    }
+
+    #[inline(always)]
+    fn hmax(a: Lanes<i8, 16>) -> i8 {
+        unsafe { vmaxvq_s8(load8(a)) }
+    }
+}
+
+impl Simd<8> for i16 {
+    #[inline(always)]
+    fn adds(a: Lanes<i16, 8>, b: Lanes<i16, 8>) -> Lanes<i16, 8> {
+        unsafe { store16(vqaddq_s16(load16(a), load16(b))) }
+    }
+
+    #[inline(always)]
+    fn max(a: Lanes<i16, 8>, b: Lanes<i16, 8>) -> Lanes<i16, 8> {
+        unsafe { store16(vmaxq_s16(load16(a), load16(b))) }
+    }
+
+    #[inline(always)]
+    fn any_gt(a: Lanes<i16, 8>, b: Lanes<i16, 8>) -> bool {
+        unsafe { vmaxvq_u16(vcgtq_s16(load16(a), load16(b))) != 0 }
+    }
+
+    #[inline(always)]
+    fn shift(a: Lanes<i16, 8>, fill: i16) -> Lanes<i16, 8> {
+        unsafe { store16(vextq_s16::<7>(vdupq_n_s16(fill), load16(a))) }
+    }
+
+    #[inline(always)]
+    fn hmax(a: Lanes<i16, 8>) -> i16 {
+        unsafe { vmaxvq_s16(load16(a)) }
+    }
+}
+
+#[inline(always)]
+fn load8(x: Lanes<i8, 16>) -> int8x16_t {
+    // SAFETY: Both types are plain 128-bit values without invalid bit patterns
+    unsafe { transmute(x) }
+}
+
+#[inline(always)]
+fn store8(x: int8x16_t) -> Lanes<i8, 16> {
+    // SAFETY: Both types are plain 128-bit values without invalid bit patterns
+    unsafe { transmute(x) }
+}
+
+#[inline(always)]
+fn load16(x: Lanes<i16, 8>) -> int16x8_t {
+    // SAFETY: Both types are plain 128-bit values without invalid bit patterns
+    unsafe { transmute(x) }
+}
+
+#[inline(always)]
+fn store16(x: int16x8_t) -> Lanes<i16, 8> {
+    // SAFETY: Both types are plain 128-bit values without invalid bit patterns
+    unsafe { transmute(x) }
+}
@@ -0,0 +1,117 @@
+use biobit_core_rs::num::PrimInt;
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64;
+#[cfg(any(test, not(any(target_arch = "x86_64", target_arch = "aarch64"))))]
+mod scalar;
+#[cfg(target_arch = "x86_64")]
+mod x86_64;
+
+// Fixed-size 128-bit vector of integers. Vector operations are implemented with SSE2 (x86-64) or
+// NEON (aarch64) intrinsics, both are part of the baseline of their architectures and don't need
+// runtime detection. Other architectures use plain loops over the lanes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, align(16))]
+pub struct Lanes<T: PrimInt, const N: usize>(pub [T; N]);
+
+// Lane types with a 128-bit vector implementation for N lanes
+pub trait Simd<const N: usize>: PrimInt {
+    // Lane-wise saturating addition
+    fn adds(a: Lanes<Self, N>, b: Lanes<Self, N>) -> Lanes<Self, N>;
+
+    // Lane-wise maximum
+    fn max(a: Lanes<Self, N>, b: Lanes<Self, N>) -> Lanes<Self, N>;
+
+    // True if any lane of a is greater than the corresponding lane of b
+    fn any_gt(a: Lanes<Self, N>, b: Lanes<Self, N>) -> bool;
+
+    // Move each lane one position up (lane i -> lane i + 1), lane 0 is set to `fill`
+    fn shift(a: Lanes<Self, N>, fill: Self) -> Lanes<Self, N>;
+
+    // Horizontal maximum
+    fn hmax(a: Lanes<Self, N>) -> Self;
+}
+
+impl<T: PrimInt, const N: usize> Lanes<T, N> {
+    #[inline(always)]
+    pub fn splat(value: T) -> Self {
+        Self([value; N])
+    }
+
+    #[inline(always)]
+    pub fn from_fn(f: impl FnMut(usize) -> T) -> Self {
+        Self(std::array::from_fn(f))
+    }
+
+    #[inline(always)]
+    pub fn lane(&self, ind: usize) -> T {
+        self.0[ind]
+    }
+}
+
+impl<T: Simd<N>, const N: usize> Lanes<T, N> {
+    #[inline(always)]
+    pub fn adds(self, other: Self) -> Self {
+        <T as Simd<N>>::adds(self, other)
+    }
+
+    #[inline(always)]
+    pub fn max(self, other: Self) -> Self {
+        <T as Simd<N>>::max(self, other)
+    }
+
+    #[inline(always)]
+    pub fn any_gt(self, other: Self) -> bool {
+        <T as Simd<N>>::any_gt(self, other)
+    }
+
+    #[inline(always)]
+    pub fn shift(self, fill: T) -> Self {
+        <T as Simd<N>>::shift(self, fill)
+    }
+
+    #[inline(always)]
+    pub fn hmax(self) -> T {
+        <T as Simd<N>>::hmax(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Random;
+
+    // Pseudo-random lanes biased towards the extreme values
+    fn random<T: PrimInt, const N: usize>(random: &mut Random) -> Lanes<T, N> {
+        Lanes::from_fn(|_| {
+            let bits = random.next_u64();
+            match bits % 8 {
+                0 => T::min_value(),
+                1 => T::max_value(),
+                2 => T::zero(),
+                _ => T::from((bits >> 3) as i64 % 256 - 128).unwrap(),
+            }
+        })
+    }
+
+    fn check<T: Simd<N>, const N: usize>() {
+        let mut rng = Random::new(42);
+        for _ in 0..10_000 {
+            let (a, b) = (random::<T, N>(&mut rng), random::<T, N>(&mut rng));
+            let fill = random::<T, N>(&mut rng).lane(0);
+
+            assert_eq!(a.adds(b), scalar::adds(a, b));
+            assert_eq!(a.max(b), scalar::max(a, b));
+            assert_eq!(a.any_gt(b), scalar::any_gt(a, b));
+            assert!(!a.any_gt(a));
+            assert_eq!(a.shift(fill), scalar::shift(a, fill));
+            assert_eq!(a.hmax(), scalar::hmax(a));
+        }
+    }
+
+    #[test]
+    fn test_lanes_match_scalar() {
+        check::<i8, 16>();
+        check::<i16, 8>();
+    }
+}
@@ -0,0 +1,68 @@
+use biobit_core_rs::num::PrimInt;
+
+use super::Lanes;
+
+// Portable implementation of the vector operations, used on architectures without a dedicated
+// implementation and as the reference in tests.
+
+#[inline(always)]
+pub fn adds<T: PrimInt, const N: usize>(a: Lanes<T, N>, b: Lanes<T, N>) -> Lanes<T, N> {
+    Lanes(std::array::from_fn(|i| a.0[i].saturating_add(b.0[i])))
+}
+
+#[inline(always)]
+pub fn max<T: PrimInt, const N: usize>(a: Lanes<T, N>, b: Lanes<T, N>) -> Lanes<T, N> {
+    Lanes(std::array::from_fn(|i| a.0[i].max(b.0[i])))
+}
+
+#[inline(always)]
+pub fn any_gt<T: PrimInt, const N: usize>(a: Lanes<T, N>, b: Lanes<T, N>) -> bool {
+    (0..N).fold(false, |acc, i| acc | (a.0[i] > b.0[i]))
+}
+
+#[inline(always)]
+pub fn shift<T: PrimInt, const N: usize>(a: Lanes<T, N>, fill: T) -> Lanes<T, N> {
+    let mut result = [fill; N];
+    result[1..].copy_from_slice(&a.0[..N - 1]);
+    Lanes(result)
+}
+
+#[inline(always)]
+pub fn hmax<T: PrimInt, const N: usize>(a: Lanes<T, N>) -> T {
+    a.0.into_iter().fold(T::min_value(), T::max)
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+macro_rules! impl_simd {
+    ($($t:ty => $n:literal),*) => {$(
+        impl super::Simd<$n> for $t {
+            #[inline(always)]
+            fn adds(a: Lanes<Self, $n>, b: Lanes<Self, $n>) -> Lanes<Self, $n> {
+                adds(a, b)
+            }
+
+            #[inline(always)]
+            fn max(a: Lanes<Self, $n>, b: Lanes<Self, $n>) -> Lanes<Self, $n> {
+                max(a, b)
+            }
+
+            #[inline(always)]
+            fn any_gt(a: Lanes<Self, $n>, b: Lanes<Self, $n>) -> bool {
+                any_gt(a, b)
+            }
+
+            #[inline(always)]
+            fn shift(a: Lanes<Self, $n>, fill: Self) -> Lanes<Self, $n> {
+                shift(a, fill)
+            }
+
+            #[inline(always)]
+            fn hmax(a: Lanes<Self, $n>) -> Self {
+                hmax(a)
+            }
+        }
+    )*};
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+impl_simd!(i8 => 16, i16 => 8);
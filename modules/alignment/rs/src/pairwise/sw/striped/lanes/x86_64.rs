@@ -0,0 +1,123 @@
+use std::arch::x86_64::*;
+use std::mem::transmute;
+
+use super::{Lanes, Simd};
+
+// SSE2 implementation. Lanes are 16-byte aligned arrays of 128 bits, i.e. they share the layout
+// of __m128i, and lane 0 is the lowest element of the register. Intrinsics are only safe to call
+// from functions with a matching #[target_feature], which trait methods can't have, hence the
+// unsafe blocks. They are sound because SSE2 is always available on x86-64.
+
+impl Simd<16> for i8 {
+    #[inline(always)]
+    fn adds(a: Lanes<i8, 16>, b: Lanes<i8, 16>) -> Lanes<i8, 16> {
+        unsafe { store8(_mm_adds_epi8(load8(a), load8(b))) }
+    }
+
+    #[inline(always)]
+    fn max(a: Lanes<i8, 16>, b: Lanes<i8, 16>) -> Lanes<i8, 16> {
+        store8(max8(load8(a), load8(b)))
+    }
+
+    #[inline(always)]
+    fn any_gt(a: Lanes<i8, 16>, b: Lanes<i8, 16>) -> bool {
+        unsafe { _mm_movemask_epi8(_mm_cmpgt_epi8(load8(a), load8(b))) != 0 }
+    }
+
+    #[inline(always)]
+    fn shift(a: Lanes<i8, 16>, fill: i8) -> Lanes<i8, 16> {
+        unsafe {
+            let shifted = _mm_slli_si128::<1>(load8(a));
+            store8(_mm_or_si128(shifted, _mm_cvtsi32_si128(fill as u8 as i32)))
+        }
+    }
+
+    #[inline(always)]
+    fn hmax(a: Lanes<i8, 16>) -> i8 {
+        unsafe {
+            // Fold the upper half onto the lower one, only lane 0 is meaningful in the end
+            let mut x = load8(a);
+            x = max8(x, _mm_srli_si128::<8>(x));
+            x = max8(x, _mm_srli_si128::<4>(x));
+            x = max8(x, _mm_srli_si128::<2>(x));
+            x = max8(x, _mm_srli_si128::<1>(x));
+            _mm_cvtsi128_si32(x) as i8
+        }
+    }
+}
+
+impl Simd<8> for i16 {
+    #[inline(always)]
+    fn adds(a: Lanes<i16, 8>, b: Lanes<i16, 8>) -> Lanes<i16, 8> {
+        unsafe { store16(_mm_adds_epi16(load16(a), load16(b))) }
+    }
+
+    #[inline(always)]
+    fn max(a: Lanes<i16, 8>, b: Lanes<i16, 8>) -> Lanes<i16, 8> {
+        unsafe { store16(_mm_max_epi16(load16(a), load16(b))) }
+    }
+
+    #[inline(always)]
+    fn any_gt(a: Lanes<i16, 8>, b: Lanes<i16, 8>) -> bool {
+        unsafe { _mm_movemask_epi8(_mm_cmpgt_epi16(load16(a), load16(b))) != 0 }
+    }
+
+    #[inline(always)]
+    fn shift(a: Lanes<i16, 8>, fill: i16) -> Lanes<i16, 8> {
+        unsafe {
+            let shifted = _mm_slli_si128::<2>(load16(a));
+            store16(_mm_insert_epi16::<0>(shifted, fill as i32))
+        }
+    }
+
+    #[inline(always)]
+    fn hmax(a: Lanes<i16, 8>) -> i16 {
+        unsafe {
+            let mut x = load16(a);
+            x = _mm_max_epi16(x, _mm_srli_si128::<8>(x));
+            x = _mm_max_epi16(x, _mm_srli_si128::<4>(x));
+            x = _mm_max_epi16(x, _mm_srli_si128::<2>(x));
+            _mm_extract_epi16::<0>(x) as i16
+        }
+    }
+}
+
+// Signed 8-bit maximum is an SSE4.1 instruction, SSE2 emulates it with a comparison
+#[inline(always)]
+fn max8(a: __m128i, b: __m128i) -> __m128i {
+    #[cfg(target_feature = "sse4.1")]
+    {
+        unsafe { _mm_max_epi8(a, b) }
+    }
+    #[cfg(not(target_feature = "sse4.1"))]
+    {
+        unsafe {
+            let gt = _mm_cmpgt_epi8(a, b);
+            _mm_or_si128(_mm_and_si128(gt, a), _mm_andnot_si128(gt, b))
+        }
+    }
+}
+
+#[inline(always)]
+fn load8(x: Lanes<i8, 16>) -> __m128i {
+    // SAFETY: Both types are plain 128-bit values without invalid bit patterns
+    unsafe { transmute(x) }
+}
+
+#[inline(always)]
+fn store8(x: __m128i) -> Lanes<i8, 16> {
+    // SAFETY: Both types are plain 128-bit values without invalid bit patterns
+    unsafe { transmute(x) }
+}
+
+#[inline(always)]
+fn load16(x: Lanes<i16, 8>) -> __m128i {
+    // SAFETY: Both types are plain 128-bit values without invalid bit patterns
+    unsafe { transmute(x) }
+}
+
+#[inline(always)]
+fn store16(x: __m128i) -> Lanes<i16, 8> {
+    // SAFETY: Both types are plain 128-bit values without invalid bit patterns
+    unsafe { transmute(x) }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use kernel::{Outcome, Profile};
+
+use crate::Alignable;
+use crate::pairwise::scoring;
+use crate::pairwise::sw::algo::FullScan;
+use crate::pairwise::sw::storage::{AlignmentSeed, Best, Storage};
+
+mod kernel;
+mod lanes;
+
+/// Score-only local alignment with the striped SIMD kernel (Farrar).
+///
+/// A fast pre-filter in front of the traced [`Engine`](super::Engine): the query (seq1) profile is
+/// built once and each scan reports the best local alignment score and its end cell, exactly as
+/// the [`Engine`](super::Engine) with the [`Best`] storage would do. Scores are computed in 16 i8
+/// lanes, then in 8 i16 lanes if they overflow, and finally by the scalar [`FullScan`].
+///
+/// Symbol scores must not depend on the position in seq2, they are precomputed per seq2 symbol.
+pub struct Striped<S, Smb, Scheme>
+where
+    S: scoring::Score,
+    Smb: Copy + Eq + Hash,
+    Scheme: scoring::Scheme<Score = S, Symbol = Smb>,
+{
+    scoring: Scheme,
+    query: Vec<Smb>,
+    // Index of the seq2 symbol in the query profile
+    symbols: HashMap<Smb, usize>,
+    profile8: Profile<i8, 16>,
+    profile16: Profile<i16, 8>,
+    indices: Vec<usize>,
+    // Scalar fallback
+    algo: FullScan<S>,
+    best: Best<S>,
+}
+
+impl<S, Smb, Scheme> Striped<S, Smb, Scheme>
+where
+    S: scoring::Score,
+    Smb: Copy + Eq + Hash,
+    Scheme: scoring::Scheme<Score = S, Symbol = Smb>,
+{
+    pub fn new(scoring: Scheme) -> Self {
+        let mut result = Self {
+            scoring,
+            query: Vec::new(),
+            symbols: HashMap::new(),
+            profile8: Profile::new(),
+            profile16: Profile::new(),
+            indices: Vec::new(),
+            algo: FullScan::new(0),
+            best: Best::new(),
+        };
+        result.reset();
+        result
+    }
+
+    pub fn with_scoring(&mut self, scoring: Scheme) {
+        self.scoring = scoring;
+        self.reset();
+    }
+
+    pub fn with_query<Seq1: Alignable<Symbol = Smb>>(&mut self, seq1: &Seq1) {
+        self.query.clear();
+        self.query.extend((0..seq1.len()).map(|pos| *seq1.at(pos)));
+        self.reset();
+    }
+
+    pub fn query(&self) -> &[Smb] {
+        &self.query
+    }
+
+    /// Best local alignment score of the query against seq2 and the cell where it ends.
+    pub fn scan<Seq2: Alignable<Symbol = Smb>>(&mut self, seq2: &Seq2) -> Option<AlignmentSeed<S>> {
+        if self.query.is_empty() || seq2.len() == 0 {
+            return None;
+        }
+
+        // Profile all new symbols
+        self.indices.clear();
+        for pos in 0..seq2.len() {
+            let symbol = seq2.at(pos);
+            let index = match self.symbols.get(symbol) {
+                Some(&index) => index,
+                None => {
+                    self.profile8.push(&self.query, symbol, &self.scoring);
+                    self.profile16.push(&self.query, symbol, &self.scoring);
+                    self.symbols.insert(*symbol, self.symbols.len());
+                    self.symbols.len() - 1
+                }
+            };
+            self.indices.push(index);
+        }
+
+        let rows = self.query.len();
+        if let Outcome::Done(result) = self.profile8.scan(rows, &self.indices, &self.scoring) {
+            return result;
+        }
+        if let Outcome::Done(result) = self.profile16.scan(rows, &self.indices, &self.scoring) {
+            return result;
+        }
+
+        self.best.reset(rows, seq2.len());
+        self.algo
+            .scan_all(&self.query, seq2, &mut self.scoring, &mut self.best);
+        self.best.finalize().pop()
+    }
+
+    // Profiles depend on both the query and the scoring scheme
+    fn reset(&mut self) {
+        self.symbols.clear();
+        self.profile8.reset(self.query.len(), &self.scoring);
+        self.profile16.reset(self.query.len(), &self.scoring);
+    }
+}
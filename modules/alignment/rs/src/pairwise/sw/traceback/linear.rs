@@ -0,0 +1,623 @@
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use eyre::{Result, ensure, eyre};
+
+use crate::Alignable;
+use crate::pairwise::sw::algo::{BestOrientationTracer, FullScan, GapTracer, Scan, Tracer};
+use crate::pairwise::sw::traceback::{RunningTrace, TraceMat, TracedAlignment};
+use crate::pairwise::{Op, Step, scoring};
+
+// Optimal alignments in linear space: https://doi.org/10.1093/bioinformatics/4.1.11
+
+// Nothing is stored during the scan. To trace an alignment ending at a given cell:
+// 1. The scan is repeated up to the cell while propagating the start of each path (Starts).
+//    The same algorithm is used, therefore the start is identical to the one in TraceMatrix.
+//    Rows computed in each column are recorded as well: banded and X-drop scans skip some cells,
+//    which must stay unreachable in the next step.
+// 2. The path between the start and the end is recovered by divide-and-conquer:
+//    a forward pass over the box records where the optimal path consumes the middle symbol
+//    of seq2 (Cross), then the box is split into two independent sub-boxes around it.
+//    A column gap crossing the middle is handled by requiring the left sub-box to end
+//    with a column gap (Exit::GapCol) and letting the right sub-box extend it (Entry::GapCol).
+// Both steps require O(rows) memory, the divide-and-conquer doubles the number of computed cells.
+
+type Node = Option<(usize, usize)>;
+
+// Follows the algorithm hooks and propagates the start node of the best path for each cell.
+// None marks a path (re)start, that is a local restart or the matrix boundary.
+// The boundary is the row/column right before the origin, (0, 0) for all scans except X-drop.
+struct Starts<S: scoring::Score> {
+    origin: (usize, usize),
+    prev: Vec<Node>,
+    cur: Vec<Node>,
+    gapcol: Vec<Node>,
+    gaprow: Node,
+    // Computed rows of each column and of the current one
+    windows: Vec<Range<usize>>,
+    window: Range<usize>,
+    col: usize,
+    phantom: PhantomData<S>,
+}
+
+impl<S: scoring::Score> Starts<S> {
+    fn new() -> Self {
+        Self {
+            origin: (0, 0),
+            prev: Vec::new(),
+            cur: Vec::new(),
+            gapcol: Vec::new(),
+            gaprow: None,
+            windows: Vec::new(),
+            window: 0..0,
+            col: 0,
+            phantom: Default::default(),
+        }
+    }
+
+    fn reset(&mut self, rows: usize, cols: usize, origin: (usize, usize)) {
+        self.origin = origin;
+        for x in [&mut self.prev, &mut self.cur, &mut self.gapcol] {
+            x.clear();
+            x.resize(rows, None);
+        }
+        self.gaprow = None;
+        self.windows.clear();
+        self.windows.resize(cols, 0..0);
+        self.window = 0..0;
+    }
+
+    #[inline(always)]
+    fn diagonal(&self, row: usize, col: usize) -> Node {
+        let diagonal = if row == self.origin.0 {
+            None
+        } else {
+            self.prev[row - 1]
+        };
+        diagonal.or(Some((row, col)))
+    }
+
+    #[inline(always)]
+    fn visit(&mut self, row: usize, col: usize) {
+        if self.window.is_empty() {
+            self.window = row..row + 1;
+        } else {
+            self.window.end = row + 1;
+        }
+        self.col = col;
+    }
+
+    #[inline(always)]
+    fn save(&mut self) {
+        if !self.window.is_empty() {
+            self.windows[self.col] = self.window.clone();
+            self.window = 0..0;
+        }
+        std::mem::swap(&mut self.prev, &mut self.cur);
+    }
+}
+
+impl<S: scoring::Score> BestOrientationTracer for Starts<S> {
+    type Score = S;
+
+    #[inline(always)]
+    fn gap_row(&mut self, row: usize, col: usize, _: Self::Score) {
+        self.visit(row, col);
+        self.cur[row] = self.gaprow;
+    }
+
+    #[inline(always)]
+    fn gap_col(&mut self, row: usize, col: usize, _: Self::Score) {
+        self.visit(row, col);
+        self.cur[row] = self.gapcol[row];
+    }
+
+    #[inline(always)]
+    fn equivalent(&mut self, row: usize, col: usize, _: Self::Score) {
+        self.visit(row, col);
+        self.cur[row] = self.diagonal(row, col);
+    }
+
+    #[inline(always)]
+    fn none(&mut self, row: usize, col: usize) {
+        self.visit(row, col);
+        self.cur[row] = None;
+    }
+}
+
+impl<S: scoring::Score> GapTracer for Starts<S> {
+    type Score = S;
+
+    // Gaps are opened from the boundary only in the global and X-drop modes
+    #[inline(always)]
+    fn row_gap_open(&mut self, row: usize, col: usize, _: Self::Score) {
+        self.gaprow = if row == self.origin.0 {
+            Some((row, col + 1))
+        } else {
+            self.cur[row - 1]
+        };
+    }
+
+    #[inline(always)]
+    fn col_gap_open(&mut self, row: usize, col: usize, _: Self::Score) {
+        self.gapcol[row] = if col == self.origin.1 {
+            Some((row + 1, col))
+        } else {
+            self.prev[row]
+        };
+    }
+}
+
+impl<S: scoring::Score> Tracer for Starts<S> {
+    type Score = S;
+
+    #[inline(always)]
+    fn first_col_end(&mut self) {
+        self.save();
+    }
+
+    #[inline(always)]
+    fn col_end(&mut self, _: usize) {
+        self.save();
+    }
+}
+
+// Leading part of the sequence, used to repeat the scan up to the traced cell
+struct Prefix<'a, T: Alignable> {
+    base: &'a T,
+    len: usize,
+}
+
+impl<T: Alignable> Alignable for Prefix<'_, T> {
+    type Symbol = T::Symbol;
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline(always)]
+    fn at(&self, pos: usize) -> &Self::Symbol {
+        debug_assert!(pos < self.len);
+        self.base.at(pos)
+    }
+}
+
+// How the path enters the box
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Entry {
+    // A new path, gaps right at the start might be prohibited (local restart or matrix boundary)
+    Start { row_gap: bool, col_gap: bool },
+    // Continues the column gap from the previous box
+    GapCol,
+}
+
+// How the path leaves the box
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Exit {
+    Any,
+    // Must end with a column gap, which is continued by the next box
+    GapCol,
+}
+
+// The step consuming the middle seq2 symbol. Stores the box row where the step starts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Cross {
+    Equivalent(usize),
+    GapOpen(usize),
+    GapExtend(usize),
+}
+
+/// Traceback in O(rows + cols) memory for alignments of long sequences.
+///
+/// Nothing is recorded during the scan. Each alignment is recovered on demand by repeating the
+/// scan up to its end and running a divide-and-conquer traceback (Myers-Miller), which makes
+/// tracing ~3 times slower than the full scan. Prefer [`TraceMatrix`](super::TraceMatrix) when
+/// the computed part of the matrix fits into memory or many alignments must be traced.
+pub struct LinearSpace<S: scoring::Score> {
+    algo: FullScan<S>,
+    starts: Starts<S>,
+    // Forward pass over the current box: best/column gap scores and middle crossings
+    scores: Vec<S>,
+    gapcol: Vec<S>,
+    cross: Vec<Cross>,
+    gapcross: Vec<Cross>,
+}
+
+impl<S: scoring::Score> LinearSpace<S> {
+    pub fn new() -> Self {
+        Self {
+            algo: FullScan::new(0),
+            starts: Starts::new(),
+            scores: Vec::new(),
+            gapcol: Vec::new(),
+            cross: Vec::new(),
+            gapcross: Vec::new(),
+        }
+    }
+
+    // Forward pass over the box that returns the crossing of the optimal path with the column
+    // `mid`. Scores of unreachable states (including cells skipped by the scan) are kept at
+    // S::min_value() via saturating arithmetic.
+    #[allow(clippy::too_many_arguments)]
+    fn crossing<Smb, Scheme, Seq1, Seq2>(
+        &mut self,
+        seq1: &Seq1,
+        seq2: &Seq2,
+        scoring: &Scheme,
+        rows: &Range<usize>,
+        cols: &Range<usize>,
+        mid: usize,
+        entry: Entry,
+        exit: Exit,
+    ) -> Cross
+    where
+        Scheme: scoring::Scheme<Symbol = Smb, Score = S>,
+        Seq1: Alignable<Symbol = Smb>,
+        Seq2: Alignable<Symbol = Smb>,
+    {
+        let none = S::min_value();
+        let (row_gap_first, col_gap_first) = match entry {
+            Entry::Start { row_gap, col_gap } => (row_gap, col_gap),
+            Entry::GapCol => (true, true),
+        };
+        let (n, m) = (rows.len(), cols.len());
+        let windows = &self.starts.windows;
+
+        // The first column: only row gaps from the entry point
+        self.scores.clear();
+        self.scores.resize(n + 1, none);
+        self.gapcol.clear();
+        self.gapcol.resize(n + 1, none);
+        self.cross.clear();
+        self.cross.resize(n + 1, Cross::Equivalent(0));
+        self.gapcross.clear();
+        self.gapcross.resize(n + 1, Cross::Equivalent(0));
+
+        self.scores[0] = S::zero();
+        if entry == Entry::GapCol {
+            self.gapcol[0] = S::zero();
+        }
+        let mut gaprow = none;
+        for i in 1..=n {
+            let row = rows.start + i - 1;
+            if cols.start > 0 && !windows[cols.start - 1].contains(&row) {
+                gaprow = none;
+                self.scores[i] = none;
+                continue;
+            }
+            let opened = if i == 1 && !row_gap_first {
+                none
+            } else {
+                self.scores[i - 1].saturating_add(scoring.seq1_gap_open(row))
+            };
+            let extended = gaprow.saturating_add(scoring.seq1_gap_extend(row));
+            gaprow = if opened > extended { opened } else { extended };
+            self.scores[i] = gaprow;
+        }
+
+        for j in 1..=m {
+            let col = cols.start + j - 1;
+            let s2 = seq2.at(col);
+            let crossing = j == mid + 1;
+
+            // The first row: only column gaps
+            let (mut diagonal, mut diagcross) = (self.scores[0], self.cross[0]);
+            let opened = if j == 1 && !col_gap_first {
+                none
+            } else {
+                self.scores[0].saturating_add(scoring.seq2_gap_open(col))
+            };
+            let extended = self.gapcol[0].saturating_add(scoring.seq2_gap_extend(col));
+            if opened > extended {
+                self.gapcol[0] = opened;
+                self.gapcross[0] = if crossing {
+                    Cross::GapOpen(0)
+                } else {
+                    self.cross[0]
+                };
+            } else {
+                self.gapcol[0] = extended;
+                if crossing {
+                    self.gapcross[0] = Cross::GapExtend(0);
+                }
+            }
+            if rows.start > 0 && !windows[col].contains(&(rows.start - 1)) {
+                self.gapcol[0] = none;
+            }
+            self.scores[0] = self.gapcol[0];
+            self.cross[0] = self.gapcross[0];
+
+            let (mut gaprow, mut gaprowcross) = (none, self.cross[0]);
+            for i in 1..=n {
+                let row = rows.start + i - 1;
+                let (left, leftcross) = (self.scores[i], self.cross[i]);
+                if !windows[col].contains(&row) {
+                    gaprow = none;
+                    self.gapcol[i] = none;
+                    self.scores[i] = none;
+                    (diagonal, diagcross) = (left, leftcross);
+                    continue;
+                }
+
+                // Vertical/row gaps
+                let opened = self.scores[i - 1].saturating_add(scoring.seq1_gap_open(row));
+                let extended = gaprow.saturating_add(scoring.seq1_gap_extend(row));
+                if opened > extended {
+                    gaprow = opened;
+                    gaprowcross = self.cross[i - 1];
+                } else {
+                    gaprow = extended;
+                }
+
+                // Horizontal/column gaps
+                let opened = left.saturating_add(scoring.seq2_gap_open(col));
+                let extended = self.gapcol[i].saturating_add(scoring.seq2_gap_extend(col));
+                if opened > extended {
+                    self.gapcol[i] = opened;
+                    self.gapcross[i] = if crossing {
+                        Cross::GapOpen(i)
+                    } else {
+                        leftcross
+                    };
+                } else {
+                    self.gapcol[i] = extended;
+                    if crossing {
+                        self.gapcross[i] = Cross::GapExtend(i);
+                    }
+                }
+
+                // Best scores
+                let equiv = diagonal.saturating_add(scoring.score(row, seq1.at(row), col, s2));
+                if crossing {
+                    diagcross = Cross::Equivalent(i - 1);
+                }
+                (self.scores[i], self.cross[i]) = if equiv > gaprow && equiv > self.gapcol[i] {
+                    (equiv, diagcross)
+                } else if self.gapcol[i] > gaprow {
+                    (self.gapcol[i], self.gapcross[i])
+                } else {
+                    (gaprow, gaprowcross)
+                };
+
+                (diagonal, diagcross) = (left, leftcross);
+            }
+        }
+
+        match exit {
+            Exit::Any => self.cross[n],
+            Exit::GapCol => self.gapcross[n],
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn solve<Smb, Scheme, Seq1, Seq2>(
+        &mut self,
+        seq1: &Seq1,
+        seq2: &Seq2,
+        scoring: &Scheme,
+        rows: Range<usize>,
+        cols: Range<usize>,
+        entry: Entry,
+        exit: Exit,
+        saveto: &mut Vec<RunningTrace>,
+    ) -> Result<()>
+    where
+        Scheme: scoring::Scheme<Symbol = Smb, Score = S>,
+        Seq1: Alignable<Symbol = Smb>,
+        Seq2: Alignable<Symbol = Smb>,
+    {
+        let (row_gap_first, col_gap_first) = match entry {
+            Entry::Start { row_gap, col_gap } => (row_gap, col_gap),
+            Entry::GapCol => (true, true),
+        };
+
+        // Trivial boxes: straight gaps or nothing at all
+        if cols.is_empty() {
+            ensure!(
+                (rows.is_empty() || row_gap_first)
+                    && (exit == Exit::Any || (rows.is_empty() && entry == Entry::GapCol)),
+                "Invalid traceback box"
+            );
+            push(saveto, Op::GapFirst, rows.len());
+            return Ok(());
+        }
+        if rows.is_empty() {
+            ensure!(col_gap_first, "Invalid traceback box");
+            push(saveto, Op::GapSecond, cols.len());
+            return Ok(());
+        }
+
+        let mid = cols.len() / 2;
+        let (start, middle, end) =
+            match self.crossing(seq1, seq2, scoring, &rows, &cols, mid, entry, exit) {
+                Cross::Equivalent(row) => (
+                    (rows.start + row, Exit::Any),
+                    Op::Equivalent,
+                    (
+                        rows.start + row + 1,
+                        Entry::Start {
+                            row_gap: true,
+                            col_gap: true,
+                        },
+                    ),
+                ),
+                Cross::GapOpen(row) => (
+                    (rows.start + row, Exit::Any),
+                    Op::GapSecond,
+                    (rows.start + row, Entry::GapCol),
+                ),
+                Cross::GapExtend(row) => (
+                    (rows.start + row, Exit::GapCol),
+                    Op::GapSecond,
+                    (rows.start + row, Entry::GapCol),
+                ),
+            };
+        let mid = cols.start + mid;
+
+        self.solve(
+            seq1,
+            seq2,
+            scoring,
+            rows.start..start.0,
+            cols.start..mid,
+            entry,
+            start.1,
+            saveto,
+        )?;
+        push(saveto, middle, 1);
+        self.solve(
+            seq1,
+            seq2,
+            scoring,
+            end.0..rows.end,
+            mid + 1..cols.end,
+            end.1,
+            exit,
+            saveto,
+        )
+    }
+}
+
+fn push(saveto: &mut Vec<RunningTrace>, op: Op, len: usize) {
+    if len == 0 {
+        return;
+    }
+    match saveto.last_mut() {
+        Some(last) if last.op == op => last.len += len,
+        _ => saveto.push(RunningTrace::new(op, len)),
+    }
+}
+
+impl<S: scoring::Score> Default for LinearSpace<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: scoring::Score> Tracer for LinearSpace<S> {
+    type Score = S;
+}
+
+impl<S: scoring::Score> BestOrientationTracer for LinearSpace<S> {
+    type Score = S;
+}
+
+impl<S: scoring::Score> GapTracer for LinearSpace<S> {
+    type Score = S;
+}
+
+impl<S: scoring::Score> TraceMat for LinearSpace<S> {
+    fn reset(&mut self, _: usize, _: usize) {}
+
+    fn trace<Smb, Scheme, Seq1, Seq2>(
+        &mut self,
+        row: usize,
+        col: usize,
+        seq1: &Seq1,
+        seq2: &Seq2,
+        scoring: &mut Scheme,
+        scan: &Scan<S>,
+    ) -> Result<TracedAlignment>
+    where
+        Scheme: scoring::Scheme<Symbol = Smb, Score = S>,
+        Seq1: Alignable<Symbol = Smb>,
+        Seq2: Alignable<Symbol = Smb>,
+    {
+        let (seq1end, seq2end) = (row + 1, col + 1);
+        if seq1end > seq1.len() || seq2end > seq2.len() {
+            return Err(eyre!("Out of bounds"));
+        }
+
+        // Locate the start of the alignment. Rows below the traced cell don't affect it, except for
+        // the X-drop scan, where they might move the X-drop threshold.
+        let prefix1 = Prefix {
+            base: seq1,
+            len: seq1end,
+        };
+        let prefix2 = Prefix {
+            base: seq2,
+            len: seq2end,
+        };
+        match scan {
+            Scan::Local(band) => {
+                self.starts.reset(seq1end, seq2end, (0, 0));
+                match band {
+                    None => self
+                        .algo
+                        .scan_all(&prefix1, &prefix2, scoring, &mut self.starts),
+                    Some(band) => {
+                        self.algo.scan_band(
+                            &prefix1,
+                            &prefix2,
+                            band,
+                            None,
+                            scoring,
+                            &mut self.starts,
+                        );
+                    }
+                }
+            }
+            Scan::Global(ends, band) => {
+                self.starts.reset(seq1end, seq2end, (0, 0));
+                match band {
+                    None => {
+                        self.algo
+                            .scan_global(&prefix1, &prefix2, ends, scoring, &mut self.starts)
+                    }
+                    Some(band) => self.algo.scan_band(
+                        &prefix1,
+                        &prefix2,
+                        band,
+                        Some(ends),
+                        scoring,
+                        &mut self.starts,
+                    ),
+                };
+            }
+            Scan::XDrop { from, xdrop } => {
+                self.starts.reset(seq1.len(), seq2end, *from);
+                self.algo
+                    .extend(seq1, &prefix2, *from, *xdrop, scoring, &mut self.starts);
+            }
+        };
+        let (start1, start2) = self.starts.prev[row].ok_or_else(|| eyre!("Invalid seed"))?;
+        let origin = self.starts.origin;
+        let entry = match scan {
+            // Local alignments always start with a diagonal step
+            Scan::Local(_) => Entry::Start {
+                row_gap: false,
+                col_gap: false,
+            },
+            // Gaps along the boundary are leading end gaps, not a part of the traced path
+            Scan::Global(..) | Scan::XDrop { .. } => Entry::Start {
+                row_gap: start2 != origin.1,
+                col_gap: start1 != origin.0,
+            },
+        };
+
+        // Recover the path
+        let mut trace = Vec::new();
+        self.solve(
+            seq1,
+            seq2,
+            scoring,
+            start1..seq1end,
+            start2..seq2end,
+            entry,
+            Exit::Any,
+            &mut trace,
+        )?;
+
+        let mut ops: Vec<Step<u8>> = Vec::with_capacity(trace.len());
+        for x in trace {
+            x.save(&mut ops);
+        }
+
+        Ok(TracedAlignment {
+            ops,
+            seq1: start1..seq1end,
+            seq2: start2..seq2end,
+        })
+    }
+}
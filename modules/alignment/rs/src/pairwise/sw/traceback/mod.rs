@@ -1,11 +1,14 @@
 use std::ops::Range;
 
+pub use linear::LinearSpace;
 pub use tracemat::TraceMatrix;
 
-use crate::pairwise::Step;
-use crate::pairwise::sw::algo::Tracer;
+use crate::Alignable;
+use crate::pairwise::sw::algo::{Scan, Tracer};
+use crate::pairwise::{Op, Step, scoring};
 use eyre::Result;
 
+mod linear;
 mod tracemat;
 
 pub struct TracedAlignment {
@@ -16,7 +19,43 @@ pub struct TracedAlignment {
 
 pub trait TraceMat: Tracer {
     fn reset(&mut self, newrows: usize, newcols: usize);
-    fn trace(&self, row: usize, col: usize) -> Result<TracedAlignment>;
+
+    // Sequences, scoring and the scan are passed for implementations that don't store
+    // the whole matrix and recompute the required parts of it on demand
+    fn trace<Smb, Scheme, Seq1, Seq2>(
+        &mut self,
+        row: usize,
+        col: usize,
+        seq1: &Seq1,
+        seq2: &Seq2,
+        scoring: &mut Scheme,
+        scan: &Scan<<Self as Tracer>::Score>,
+    ) -> Result<TracedAlignment>
+    where
+        Scheme: scoring::Scheme<Symbol = Smb, Score = <Self as Tracer>::Score>,
+        Seq1: Alignable<Symbol = Smb>,
+        Seq2: Alignable<Symbol = Smb>;
+}
+
+struct RunningTrace {
+    pub op: Op,
+    pub len: usize,
+}
+
+impl RunningTrace {
+    pub fn new(op: Op, len: usize) -> Self {
+        Self { op, len }
+    }
+
+    pub fn save(self, saveto: &mut Vec<Step<u8>>) {
+        let tail = self.len % (u8::MAX as usize);
+        if tail > 0 {
+            saveto.push(Step::new(self.op, tail as u8).unwrap());
+        }
+        for _ in 0..(self.len / (u8::MAX as usize)) {
+            saveto.push(Step::new(self.op, u8::MAX).unwrap());
+        }
+    }
 }
 
 // #[cfg(test)]
@@ -3,9 +3,10 @@ use std::ops::Range;
 
 use eyre::{Result, eyre};
 
-use crate::pairwise::sw::algo::{BestOrientationTracer, GapTracer, Tracer};
-use crate::pairwise::sw::traceback::{TraceMat, TracedAlignment};
-use crate::pairwise::{Op, Step, scoring};
+use crate::Alignable;
+use crate::pairwise::sw::algo::{BestOrientationTracer, GapTracer, Scan, Tracer};
+use crate::pairwise::sw::traceback::{RunningTrace, TraceMat, TracedAlignment};
+use crate::pairwise::{Op, scoring};
 
 // TODO: implement to use only 2 bits
 #[repr(u8)]
@@ -37,31 +38,17 @@ impl TryFrom<Trace> for Op {
     }
 }
 
-struct RunningTrace {
-    pub op: Op,
-    pub len: usize,
-}
-
-impl RunningTrace {
-    pub fn new(op: Op, len: usize) -> Self {
-        Self { op, len }
-    }
-
-    pub fn save(self, saveto: &mut Vec<Step<u8>>) {
-        let tail = self.len % (u8::MAX as usize);
-        if tail > 0 {
-            saveto.push(Step::new(self.op, tail as u8).unwrap());
-        }
-        for _ in 0..(self.len / (u8::MAX as usize)) {
-            saveto.push(Step::new(self.op, u8::MAX).unwrap());
-        }
-    }
-}
-
+/// Traceback from the recorded scan decisions, 3 bytes per computed cell.
+///
+/// Cells are stored column-by-column and only for the computed part of the matrix, i.e. banded
+/// scans and X-drop extensions don't allocate the whole `rows * cols` matrix. Computed rows must
+/// be contiguous in each column, which holds for all scans in [`algo`](crate::pairwise::sw::algo).
 pub struct TraceMatrix<S: scoring::Score> {
     best: Vec<Trace>,
     row_gap: Vec<GapTrace>,
     col_gap: Vec<GapTrace>,
+    // First computed row of each column and the offset of its first cell in the traces
+    columns: Vec<(usize, usize)>,
     rows: usize,
     cols: usize,
     phantom: PhantomData<S>,
@@ -73,11 +60,70 @@ impl<S: scoring::Score> TraceMatrix<S> {
             best: Vec::new(),
             row_gap: Vec::new(),
             col_gap: Vec::new(),
+            columns: Vec::new(),
             rows: 0,
             cols: 0,
             phantom: Default::default(),
         }
     }
+
+    // Index of the computed cell, new cells are appended to the last column
+    #[inline(always)]
+    fn slot(&mut self, row: usize, col: usize) -> usize {
+        if col + 1 != self.columns.len() {
+            self.start_column(row, col);
+        }
+        let (first, offset) = self.columns[col];
+        let index = offset + row - first;
+        if index == self.best.len() {
+            self.best.push(Trace::Start);
+            self.row_gap.push(GapTrace::Open);
+            self.col_gap.push(GapTrace::Open);
+        }
+        debug_assert!(
+            index < self.best.len(),
+            "Rows must be contiguous in each column"
+        );
+        index
+    }
+
+    #[cold]
+    fn start_column(&mut self, row: usize, col: usize) {
+        debug_assert!(
+            col >= self.columns.len(),
+            "Columns must be computed in order"
+        );
+        // Skipped columns are empty
+        self.columns.resize(col, (0, self.best.len()));
+        self.columns.push((row, self.best.len()));
+    }
+
+    // Index of the cell (row, col) if it was computed, the matrix boundary is never computed
+    #[inline(always)]
+    fn index(&self, row: usize, col: usize) -> Option<usize> {
+        let (row, col) = (row.checked_sub(1)?, col.checked_sub(1)?);
+        let (first, offset) = *self.columns.get(col)?;
+        let end = self.columns.get(col + 1).map_or(self.best.len(), |x| x.1);
+        let index = offset + row.checked_sub(first)?;
+        (index < end).then_some(index)
+    }
+
+    #[inline(always)]
+    fn best_at(&self, row: usize, col: usize) -> Trace {
+        self.index(row, col).map_or(Trace::Start, |x| self.best[x])
+    }
+
+    #[inline(always)]
+    fn row_gap_at(&self, row: usize, col: usize) -> GapTrace {
+        self.index(row, col)
+            .map_or(GapTrace::Open, |x| self.row_gap[x])
+    }
+
+    #[inline(always)]
+    fn col_gap_at(&self, row: usize, col: usize) -> GapTrace {
+        self.index(row, col)
+            .map_or(GapTrace::Open, |x| self.col_gap[x])
+    }
 }
 
 impl<S: scoring::Score> Default for TraceMatrix<S> {
@@ -95,22 +141,26 @@ impl<S: scoring::Score> BestOrientationTracer for TraceMatrix<S> {
 
     #[inline(always)]
     fn gap_row(&mut self, row: usize, col: usize, _: Self::Score) {
-        self.best[(row + 1) * self.cols + (col + 1)] = Trace::GapRow;
+        let index = self.slot(row, col);
+        self.best[index] = Trace::GapRow;
     }
 
     #[inline(always)]
     fn gap_col(&mut self, row: usize, col: usize, _: Self::Score) {
-        self.best[(row + 1) * self.cols + (col + 1)] = Trace::GapCol;
+        let index = self.slot(row, col);
+        self.best[index] = Trace::GapCol;
     }
 
     #[inline(always)]
     fn equivalent(&mut self, row: usize, col: usize, _: Self::Score) {
-        self.best[(row + 1) * self.cols + (col + 1)] = Trace::Equivalent;
+        let index = self.slot(row, col);
+        self.best[index] = Trace::Equivalent;
     }
 
     #[inline(always)]
     fn none(&mut self, row: usize, col: usize) {
-        self.best[(row + 1) * self.cols + (col + 1)] = Trace::Start;
+        let index = self.slot(row, col);
+        self.best[index] = Trace::Start;
     }
 }
 
@@ -119,22 +169,26 @@ impl<S: scoring::Score> GapTracer for TraceMatrix<S> {
 
     #[inline(always)]
     fn row_gap_open(&mut self, row: usize, col: usize, _: Self::Score) {
-        self.row_gap[(row + 1) * self.cols + (col + 1)] = GapTrace::Open;
+        let index = self.slot(row, col);
+        self.row_gap[index] = GapTrace::Open;
     }
 
     #[inline(always)]
     fn row_gap_extend(&mut self, row: usize, col: usize, _: Self::Score) {
-        self.row_gap[(row + 1) * self.cols + (col + 1)] = GapTrace::Extend;
+        let index = self.slot(row, col);
+        self.row_gap[index] = GapTrace::Extend;
     }
 
     #[inline(always)]
     fn col_gap_open(&mut self, row: usize, col: usize, _: Self::Score) {
-        self.col_gap[(row + 1) * self.cols + (col + 1)] = GapTrace::Open;
+        let index = self.slot(row, col);
+        self.col_gap[index] = GapTrace::Open;
     }
 
     #[inline(always)]
     fn col_gap_extend(&mut self, row: usize, col: usize, _: Self::Score) {
-        self.col_gap[(row + 1) * self.cols + (col + 1)] = GapTrace::Extend;
+        let index = self.slot(row, col);
+        self.col_gap[index] = GapTrace::Extend;
     }
 }
 
@@ -144,23 +198,32 @@ impl<S: scoring::Score> TraceMat for TraceMatrix<S> {
         self.cols = cols + 1;
 
         self.best.clear();
-        self.best.resize(self.rows * self.cols, Trace::Start);
-
         self.row_gap.clear();
-        self.row_gap.resize(self.rows * self.cols, GapTrace::Open);
-
         self.col_gap.clear();
-        self.col_gap.resize(self.rows * self.cols, GapTrace::Open);
+        self.columns.clear();
     }
 
-    fn trace(&self, row: usize, col: usize) -> Result<TracedAlignment> {
+    fn trace<Smb, Scheme, Seq1, Seq2>(
+        &mut self,
+        row: usize,
+        col: usize,
+        _: &Seq1,
+        _: &Seq2,
+        _: &mut Scheme,
+        _: &Scan<S>,
+    ) -> Result<TracedAlignment>
+    where
+        Scheme: scoring::Scheme<Symbol = Smb, Score = S>,
+        Seq1: Alignable<Symbol = Smb>,
+        Seq2: Alignable<Symbol = Smb>,
+    {
         let (seq1end, seq2end) = (row + 1, col + 1);
         if seq1end >= self.rows || seq2end >= self.cols {
             return Err(eyre!("Out of bounds"));
         }
 
         let (mut row, mut col) = (seq1end, seq2end);
-        let seed = match self.best[row * self.cols + col].try_into() {
+        let seed = match self.best_at(row, col).try_into() {
             Err(()) => return Err(eyre!("Invalid seed")),
             Ok(op) => op,
         };
@@ -169,7 +232,7 @@ impl<S: scoring::Score> TraceMat for TraceMatrix<S> {
         let mut trace = RunningTrace::new(seed, 0);
 
         loop {
-            let op = self.best[row * self.cols + col];
+            let op = self.best_at(row, col);
             let aop = match op.try_into() {
                 Err(()) => {
                     trace.save(&mut result);
@@ -191,14 +254,14 @@ impl<S: scoring::Score> TraceMat for TraceMatrix<S> {
                     break;
                 }
                 Trace::GapRow => {
-                    while self.row_gap[row * self.cols + col] != GapTrace::Open {
+                    while self.row_gap_at(row, col) != GapTrace::Open {
                         trace.len += 1;
                         row -= 1;
                     }
                     row -= 1;
                 }
                 Trace::GapCol => {
-                    while self.col_gap[row * self.cols + col] != GapTrace::Open {
+                    while self.col_gap_at(row, col) != GapTrace::Open {
                         trace.len += 1;
                         col -= 1;
                     }
@@ -0,0 +1,78 @@
+// Wavefronts of the gap-affine WFA: https://doi.org/10.1093/bioinformatics/btaa777
+
+// Cells are addressed by the diagonal k = col - row and store the furthest reaching offset (col)
+// on the diagonal. Each score has three components: M (any operation), I (ends with a seq2 gap
+// column) and D (ends with a seq1 gap column).
+
+// Offset of unreachable cells, large enough to stay negative after any number of increments
+pub const NONE: isize = isize::MIN / 2;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Component {
+    M,
+    I,
+    D,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Front {
+    pub lo: isize,
+    pub hi: isize,
+    pub m: Vec<isize>,
+    pub i: Vec<isize>,
+    pub d: Vec<isize>,
+}
+
+impl Front {
+    pub fn new(lo: isize, hi: isize) -> Self {
+        let len = (hi - lo + 1) as usize;
+        Self {
+            lo,
+            hi,
+            m: vec![NONE; len],
+            i: vec![NONE; len],
+            d: vec![NONE; len],
+        }
+    }
+
+    #[inline(always)]
+    pub fn get(&self, component: Component, k: isize) -> isize {
+        if k < self.lo || k > self.hi {
+            return NONE;
+        }
+        let ind = (k - self.lo) as usize;
+        match component {
+            Component::M => self.m[ind],
+            Component::I => self.i[ind],
+            Component::D => self.d[ind],
+        }
+    }
+}
+
+/// Wavefronts of all scores processed so far, unreachable scores have no wavefront
+#[derive(Clone, Debug, Default)]
+pub struct Fronts {
+    fronts: Vec<Option<Front>>,
+}
+
+impl Fronts {
+    pub fn clear(&mut self) {
+        self.fronts.clear();
+    }
+
+    pub fn push(&mut self, front: Option<Front>) {
+        self.fronts.push(front);
+    }
+
+    pub fn front(&self, score: isize) -> Option<&Front> {
+        if score < 0 {
+            return None;
+        }
+        self.fronts.get(score as usize).and_then(|x| x.as_ref())
+    }
+
+    #[inline(always)]
+    pub fn get(&self, score: isize, component: Component, k: isize) -> isize {
+        self.front(score).map_or(NONE, |x| x.get(component, k))
+    }
+}
@@ -0,0 +1,262 @@
+use std::marker::PhantomData;
+
+use eyre::{Result, ensure};
+
+use crate::Alignable;
+use crate::pairwise::alignment::Alignment;
+use crate::pairwise::scoring::{Score, gaps};
+use crate::pairwise::{Op, Step};
+
+use fronts::{Component, Front, Fronts, NONE};
+
+mod fronts;
+
+/// Global gap-affine alignment with the wavefront algorithm (WFA, Marco-Sola et al. 2021).
+///
+/// Time and memory scale with the alignment penalty rather than the sequence lengths, which makes
+/// it much faster than the [`Engine`](crate::pairwise::sw::Engine) for long and similar sequences
+/// (haplotypes, transcript isoforms, repeat copies). For divergent sequences it degrades to
+/// O(penalty^2) memory, use the [`Engine`](crate::pairwise::sw::Engine) instead.
+///
+/// Identical symbols score 0, different symbols score `mismatch` and gaps follow the
+/// [`gaps::Affine`] scoring, i.e. the result is the same as for the global
+/// [`Engine`](crate::pairwise::sw::Engine) with `Equality::new(0, mismatch)` symbol scores. All
+/// penalties must be negative. Both sequences are aligned end-to-end.
+pub struct Wavefront<S: Score> {
+    mismatch: usize,
+    // Penalty of a gap with length L is open + L * extend
+    open: usize,
+    extend: usize,
+    fronts: Fronts,
+    rows: isize,
+    cols: isize,
+    _phantom: PhantomData<S>,
+}
+
+impl<S: Score> Wavefront<S> {
+    pub fn new(mismatch: S, gaps: &gaps::Affine<S>) -> Result<Self> {
+        ensure!(
+            mismatch < S::zero() && gaps.extend < S::zero() && gaps.open <= gaps.extend,
+            "Mismatch and gap extension scores must be negative and the gap opening score must not \
+             exceed the gap extension one"
+        );
+        Ok(Self {
+            mismatch: (S::zero() - mismatch).to_usize().unwrap(),
+            open: (gaps.extend - gaps.open).to_usize().unwrap(),
+            extend: (S::zero() - gaps.extend).to_usize().unwrap(),
+            fronts: Fronts::default(),
+            rows: 0,
+            cols: 0,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Score of the optimal global alignment.
+    pub fn score<S1, S2, Smb>(&mut self, seq1: &S1, seq2: &S2) -> S
+    where
+        S1: Alignable<Symbol = Smb>,
+        S2: Alignable<Symbol = Smb>,
+        Smb: PartialEq,
+    {
+        Self::_score(self.run(seq1, seq2))
+    }
+
+    /// Optimal global alignment of the whole sequences.
+    pub fn align<S1, S2, Smb>(&mut self, seq1: &S1, seq2: &S2) -> Alignment<S, u8, usize, usize>
+    where
+        S1: Alignable<Symbol = Smb>,
+        S2: Alignable<Symbol = Smb>,
+        Smb: PartialEq,
+    {
+        let penalty = self.run(seq1, seq2);
+        let steps = self.traceback(penalty);
+        Alignment::new(Self::_score(penalty), steps, 0..seq1.len(), 0..seq2.len())
+    }
+
+    fn _score(penalty: usize) -> S {
+        S::zero() - S::from(penalty).unwrap()
+    }
+
+    // Compute wavefronts with increasing penalty until the end cell is reached
+    fn run<S1, S2, Smb>(&mut self, seq1: &S1, seq2: &S2) -> usize
+    where
+        S1: Alignable<Symbol = Smb>,
+        S2: Alignable<Symbol = Smb>,
+        Smb: PartialEq,
+    {
+        self.fronts.clear();
+        (self.rows, self.cols) = (seq1.len() as isize, seq2.len() as isize);
+        let target = self.cols - self.rows;
+
+        let mut initial = Front::new(0, 0);
+        initial.m[0] = Self::_extend(seq1, seq2, 0, 0);
+        self.fronts.push(Some(initial));
+
+        let mut penalty = 0;
+        while self.fronts.get(penalty as isize, Component::M, target) != self.cols {
+            penalty += 1;
+            let front = self.next(penalty as isize).map(|mut front| {
+                for (ind, offset) in front.m.iter_mut().enumerate() {
+                    if *offset >= 0 {
+                        *offset = Self::_extend(seq1, seq2, front.lo + ind as isize, *offset);
+                    }
+                }
+                front
+            });
+            self.fronts.push(front);
+        }
+        penalty
+    }
+
+    // Wavefront of the given penalty before extending the matches
+    fn next(&self, penalty: isize) -> Option<Front> {
+        let (x, o, e) = self.penalties();
+
+        let (mut lo, mut hi) = (isize::MAX, isize::MIN);
+        for (score, shift) in [
+            (penalty - x, 0),
+            (penalty - o - e, 1),
+            (penalty - o - e, -1),
+            (penalty - e, 1),
+            (penalty - e, -1),
+        ] {
+            if let Some(front) = self.fronts.front(score) {
+                lo = lo.min(front.lo + shift);
+                hi = hi.max(front.hi + shift);
+            }
+        }
+        let (lo, hi) = (lo.max(-self.rows), hi.min(self.cols));
+        if lo > hi {
+            return None;
+        }
+
+        let mut front = Front::new(lo, hi);
+        for k in lo..=hi {
+            let ind = (k - lo) as usize;
+            let (mismatch, insertion, deletion) = self.sources(penalty, k);
+            front.i[ind] = insertion;
+            front.d[ind] = deletion;
+            front.m[ind] = mismatch.max(insertion).max(deletion);
+        }
+        Some(front)
+    }
+
+    // Offsets on the diagonal reached by a mismatch, a seq2 gap (I) or a seq1 gap (D)
+    fn sources(&self, penalty: isize, k: isize) -> (isize, isize, isize) {
+        let (x, o, e) = self.penalties();
+        let fronts = &self.fronts;
+
+        let mismatch = fronts.get(penalty - x, Component::M, k) + 1;
+        let insertion = fronts
+            .get(penalty - o - e, Component::M, k - 1)
+            .max(fronts.get(penalty - e, Component::I, k - 1))
+            + 1;
+        let deletion = fronts
+            .get(penalty - o - e, Component::M, k + 1)
+            .max(fronts.get(penalty - e, Component::D, k + 1));
+        (
+            self.bounded(k, mismatch),
+            self.bounded(k, insertion),
+            self.bounded(k, deletion),
+        )
+    }
+
+    fn penalties(&self) -> (isize, isize, isize) {
+        (
+            self.mismatch as isize,
+            self.open as isize,
+            self.extend as isize,
+        )
+    }
+
+    fn bounded(&self, k: isize, offset: isize) -> isize {
+        if offset < 0 || offset > self.cols || offset - k < 0 || offset - k > self.rows {
+            NONE
+        } else {
+            offset
+        }
+    }
+
+    // Follow the matching symbols along the diagonal
+    fn _extend<S1, S2, Smb>(seq1: &S1, seq2: &S2, k: isize, mut offset: isize) -> isize
+    where
+        S1: Alignable<Symbol = Smb>,
+        S2: Alignable<Symbol = Smb>,
+        Smb: PartialEq,
+    {
+        let (rows, cols) = (seq1.len() as isize, seq2.len() as isize);
+        while offset < cols
+            && offset - k < rows
+            && seq1.at((offset - k) as usize) == seq2.at(offset as usize)
+        {
+            offset += 1;
+        }
+        offset
+    }
+
+    fn traceback(&self, penalty: usize) -> Vec<Step<u8>> {
+        let (x, o, e) = self.penalties();
+
+        let mut ops = Vec::new();
+        let (mut penalty, mut k) = (penalty as isize, self.cols - self.rows);
+        let mut offset = self.cols;
+        let mut component = Component::M;
+        loop {
+            match component {
+                Component::M => {
+                    if penalty == 0 {
+                        ops.extend(std::iter::repeat_n(Op::Match, offset as usize));
+                        offset = 0;
+                        break;
+                    }
+                    let (mismatch, insertion, deletion) = self.sources(penalty, k);
+                    let start = mismatch.max(insertion).max(deletion);
+                    ops.extend(std::iter::repeat_n(Op::Match, (offset - start) as usize));
+                    offset = start;
+
+                    if offset == insertion {
+                        component = Component::I;
+                    } else if offset == deletion {
+                        component = Component::D;
+                    } else {
+                        ops.push(Op::Mismatch);
+                        penalty -= x;
+                        offset -= 1;
+                    }
+                }
+                Component::I => {
+                    ops.push(Op::GapSecond);
+                    k -= 1;
+                    offset -= 1;
+                    if self.fronts.get(penalty - o - e, Component::M, k) == offset {
+                        penalty -= o + e;
+                        component = Component::M;
+                    } else {
+                        debug_assert_eq!(self.fronts.get(penalty - e, Component::I, k), offset);
+                        penalty -= e;
+                    }
+                }
+                Component::D => {
+                    ops.push(Op::GapFirst);
+                    k += 1;
+                    if self.fronts.get(penalty - o - e, Component::M, k) == offset {
+                        penalty -= o + e;
+                        component = Component::M;
+                    } else {
+                        debug_assert_eq!(self.fronts.get(penalty - e, Component::D, k), offset);
+                        penalty -= e;
+                    }
+                }
+            }
+        }
+        debug_assert_eq!((k, offset), (0, 0));
+
+        let mut steps = ops
+            .into_iter()
+            .rev()
+            .map(|op| Step::new(op, 1).unwrap())
+            .collect::<Vec<_>>();
+        Step::collapse(&mut steps);
+        steps
+    }
+}
@@ -0,0 +1,29 @@
+// Deterministic pseudo-random numbers for tests and benchmarks (xorshift64 seeded with a 64-bit
+// LCG step). Unit tests, integration tests and benchmarks include this file, none of them needs
+// every method.
+#![allow(dead_code)]
+
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407),
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    pub fn symbol(&mut self) -> u8 {
+        b"ACGT"[(self.next_u64() % 4) as usize]
+    }
+}
@@ -0,0 +1,209 @@
+use biobit_alignment_rs::pairwise::{alignment, sw};
+
+use super::common::{self, Engine, Score, scheme};
+use super::random::Random;
+
+fn engine() -> Engine {
+    common::engine(scheme(1, -2, -5, -1))
+}
+
+// Cells (row, col) visited by the alignment
+fn cells(result: &alignment::Alignment<Score, u8, usize, usize>) -> Vec<(usize, usize)> {
+    let (mut row, mut col) = (result.seq1().start, result.seq2().start);
+    let mut cells = Vec::new();
+    for step in result.steps() {
+        for _ in 0..*step.len() {
+            match step.op() {
+                alignment::Op::GapFirst => row += 1,
+                alignment::Op::GapSecond => col += 1,
+                _ => {
+                    row += 1;
+                    col += 1;
+                }
+            }
+            cells.push((row - 1, col - 1));
+        }
+    }
+    assert_eq!(row, result.seq1().end);
+    assert_eq!(col, result.seq2().end);
+    cells
+}
+
+fn ensure_inside(result: &alignment::Alignment<Score, u8, usize, usize>, band: &sw::algo::Band) {
+    for (row, col) in cells(result) {
+        assert!(band.contains(row, col));
+    }
+}
+
+#[test]
+fn test_band_rows() {
+    let band = sw::algo::Band::around(2, 1);
+    assert_eq!(band, sw::algo::Band::new(1, 3));
+    assert_eq!(band.rows(0, 10), 0..0);
+    assert_eq!(band.rows(1, 10), 0..1);
+    assert_eq!(band.rows(3, 10), 0..3);
+    assert_eq!(band.rows(7, 10), 4..7);
+    assert_eq!(band.rows(12, 10), 9..10);
+    assert_eq!(band.rows(20, 10), 10..10);
+
+    assert!(band.contains(0, 1));
+    assert!(band.contains(4, 7));
+    assert!(!band.contains(4, 4));
+    assert!(!band.contains(0, 4));
+}
+
+#[test]
+fn test_band_covers_optimum() {
+    let (seq1, seq2) = (b"GGACGTGGACGTCC".as_slice(), b"TTACGTACGTAA".as_slice());
+    let mut engine = engine();
+
+    for mode in [
+        sw::Mode::Local,
+        sw::Mode::Global(sw::algo::EndGaps::penalized()),
+        sw::Mode::Global(sw::algo::EndGaps::free()),
+    ] {
+        engine.with_mode(mode);
+        engine.with_band(None);
+        let expected = engine.scan_all(&seq1, &seq2);
+        assert_eq!(expected.len(), 1);
+
+        // The narrowest band around the optimal path
+        let diagonals = cells(&expected[0])
+            .into_iter()
+            .map(|(row, col)| col as isize - row as isize);
+        let narrow =
+            sw::algo::Band::new(diagonals.clone().min().unwrap(), diagonals.max().unwrap());
+
+        for band in [
+            sw::algo::Band::new(-14, 12),
+            sw::algo::Band::new(-100, 100),
+            narrow,
+        ] {
+            engine.with_band(Some(band));
+            let result = engine.scan_all(&seq1, &seq2);
+            assert_eq!(result, expected, "{mode:?} {band:?}");
+            ensure_inside(&result[0], &band);
+        }
+    }
+}
+
+#[test]
+fn test_local_band() {
+    let (seq1, seq2) = (b"ACGTACGTAC".as_slice(), b"GGGGGACGTACGTAC".as_slice());
+    let mut engine = engine();
+
+    // The optimum is on the diagonal 5
+    let result = engine.scan_all(&seq1, &seq2).pop().unwrap();
+    assert_eq!(*result.seq2(), 5..15);
+
+    for (band, score) in [
+        (sw::algo::Band::around(5, 0), 10),
+        (sw::algo::Band::around(4, 1), 10),
+        (sw::algo::Band::around(0, 2), 6),
+    ] {
+        engine.with_band(Some(band));
+        let result = engine.scan_all(&seq1, &seq2);
+        assert_eq!(result.len(), 1);
+        assert_eq!(*result[0].score(), score, "{band:?}");
+        ensure_inside(&result[0], &band);
+    }
+
+    // Nothing to align outside the matrix
+    engine.with_band(Some(sw::algo::Band::around(-20, 3)));
+    assert!(engine.scan_all(&seq1, &seq2).is_empty());
+}
+
+#[test]
+fn test_global_band() {
+    let (seq1, seq2) = (b"ACGTGGACGT".as_slice(), b"ACGTACGT".as_slice());
+    let mut engine = engine();
+    engine.with_mode(sw::Mode::Global(sw::algo::EndGaps::penalized()));
+
+    engine.with_band(Some(sw::algo::Band::new(-2, 0)));
+    let result = engine.scan_all(&seq1, &seq2).pop().unwrap();
+    assert_eq!(*result.score(), 2);
+    assert_eq!(result.rle(), "4=2v4=");
+
+    // The last cell is outside the band
+    engine.with_band(Some(sw::algo::Band::new(-1, 1)));
+    assert!(engine.scan_all(&seq1, &seq2).is_empty());
+
+    // ...unless the trailing gap is free
+    engine.with_mode(sw::Mode::Global(sw::algo::EndGaps::free_seq1()));
+    let result = engine.scan_all(&seq1, &seq2).pop().unwrap();
+    assert_eq!(*result.score(), -4);
+    assert_eq!(*result.seq2(), 0..8);
+    ensure_inside(&result, &sw::algo::Band::new(-1, 1));
+}
+
+#[test]
+fn test_xdrop() {
+    let mut engine = engine();
+
+    // Mismatching flanks stop the extension
+    let seq1 = b"GGGGACGTACGTACTTTTTTTT".as_slice();
+    let seq2 = b"CCCCCCACGTACGTACAAAAAAAA".as_slice();
+    let result = engine.extend(&seq1, &seq2, (4, 6), 5).unwrap();
+    assert_eq!(*result.score(), 10);
+    assert_eq!(*result.seq1(), 4..14);
+    assert_eq!(*result.seq2(), 6..16);
+    assert_eq!(result.rle(), "10=");
+
+    // The gap is crossed only if the X-drop threshold allows it
+    let seq1 = b"ACGTACGTACGAACGTACGTAC".as_slice();
+    let seq2 = b"ACGTACGTACACGTACGTAC".as_slice();
+    for (xdrop, score, end1, end2, rle) in [(3, 10, 10, 10, "10="), (10, 14, 22, 20, "10=2v10=")] {
+        let result = engine.extend(&seq1, &seq2, (0, 0), xdrop).unwrap();
+        assert_eq!(*result.score(), score);
+        assert_eq!(*result.seq1(), 0..end1);
+        assert_eq!(*result.seq2(), 0..end2);
+        assert_eq!(result.rle(), rle);
+    }
+
+    // Leading gaps (from the origin) are penalized and reported
+    let seq1 = b"GGGGTACGTACGTAC".as_slice();
+    let seq2 = b"CCCCCCACGTACGTAC".as_slice();
+    let result = engine.extend(&seq1, &seq2, (4, 6), 10).unwrap();
+    assert_eq!(*result.score(), 5);
+    assert_eq!(*result.seq1(), 4..15);
+    assert_eq!(*result.seq2(), 6..16);
+    assert_eq!(result.rle(), "1v10=");
+
+    // Nothing to extend
+    assert!(engine.extend(&seq1, &seq2, (15, 0), 10).is_none());
+    assert!(engine.extend(&seq1, &seq2, (0, 16), 10).is_none());
+    assert!(
+        engine
+            .extend(&b"AAAA".as_slice(), &b"CCCC".as_slice(), (0, 0), 10)
+            .is_none()
+    );
+}
+
+#[test]
+fn test_long_sequences() {
+    // The full trace matrix would take ~120GB, only the band/X-drop window must be stored
+    let mut random = Random::new(43);
+    let seq1: Vec<u8> = (0..200_000).map(|_| random.symbol()).collect();
+    let mut seq2 = seq1.clone();
+    seq2.splice(100_000..100_000, *b"TT");
+
+    // Every alignment must insert 2 gaps in seq1, i.e. the best one matches everything else
+    let expected = 200_000 - 6;
+    let mut engine = engine();
+    engine.with_band(Some(sw::algo::Band::around(0, 16)));
+    for mode in [
+        sw::Mode::Local,
+        sw::Mode::Global(sw::algo::EndGaps::penalized()),
+    ] {
+        engine.with_mode(mode);
+        let result = engine.scan_all(&seq1, &seq2).pop().unwrap();
+        assert_eq!(*result.score(), expected, "{mode:?}");
+        assert_eq!(*result.seq1(), 0..seq1.len());
+        assert_eq!(*result.seq2(), 0..seq2.len());
+    }
+
+    let result = engine.extend(&seq1, &seq2, (0, 0), 20).unwrap();
+    assert_eq!(*result.score(), expected);
+    assert_eq!(*result.seq1(), 0..seq1.len());
+    assert_eq!(*result.seq2(), 0..seq2.len());
+}
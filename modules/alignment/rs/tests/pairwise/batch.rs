@@ -0,0 +1,130 @@
+use rayon::ThreadPoolBuilder;
+
+use biobit_alignment_rs::pairwise::{alignment, sw};
+
+use super::common::{self, Engine, Scheme, Score, Symbol, scheme};
+use super::random::workload;
+
+fn engine(mode: sw::Mode) -> Engine {
+    let mut engine = common::engine(scheme(2, -3, -5, -1));
+    engine.with_mode(mode);
+    engine
+}
+
+fn batch(
+    mode: sw::Mode,
+) -> sw::Batch<
+    Score,
+    Symbol,
+    sw::storage::Best<Score>,
+    sw::traceback::TraceMatrix<Score>,
+    Scheme,
+    impl Fn() -> Engine + Sync,
+> {
+    let pool = ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+    sw::Batch::new(pool, move || engine(mode))
+}
+
+fn summary(alignments: &[alignment::Alignment<Score, u8, usize, usize>]) -> Vec<String> {
+    alignments
+        .iter()
+        .map(|x| format!("{} {:?} {:?} {}", x.score(), x.seq1(), x.seq2(), x.rle()))
+        .collect()
+}
+
+#[test]
+fn test_scan_pairs() {
+    let pairs = (0..200)
+        .map(|seed| {
+            workload(
+                seed,
+                10 + (seed as usize * 7) % 90,
+                20 + (seed as usize * 13) % 150,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for mode in [
+        sw::Mode::Local,
+        sw::Mode::Global(sw::algo::EndGaps::default()),
+    ] {
+        let mut sequential = engine(mode);
+        let expected = pairs
+            .iter()
+            .map(|(seq1, seq2)| summary(&sequential.scan_all(seq1, seq2)))
+            .collect::<Vec<_>>();
+
+        let batch = batch(mode);
+        // Engines are reused across calls
+        for _ in 0..2 {
+            let hits =
+                batch.scan_pairs(pairs.iter().map(|(s1, s2)| (s1.as_slice(), s2.as_slice())));
+            assert_eq!(hits.len(), pairs.len());
+            for (ind, hit) in hits.iter().enumerate() {
+                assert_eq!(hit.index, ind);
+                assert_eq!(summary(&hit.alignments), expected[ind]);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_scan_targets() {
+    let (query, _) = workload(1, 60, 1);
+    let mut targets = (0..100)
+        .map(|seed| workload(seed + 1000, 80, 120).1)
+        .collect::<Vec<_>>();
+    targets[17] = query[5..55].to_vec();
+    targets[42] = query[10..50].to_vec();
+    targets[73] = b"".to_vec();
+
+    let mut sequential = engine(sw::Mode::Local);
+    let expected = targets
+        .iter()
+        .map(|target| summary(&sequential.scan_all(&query, target)))
+        .collect::<Vec<_>>();
+
+    let mut batch = batch(sw::Mode::Local);
+    assert_eq!(batch.top_k(), None);
+    let hits = batch.scan_targets(&query, targets.iter().map(|x| x.as_slice()));
+    assert_eq!(hits.len(), targets.len());
+    for (ind, hit) in hits.iter().enumerate() {
+        assert_eq!(hit.index, ind);
+        assert_eq!(summary(&hit.alignments), expected[ind]);
+    }
+    assert_eq!(hits[17].score(), Some(100));
+    assert_eq!(hits[42].score(), Some(80));
+    assert_eq!(hits[73].score(), None);
+
+    // Top hits are reported in the input order
+    batch.with_top_k(Some(2));
+    let top = batch.scan_targets(&query, targets.iter().map(|x| x.as_slice()));
+    assert_eq!(
+        top.iter().map(|x| x.index).collect::<Vec<_>>(),
+        vec![17, 42]
+    );
+    assert_eq!(summary(&top[0].alignments), expected[17]);
+
+    let mut ranked = hits
+        .iter()
+        .filter_map(|x| x.score().map(|score| (-score, x.index)))
+        .collect::<Vec<_>>();
+    ranked.sort();
+    let mut expected = ranked[..10].iter().map(|x| x.1).collect::<Vec<_>>();
+    expected.sort();
+
+    batch.with_top_k(Some(10));
+    let top = batch.scan_targets(&query, targets.iter().map(|x| x.as_slice()));
+    assert_eq!(top.iter().map(|x| x.index).collect::<Vec<_>>(), expected);
+
+    batch.with_top_k(Some(1000));
+    let top = batch.scan_targets(&query, targets.iter().map(|x| x.as_slice()));
+    assert_eq!(top.len(), targets.len() - 1);
+
+    batch.with_top_k(Some(0));
+    assert!(
+        batch
+            .scan_targets(&query, targets.iter().map(|x| x.as_slice()))
+            .is_empty()
+    );
+}
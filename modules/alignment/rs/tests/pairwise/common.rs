@@ -0,0 +1,33 @@
+use biobit_alignment_rs::pairwise::{scoring, sw};
+
+pub type Score = i32;
+pub type Symbol = u8;
+
+// Equality scoring with affine gaps
+pub type Scheme = scoring::Delegate<
+    Score,
+    Symbol,
+    scoring::symbols::Equality<Score, u8>,
+    scoring::gaps::Affine<Score>,
+    scoring::equiv::Equality,
+>;
+
+pub type Engine<Storage = sw::storage::Best<Score>, TraceMat = sw::traceback::TraceMatrix<Score>> =
+    sw::Engine<Score, Symbol, Storage, TraceMat, Scheme>;
+
+pub fn scheme(equal: Score, different: Score, open: Score, extend: Score) -> Scheme {
+    scoring::compose(
+        scoring::symbols::Equality::new(equal, different),
+        scoring::gaps::Affine { open, extend },
+        scoring::equiv::Equality {},
+    )
+}
+
+// Engine reporting the best local alignment
+pub fn engine(scheme: Scheme) -> Engine {
+    Engine::new(
+        sw::storage::Best::new(),
+        sw::traceback::TraceMatrix::new(),
+        scheme,
+    )
+}
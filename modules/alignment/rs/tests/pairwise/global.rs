@@ -0,0 +1,228 @@
+use biobit_alignment_rs::pairwise::{alignment, sw};
+
+use super::common::{Engine, Score, engine, scheme};
+use super::local::invrle;
+
+struct Workload<'a> {
+    seq1: (&'a [u8], usize, usize),
+    seq2: (&'a [u8], usize, usize),
+    score: Score,
+    rle: &'a str,
+}
+
+fn swapped(ends: sw::algo::EndGaps) -> sw::algo::EndGaps {
+    sw::algo::EndGaps {
+        seq1_leading: ends.seq2_leading,
+        seq1_trailing: ends.seq2_trailing,
+        seq2_leading: ends.seq1_leading,
+        seq2_trailing: ends.seq1_trailing,
+    }
+}
+
+fn ensure(engine: &mut Engine, ends: sw::algo::EndGaps, w: Workload<'_>) {
+    let invrle = invrle(w.rle);
+
+    for (seq1, seq2, ends, rle) in [
+        (w.seq1, w.seq2, ends, w.rle),
+        (w.seq2, w.seq1, swapped(ends), &invrle),
+    ] {
+        engine.with_mode(sw::Mode::Global(ends));
+        let mut result = engine.scan_all(&seq1.0, &seq2.0);
+        assert_eq!(result.len(), 1);
+        let result = result.pop().unwrap();
+
+        assert_eq!(*result.seq1(), seq1.1..seq1.2);
+        assert_eq!(*result.seq2(), seq2.1..seq2.2);
+        assert_eq!(*result.score(), w.score);
+        assert_eq!(result.rle(), rle);
+    }
+}
+
+fn test_empty(engine: &mut Engine) {
+    // The non-empty sequence is covered by a single gap...
+    let (acgt, empty) = (b"ACGT".as_slice(), b"".as_slice());
+    for ends in [
+        sw::algo::EndGaps::penalized(),
+        sw::algo::EndGaps::free_seq2(),
+    ] {
+        let workload = Workload {
+            seq1: (acgt, 0, 4),
+            seq2: (empty, 0, 0),
+            score: -5 - 3,
+            rle: "4v",
+        };
+        ensure(engine, ends, workload);
+    }
+
+    // ...which is left out if any of its ends is free
+    let leading = sw::algo::EndGaps {
+        seq1_leading: true,
+        ..sw::algo::EndGaps::penalized()
+    };
+    for (ends, start) in [(leading, 4), (sw::algo::EndGaps::free_seq1(), 0)] {
+        let workload = Workload {
+            seq1: (acgt, start, start),
+            seq2: (empty, 0, 0),
+            score: 0,
+            rle: "",
+        };
+        ensure(engine, ends, workload);
+    }
+    let workload = Workload {
+        seq1: (empty, 0, 0),
+        seq2: (empty, 0, 0),
+        score: 0,
+        rle: "",
+    };
+    ensure(engine, sw::algo::EndGaps::penalized(), workload);
+
+    // The gap must fit into the band
+    engine.with_mode(sw::Mode::Global(sw::algo::EndGaps::penalized()));
+    engine.with_band(Some(sw::algo::Band::new(-4, 0)));
+    assert_eq!(engine.scan_all(&acgt, &empty).len(), 1);
+    engine.with_band(Some(sw::algo::Band::new(-3, 0)));
+    assert!(engine.scan_all(&acgt, &empty).is_empty());
+    engine.with_band(None);
+
+    // There is nothing to align locally
+    engine.with_mode(sw::Mode::Local);
+    for (seq1, seq2) in [(acgt, empty), (empty, acgt), (empty, empty)] {
+        assert!(engine.scan_all(&seq1, &seq2).is_empty());
+    }
+}
+
+fn test_global(engine: &mut Engine) {
+    let ends = sw::algo::EndGaps::penalized();
+    let workload = vec![
+        Workload {
+            seq1: (b"ACGT", 0, 4),
+            seq2: (b"ACGT", 0, 4),
+            score: 4,
+            rle: "4=",
+        },
+        Workload {
+            seq1: (b"GACGTC", 0, 6),
+            seq2: (b"TACGTA", 0, 6),
+            score: 0,
+            rle: "1X4=1X",
+        },
+        Workload {
+            seq1: (b"TTACGT", 0, 6),
+            seq2: (b"ACGT", 0, 4),
+            score: -2,
+            rle: "2v4=",
+        },
+        Workload {
+            seq1: (b"ACGTTT", 0, 6),
+            seq2: (b"ACGT", 0, 4),
+            score: -2,
+            rle: "4=2v",
+        },
+        Workload {
+            seq1: (b"ACGTGGACGT", 0, 10),
+            seq2: (b"ACGTACGT", 0, 8),
+            score: 2,
+            rle: "4=2v4=",
+        },
+        Workload {
+            seq1: (b"ACGT", 0, 4),
+            seq2: (b"TTTTACGTTTTT", 0, 12),
+            score: -12,
+            rle: "4^4=4^",
+        },
+    ];
+
+    for w in workload {
+        ensure(engine, ends, w);
+    }
+}
+
+fn test_semi_global(engine: &mut Engine) {
+    // Read-to-reference
+    ensure(
+        engine,
+        sw::algo::EndGaps::free_seq2(),
+        Workload {
+            seq1: (b"ACGTGCA", 0, 7),
+            seq2: (b"TTTTACGTCATTTT", 4, 10),
+            score: 4,
+            rle: "4=1v2=",
+        },
+    );
+
+    // Only the trailing seq2 gap is free
+    ensure(
+        engine,
+        sw::algo::EndGaps {
+            seq2_trailing: true,
+            ..sw::algo::EndGaps::penalized()
+        },
+        Workload {
+            seq1: (b"ACGT", 0, 4),
+            seq2: (b"TTACGTTTTT", 0, 6),
+            score: 1,
+            rle: "2^4=",
+        },
+    );
+
+    // Overlap between the seq1 suffix and the seq2 prefix
+    ensure(
+        engine,
+        sw::algo::EndGaps::overlap(),
+        Workload {
+            seq1: (b"GGGGGACGT", 5, 9),
+            seq2: (b"ACGTCCCCC", 0, 4),
+            score: 4,
+            rle: "4=",
+        },
+    );
+
+    // Everything is free, but the alignment must still start and end at sequence ends
+    ensure(
+        engine,
+        sw::algo::EndGaps::free(),
+        Workload {
+            seq1: (b"AAAAGGCTT", 4, 9),
+            seq2: (b"GGATTCCC", 0, 5),
+            score: 2,
+            rle: "2=1X2=",
+        },
+    );
+}
+
+fn test_long_end_gap(engine: &mut Engine) {
+    let mut seq1 = vec![b'C'; 300];
+    seq1.extend_from_slice(b"ACGT");
+
+    engine.with_mode(sw::Mode::Global(sw::algo::EndGaps::penalized()));
+    let result = engine.scan_all(&seq1, &b"ACGT".as_slice()).pop().unwrap();
+    assert_eq!(*result.score(), 4 - 5 - 299);
+    assert_eq!(*result.seq1(), 0..304);
+    assert_eq!(*result.seq2(), 0..4);
+
+    let gaps: usize = result
+        .steps()
+        .iter()
+        .filter(|x| *x.op() == alignment::Op::GapFirst)
+        .map(|x| *x.len() as usize)
+        .sum();
+    assert_eq!(gaps, 300);
+}
+
+#[test]
+fn test_all() {
+    let mut engine = engine(scheme(1, -2, -5, -1));
+    test_empty(&mut engine);
+    test_global(&mut engine);
+    test_long_end_gap(&mut engine);
+
+    engine.with_scoring(scheme(1, -2, -2, -1));
+    test_semi_global(&mut engine);
+
+    // Switching back to the local mode restores the local behavior
+    engine.with_mode(sw::Mode::Local);
+    let result = engine.scan_all(&b"GACGTC".as_slice(), &b"TACGTA".as_slice());
+    assert_eq!(result.len(), 1);
+    assert_eq!(*result[0].score(), 4);
+    assert_eq!(result[0].rle(), "4=");
+}
@@ -0,0 +1,152 @@
+use biobit_alignment_rs::pairwise::sw;
+
+use super::common::{Engine, Score, engine, scheme};
+use super::random::workload;
+
+fn ensure<Storage, Reference, Linear>(
+    reference: &mut Engine<Storage, Reference>,
+    linear: &mut Engine<Storage, Linear>,
+    seq1: &[u8],
+    seq2: &[u8],
+) where
+    Storage: sw::storage::Storage + sw::algo::Tracer<Score = Score>,
+    Reference: sw::traceback::TraceMat + sw::algo::Tracer<Score = Score>,
+    Linear: sw::traceback::TraceMat + sw::algo::Tracer<Score = Score>,
+{
+    let expected = reference.scan_all(&seq1, &seq2);
+    let result = linear.scan_all(&seq1, &seq2);
+    assert_eq!(
+        result,
+        expected,
+        "Linear-space traceback mismatch for {} vs {}",
+        String::from_utf8_lossy(seq1),
+        String::from_utf8_lossy(seq2)
+    );
+}
+
+fn modes() -> Vec<sw::Mode> {
+    let mut modes = vec![sw::Mode::Local];
+    for ends in [
+        sw::algo::EndGaps::penalized(),
+        sw::algo::EndGaps::free_seq1(),
+        sw::algo::EndGaps::free_seq2(),
+        sw::algo::EndGaps::overlap(),
+        sw::algo::EndGaps::free(),
+    ] {
+        modes.push(sw::Mode::Global(ends));
+    }
+    modes
+}
+
+#[test]
+fn test_best() {
+    for (open, extend) in [(-5, -1), (-3, -3), (-1, -1)] {
+        let mut reference = engine(scheme(2, -3, open, extend));
+        let mut linear = Engine::new(
+            sw::storage::Best::new(),
+            sw::traceback::LinearSpace::new(),
+            scheme(2, -3, open, extend),
+        );
+
+        for mode in modes() {
+            reference.with_mode(mode);
+            linear.with_mode(mode);
+            for seed in 0..64 {
+                let (len1, len2) = (1 + seed as usize % 37, 1 + (seed as usize * 7) % 53);
+                let (seq1, seq2) = workload(seed, len1, len2);
+                ensure(&mut reference, &mut linear, &seq1, &seq2);
+                ensure(&mut reference, &mut linear, &seq2, &seq1);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_band() {
+    let mut reference = engine(scheme(2, -3, -5, -1));
+    let mut linear = Engine::new(
+        sw::storage::Best::new(),
+        sw::traceback::LinearSpace::new(),
+        scheme(2, -3, -5, -1),
+    );
+
+    for mode in modes() {
+        reference.with_mode(mode);
+        linear.with_mode(mode);
+        for seed in 0..32 {
+            let (seq1, seq2) = workload(seed, 60, 50);
+            for band in [
+                sw::algo::Band::around(-10, 0),
+                sw::algo::Band::around(0, 3),
+                sw::algo::Band::new(-15, 2),
+                sw::algo::Band::around(seed as isize - 16, 8),
+            ] {
+                reference.with_band(Some(band));
+                linear.with_band(Some(band));
+                ensure(&mut reference, &mut linear, &seq1, &seq2);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_xdrop() {
+    for (open, extend) in [(-5, -1), (-3, -3), (-1, -1)] {
+        let mut reference = engine(scheme(2, -3, open, extend));
+        let mut linear = Engine::new(
+            sw::storage::Best::new(),
+            sw::traceback::LinearSpace::new(),
+            scheme(2, -3, open, extend),
+        );
+
+        for seed in 0..32 {
+            let (seq1, seq2) = workload(seed, 90, 70);
+            for from in [(0, 0), (5, 3), (seed as usize, 60 - seed as usize)] {
+                for xdrop in [1, 5, 20] {
+                    let expected = reference.extend(&seq1, &seq2, from, xdrop);
+                    let result = linear.extend(&seq1, &seq2, from, xdrop);
+                    assert_eq!(
+                        result, expected,
+                        "X-drop {xdrop} from {from:?}, seed {seed}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_all_optimal() {
+    let mut reference = Engine::new(
+        sw::storage::AllOptimal::new(8),
+        sw::traceback::TraceMatrix::new(),
+        scheme(2, -3, -5, -1),
+    );
+    let mut linear = Engine::new(
+        sw::storage::AllOptimal::new(8),
+        sw::traceback::LinearSpace::new(),
+        scheme(2, -3, -5, -1),
+    );
+
+    for seed in 0..32 {
+        let (seq1, seq2) = workload(seed, 120, 80);
+        ensure(&mut reference, &mut linear, &seq1, &seq2);
+    }
+}
+
+#[test]
+fn test_long() {
+    let mut reference = engine(scheme(2, -3, -5, -1));
+    let mut linear = Engine::new(
+        sw::storage::Best::new(),
+        sw::traceback::LinearSpace::new(),
+        scheme(2, -3, -5, -1),
+    );
+
+    let (seq1, seq2) = workload(42, 1500, 1200);
+    for mode in modes() {
+        reference.with_mode(mode);
+        linear.with_mode(mode);
+        ensure(&mut reference, &mut linear, &seq1, &seq2);
+    }
+}
@@ -0,0 +1,185 @@
+use biobit_alignment_rs::pairwise::scoring::symbols::PosInvariantScorer;
+use biobit_alignment_rs::pairwise::{scoring, sw};
+
+pub type Score = i32;
+pub type Symbol = u8;
+
+type Engine<S, E> = sw::Engine<
+    Score,
+    Symbol,
+    sw::storage::Best<Score>,
+    sw::traceback::TraceMatrix<Score>,
+    scoring::Delegate<Score, Symbol, S, scoring::gaps::Affine<Score>, E>,
+>;
+
+#[test]
+fn test_builtin() {
+    for (matrix, lowest, tryptophan) in [
+        (scoring::Matrix::<Score>::blosum62(), -4, 11),
+        (scoring::Matrix::<Score>::pam250(), -8, 17),
+    ] {
+        assert_eq!(matrix.symbols(), b"ARNDCQEGHILKMFPSTWYVBZX*");
+        for &s1 in matrix.symbols() {
+            for &s2 in matrix.symbols() {
+                assert_eq!(matrix.get(s1, s2), matrix.get(s2, s1));
+                assert_eq!(matrix.get(s1, s2), matrix.get(s1.to_ascii_lowercase(), s2));
+            }
+        }
+        assert_eq!(matrix.get(b'W', b'W'), tryptophan);
+        assert_eq!(matrix.get(b'w', b'w'), tryptophan);
+        // Unknown symbols score as the lowest matrix value
+        assert_eq!(matrix.get(b'J', b'A'), lowest);
+        assert_eq!(matrix.get(b'J', b'J'), lowest);
+        assert_eq!(matrix.get(b'.', b'A'), lowest);
+    }
+
+    let blosum62 = scoring::Matrix::<Score>::blosum62();
+    for (s1, s2, score) in [
+        (b'A', b'A', 4),
+        (b'C', b'C', 9),
+        (b'I', b'V', 3),
+        (b'D', b'W', -4),
+    ] {
+        assert_eq!(blosum62.get(s1, s2), score);
+        assert_eq!(PosInvariantScorer::score(&blosum62, &s1, &s2), score);
+    }
+}
+
+#[test]
+fn test_parse() {
+    let matrix = scoring::Matrix::<i8>::parse(
+        "# Comment line
+
+            a  c  g
+        G -1 -2  3
+        # Rows may be in any order
+        A  2 -1 -1
+        C -1  4 -2
+        ",
+    )
+    .unwrap();
+    assert_eq!(matrix.symbols(), b"ACG");
+    assert_eq!(matrix.get(b'A', b'A'), 2);
+    assert_eq!(matrix.get(b'C', b'C'), 4);
+    assert_eq!(matrix.get(b'g', b'C'), -2);
+    assert_eq!(matrix.get(b'C', b'G'), -2);
+    assert_eq!(matrix.get(b'N', b'A'), -2);
+
+    for invalid in [
+        "",
+        "# Only comments",
+        "AA C\nAA 1 1\nC 1 1",
+        "A C A\nA 1 1 1\nC 1 1 1",
+        "A a\nA 1 1",
+        "A C\nA 1 1\nC 1",
+        "A C\nA 1 1\nC 1 1 1",
+        "A C\nA 1 1",
+        "A C\nA 1 1\nA 1 1",
+        "A C\nA 1 1\nG 1 1",
+        "A C\nA 1 1\nC 1 x",
+        "A C\nA 1 1\nC 1 300",
+    ] {
+        assert!(
+            scoring::Matrix::<i8>::parse(invalid).is_err(),
+            "{invalid:?}"
+        );
+    }
+
+    // The same scores fit into the wider score type
+    assert!(scoring::Matrix::<i16>::parse("A C\nA 1 1\nC 1 300").is_ok());
+}
+
+#[test]
+fn test_protein() {
+    let matrix = scoring::Matrix::blosum62();
+    let mut engine: Engine<_, _> = Engine::new(
+        sw::storage::Best::new(),
+        sw::traceback::TraceMatrix::new(),
+        scoring::compose(
+            matrix.clone(),
+            scoring::gaps::Affine {
+                open: -11,
+                extend: -1,
+            },
+            scoring::equiv::Positives::new(&matrix),
+        ),
+    );
+
+    let (seq1, seq2) = (
+        b"MKTAYIAKQRQISFVKSHFSRQ".as_slice(),
+        b"GGKTAYVAKQRQLSFVKGG".as_slice(),
+    );
+    let result = engine.scan_all(&seq1, &seq2).pop().unwrap();
+    assert_eq!(*result.seq1(), 1..16);
+    assert_eq!(*result.seq2(), 2..17);
+    assert_eq!(*result.score(), 69);
+    assert_eq!(result.rle(), "4=1~5=1~4=");
+
+    // Case-insensitive
+    let lower = seq2.to_ascii_lowercase();
+    let result = engine.scan_all(&seq1, &lower.as_slice()).pop().unwrap();
+    assert_eq!(*result.score(), 69);
+    assert_eq!(result.rle(), "4=1~5=1~4=");
+
+    // The striped scan agrees with the engine
+    let mut striped = sw::Striped::new(scoring::compose(
+        matrix.clone(),
+        scoring::gaps::Affine {
+            open: -11,
+            extend: -1,
+        },
+        scoring::equiv::Positives::new(&matrix),
+    ));
+    striped.with_query(&seq1);
+    let seed = striped.scan(&seq2).unwrap();
+    assert_eq!((seed.score, seed.row, seed.col), (69, 15, 16));
+}
+
+#[test]
+fn test_iupac() {
+    let scorer = scoring::symbols::IUPAC::new(5, -4);
+    let classifier = scoring::equiv::IUPAC {};
+    for (s1, s2, score, class) in [
+        (b'A', b'A', 5, scoring::equiv::Type::Match),
+        (b'A', b'a', 5, scoring::equiv::Type::Match),
+        (b'T', b'U', 5, scoring::equiv::Type::Match),
+        (b'A', b'C', -4, scoring::equiv::Type::Mismatch),
+        (b'A', b'R', 1, scoring::equiv::Type::Equivalent),
+        (b'C', b'R', -4, scoring::equiv::Type::Mismatch),
+        (b'R', b'R', 1, scoring::equiv::Type::Equivalent),
+        (b'R', b'Y', -4, scoring::equiv::Type::Mismatch),
+        (b'A', b'N', -2, scoring::equiv::Type::Equivalent),
+        (b'N', b'N', -2, scoring::equiv::Type::Equivalent),
+        (b'B', b'V', -2, scoring::equiv::Type::Equivalent),
+        (b'A', b'X', -4, scoring::equiv::Type::Mismatch),
+        (b'X', b'X', -4, scoring::equiv::Type::Mismatch),
+    ] {
+        assert_eq!(PosInvariantScorer::score(&scorer, &s1, &s2), score);
+        assert_eq!(PosInvariantScorer::score(&scorer, &s2, &s1), score);
+        assert_eq!(
+            scoring::equiv::Classifier::classify(&classifier, &s1, &s2),
+            class
+        );
+    }
+
+    // Degenerate primer
+    let mut engine: Engine<_, _> = Engine::new(
+        sw::storage::Best::new(),
+        sw::traceback::TraceMatrix::new(),
+        scoring::compose(
+            scoring::symbols::IUPAC::new(5, -4),
+            scoring::gaps::Affine {
+                open: -10,
+                extend: -2,
+            },
+            scoring::equiv::IUPAC {},
+        ),
+    );
+    let primer = b"GTNCCRTAYGG".as_slice();
+    let target = b"TTTTTTGTACCGTATGGTTTTT".as_slice();
+    let result = engine.scan_all(&primer, &target).pop().unwrap();
+    assert_eq!(*result.seq1(), 0..11);
+    assert_eq!(*result.seq2(), 6..17);
+    assert_eq!(*result.score(), 40);
+    assert_eq!(result.rle(), "2=1~2=1~2=1~2=");
+}
@@ -1 +1,12 @@
+mod banded;
+mod batch;
+mod common;
+mod global;
+mod linear;
 mod local;
+mod matrices;
+mod myers;
+mod random;
+mod stats;
+mod striped;
+mod wfa;
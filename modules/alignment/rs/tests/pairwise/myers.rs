@@ -0,0 +1,171 @@
+use biobit_alignment_rs::pairwise::alignment::{Alignment, Op};
+use biobit_alignment_rs::pairwise::myers::{Hit, Myers};
+use biobit_alignment_rs::pairwise::scoring::iupac;
+
+use super::random::workload;
+
+// Textbook O(nm) dynamic programming: the last row of the edit distance matrix
+fn last_row(pattern: &[u8], text: &[u8], global: bool) -> Vec<usize> {
+    let mut row = (0..=text.len())
+        .map(|x| if global { x } else { 0 })
+        .collect::<Vec<_>>();
+    for (i, &p) in pattern.iter().enumerate() {
+        let mut next = vec![i + 1; text.len() + 1];
+        for (j, &t) in text.iter().enumerate() {
+            next[j + 1] = (row[j] + (p != t) as usize)
+                .min(row[j + 1] + 1)
+                .min(next[j] + 1);
+        }
+        row = next;
+    }
+    row
+}
+
+// The alignment must be consistent with the sequences and cost exactly its score
+fn ensure(aln: &Alignment<usize, u8, usize, usize>, pattern: &[u8], text: &[u8]) {
+    assert_eq!(*aln.seq1(), 0..pattern.len());
+    let (mut i, mut j, mut cost) = (0, aln.seq2().start, 0);
+    for step in aln.steps() {
+        for _ in 0..*step.len() {
+            match step.op() {
+                Op::Match => {
+                    assert_eq!(pattern[i], text[j]);
+                    i += 1;
+                    j += 1;
+                }
+                Op::Mismatch => {
+                    assert_ne!(pattern[i], text[j]);
+                    cost += 1;
+                    i += 1;
+                    j += 1;
+                }
+                Op::Equivalent => unreachable!(),
+                Op::GapFirst => {
+                    cost += 1;
+                    i += 1;
+                }
+                Op::GapSecond => {
+                    cost += 1;
+                    j += 1;
+                }
+            }
+        }
+    }
+    assert_eq!((i, j), (pattern.len(), aln.seq2().end));
+    assert_eq!(cost, *aln.score());
+}
+
+#[test]
+fn test_distance() {
+    let mut myers = Myers::new(&b"kitten".as_slice());
+    assert_eq!(myers.pattern(), b"kitten");
+    assert_eq!(myers.distance(&b"sitting".as_slice()), 3);
+    assert_eq!(myers.distance(&b"kitten".as_slice()), 0);
+    assert_eq!(myers.distance(&b"".as_slice()), 6);
+
+    let mut empty = Myers::new(&b"".as_slice());
+    assert_eq!(empty.distance(&b"ACGT".as_slice()), 4);
+    assert!(empty.search(&b"ACGT".as_slice(), 2).is_empty());
+    assert_eq!(empty.align(&b"ACGT".as_slice()).rle(), "4^");
+
+    let aln = myers.align(&b"sitting".as_slice());
+    assert_eq!(*aln.score(), 3);
+    assert_eq!(aln.rle(), "1X3=1X1=1^");
+}
+
+#[test]
+fn test_random() {
+    // Patterns within a single word and spanning multiple blocks
+    for (seed, len1, len2) in [
+        (1, 1, 10),
+        (2, 12, 40),
+        (3, 63, 80),
+        (4, 64, 64),
+        (5, 65, 100),
+        (6, 128, 200),
+        (7, 150, 140),
+        (8, 300, 500),
+    ] {
+        let (pattern, text) = workload(seed, len1, len2);
+        let mut myers = Myers::new(&pattern);
+
+        let global = last_row(&pattern, &text, true);
+        assert_eq!(myers.distance(&text), global[text.len()]);
+        let aln = myers.align(&text);
+        assert_eq!(*aln.score(), global[text.len()]);
+        assert_eq!(*aln.seq2(), 0..text.len());
+        ensure(&aln, &pattern, &text);
+
+        let local = last_row(&pattern, &text, false);
+        let best = *local[1..].iter().min().unwrap();
+        for k in [0, best, best + 3, len1] {
+            let expected = (1..=text.len())
+                .filter(|&end| local[end] <= k)
+                .map(|end| Hit {
+                    end,
+                    distance: local[end],
+                })
+                .collect::<Vec<_>>();
+            let hits = myers.search(&text, k);
+            assert_eq!(hits, expected, "seed {seed}, k {k}");
+
+            for hit in hits.iter().step_by(7) {
+                let aln = myers.align_hit(&text, hit);
+                assert_eq!(*aln.score(), hit.distance);
+                assert_eq!(aln.seq2().end, hit.end);
+                ensure(&aln, &pattern, &text);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_adapter() {
+    let adapter = b"AGATCGGAAGAGC".as_slice();
+    let read = b"TTGCACCTGAGTCAGATCGGTAGAGCACACG".as_slice();
+    let mut myers = Myers::new(&adapter);
+
+    assert!(myers.search(&read, 0).is_empty());
+    let hits = myers.search(&read, 1);
+    assert_eq!(
+        hits,
+        vec![Hit {
+            end: 26,
+            distance: 1
+        }]
+    );
+    let aln = myers.align_hit(&read, &hits[0]);
+    assert_eq!(*aln.seq2(), 13..26);
+    assert_eq!(aln.rle(), "7=1X5=");
+    assert_eq!(aln.cigar(false), "13M");
+}
+
+#[test]
+fn test_degenerate_primer() {
+    let primer = b"GTNCCRTAYGG".as_slice();
+    let target = b"TTTTTTGTACCGTATGGTTTTT".as_slice();
+
+    // IUPAC codes in the primer match any of the encoded nucleotides
+    let mut myers = Myers::with_matcher(&primer, |p, t| iupac::bases(p) & iupac::bases(t) != 0);
+    let hits = myers.search(&target, 0);
+    assert_eq!(
+        hits,
+        vec![Hit {
+            end: 17,
+            distance: 0
+        }]
+    );
+    let aln = myers.align_hit(&target, &hits[0]);
+    assert_eq!(*aln.seq2(), 6..17);
+    assert_eq!(aln.rle(), "2=1~2=1~2=1~2=");
+
+    // Exact matching treats ambiguity codes as regular symbols
+    let mut exact = Myers::new(&primer);
+    assert_eq!(
+        exact.search(&target, 3),
+        vec![Hit {
+            end: 17,
+            distance: 3
+        }]
+    );
+}
@@ -0,0 +1,24 @@
+#[path = "../../src/random.rs"]
+mod generator;
+
+pub use generator::Random;
+
+// Deterministic pseudo-random sequences with a shared (mutated) core to get non-trivial gaps
+pub fn workload(seed: u64, len1: usize, len2: usize) -> (Vec<u8>, Vec<u8>) {
+    let mut random = Random::new(seed);
+
+    let seq1: Vec<u8> = (0..len1).map(|_| random.symbol()).collect();
+    let mut seq2 = Vec::with_capacity(len2);
+    let mut pos = (random.next_u64() as usize) % (len1 / 2 + 1);
+    while seq2.len() < len2 {
+        match random.next_u64() % 10 {
+            0 => seq2.push(random.symbol()),
+            1 => pos += 1 + (random.next_u64() % 3) as usize,
+            _ => {
+                seq2.push(seq1[pos % len1]);
+                pos += 1;
+            }
+        }
+    }
+    (seq1, seq2)
+}
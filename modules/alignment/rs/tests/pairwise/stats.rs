@@ -0,0 +1,153 @@
+use biobit_alignment_rs::pairwise::alignment::{Alignment, Identity, KarlinAltschul};
+use biobit_alignment_rs::pairwise::{scoring, sw};
+
+pub type Score = i32;
+
+// Robinson & Robinson (1991) amino acid frequencies, used by NCBI BLAST
+const ROBINSON: [(u8, f64); 20] = [
+    (b'A', 0.07805),
+    (b'R', 0.05129),
+    (b'N', 0.04487),
+    (b'D', 0.05364),
+    (b'C', 0.01925),
+    (b'Q', 0.04264),
+    (b'E', 0.06295),
+    (b'G', 0.07377),
+    (b'H', 0.02199),
+    (b'I', 0.05142),
+    (b'L', 0.09019),
+    (b'K', 0.05744),
+    (b'M', 0.02243),
+    (b'F', 0.03856),
+    (b'P', 0.05203),
+    (b'S', 0.07120),
+    (b'T', 0.05841),
+    (b'W', 0.01330),
+    (b'Y', 0.03216),
+    (b'V', 0.06441),
+];
+
+const NUCLEOTIDES: [(u8, f64); 4] = [(b'A', 0.25), (b'C', 0.25), (b'G', 0.25), (b'T', 0.25)];
+
+fn assert_close(value: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (value - expected).abs() <= tolerance,
+        "{value} != {expected} ± {tolerance}"
+    );
+}
+
+#[test]
+fn test_ungapped() {
+    // Reference values reported by NCBI BLAST
+    for (equal, different, lambda, k) in [
+        (1, -2, 1.33, 0.621),
+        (1, -3, 1.374, 0.711),
+        (1, -4, 1.383, 0.738),
+    ] {
+        let scorer = scoring::symbols::Equality::<Score, u8>::new(equal, different);
+        let params = KarlinAltschul::ungapped(&scorer, &NUCLEOTIDES).unwrap();
+        // Published values are rounded to 3 significant digits
+        assert_close(*params.lambda(), lambda, 5e-3);
+        assert_close(*params.k(), k, 5e-4);
+    }
+
+    let params =
+        KarlinAltschul::ungapped(&scoring::Matrix::<Score>::blosum62(), &ROBINSON).unwrap();
+    assert_close(*params.lambda(), 0.3176, 5e-5);
+    assert_close(*params.k(), 0.134, 5e-4);
+
+    // Frequencies are normalized
+    let scaled = ROBINSON.map(|(s, f)| (s, f * 10.0));
+    let same = KarlinAltschul::ungapped(&scoring::Matrix::<Score>::blosum62(), &scaled).unwrap();
+    assert_close(*same.lambda(), *params.lambda(), 1e-9);
+    assert_close(*same.k(), *params.k(), 1e-9);
+
+    // Non-negative expected score, no positive scores, invalid frequencies
+    for (equal, different, background) in [
+        (3, -1, NUCLEOTIDES.as_slice()),
+        (-1, -2, NUCLEOTIDES.as_slice()),
+        (1, -2, [].as_slice()),
+        (1, -2, [(b'A', 0.5), (b'C', -0.5)].as_slice()),
+    ] {
+        let scorer = scoring::symbols::Equality::<Score, u8>::new(equal, different);
+        assert!(KarlinAltschul::ungapped(&scorer, background).is_err());
+    }
+}
+
+#[test]
+fn test_evalue() {
+    let gaps = scoring::gaps::Affine::<Score> {
+        open: -12,
+        extend: -1,
+    };
+    let params = KarlinAltschul::blosum62(&gaps).unwrap();
+    assert_eq!((*params.lambda(), *params.k()), (0.267, 0.041));
+    assert!(
+        KarlinAltschul::blosum62(&scoring::gaps::Affine {
+            open: -20,
+            extend: -1
+        })
+        .is_err()
+    );
+    assert!(KarlinAltschul::new(0.0, 0.1).is_err());
+    assert!(KarlinAltschul::new(0.1, -1.0).is_err());
+
+    let matrix = scoring::Matrix::blosum62();
+    let mut engine = sw::Engine::new(
+        sw::storage::Best::new(),
+        sw::traceback::TraceMatrix::new(),
+        scoring::compose(
+            matrix.clone(),
+            gaps,
+            scoring::equiv::Positives::new(&matrix),
+        ),
+    );
+    let (seq1, seq2) = (
+        b"MKTAYIAKQRQISFVKSHFSRQ".as_slice(),
+        b"GGKTAYVAKQRQLSFVKGG".as_slice(),
+    );
+    let result = engine.scan_all(&seq1, &seq2).pop().unwrap();
+    assert_eq!(*result.score(), 69);
+
+    // bits = (lambda * S - ln K) / ln 2
+    assert_close(result.bit_score(&params), 31.19, 0.005);
+    let evalue = result.evalue(&params, seq1.len(), 1_000_000);
+    assert_close(evalue, 0.041 * 22.0 * 1e6 * (-0.267 * 69.0f64).exp(), 1e-12);
+    assert_close(
+        evalue,
+        22.0 * 1e6 * 2f64.powf(-result.bit_score(&params)),
+        1e-12,
+    );
+}
+
+#[test]
+fn test_stats() {
+    type Aln = Alignment<Score, u8, usize, usize>;
+
+    let aln = Aln::from_cigar("2S4=1X2I3=1D2M3=5S", 0, 0).unwrap();
+    let stats = aln.stats();
+    assert_eq!(stats.dissolve(), (10, 1, 2, (1, 1), (2, 1)));
+    assert_eq!(stats.aligned(), 13);
+    assert_eq!(stats.columns(), 16);
+    assert_eq!(stats.identity(Identity::Blast), 10.0 / 16.0);
+    assert_eq!(stats.identity(Identity::GapCompressed), 10.0 / 15.0);
+    assert_eq!(stats.identity(Identity::Ungapped), 10.0 / 13.0);
+
+    assert_eq!(aln.seq1_coverage(22), 15.0 / 22.0);
+    assert_eq!(aln.seq2_coverage(28), 0.5);
+    assert_eq!(aln.seq2_coverage(0), 0.0);
+
+    // Long gaps are split into multiple steps but counted once
+    let aln = Aln::from_cigar("1=300I1=2D1I", 0, 0).unwrap();
+    assert_eq!(aln.steps().len(), 6);
+    let stats = aln.stats();
+    assert_eq!(*stats.gap_openings(), (2, 1));
+    assert_eq!(*stats.gap_length(), (301, 2));
+
+    // Empty alignment
+    let stats = Aln::from_cigar("", 0, 0).unwrap().stats();
+    assert_eq!(stats.columns(), 0);
+    for definition in [Identity::Blast, Identity::GapCompressed, Identity::Ungapped] {
+        assert_eq!(stats.identity(definition), 0.0);
+    }
+}
@@ -0,0 +1,111 @@
+use biobit_alignment_rs::pairwise::sw;
+
+use super::common::{Engine, Scheme, Score, Symbol, engine, scheme};
+use super::random::workload;
+
+// The striped scan must report exactly the same score and end cell as the traced engine
+fn ensure(
+    engine: &mut Engine,
+    striped: &mut sw::Striped<Score, Symbol, Scheme>,
+    seq1: &[u8],
+    seq2: &[u8],
+) {
+    let expected = engine
+        .scan_all(&seq1, &seq2)
+        .pop()
+        .map(|x| (*x.score(), x.seq1().end - 1, x.seq2().end - 1));
+
+    striped.with_query(&seq1);
+    let result = striped.scan(&seq2).map(|x| (x.score, x.row, x.col));
+    assert_eq!(
+        result,
+        expected,
+        "Striped scan mismatch for {} vs {}",
+        String::from_utf8_lossy(seq1),
+        String::from_utf8_lossy(seq2)
+    );
+}
+
+#[test]
+fn test_empty() {
+    let mut striped = sw::Striped::new(scheme(2, -3, -5, -1));
+    assert!(striped.scan(&b"ACGT".as_slice()).is_none());
+
+    striped.with_query(&b"ACGT".as_slice());
+    assert_eq!(striped.query(), b"ACGT");
+    assert!(striped.scan(&b"".as_slice()).is_none());
+    assert!(striped.scan(&b"NNNN".as_slice()).is_none());
+}
+
+#[test]
+fn test_random() {
+    for (equal, different, open, extend) in [
+        (2, -3, -5, -1),
+        (1, -1, -1, -1),
+        (5, -4, -10, -1),
+        (1, -2, -3, -3),
+    ] {
+        let mut engine = engine(scheme(equal, different, open, extend));
+        let mut striped = sw::Striped::new(scheme(equal, different, open, extend));
+
+        for seed in 0..64 {
+            // From queries shorter than the number of lanes to multiple segments per lane
+            let (len1, len2) = (1 + (seed as usize * 5) % 97, 1 + (seed as usize * 11) % 89);
+            let (seq1, seq2) = workload(seed, len1, len2);
+            ensure(&mut engine, &mut striped, &seq1, &seq2);
+            ensure(&mut engine, &mut striped, &seq2, &seq1);
+        }
+    }
+}
+
+#[test]
+fn test_profile_reuse() {
+    let mut engine = engine(scheme(2, -3, -5, -1));
+    let mut striped = sw::Striped::new(scheme(2, -3, -5, -1));
+
+    // The same query against many targets, some symbols appear only in later targets
+    let (query, _) = workload(7, 50, 1);
+    striped.with_query(&query);
+    for seed in 0..16 {
+        let (_, mut target) = workload(seed, 50, 70);
+        if seed % 2 == 1 {
+            target[seed as usize] = b'N';
+        }
+        let expected = engine.scan_all(&query, &target).pop().unwrap();
+        let result = striped.scan(&target).unwrap();
+        assert_eq!(result.score, *expected.score());
+        assert_eq!(result.row, expected.seq1().end - 1);
+        assert_eq!(result.col, expected.seq2().end - 1);
+    }
+
+    // Changing the scoring rebuilds the profile
+    engine.with_scoring(scheme(1, -1, -2, -1));
+    striped.with_scoring(scheme(1, -1, -2, -1));
+    let (_, target) = workload(3, 50, 70);
+    let expected = engine.scan_all(&query, &target).pop().unwrap();
+    assert_eq!(striped.scan(&target).unwrap().score, *expected.score());
+}
+
+#[test]
+fn test_overflow() {
+    for (equal, different, open, extend, len) in [
+        // Overflows i8 lanes
+        (2, -3, -5, -1, 400),
+        // Symbol scores don't fit into i8 lanes
+        (200, -300, -500, -100, 30),
+        // Overflows i16 lanes
+        (1000, -1500, -2000, -500, 300),
+        // Gap scores don't fit into i16 lanes
+        (1, -1, -100_000, -1, 40),
+    ] {
+        let mut engine = engine(scheme(equal, different, open, extend));
+        let mut striped = sw::Striped::new(scheme(equal, different, open, extend));
+        for seed in 0..4 {
+            let (seq1, seq2) = workload(seed, len, len);
+            ensure(&mut engine, &mut striped, &seq1, &seq2);
+        }
+
+        let seq = workload(11, len, 1).0;
+        ensure(&mut engine, &mut striped, &seq, &seq);
+    }
+}
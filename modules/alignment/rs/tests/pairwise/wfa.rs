@@ -0,0 +1,157 @@
+use biobit_alignment_rs::pairwise::alignment::{Alignment, Op};
+use biobit_alignment_rs::pairwise::wfa::Wavefront;
+use biobit_alignment_rs::pairwise::{scoring, sw};
+
+use super::common::{self, Engine, Score, scheme};
+use super::random::workload;
+
+fn engine(mismatch: Score, open: Score, extend: Score) -> Engine {
+    let mut engine = common::engine(scheme(0, mismatch, open, extend));
+    engine.with_mode(sw::Mode::Global(sw::algo::EndGaps::penalized()));
+    engine
+}
+
+fn wavefront(mismatch: Score, open: Score, extend: Score) -> Wavefront<Score> {
+    Wavefront::new(mismatch, &scoring::gaps::Affine { open, extend }).unwrap()
+}
+
+// The alignment must be consistent with the sequences and cost exactly its score
+fn ensure(
+    aln: &Alignment<Score, u8, usize, usize>,
+    seq1: &[u8],
+    seq2: &[u8],
+    (mismatch, open, extend): (Score, Score, Score),
+) {
+    assert_eq!(*aln.seq1(), 0..seq1.len());
+    assert_eq!(*aln.seq2(), 0..seq2.len());
+
+    let (mut i, mut j, mut score) = (0, 0, 0);
+    let mut previous = None;
+    for step in aln.steps() {
+        for _ in 0..*step.len() {
+            match step.op() {
+                Op::Match => {
+                    assert_eq!(seq1[i], seq2[j]);
+                    i += 1;
+                    j += 1;
+                }
+                Op::Mismatch => {
+                    assert_ne!(seq1[i], seq2[j]);
+                    score += mismatch;
+                    i += 1;
+                    j += 1;
+                }
+                Op::Equivalent => unreachable!(),
+                Op::GapFirst => i += 1,
+                Op::GapSecond => j += 1,
+            }
+            if matches!(step.op(), Op::GapFirst | Op::GapSecond) {
+                score += if previous == Some(*step.op()) {
+                    extend
+                } else {
+                    open
+                };
+            }
+            previous = Some(*step.op());
+        }
+    }
+    assert_eq!((i, j), (seq1.len(), seq2.len()));
+    assert_eq!(score, *aln.score());
+}
+
+#[test]
+fn test_wavefront() {
+    let mut wfa = wavefront(-4, -8, -2);
+    let aln = wfa.align(&b"ACGTACGT".as_slice(), &b"ACGTACGT".as_slice());
+    assert_eq!((*aln.score(), aln.rle()), (0, "8=".to_string()));
+
+    let aln = wfa.align(&b"ACGTACGT".as_slice(), &b"ACGAACGT".as_slice());
+    assert_eq!((*aln.score(), aln.rle()), (-4, "3=1X4=".to_string()));
+
+    // A single long gap is cheaper than many mismatches
+    let aln = wfa.align(
+        &b"GATTACAGATTACA".as_slice(),
+        &b"GATTACATTTTGATTACA".as_slice(),
+    );
+    assert_eq!(*aln.score(), -14);
+    assert_eq!(
+        wfa.score(
+            &b"GATTACAGATTACA".as_slice(),
+            &b"GATTACATTTTGATTACA".as_slice()
+        ),
+        -14
+    );
+    ensure(&aln, b"GATTACAGATTACA", b"GATTACATTTTGATTACA", (-4, -8, -2));
+
+    // Empty sequences are aligned with end gaps
+    assert_eq!(wfa.align(&b"".as_slice(), &b"".as_slice()).rle(), "");
+    let aln = wfa.align(&b"ACG".as_slice(), &b"".as_slice());
+    assert_eq!((*aln.score(), aln.rle()), (-12, "3v".to_string()));
+    let aln = wfa.align(&b"".as_slice(), &b"ACGTA".as_slice());
+    assert_eq!((*aln.score(), aln.rle()), (-16, "5^".to_string()));
+}
+
+#[test]
+fn test_invalid() {
+    for (mismatch, open, extend) in [(0, -5, -1), (-2, -5, 0), (-2, -1, -3), (3, -5, -1)] {
+        assert!(
+            Wavefront::new(mismatch, &scoring::gaps::Affine { open, extend }).is_err(),
+            "{mismatch} {open} {extend}"
+        );
+    }
+    assert!(
+        Wavefront::new(
+            -1,
+            &scoring::gaps::Affine {
+                open: -1,
+                extend: -1
+            }
+        )
+        .is_ok()
+    );
+}
+
+#[test]
+fn test_random() {
+    for penalties in [(-4, -8, -2), (-1, -1, -1), (-3, -10, -1), (-5, -5, -3)] {
+        let (mismatch, open, extend) = penalties;
+        let mut wfa = wavefront(mismatch, open, extend);
+        let mut engine = engine(mismatch, open, extend);
+
+        for (seed, len1, len2) in [
+            (1, 1, 1),
+            (2, 1, 10),
+            (3, 10, 1),
+            (4, 20, 25),
+            (5, 60, 50),
+            (6, 100, 110),
+            (7, 250, 240),
+            (8, 17, 180),
+        ] {
+            let (seq1, seq2) = workload(seed, len1, len2);
+            let expected = engine.scan_all(&seq1, &seq2).pop().unwrap();
+
+            let aln = wfa.align(&seq1, &seq2);
+            assert_eq!(aln.score(), expected.score(), "{penalties:?}, seed {seed}");
+            assert_eq!(wfa.score(&seq1, &seq2), *expected.score());
+            ensure(&aln, &seq1, &seq2, penalties);
+        }
+    }
+}
+
+#[test]
+fn test_similar() {
+    // Long sequences with a few differences
+    let (seq1, _) = workload(11, 20_000, 1);
+    let mut seq2 = seq1.clone();
+    seq2[1_000] = if seq2[1_000] == b'A' { b'C' } else { b'A' };
+    seq2.drain(5_000..5_010);
+    seq2.splice(12_000..12_000, b"TTTTT".iter().copied());
+    seq2.remove(17_000);
+
+    let mut wfa = wavefront(-4, -8, -2);
+    let aln = wfa.align(&seq1, &seq2);
+    ensure(&aln, &seq1, &seq2, (-4, -8, -2));
+    assert!(*aln.score() >= -4 - 26 - 16 - 8);
+    assert_eq!(aln.stats().gap_openings(), &(2, 1));
+}
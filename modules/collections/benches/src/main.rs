@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::hint::black_box;
+use std::io::{BufRead, BufReader};
+use std::time::{Duration, Instant};
+
+use flate2::read::MultiGzDecoder;
+
+use biobit_collections_rs::interval_tree::{BatchHits, Bits, Builder, IITree, ITree};
+use biobit_core_rs::loc::{Interval, IntervalOp};
+
+const BED: &str = "/home/alnfedorov/projects/biobit/resources/bed/gencode-basic-47.CHM13v2.bed.bgz";
+const REPEATS: usize = 5;
+
+fn read_bed(path: &str) -> HashMap<String, Vec<Interval<u64>>> {
+    let reader = File::open(path).expect("Failed to open BED file");
+    let mut reader = BufReader::new(MultiGzDecoder::new(reader));
+
+    let mut records = HashMap::new();
+    let mut buf = String::new();
+    while reader.read_line(&mut buf).expect("Failed to read BED file") != 0 {
+        let line = buf.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            buf.clear();
+            continue;
+        }
+        let split: Vec<&str> = line.split('\t').take(3).collect();
+        assert!(split.len() >= 3, "{}", line);
+
+        let start = split[1].parse().expect("Failed to parse start");
+        let end = split[2].parse().expect("Failed to parse end");
+        let interval = Interval::new(start, end).expect("Failed to create interval");
+
+        records
+            .entry(split[0].to_string())
+            .or_insert_with(Vec::new)
+            .push(interval);
+        buf.clear();
+    }
+    records
+}
+
+// Build trees for all contigs and query them with the given intervals, returns the best timings
+// and the total number of hits.
+fn run<B, T>(
+    builder: impl Fn() -> B,
+    annotation: &HashMap<String, Vec<Interval<u64>>>,
+    queries: &HashMap<String, Vec<Interval<u64>>>,
+) -> (Duration, Duration, usize)
+where
+    B: Builder<Target = T>,
+    T: ITree<Idx = u64, Data = usize>,
+{
+    let (mut build, mut query, mut hits) = (Duration::MAX, Duration::MAX, 0);
+    for _ in 0..REPEATS {
+        let start = Instant::now();
+        let trees: HashMap<_, _> = annotation
+            .iter()
+            .map(|(contig, intervals)| {
+                let tree = builder()
+                    .extend(intervals.iter().cloned().enumerate().map(|(i, x)| (x, i)))
+                    .build();
+                (contig, tree)
+            })
+            .collect();
+        build = build.min(start.elapsed());
+
+        let start = Instant::now();
+        let mut buffer = BatchHits::new();
+        hits = 0;
+        for (contig, queries) in queries {
+            if let Some(tree) = trees.get(contig) {
+                tree.batch_intersect_intervals(queries, &mut buffer);
+                hits += black_box(buffer.total_hits());
+            }
+        }
+        query = query.min(start.elapsed());
+    }
+    (build, query, hits)
+}
+
+fn report(
+    scenario: &str,
+    annotation: &HashMap<String, Vec<Interval<u64>>>,
+    queries: &HashMap<String, Vec<Interval<u64>>>,
+) {
+    println!("{scenario}:");
+    let bits = run(Bits::builder, annotation, queries);
+    let iitree = run(IITree::builder, annotation, queries);
+    assert_eq!(bits.2, iitree.2, "Trees reported different hits");
+
+    for (name, (build, query, hits)) in [("Bits", bits), ("IITree", iitree)] {
+        println!("\t{name:<8} build: {build:>12.3?}\tquery: {query:>12.3?}\thits: {hits}");
+    }
+}
+
+fn main() {
+    let annotation = read_bed(BED);
+    let total = annotation.values().map(|x| x.len()).sum::<usize>();
+    println!(
+        "Annotation: {} intervals, {} contigs",
+        total,
+        annotation.len()
+    );
+
+    // Annotation intervals queried against themselves
+    report("GENCODE vs GENCODE", &annotation, &annotation);
+
+    // Short windows tiled over every annotated region
+    let windows = annotation
+        .iter()
+        .map(|(contig, intervals)| {
+            let end = intervals.iter().map(|x| x.end()).max().unwrap_or(0);
+            let windows = (0..end)
+                .step_by(1_000)
+                .map(|x| Interval::new(x, x + 100).unwrap())
+                .collect();
+            (contig.clone(), windows)
+        })
+        .collect();
+    report("GENCODE vs 100bp windows", &annotation, &windows);
+
+    // A single whole-contig interval makes Bits scan the whole contig for each query
+    let partitioned = annotation
+        .iter()
+        .map(|(contig, intervals)| {
+            let mut intervals = intervals.clone();
+            let end = intervals.iter().map(|x| x.end()).max().unwrap_or(1);
+            intervals.push(Interval::new(0, end).unwrap());
+            (contig.clone(), intervals)
+        })
+        .collect();
+    report(
+        "GENCODE + whole-contig partitions vs 100bp windows",
+        &partitioned,
+        &windows,
+    );
+}
@@ -0,0 +1,132 @@
+//! Bitcode support for genomic arrays.
+//!
+//! Tracks keep invariants that bitcode derives can't check, so arrays are encoded as unchecked
+//! mirrors and converted back with [`TryFrom`], which validates them:
+//!
+//! ```ignore
+//! let bytes = bitcode::encode(&RawGenomicArray::from(array));
+//! let array: GenomicArray<_, _> = bitcode::decode::<RawGenomicArray<_, _>>(&bytes)?.try_into()?;
+//! ```
+
+use std::collections::BTreeMap;
+
+use ahash::HashMap;
+use bitcode::{Decode, Encode};
+use eyre::{Report, Result, ensure};
+
+use biobit_core_rs::loc::{Contig, PerOrientation};
+
+use super::genomic_array::GenomicArray;
+use super::track::{Storage, Track, run_ends};
+use crate::rle_vec::{Equal, RawRleVec, RleVec};
+
+/// Unchecked mirror of [`GenomicArray`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct RawGenomicArray<Ctg: Contig, V> {
+    pub default: V,
+    pub tracks: HashMap<Ctg, PerOrientation<RawTrack<V>>>,
+}
+
+/// Unchecked mirror of [`Track`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct RawTrack<V> {
+    pub length: u64,
+    pub storage: RawStorage<V>,
+}
+
+/// Values of a [`RawTrack`] in one of the track layouts.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum RawStorage<V> {
+    Dense(Vec<V>),
+    // Start -> (exclusive end, value)
+    Sparse(BTreeMap<u64, (u64, V)>),
+    Rle(RawRleVec<V, u32, Equal>),
+}
+
+impl<Ctg: Contig, V: PartialEq> From<GenomicArray<Ctg, V>> for RawGenomicArray<Ctg, V> {
+    fn from(array: GenomicArray<Ctg, V>) -> Self {
+        let (default, tracks) = array.dissolve();
+        Self {
+            default,
+            tracks: tracks
+                .into_iter()
+                .map(|(contig, tracks)| (contig, tracks.map(|_, track| track.into())))
+                .collect(),
+        }
+    }
+}
+
+impl<Ctg: Contig, V: PartialEq> TryFrom<RawGenomicArray<Ctg, V>> for GenomicArray<Ctg, V> {
+    type Error = Report;
+
+    fn try_from(raw: RawGenomicArray<Ctg, V>) -> Result<Self> {
+        let mut tracks = HashMap::default();
+        for (contig, raw) in raw.tracks {
+            let length = raw.forward.length;
+            ensure!(
+                raw.iter().all(|(_, track)| track.length == length),
+                "Tracks of contig {contig:?} must have the same length in all orientations"
+            );
+            tracks.insert(contig, raw.try_map(|_, track| track.try_into())?);
+        }
+        Ok(GenomicArray {
+            default: raw.default,
+            tracks,
+        })
+    }
+}
+
+impl<V: PartialEq> From<Track<V>> for RawTrack<V> {
+    fn from(track: Track<V>) -> Self {
+        let storage = match track.storage {
+            Storage::Dense(values) => RawStorage::Dense(values),
+            Storage::Sparse(intervals) => RawStorage::Sparse(intervals),
+            Storage::Rle { rle, .. } => RawStorage::Rle(rle.into()),
+        };
+        Self {
+            length: track.length,
+            storage,
+        }
+    }
+}
+
+impl<V: PartialEq> TryFrom<RawTrack<V>> for Track<V> {
+    type Error = Report;
+
+    fn try_from(raw: RawTrack<V>) -> Result<Self> {
+        let length = raw.length;
+        let storage = match raw.storage {
+            RawStorage::Dense(values) => {
+                ensure!(
+                    values.len() as u64 == length,
+                    "Dense track must store {length} values, got {}",
+                    values.len()
+                );
+                Storage::Dense(values)
+            }
+            RawStorage::Sparse(intervals) => {
+                let mut cursor = 0;
+                for (start, (end, _)) in &intervals {
+                    ensure!(
+                        cursor <= *start && start < end && *end <= length,
+                        "Sparse track intervals must be non-empty, sorted, non-overlapping and \
+                         fit into the track of length {length}"
+                    );
+                    cursor = *end;
+                }
+                Storage::Sparse(intervals)
+            }
+            RawStorage::Rle(raw) => {
+                let rle: RleVec<V, u32, Equal> = raw.try_into()?;
+                let ends = run_ends(&rle);
+                let total = ends.last().copied().unwrap_or(0);
+                ensure!(
+                    total == length,
+                    "RLE track runs must cover {length} positions, got {total}"
+                );
+                Storage::Rle { rle, ends }
+            }
+        };
+        Ok(Track { length, storage })
+    }
+}
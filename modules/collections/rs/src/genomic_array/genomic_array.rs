@@ -0,0 +1,428 @@
+use std::ops::Add;
+
+use ahash::HashMap;
+use derive_getters::Dissolve;
+use eyre::{Result, ensure, eyre};
+
+use biobit_core_rs::loc::{Contig, Interval, IntervalOp, Orientation, PerOrientation};
+
+use super::track::{Track, ensure_fits};
+
+/// Values assigned to every position of a genome, separately for each contig and orientation.
+///
+/// Each track starts empty, i.e. filled with the default value, and stores explicitly only
+/// positions that differ from it. Tracks switch to a dense layout once they become too populated,
+/// and [`GenomicArray::optimize`] can be used to pick the most compact layout (dense, sparse or
+/// run-length encoded) after the array is filled.
+#[derive(Debug, Clone, Dissolve)]
+pub struct GenomicArray<Ctg: Contig, V: PartialEq> {
+    pub(super) default: V,
+    pub(super) tracks: HashMap<Ctg, PerOrientation<Track<V>>>,
+}
+
+impl<Ctg: Contig, V: Clone + PartialEq> GenomicArray<Ctg, V> {
+    /// Create a new array filled with the default value for the given `(contig, length)` pairs.
+    pub fn new(default: V, contigs: impl IntoIterator<Item = (Ctg, u64)>) -> Result<Self> {
+        let mut tracks = HashMap::default();
+        for (contig, length) in contigs {
+            ensure!(
+                !tracks.contains_key(&contig),
+                "Duplicate contig in the genomic array: {contig:?}"
+            );
+            tracks.insert(contig, PerOrientation::with_fn(|_| Track::new(length)));
+        }
+        Ok(Self { default, tracks })
+    }
+
+    /// Value reported for positions that were never assigned.
+    pub fn default_value(&self) -> &V {
+        &self.default
+    }
+
+    /// Length of the given contig or `None` if the contig is unknown.
+    pub fn length(&self, contig: &Ctg) -> Option<u64> {
+        self.tracks
+            .get(contig)
+            .map(|x| x[Orientation::Forward].length())
+    }
+
+    /// Returns an iterator over all contigs in the array. The order is arbitrary.
+    pub fn contigs(&self) -> impl Iterator<Item = &Ctg> {
+        self.tracks.keys()
+    }
+
+    /// Track for the given contig and orientation or `None` if the contig is unknown.
+    pub fn track(&self, contig: &Ctg, orientation: Orientation) -> Option<&Track<V>> {
+        self.tracks.get(contig).map(|x| &x[orientation])
+    }
+
+    /// Value at the given position.
+    pub fn get(&self, contig: &Ctg, orientation: Orientation, pos: u64) -> Result<&V> {
+        let track = self.checked_track(contig, orientation)?;
+        ensure!(
+            pos < track.length(),
+            "Position {pos} is out of bounds for contig {contig:?} of length {}",
+            track.length()
+        );
+        Ok(track.get(pos, &self.default))
+    }
+
+    /// Copy values inside the given interval.
+    pub fn values(
+        &self,
+        contig: &Ctg,
+        orientation: Orientation,
+        interval: Interval<u64>,
+    ) -> Result<Vec<V>> {
+        let track = self.checked_track(contig, orientation)?;
+        ensure_fits(&interval, track.length())?;
+        Ok(track.values(interval, &self.default))
+    }
+
+    /// Iterate over maximal runs of identical values inside the given interval. Runs are clipped
+    /// to the interval boundaries.
+    pub fn runs(
+        &self,
+        contig: &Ctg,
+        orientation: Orientation,
+        interval: Interval<u64>,
+    ) -> Result<impl Iterator<Item = (Interval<u64>, &V)>> {
+        let track = self.checked_track(contig, orientation)?;
+        ensure_fits(&interval, track.length())?;
+
+        let (start, end) = (interval.start(), interval.end());
+        let iter = track
+            .runs(&self.default)
+            .skip_while(move |(it, _)| it.end() <= start)
+            .take_while(move |(it, _)| it.start() < end)
+            .map(move |(it, value)| {
+                let (start, end) = (it.start().max(start), it.end().min(end));
+                // SAFETY: the run intersects the interval, hence start < end
+                (unsafe { Interval::new_unchecked(start, end) }, value)
+            });
+        Ok(iter)
+    }
+
+    /// Assign the value to all positions inside the given interval.
+    pub fn set(
+        &mut self,
+        contig: &Ctg,
+        orientation: Orientation,
+        interval: Interval<u64>,
+        value: V,
+    ) -> Result<()> {
+        let track = Self::checked_track_mut(&mut self.tracks, contig, orientation)?;
+        ensure_fits(&interval, track.length())?;
+        track.set(interval, value, &self.default);
+        Ok(())
+    }
+
+    /// Add the value to all positions inside the given interval.
+    pub fn add(
+        &mut self,
+        contig: &Ctg,
+        orientation: Orientation,
+        interval: Interval<u64>,
+        value: V,
+    ) -> Result<()>
+    where
+        V: Add<Output = V>,
+    {
+        let track = Self::checked_track_mut(&mut self.tracks, contig, orientation)?;
+        ensure_fits(&interval, track.length())?;
+        track.add(interval, value, &self.default);
+        Ok(())
+    }
+
+    /// Convert every track into the layout with the smallest memory footprint.
+    pub fn optimize(&mut self) {
+        for tracks in self.tracks.values_mut() {
+            tracks.apply(|_, track| track.optimize(&self.default));
+        }
+    }
+
+    /// Returns an iterator over all runs holding non-default values as
+    /// `(contig, orientation, interval, value)` tuples. Runs are sorted within each track, but
+    /// the order of tracks is arbitrary.
+    pub fn iter(&self) -> impl Iterator<Item = (&Ctg, Orientation, Interval<u64>, &V)> {
+        self.tracks.iter().flat_map(move |(contig, tracks)| {
+            tracks.iter().flat_map(move |(orientation, track)| {
+                track
+                    .runs(&self.default)
+                    .filter(|(_, value)| *value != &self.default)
+                    .map(move |(interval, value)| (contig, orientation, interval, value))
+            })
+        })
+    }
+
+    fn checked_track(&self, contig: &Ctg, orientation: Orientation) -> Result<&Track<V>> {
+        self.track(contig, orientation)
+            .ok_or_else(|| eyre!("Contig {contig:?} is not present in the genomic array"))
+    }
+
+    fn checked_track_mut<'a>(
+        tracks: &'a mut HashMap<Ctg, PerOrientation<Track<V>>>,
+        contig: &Ctg,
+        orientation: Orientation,
+    ) -> Result<&'a mut Track<V>> {
+        tracks
+            .get_mut(contig)
+            .map(|x| &mut x[orientation])
+            .ok_or_else(|| eyre!("Contig {contig:?} is not present in the genomic array"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genomic_array::Layout;
+    use crate::random::Lcg;
+
+    // Straightforward dense model of a single track
+    fn apply(model: &mut [i32], interval: Interval<u64>, f: impl Fn(i32) -> i32) {
+        for x in &mut model[interval.start() as usize..interval.end() as usize] {
+            *x = f(*x);
+        }
+    }
+
+    fn assert_matches(array: &GenomicArray<&str, i32>, model: &[i32]) -> Result<()> {
+        let whole = Interval::new(0, model.len() as u64)?;
+        assert_eq!(array.values(&"1", Orientation::Forward, whole)?, model);
+        for (pos, expected) in model.iter().enumerate() {
+            assert_eq!(array.get(&"1", Orientation::Forward, pos as u64)?, expected);
+        }
+
+        let decoded = array
+            .runs(&"1", Orientation::Forward, whole)?
+            .flat_map(|(it, val)| std::iter::repeat_n(*val, it.len() as usize))
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, model);
+
+        let runs = array
+            .runs(&"1", Orientation::Forward, whole)?
+            .map(|(_, val)| *val)
+            .collect::<Vec<_>>();
+        assert!(runs.windows(2).all(|x| x[0] != x[1]), "{runs:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_genomic_array_updates() -> Result<()> {
+        let length = 50;
+        let mut array = GenomicArray::new(0, [("1", length), ("2", 10)])?;
+        let mut model = vec![0; length as usize];
+
+        let updates = [
+            (3, 7, 1, false),
+            (5, 12, 2, true),
+            (0, 1, 5, false),
+            (10, 11, 0, false),
+            (20, 50, 3, true),
+            (15, 25, -3, true),
+            (6, 8, 0, false),
+        ];
+        for layout in [Layout::Sparse, Layout::Dense, Layout::Rle] {
+            for (start, end, value, add) in updates {
+                let interval = Interval::new(start, end)?;
+                if add {
+                    array.add(&"1", Orientation::Forward, interval, value)?;
+                    apply(&mut model, interval, |x| x + value);
+                } else {
+                    array.set(&"1", Orientation::Forward, interval, value)?;
+                    apply(&mut model, interval, |_| value);
+                }
+                assert_matches(&array, &model)?;
+
+                array.tracks.get_mut(&"1").unwrap()[Orientation::Forward].convert(layout, &0);
+                assert_matches(&array, &model)?;
+            }
+            array.optimize();
+            assert_matches(&array, &model)?;
+        }
+
+        // Other tracks are untouched
+        for orientation in [Orientation::Reverse, Orientation::Dual] {
+            assert!(
+                array
+                    .values(&"1", orientation, Interval::new(0, length)?)?
+                    .iter()
+                    .all(|x| *x == 0)
+            );
+        }
+        assert_eq!(array.length(&"2"), Some(10));
+        assert_eq!(array.length(&"3"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_genomic_array_many_updates() -> Result<()> {
+        // Updates touch only the stored intervals/runs around them, not every covered position
+        let length = 1_000_000;
+        for (layout, updates) in [(Layout::Sparse, 100_000), (Layout::Rle, 10_000)] {
+            let mut array = GenomicArray::new(0, [("1", length)])?;
+            array.tracks.get_mut(&"1").unwrap()[Orientation::Forward].convert(layout, &0);
+            let mut model = vec![0; length as usize];
+
+            let mut lcg = Lcg::new(11);
+            for step in 0..updates {
+                let start = lcg.below(length - 1);
+                let end = (start + 1 + lcg.below(1_000)).min(length);
+                let interval = Interval::new(start, end)?;
+                let value = lcg.below(5) as i32;
+                if step % 3 == 0 {
+                    array.set(&"1", Orientation::Forward, interval, value)?;
+                    apply(&mut model, interval, |_| value);
+                } else {
+                    array.add(&"1", Orientation::Forward, interval, value - 2)?;
+                    apply(&mut model, interval, |x| x + value - 2);
+                }
+            }
+            assert_eq!(
+                array.track(&"1", Orientation::Forward).unwrap().layout(),
+                layout
+            );
+            assert_matches(&array, &model)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_genomic_array_iter() -> Result<()> {
+        let mut array = GenomicArray::new(0u32, [("1", 100), ("2", 100)])?;
+        array.set(&"1", Orientation::Forward, Interval::new(10, 20)?, 1)?;
+        array.add(&"1", Orientation::Forward, Interval::new(15, 30)?, 1)?;
+        array.set(&"2", Orientation::Reverse, Interval::new(90, 100)?, 7)?;
+        array.optimize();
+
+        let mut runs = array
+            .iter()
+            .map(|(ctg, orientation, it, val)| (*ctg, orientation, it, *val))
+            .collect::<Vec<_>>();
+        runs.sort();
+        assert_eq!(
+            runs,
+            vec![
+                ("1", Orientation::Forward, Interval::new(10, 15)?, 1),
+                ("1", Orientation::Forward, Interval::new(15, 20)?, 2),
+                ("1", Orientation::Forward, Interval::new(20, 30)?, 1),
+                ("2", Orientation::Reverse, Interval::new(90, 100)?, 7),
+            ]
+        );
+
+        let clipped = array
+            .runs(&"1", Orientation::Forward, Interval::new(12, 25)?)?
+            .map(|(it, val)| (it, *val))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            clipped,
+            vec![
+                (Interval::new(12, 15)?, 1),
+                (Interval::new(15, 20)?, 2),
+                (Interval::new(20, 25)?, 1),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_genomic_array_long_contig() -> Result<()> {
+        // Runs longer than u32::MAX must be split in the RLE layout
+        let length = 10_000_000_000;
+        let mut array = GenomicArray::new(0u8, [("1", length)])?;
+        let track = &mut array.tracks.get_mut(&"1").unwrap()[Orientation::Forward];
+        track.convert(Layout::Rle, &0);
+        array.add(&"1", Orientation::Forward, Interval::new(1, length - 1)?, 1)?;
+        for layout in [Layout::Rle, Layout::Sparse] {
+            array.tracks.get_mut(&"1").unwrap()[Orientation::Forward].convert(layout, &0);
+            for (pos, expected) in [(0, 0), (1, 1), (length / 2, 1), (length - 1, 0)] {
+                assert_eq!(*array.get(&"1", Orientation::Forward, pos)?, expected);
+            }
+            assert_eq!(
+                array.iter().map(|(_, _, it, _)| it).collect::<Vec<_>>(),
+                vec![Interval::new(1, length - 1)?]
+            );
+        }
+        // A single interval is stored most compactly in the sparse layout
+        array.optimize();
+        assert_eq!(
+            array.track(&"1", Orientation::Forward).unwrap().layout(),
+            Layout::Sparse
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "bitcode")]
+    fn test_genomic_array_bitcode() -> Result<()> {
+        use crate::genomic_array::{RawGenomicArray, RawStorage};
+
+        let mut array = GenomicArray::new(0, [("1", 30), ("2", 10)])?;
+        array.set(&"1", Orientation::Forward, Interval::new(3, 7)?, 1)?;
+        array.add(&"1", Orientation::Forward, Interval::new(5, 20)?, 2)?;
+        array.set(&"2", Orientation::Dual, Interval::new(0, 4)?, 5)?;
+        let track = &mut array.tracks.get_mut(&"1").unwrap();
+        track[Orientation::Forward].convert(Layout::Rle, &0);
+        track[Orientation::Reverse].convert(Layout::Dense, &0);
+
+        let bytes = bitcode::encode(&RawGenomicArray::from(array.clone()));
+        let decoded: GenomicArray<&str, i32> =
+            bitcode::decode::<RawGenomicArray<&str, i32>>(&bytes)?.try_into()?;
+        let mut expected = array.iter().collect::<Vec<_>>();
+        let mut actual = decoded.iter().collect::<Vec<_>>();
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected);
+        for (contig, orientation) in [("1", Orientation::Forward), ("1", Orientation::Reverse)] {
+            assert_eq!(
+                decoded.track(&contig, orientation).unwrap().layout(),
+                array.track(&contig, orientation).unwrap().layout()
+            );
+        }
+
+        // Tracks that don't match their length are rejected
+        let raw = RawGenomicArray::from(array);
+        let mut broken = raw.clone();
+        broken.tracks.get_mut(&"1").unwrap().reverse.length = 31;
+        assert!(GenomicArray::try_from(broken).is_err());
+        for (orientation, storage) in [
+            (Orientation::Forward, RawStorage::Dense(vec![0; 29])),
+            (
+                Orientation::Reverse,
+                RawStorage::Sparse([(0, (5, 1)), (4, (8, 2))].into()),
+            ),
+            (
+                Orientation::Dual,
+                RawStorage::Sparse([(25, (31, 1))].into()),
+            ),
+        ] {
+            let mut broken = raw.clone();
+            broken.tracks.get_mut(&"1").unwrap()[orientation].storage = storage;
+            assert!(GenomicArray::try_from(broken).is_err());
+        }
+        let mut broken = raw;
+        match &mut broken.tracks.get_mut(&"1").unwrap().forward.storage {
+            RawStorage::Rle(rle) => rle.lengths[0] += 1,
+            _ => unreachable!(),
+        }
+        assert!(GenomicArray::try_from(broken).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_genomic_array_errors() -> Result<()> {
+        assert!(GenomicArray::new(0, [("1", 10), ("1", 20)]).is_err());
+
+        let mut array = GenomicArray::new(0, [("1", 10)])?;
+        let interval = Interval::new(5, 11)?;
+        assert!(array.set(&"1", Orientation::Forward, interval, 1).is_err());
+        assert!(
+            array
+                .add(&"2", Orientation::Forward, Interval::new(0, 1)?, 1)
+                .is_err()
+        );
+        assert!(array.get(&"1", Orientation::Dual, 10).is_err());
+        assert!(array.values(&"1", Orientation::Dual, interval).is_err());
+        assert!(array.runs(&"1", Orientation::Dual, interval).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,11 @@
+pub use genomic_array::GenomicArray;
+pub use track::{Layout, Track};
+
+#[cfg(feature = "bitcode")]
+pub use codec::{RawGenomicArray, RawStorage, RawTrack};
+
+#[cfg(feature = "bitcode")]
+mod codec;
+#[allow(clippy::module_inception)]
+mod genomic_array;
+mod track;
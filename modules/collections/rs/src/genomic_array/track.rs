@@ -0,0 +1,374 @@
+use std::collections::BTreeMap;
+use std::mem::size_of;
+use std::ops::Add;
+
+use eyre::{Result, ensure};
+
+use biobit_core_rs::loc::{Interval, IntervalOp};
+
+use crate::rle_vec::{Equal, RleVec};
+
+#[cfg(feature = "bitcode")]
+use bitcode::{Decode, Encode};
+
+/// Physical layout of values along a single track.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layout {
+    /// One value per position.
+    Dense,
+    /// Non-overlapping intervals holding non-default values.
+    Sparse,
+    /// Run-length encoded values.
+    Rle,
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum Storage<V: PartialEq> {
+    Dense(Vec<V>),
+    // Start -> (exclusive end, value) of maximal intervals with non-default values
+    Sparse(BTreeMap<u64, (u64, V)>),
+    Rle {
+        rle: RleVec<V, u32, Equal>,
+        // Exclusive end of each run, used for O(log n) random access
+        ends: Vec<u64>,
+    },
+}
+
+/// Values for every position of a single contig and orientation. Positions not stored
+/// explicitly hold the default value of the parent array.
+#[derive(Debug, Clone)]
+pub struct Track<V: PartialEq> {
+    pub(super) length: u64,
+    pub(super) storage: Storage<V>,
+}
+
+impl<V: Clone + PartialEq> Track<V> {
+    pub(super) fn new(length: u64) -> Self {
+        Self {
+            length,
+            storage: Storage::Sparse(BTreeMap::new()),
+        }
+    }
+
+    /// Number of positions in the track.
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// Current physical layout of the track.
+    pub fn layout(&self) -> Layout {
+        match self.storage {
+            Storage::Dense(_) => Layout::Dense,
+            Storage::Sparse(_) => Layout::Sparse,
+            Storage::Rle { .. } => Layout::Rle,
+        }
+    }
+
+    pub(super) fn get<'a>(&'a self, pos: u64, default: &'a V) -> &'a V {
+        debug_assert!(pos < self.length);
+        match &self.storage {
+            Storage::Dense(values) => &values[pos as usize],
+            Storage::Sparse(intervals) => match intervals.range(..=pos).next_back() {
+                Some((_, (end, value))) if pos < *end => value,
+                _ => default,
+            },
+            Storage::Rle { rle, ends } => &rle.values[ends.partition_point(|x| *x <= pos)],
+        }
+    }
+
+    /// Iterate over maximal runs of identical values covering the whole track.
+    pub(super) fn runs<'a>(
+        &'a self,
+        default: &'a V,
+    ) -> Box<dyn Iterator<Item = (Interval<u64>, &'a V)> + 'a> {
+        let runs: Box<dyn Iterator<Item = (u64, u64, &V)>> = match &self.storage {
+            Storage::Dense(values) => Box::new(
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(pos, value)| (pos as u64, pos as u64 + 1, value)),
+            ),
+            Storage::Sparse(intervals) => {
+                let mut cursor = 0;
+                let stored = intervals.iter().flat_map(move |(start, (end, value))| {
+                    let gap = (cursor < *start).then_some((cursor, *start, default));
+                    cursor = *end;
+                    gap.into_iter().chain([(*start, *end, value)])
+                });
+                let tail = intervals.last_key_value().map_or(0, |(_, (end, _))| *end);
+                Box::new(stored.chain((tail < self.length).then_some((tail, self.length, default))))
+            }
+            Storage::Rle { rle, ends } => Box::new(
+                ends.iter()
+                    .zip(rle.runs())
+                    .map(|(end, (value, length))| (end - *length as u64, *end, value))
+                    .filter(|(start, end, _)| start < end),
+            ),
+        };
+        Box::new(Coalesce {
+            inner: runs,
+            current: None,
+        })
+    }
+
+    /// Copy values in the interval into a dense vector.
+    pub(super) fn values(&self, interval: Interval<u64>, default: &V) -> Vec<V> {
+        match &self.storage {
+            Storage::Dense(values) => {
+                values[interval.start() as usize..interval.end() as usize].to_vec()
+            }
+            _ => (interval.start()..interval.end())
+                .map(|pos| self.get(pos, default).clone())
+                .collect(),
+        }
+    }
+
+    pub(super) fn set(&mut self, interval: Interval<u64>, value: V, default: &V) {
+        self.update(interval, default, |_| value.clone());
+    }
+
+    pub(super) fn add(&mut self, interval: Interval<u64>, value: V, default: &V)
+    where
+        V: Add<Output = V>,
+    {
+        self.update(interval, default, |x| x.clone() + value.clone());
+    }
+
+    /// Switch to the layout with the smallest memory footprint.
+    pub(super) fn optimize(&mut self, default: &V) {
+        let (mut nondefault, mut runs) = (0u64, 0u64);
+        for (_, value) in self.runs(default) {
+            runs += 1;
+            if value != default {
+                nondefault += 1;
+            }
+        }
+
+        let value = size_of::<V>() as u64;
+        let layout = [
+            (self.length * value, Layout::Dense),
+            (nondefault * sparse_entry::<V>(), Layout::Sparse),
+            (
+                runs * (value + (size_of::<u32>() + size_of::<u64>()) as u64),
+                Layout::Rle,
+            ),
+        ]
+        .into_iter()
+        .min_by_key(|(size, _)| *size)
+        .unwrap()
+        .1;
+        self.convert(layout, default);
+    }
+
+    /// Convert the track into the given layout.
+    pub(super) fn convert(&mut self, layout: Layout, default: &V) {
+        if self.layout() == layout {
+            return;
+        }
+
+        let storage = match layout {
+            Layout::Dense => {
+                let mut values = Vec::with_capacity(self.length as usize);
+                for (interval, value) in self.runs(default) {
+                    values.extend(std::iter::repeat_n(value.clone(), interval.len() as usize));
+                }
+                Storage::Dense(values)
+            }
+            Layout::Sparse => Storage::Sparse(
+                self.runs(default)
+                    .filter(|(_, x)| *x != default)
+                    .map(|(it, value)| (it.start(), (it.end(), value.clone())))
+                    .collect(),
+            ),
+            Layout::Rle => {
+                let mut rle = RleVec::builder(Equal).build();
+                for (interval, value) in self.runs(default) {
+                    push_rle(&mut rle, value.clone(), interval.len());
+                }
+                let ends = run_ends(&rle);
+                Storage::Rle { rle, ends }
+            }
+        };
+        self.storage = storage;
+    }
+
+    // Replace values inside the interval with f(old value)
+    fn update(&mut self, interval: Interval<u64>, default: &V, mut f: impl FnMut(&V) -> V) {
+        debug_assert!(interval.end() <= self.length);
+        let (start, end) = (interval.start(), interval.end());
+
+        match &mut self.storage {
+            Storage::Dense(values) => {
+                for value in &mut values[start as usize..end as usize] {
+                    *value = f(value);
+                }
+            }
+            Storage::Sparse(intervals) => {
+                // Stored intervals overlapping or touching the updated one, touching intervals
+                // are removed as well to merge them with the new values when possible
+                let touched = intervals
+                    .range(..=end)
+                    .rev()
+                    .take_while(|(_, (stop, _))| *stop >= start)
+                    .map(|(start, _)| *start)
+                    .collect::<Vec<_>>();
+                let stored = touched
+                    .into_iter()
+                    .rev()
+                    .map(|x| {
+                        let (stop, value) = intervals.remove(&x).unwrap();
+                        (x, stop, value)
+                    })
+                    .collect::<Vec<_>>();
+
+                for (start, end, value) in rewrite(stored, start, end, default, f) {
+                    if value != *default {
+                        intervals.insert(start, (end, value));
+                    }
+                }
+
+                // Sparse storage is only worth it while most positions hold the default value
+                if intervals.len() as u64 * sparse_entry::<V>()
+                    > self.length * size_of::<V>() as u64
+                {
+                    self.convert(Layout::Dense, default);
+                }
+            }
+            Storage::Rle { rle, ends } => {
+                // Runs overlapping the interval and their neighbours that might be merged
+                let lo = ends.partition_point(|x| *x < start);
+                let hi = (ends.partition_point(|x| *x < end) + 2).min(ends.len());
+                let stored = (lo..hi)
+                    .map(|run| {
+                        let length = rle.lengths[run] as u64;
+                        (ends[run] - length, ends[run], rle.values[run].clone())
+                    })
+                    .collect::<Vec<_>>();
+
+                let (mut values, mut lengths, mut newends) = (Vec::new(), Vec::new(), Vec::new());
+                for (mut start, end, value) in rewrite(stored, start, end, default, f) {
+                    // Runs longer than u32::MAX are split
+                    while start < end {
+                        let length = (end - start).min(u32::MAX as u64);
+                        start += length;
+                        values.push(value.clone());
+                        lengths.push(length as u32);
+                        newends.push(start);
+                    }
+                }
+                rle.values.splice(lo..hi, values);
+                rle.lengths.splice(lo..hi, lengths);
+                ends.splice(lo..hi, newends);
+            }
+        }
+    }
+}
+
+// Memory used by a single interval in the sparse layout
+fn sparse_entry<V>() -> u64 {
+    (2 * size_of::<u64>() + size_of::<V>()) as u64
+}
+
+// Apply f to values of the stored intervals inside [start, end) and to the default value of
+// gaps between them. Parts of the stored intervals outside [start, end) are kept as is. Stored
+// intervals must be sorted and cover a contiguous range when combined with [start, end). Returns
+// contiguous intervals with adjacent equal values merged.
+fn rewrite<V: Clone + PartialEq>(
+    stored: Vec<(u64, u64, V)>,
+    start: u64,
+    end: u64,
+    default: &V,
+    mut f: impl FnMut(&V) -> V,
+) -> Vec<(u64, u64, V)> {
+    let mut pieces = Vec::with_capacity(stored.len() * 2 + 1);
+    let mut cursor = start;
+    for (lo, hi, value) in stored {
+        let (inside_lo, inside_hi) = (lo.clamp(start, end), hi.clamp(start, end));
+        if lo < start {
+            pieces.push((lo, hi.min(start), value.clone()));
+        }
+        if cursor < inside_lo {
+            pieces.push((cursor, inside_lo, f(default)));
+            cursor = inside_lo;
+        }
+        if inside_lo < inside_hi {
+            pieces.push((inside_lo, inside_hi, f(&value)));
+            cursor = inside_hi;
+        }
+        if hi > end {
+            pieces.push((lo.max(end), hi, value));
+        }
+    }
+    if cursor < end {
+        pieces.push((cursor, end, f(default)));
+    }
+
+    let mut result: Vec<(u64, u64, V)> = Vec::with_capacity(pieces.len());
+    for (lo, hi, value) in pieces {
+        match result.last_mut() {
+            Some(last) if last.1 == lo && last.2 == value => last.1 = hi,
+            _ => result.push((lo, hi, value)),
+        }
+    }
+    result
+}
+
+/// Check that the interval fits into the track.
+pub(super) fn ensure_fits(interval: &Interval<u64>, length: u64) -> Result<()> {
+    ensure!(
+        interval.end() <= length,
+        "Interval {interval} is out of bounds for a track of length {length}"
+    );
+    Ok(())
+}
+
+// Append a run of arbitrary length splitting it into chunks that fit into u32
+fn push_rle<V: Clone + PartialEq>(rle: &mut RleVec<V, u32, Equal>, value: V, mut length: u64) {
+    while length > 0 {
+        let chunk = length.min(u32::MAX as u64);
+        rle.append(value.clone(), chunk as u32);
+        length -= chunk;
+    }
+}
+
+pub(super) fn run_ends<V: PartialEq>(rle: &RleVec<V, u32, Equal>) -> Vec<u64> {
+    let mut end = 0;
+    rle.lengths
+        .iter()
+        .map(|x| {
+            end += *x as u64;
+            end
+        })
+        .collect()
+}
+
+// Merge adjacent runs with equal values
+struct Coalesce<'a, V, I: Iterator<Item = (u64, u64, &'a V)>> {
+    inner: I,
+    current: Option<(u64, u64, &'a V)>,
+}
+
+impl<'a, V: PartialEq, I: Iterator<Item = (u64, u64, &'a V)>> Iterator for Coalesce<'a, V, I> {
+    type Item = (Interval<u64>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match (self.current.take(), self.inner.next()) {
+                (None, None) => return None,
+                (None, Some(next)) => self.current = Some(next),
+                (Some((start, end, value)), None) => {
+                    return Some((Interval::new(start, end).unwrap(), value));
+                }
+                (Some((start, end, value)), Some((nstart, nend, nvalue))) => {
+                    if end == nstart && value == nvalue {
+                        self.current = Some((start, nend, value));
+                    } else {
+                        self.current = Some((nstart, nend, nvalue));
+                        return Some((Interval::new(start, end).unwrap(), value));
+                    }
+                }
+            }
+        }
+    }
+}
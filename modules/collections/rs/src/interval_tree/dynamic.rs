@@ -0,0 +1,713 @@
+//! Implementation of a mutable interval tree: an AVL tree ordered by interval start, where each
+//! node is augmented with the maximum end coordinate of its subtree.
+//!
+//! Nodes live in an arena and are addressed by [`Handle`]s returned on insertion. Handles stay
+//! valid until the corresponding entry is removed; stale handles are detected and ignored.
+
+use super::results::{BatchHits, Hits};
+use super::tree::{Builder, ITree};
+use biobit_core_rs::{
+    loc::{Interval, IntervalOp},
+    num::PrimInt,
+};
+use derive_getters::Dissolve;
+use derive_more::From;
+use std::cmp::Ordering;
+
+#[cfg(feature = "bitcode")]
+use bitcode::{Decode, Encode};
+
+// Marks missing children and the end of the free list
+const NIL: usize = usize::MAX;
+
+// Maximum height of an AVL tree with 2^64 nodes is below 1.45 * 64
+const MAX_HEIGHT: usize = 96;
+
+/// A builder for constructing [`Dynamic`] interval trees.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, From, Dissolve)]
+pub struct DynamicBuilder<Idx: PrimInt, Data> {
+    records: Vec<(Interval<Idx>, Data)>,
+}
+
+impl<Idx: PrimInt, Data> Default for DynamicBuilder<Idx, Data> {
+    fn default() -> Self {
+        Self {
+            records: Vec::new(),
+        }
+    }
+}
+
+impl<Idx: PrimInt, Data> Builder for DynamicBuilder<Idx, Data> {
+    type Target = Dynamic<Idx, Data>;
+
+    fn add(
+        mut self,
+        interval: Interval<<Self::Target as ITree>::Idx>,
+        data: <Self::Target as ITree>::Data,
+    ) -> Self {
+        self.records.push((interval, data));
+        self
+    }
+
+    fn extend(
+        mut self,
+        records: impl IntoIterator<
+            Item = (
+                Interval<<Self::Target as ITree>::Idx>,
+                <Self::Target as ITree>::Data,
+            ),
+        >,
+    ) -> Self {
+        self.records.extend(records);
+        self
+    }
+
+    fn build(self) -> Self::Target {
+        Dynamic::new(self.records)
+    }
+}
+
+/// A stable reference to an entry of the [`Dynamic`] interval tree.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Handle {
+    slot: usize,
+    generation: u32,
+}
+
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Node<Idx: PrimInt, Data> {
+    interval: Interval<Idx>,
+    data: Data,
+    // The maximum end coordinate in the subtree rooted at this node.
+    max_end: Idx,
+    height: u8,
+    left: usize,
+    right: usize,
+    generation: u32,
+}
+
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Slot<Idx: PrimInt, Data> {
+    Occupied(Node<Idx, Data>),
+    Vacant { next: usize, generation: u32 },
+}
+
+/// A mutable interval tree supporting insertion, removal, and in-place updates in `O(log n)` time.
+/// Queries take `O(min(n, k log n))` time, where `k` is the number of reported entries: every
+/// reported node may require a separate descent in the max-end augmented tree.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dynamic<Idx: PrimInt, Data> {
+    slots: Vec<Slot<Idx, Data>>,
+    root: usize,
+    // Head of the vacant slots list.
+    free: usize,
+    len: usize,
+}
+
+impl<Idx: PrimInt, Data> Default for Dynamic<Idx, Data> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            root: NIL,
+            free: NIL,
+            len: 0,
+        }
+    }
+}
+
+impl<Idx: PrimInt, Data> Dynamic<Idx, Data> {
+    /// Creates a new `Dynamic` interval tree from an iterator of `(Interval, Data)` pairs. The tree
+    /// is bulk-loaded and perfectly balanced.
+    pub fn new(iter: impl IntoIterator<Item = (Interval<Idx>, Data)>) -> Self {
+        let mut slots: Vec<_> = iter
+            .into_iter()
+            .map(|(interval, data)| {
+                Slot::Occupied(Node {
+                    interval,
+                    data,
+                    max_end: interval.end(),
+                    height: 1,
+                    left: NIL,
+                    right: NIL,
+                    generation: 0,
+                })
+            })
+            .collect();
+        slots.sort_by_key(|x| match x {
+            Slot::Occupied(node) => (node.interval.start(), node.interval.end()),
+            Slot::Vacant { .. } => unreachable!(),
+        });
+
+        let mut tree = Self {
+            len: slots.len(),
+            slots,
+            root: NIL,
+            free: NIL,
+        };
+        tree.root = tree.bulk_load(0, tree.len);
+        tree
+    }
+
+    // Link sorted slots in the [start, end) range into a balanced subtree
+    fn bulk_load(&mut self, start: usize, end: usize) -> usize {
+        if start >= end {
+            return NIL;
+        }
+        let mid = start + (end - start) / 2;
+        let left = self.bulk_load(start, mid);
+        let right = self.bulk_load(mid + 1, end);
+
+        let node = self.node_mut(mid);
+        node.left = left;
+        node.right = right;
+        self.refresh(mid);
+        mid
+    }
+
+    /// Inserts a new entry and returns a handle to it.
+    pub fn insert(&mut self, interval: Interval<Idx>, data: Data) -> Handle {
+        let (slot, generation) = match self.free {
+            NIL => {
+                self.slots.push(Slot::Vacant {
+                    next: NIL,
+                    generation: 0,
+                });
+                (self.slots.len() - 1, 0)
+            }
+            slot => match self.slots[slot] {
+                Slot::Vacant { next, generation } => {
+                    self.free = next;
+                    (slot, generation)
+                }
+                Slot::Occupied(_) => unreachable!("Free list points to an occupied slot"),
+            },
+        };
+        self.slots[slot] = Slot::Occupied(Node {
+            interval,
+            data,
+            max_end: interval.end(),
+            height: 1,
+            left: NIL,
+            right: NIL,
+            generation,
+        });
+        self.root = self.attach(self.root, slot);
+        self.len += 1;
+
+        Handle { slot, generation }
+    }
+
+    /// Removes the entry with the given handle and returns it. Returns `None` if the handle is
+    /// stale, i.e., the entry was already removed.
+    pub fn remove(&mut self, handle: Handle) -> Option<(Interval<Idx>, Data)> {
+        self.get(handle)?;
+        self.root = self.detach(self.root, handle.slot);
+        self.len -= 1;
+
+        let vacant = Slot::Vacant {
+            next: self.free,
+            generation: handle.generation.wrapping_add(1),
+        };
+        self.free = handle.slot;
+        match std::mem::replace(&mut self.slots[handle.slot], vacant) {
+            Slot::Occupied(node) => Some((node.interval, node.data)),
+            Slot::Vacant { .. } => unreachable!(),
+        }
+    }
+
+    /// Moves the entry with the given handle to a new interval, keeping the handle valid. Returns
+    /// the previous interval or `None` if the handle is stale.
+    pub fn update(&mut self, handle: Handle, interval: Interval<Idx>) -> Option<Interval<Idx>> {
+        self.get(handle)?;
+        self.root = self.detach(self.root, handle.slot);
+
+        let node = self.node_mut(handle.slot);
+        let previous = std::mem::replace(&mut node.interval, interval);
+        (node.left, node.right, node.height) = (NIL, NIL, 1);
+        node.max_end = interval.end();
+        self.root = self.attach(self.root, handle.slot);
+        Some(previous)
+    }
+
+    /// Returns the entry with the given handle or `None` if the handle is stale.
+    pub fn get(&self, handle: Handle) -> Option<(Interval<Idx>, &Data)> {
+        match self.slots.get(handle.slot)? {
+            Slot::Occupied(node) if node.generation == handle.generation => {
+                Some((node.interval, &node.data))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the data of the entry with the given handle or `None` if
+    /// the handle is stale.
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut Data> {
+        match self.slots.get_mut(handle.slot)? {
+            Slot::Occupied(node) if node.generation == handle.generation => Some(&mut node.data),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the entry with the given handle is present in the tree.
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.get(handle).is_some()
+    }
+
+    /// Removes all entries from the tree. All previously issued handles become stale.
+    pub fn clear(&mut self) {
+        for slot in 0..self.slots.len() {
+            if let Slot::Occupied(node) = &self.slots[slot] {
+                self.slots[slot] = Slot::Vacant {
+                    next: self.free,
+                    generation: node.generation.wrapping_add(1),
+                };
+                self.free = slot;
+            }
+        }
+        self.root = NIL;
+        self.len = 0;
+    }
+
+    /// Creates an iterator over entries overlapping the given interval.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval`: The interval to query for overlapping entries.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding `(Handle, Interval<Idx>, &'tree Data)` tuples for overlapping entries.
+    #[inline]
+    pub fn query(&self, interval: Interval<Idx>) -> Iter<'_, Idx, Data> {
+        Iter {
+            cursor: QueryCursor::new(interval, self.root),
+            tree: self,
+        }
+    }
+
+    /// Returns an iterator over all entries and their handles in the tree.
+    pub fn iter(&self) -> impl Iterator<Item = (Handle, Interval<Idx>, &Data)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, x)| match x {
+                Slot::Occupied(node) => Some((
+                    Handle {
+                        slot,
+                        generation: node.generation,
+                    },
+                    node.interval,
+                    &node.data,
+                )),
+                Slot::Vacant { .. } => None,
+            })
+    }
+
+    /// Returns the number of intervals stored in the tree.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the tree contains no intervals.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a builder for constructing a new `Dynamic` interval tree.
+    pub fn builder() -> DynamicBuilder<Idx, Data> {
+        DynamicBuilder::default()
+    }
+
+    #[inline(always)]
+    fn node(&self, slot: usize) -> &Node<Idx, Data> {
+        match &self.slots[slot] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("Tree links point to a vacant slot"),
+        }
+    }
+
+    #[inline(always)]
+    fn node_mut(&mut self, slot: usize) -> &mut Node<Idx, Data> {
+        match &mut self.slots[slot] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("Tree links point to a vacant slot"),
+        }
+    }
+
+    #[inline(always)]
+    fn height(&self, slot: usize) -> u8 {
+        if slot == NIL {
+            0
+        } else {
+            self.node(slot).height
+        }
+    }
+
+    // Total order over entries: by start, end, and slot index to break ties
+    #[inline(always)]
+    fn cmp(&self, a: usize, b: usize) -> Ordering {
+        let (x, y) = (&self.node(a).interval, &self.node(b).interval);
+        (x.start(), x.end(), a).cmp(&(y.start(), y.end(), b))
+    }
+
+    // Recompute the height and the max end of the node from its children
+    #[inline(always)]
+    fn refresh(&mut self, slot: usize) {
+        let node = self.node(slot);
+        let (left, right) = (node.left, node.right);
+
+        let mut max_end = node.interval.end();
+        for child in [left, right] {
+            if child != NIL {
+                max_end = max_end.max(self.node(child).max_end);
+            }
+        }
+        let height = 1 + self.height(left).max(self.height(right));
+
+        let node = self.node_mut(slot);
+        node.max_end = max_end;
+        node.height = height;
+    }
+
+    fn rotate_right(&mut self, slot: usize) -> usize {
+        let pivot = self.node(slot).left;
+        self.node_mut(slot).left = self.node(pivot).right;
+        self.refresh(slot);
+        self.node_mut(pivot).right = slot;
+        self.refresh(pivot);
+        pivot
+    }
+
+    fn rotate_left(&mut self, slot: usize) -> usize {
+        let pivot = self.node(slot).right;
+        self.node_mut(slot).right = self.node(pivot).left;
+        self.refresh(slot);
+        self.node_mut(pivot).left = slot;
+        self.refresh(pivot);
+        pivot
+    }
+
+    // Restore the AVL invariant for the subtree and return its new root
+    fn rebalance(&mut self, slot: usize) -> usize {
+        self.refresh(slot);
+        let (left, right) = (self.node(slot).left, self.node(slot).right);
+        let balance = self.height(left) as i16 - self.height(right) as i16;
+
+        if balance > 1 {
+            let node = self.node(left);
+            if self.height(node.left) < self.height(node.right) {
+                let rotated = self.rotate_left(left);
+                self.node_mut(slot).left = rotated;
+            }
+            self.rotate_right(slot)
+        } else if balance < -1 {
+            let node = self.node(right);
+            if self.height(node.right) < self.height(node.left) {
+                let rotated = self.rotate_right(right);
+                self.node_mut(slot).right = rotated;
+            }
+            self.rotate_left(slot)
+        } else {
+            slot
+        }
+    }
+
+    // Insert a detached node into the subtree and return its new root
+    fn attach(&mut self, root: usize, slot: usize) -> usize {
+        if root == NIL {
+            return slot;
+        }
+        if self.cmp(slot, root) == Ordering::Less {
+            let left = self.attach(self.node(root).left, slot);
+            self.node_mut(root).left = left;
+        } else {
+            let right = self.attach(self.node(root).right, slot);
+            self.node_mut(root).right = right;
+        }
+        self.rebalance(root)
+    }
+
+    // Unlink the node from the subtree and return its new root
+    fn detach(&mut self, root: usize, slot: usize) -> usize {
+        debug_assert!(root != NIL, "Node is missing from the tree");
+        match self.cmp(slot, root) {
+            Ordering::Less => {
+                let left = self.detach(self.node(root).left, slot);
+                self.node_mut(root).left = left;
+                self.rebalance(root)
+            }
+            Ordering::Greater => {
+                let right = self.detach(self.node(root).right, slot);
+                self.node_mut(root).right = right;
+                self.rebalance(root)
+            }
+            Ordering::Equal => {
+                let (left, right) = (self.node(root).left, self.node(root).right);
+                if left == NIL {
+                    return right;
+                }
+                if right == NIL {
+                    return left;
+                }
+                let (right, successor) = self.detach_min(right);
+                let node = self.node_mut(successor);
+                node.left = left;
+                node.right = right;
+                self.rebalance(successor)
+            }
+        }
+    }
+
+    // Unlink the leftmost node from the subtree, returns the new subtree root and the unlinked node
+    fn detach_min(&mut self, root: usize) -> (usize, usize) {
+        let left = self.node(root).left;
+        if left == NIL {
+            return (self.node(root).right, root);
+        }
+        let (left, min) = self.detach_min(left);
+        self.node_mut(root).left = left;
+        (self.rebalance(root), min)
+    }
+}
+
+/// Helper struct to manage the state of an ongoing query.
+struct QueryCursor<Idx: PrimInt> {
+    /// The query interval.
+    query: Interval<Idx>,
+    /// Subtrees left to visit.
+    stack: [usize; MAX_HEIGHT],
+    top: usize,
+}
+
+impl<Idx: PrimInt> QueryCursor<Idx> {
+    fn new(query: Interval<Idx>, root: usize) -> Self {
+        let mut cursor = Self {
+            query,
+            stack: [NIL; MAX_HEIGHT],
+            top: 0,
+        };
+        if root != NIL {
+            cursor.stack[0] = root;
+            cursor.top = 1;
+        }
+        cursor
+    }
+
+    /// Advances the cursor to the next overlapping interval. Returns `None` if no more
+    /// overlapping intervals are found.
+    #[inline]
+    fn next<Data>(&mut self, tree: &Dynamic<Idx, Data>) -> Option<usize> {
+        let (start, end) = (self.query.start(), self.query.end());
+        while self.top > 0 {
+            self.top -= 1;
+            let slot = self.stack[self.top];
+            let node = tree.node(slot);
+
+            // Nothing in the subtree reaches the query
+            if node.max_end <= start {
+                continue;
+            }
+            // Right subtree starts at or after the node start
+            if node.interval.start() < end && node.right != NIL {
+                self.stack[self.top] = node.right;
+                self.top += 1;
+            }
+            if node.left != NIL {
+                self.stack[self.top] = node.left;
+                self.top += 1;
+            }
+            if node.interval.start() < end && start < node.interval.end() {
+                return Some(slot);
+            }
+        }
+        None
+    }
+}
+
+/// An iterator over overlapping entries produced by `Dynamic::query`.
+pub struct Iter<'tree, Idx: PrimInt, Data> {
+    cursor: QueryCursor<Idx>,
+    tree: &'tree Dynamic<Idx, Data>,
+}
+
+impl<'tree, Idx: PrimInt, Data> Iterator for Iter<'tree, Idx, Data> {
+    type Item = (Handle, Interval<Idx>, &'tree Data);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let slot = self.cursor.next(self.tree)?;
+        let node = self.tree.node(slot);
+        let handle = Handle {
+            slot,
+            generation: node.generation,
+        };
+        Some((handle, node.interval, &node.data))
+    }
+}
+
+impl<Idx: PrimInt, Data> ITree for Dynamic<Idx, Data> {
+    type Idx = Idx;
+    type Data = Data;
+
+    fn data(&self) -> impl Iterator<Item = &Self::Data> {
+        self.iter().map(|(_, _, data)| data)
+    }
+
+    fn intervals(&self) -> impl Iterator<Item = Interval<Self::Idx>> {
+        self.iter().map(|(_, interval, _)| interval)
+    }
+
+    fn records(&self) -> impl Iterator<Item = (Interval<Self::Idx>, &Self::Data)> {
+        self.iter().map(|(_, interval, data)| (interval, data))
+    }
+
+    fn intersect_interval<'hits, 'tree: 'hits>(
+        &'tree self,
+        interval: &Interval<Self::Idx>,
+        buffer: &mut Hits<'hits, Self::Idx, Self::Data>,
+    ) {
+        buffer.clear();
+        for (_, interval, element) in self.query(*interval) {
+            buffer.push(interval, element)
+        }
+    }
+
+    fn batch_intersect_intervals<'hits, 'tree: 'hits>(
+        &'tree self,
+        intervals: &[Interval<Self::Idx>],
+        buffer: &mut BatchHits<'hits, Self::Idx, Self::Data>,
+    ) {
+        buffer.clear();
+
+        for query in intervals {
+            let mut hits = buffer.add_hits();
+            for (_, interval, element) in self.query(*query) {
+                hits.add(interval, element);
+            }
+            hits.push();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interval_tree::{Bits, test_stand};
+    use crate::random::Lcg;
+    use itertools::Itertools;
+
+    // Check the AVL and max end invariants, returns the subtree height
+    fn validate<Data>(tree: &Dynamic<i64, Data>, slot: usize) -> u8 {
+        if slot == NIL {
+            return 0;
+        }
+        let node = tree.node(slot);
+        let (left, right) = (validate(tree, node.left), validate(tree, node.right));
+        assert!((left as i16 - right as i16).abs() <= 1);
+        assert_eq!(node.height, 1 + left.max(right));
+
+        let mut max_end = node.interval.end();
+        for child in [node.left, node.right] {
+            if child != NIL {
+                max_end = max_end.max(tree.node(child).max_end);
+            }
+        }
+        assert_eq!(node.max_end, max_end);
+        if node.left != NIL {
+            assert_eq!(tree.cmp(node.left, slot), Ordering::Less);
+        }
+        if node.right != NIL {
+            assert_eq!(tree.cmp(node.right, slot), Ordering::Greater);
+        }
+        node.height
+    }
+
+    #[test]
+    fn test_dynamic_interval_tree() {
+        test_stand::run_all(Dynamic::builder());
+    }
+
+    #[test]
+    fn test_dynamic_matches_bits() {
+        let mut lcg = Lcg::new(7);
+        let mut next = move || lcg.next_u64();
+        let mut random_interval = |max_len: u64| {
+            let start = (next() % 10_000) as i64;
+            Interval::new(start, start + (next() % max_len + 1) as i64).unwrap()
+        };
+
+        let mut tree = Dynamic::default();
+        let mut handles = Vec::new();
+        let mut expected = ahash::HashMap::default();
+        for step in 0..3_000usize {
+            match step % 5 {
+                // Remove a random entry
+                3 if !handles.is_empty() => {
+                    let handle = handles.swap_remove(step * 31 % handles.len());
+                    assert_eq!(tree.remove(handle), expected.remove(&handle));
+                    assert!(tree.remove(handle).is_none());
+                }
+                // Move a random entry
+                4 if !handles.is_empty() => {
+                    let handle = handles[step * 17 % handles.len()];
+                    let interval = random_interval(200);
+                    let entry = expected.get_mut(&handle).unwrap();
+                    assert_eq!(tree.update(handle, interval), Some(entry.0));
+                    entry.0 = interval;
+                }
+                _ => {
+                    let interval = random_interval(if step % 101 == 0 { 5_000 } else { 100 });
+                    let handle = tree.insert(interval, step);
+                    handles.push(handle);
+                    expected.insert(handle, (interval, step));
+                }
+            }
+            assert_eq!(tree.len(), expected.len());
+
+            if step % 100 == 0 {
+                validate(&tree, tree.root);
+                let bits = Bits::new(expected.values().cloned());
+                for _ in 0..20 {
+                    let query = random_interval(500);
+                    let result = tree.query(query).map(|x| *x.2).sorted().collect_vec();
+                    let expected = bits.query(query).map(|x| *x.1).sorted().collect_vec();
+                    assert_eq!(result, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dynamic_handles() {
+        let mut tree = Dynamic::new([(Interval::new(0, 10).unwrap(), "a")]);
+        let handle = tree.iter().next().unwrap().0;
+        assert_eq!(
+            tree.get(handle),
+            Some((Interval::new(0, 10).unwrap(), &"a"))
+        );
+
+        *tree.get_mut(handle).unwrap() = "b";
+        assert_eq!(
+            tree.remove(handle),
+            Some((Interval::new(0, 10).unwrap(), "b"))
+        );
+        assert!(tree.is_empty());
+
+        // Slots are reused, but stale handles are rejected
+        let new = tree.insert(Interval::new(5, 6).unwrap(), "c");
+        assert_eq!(new.slot, handle.slot);
+        assert!(!tree.contains(handle));
+        assert!(tree.get_mut(handle).is_none());
+        assert!(tree.update(handle, Interval::new(0, 1).unwrap()).is_none());
+
+        tree.clear();
+        assert!(!tree.contains(new));
+        assert_eq!(tree.query(Interval::new(0, 10).unwrap()).count(), 0);
+    }
+}
@@ -0,0 +1,443 @@
+//! A collection of interval trees indexed by contig and orientation.
+
+use super::results::{BatchHits, Hits};
+use super::tree::{Builder, ITree};
+use ahash::HashMap;
+use biobit_core_rs::loc::{Contig, Interval, Orientation, PerOrientation};
+use derive_getters::Dissolve;
+
+#[cfg(feature = "bitcode")]
+use bitcode::{Decode, Encode};
+
+/// Defines how the orientation of a query is matched against the orientation of indexed entries.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Stranding {
+    /// Entries must share the query orientation. Dual entries match any query, and dual queries
+    /// match entries of any orientation.
+    #[default]
+    Aware,
+    /// Orientation is ignored, entries of all orientations are reported.
+    Agnostic,
+}
+
+impl Stranding {
+    /// Returns `true` if entries with the given orientation should be reported for the query
+    /// orientation.
+    #[inline]
+    pub fn matches(&self, query: Orientation, entry: Orientation) -> bool {
+        match self {
+            Stranding::Aware => {
+                query == entry || query == Orientation::Dual || entry == Orientation::Dual
+            }
+            Stranding::Agnostic => true,
+        }
+    }
+}
+
+/// A builder for constructing [`Forest`] collections. Each contig and orientation gets its own
+/// tree, created from a copy of the template builder.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, Dissolve)]
+pub struct ForestBuilder<Ctg: Contig, B: Builder> {
+    template: B,
+    builders: HashMap<Ctg, PerOrientation<Option<B>>>,
+}
+
+impl<Ctg: Contig, B: Builder + Clone> ForestBuilder<Ctg, B> {
+    /// Creates a builder that constructs trees from copies of the given builder.
+    pub fn new(template: B) -> Self {
+        Self {
+            template,
+            builders: HashMap::default(),
+        }
+    }
+
+    /// Add an interval located on the given contig and orientation along with its data element.
+    pub fn add(
+        mut self,
+        contig: Ctg,
+        orientation: Orientation,
+        interval: Interval<<B::Target as ITree>::Idx>,
+        data: <B::Target as ITree>::Data,
+    ) -> Self {
+        self.push(contig, orientation, interval, data);
+        self
+    }
+
+    /// Extend the forest from an iterator of `(contig, orientation, interval, data)` records.
+    pub fn extend(
+        mut self,
+        records: impl IntoIterator<
+            Item = (
+                Ctg,
+                Orientation,
+                Interval<<B::Target as ITree>::Idx>,
+                <B::Target as ITree>::Data,
+            ),
+        >,
+    ) -> Self {
+        for (contig, orientation, interval, data) in records {
+            self.push(contig, orientation, interval, data);
+        }
+        self
+    }
+
+    /// Build and return the final forest.
+    pub fn build(self) -> Forest<Ctg, B::Target> {
+        let template = self.template;
+        let trees = self
+            .builders
+            .into_iter()
+            .map(|(contig, builders)| {
+                let trees =
+                    builders.map(|_, builder| builder.unwrap_or_else(|| template.clone()).build());
+                (contig, trees)
+            })
+            .collect();
+        Forest { trees }
+    }
+
+    fn push(
+        &mut self,
+        contig: Ctg,
+        orientation: Orientation,
+        interval: Interval<<B::Target as ITree>::Idx>,
+        data: <B::Target as ITree>::Data,
+    ) {
+        let builder = &mut self.builders.entry(contig).or_default()[orientation];
+        let current = builder.take().unwrap_or_else(|| self.template.clone());
+        *builder = Some(current.add(interval, data));
+    }
+}
+
+/// A collection of interval trees, one per contig and orientation. It's the recommended way to
+/// index genomic annotations and query them in a strand-aware or strand-agnostic manner.
+///
+/// The forest works with any [`ITree`] implementation, e.g. [`Bits`](super::Bits) for static
+/// annotations or [`Dynamic`](super::Dynamic) for collections that are updated after construction.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, Dissolve)]
+pub struct Forest<Ctg: Contig, T: ITree> {
+    trees: HashMap<Ctg, PerOrientation<T>>,
+}
+
+impl<Ctg: Contig, T: ITree> Default for Forest<Ctg, T> {
+    fn default() -> Self {
+        Self {
+            trees: HashMap::default(),
+        }
+    }
+}
+
+impl<Ctg: Contig, T: ITree> Forest<Ctg, T> {
+    /// Returns a builder for constructing a new `Forest` from copies of the given tree builder.
+    pub fn builder<B: Builder<Target = T> + Clone>(builder: B) -> ForestBuilder<Ctg, B> {
+        ForestBuilder::new(builder)
+    }
+
+    /// Returns trees for the given contig or `None` if the contig wasn't indexed.
+    pub fn get(&self, contig: &Ctg) -> Option<&PerOrientation<T>> {
+        self.trees.get(contig)
+    }
+
+    /// Returns mutable trees for the given contig or `None` if the contig wasn't indexed.
+    pub fn get_mut(&mut self, contig: &Ctg) -> Option<&mut PerOrientation<T>> {
+        self.trees.get_mut(contig)
+    }
+
+    /// Returns an iterator over all indexed contigs. The order is arbitrary.
+    pub fn contigs(&self) -> impl Iterator<Item = &Ctg> {
+        self.trees.keys()
+    }
+
+    /// Returns an iterator over all `(contig, orientation, tree)` triplets. The order is arbitrary.
+    pub fn trees(&self) -> impl Iterator<Item = (&Ctg, Orientation, &T)> {
+        self.trees.iter().flat_map(|(contig, trees)| {
+            trees
+                .iter()
+                .map(move |(orientation, tree)| (contig, orientation, tree))
+        })
+    }
+
+    /// Returns the total number of intervals stored in the forest.
+    pub fn len(&self) -> usize {
+        self.trees()
+            .map(|(_, _, tree)| tree.intervals().count())
+            .sum()
+    }
+
+    /// Returns `true` if the forest contains no intervals.
+    pub fn is_empty(&self) -> bool {
+        self.trees()
+            .all(|(_, _, tree)| tree.intervals().next().is_none())
+    }
+
+    /// Creates an iterator over entries overlapping the given location. Entries are reported
+    /// tree by tree, i.e. they are not globally sorted.
+    ///
+    /// Hits of each tree are collected into a temporary buffer. Prefer [`Forest::intersect`] to
+    /// reuse allocations across queries.
+    ///
+    /// # Arguments
+    ///
+    /// * `contig`: The contig of the query.
+    /// * `orientation`: The orientation of the query.
+    /// * `interval`: The query interval.
+    /// * `stranding`: How the query orientation is matched against indexed entries.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding `(Orientation, Interval<Idx>, &Data)` tuples for overlapping entries.
+    pub fn query<'tree>(
+        &'tree self,
+        contig: &Ctg,
+        orientation: Orientation,
+        interval: Interval<T::Idx>,
+        stranding: Stranding,
+    ) -> impl Iterator<Item = (Orientation, Interval<T::Idx>, &'tree T::Data)> + 'tree {
+        self.matching(contig, orientation, stranding)
+            .flat_map(move |(orientation, tree)| {
+                let mut hits = Hits::new();
+                tree.intersect_interval(&interval, &mut hits);
+
+                let (intervals, data) = hits.dissolve();
+                intervals
+                    .into_iter()
+                    .zip(data)
+                    .map(move |(interval, data)| (orientation, interval, data))
+            })
+    }
+
+    /// Finds all entries intersecting the given location.
+    ///
+    /// Results are placed into the provided `buffer`, overwriting its previous contents. Querying
+    /// a contig that is not present in the forest yields no hits.
+    pub fn intersect<'hits, 'tree: 'hits>(
+        &'tree self,
+        contig: &Ctg,
+        orientation: Orientation,
+        interval: &Interval<T::Idx>,
+        stranding: Stranding,
+        buffer: &mut Hits<'hits, T::Idx, T::Data>,
+    ) {
+        let mut trees = self.matching(contig, orientation, stranding);
+        match trees.next() {
+            Some((_, tree)) => tree.intersect_interval(interval, buffer),
+            None => buffer.clear(),
+        }
+
+        // Remaining trees are queried into a scratch buffer that is only allocated on demand
+        let mut hits = Hits::new();
+        for (_, tree) in trees {
+            tree.intersect_interval(interval, &mut hits);
+            buffer.extend(hits.iter().map(|(interval, data)| (*interval, data)));
+        }
+    }
+
+    /// Batch version of [`Forest::intersect`] for queries sharing the same contig and orientation.
+    ///
+    /// Results are placed into the provided `buffer`, organizing results by query interval and
+    /// overwriting its previous contents.
+    pub fn batch_intersect<'hits, 'tree: 'hits>(
+        &'tree self,
+        contig: &Ctg,
+        orientation: Orientation,
+        intervals: &[Interval<T::Idx>],
+        stranding: Stranding,
+        buffer: &mut BatchHits<'hits, T::Idx, T::Data>,
+    ) {
+        buffer.clear();
+
+        let mut hits = Hits::new();
+        for query in intervals {
+            let mut batch = buffer.add_hits();
+            for (_, tree) in self.matching(contig, orientation, stranding) {
+                tree.intersect_interval(query, &mut hits);
+                for (interval, data) in hits.iter() {
+                    batch.add(*interval, data);
+                }
+            }
+            batch.push();
+        }
+    }
+
+    // Trees of the given contig whose orientation matches the query
+    fn matching<'tree>(
+        &'tree self,
+        contig: &Ctg,
+        orientation: Orientation,
+        stranding: Stranding,
+    ) -> impl Iterator<Item = (Orientation, &'tree T)> + use<'tree, Ctg, T> {
+        self.trees
+            .get(contig)
+            .into_iter()
+            .flat_map(|trees| trees.iter())
+            .filter(move |(x, _)| stranding.matches(orientation, *x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interval_tree::{Bits, Dynamic, IITree};
+    use biobit_core_rs::loc::IntervalOp;
+
+    fn forest<B>(builder: B) -> Forest<&'static str, B::Target>
+    where
+        B: Builder<Target: ITree<Idx = i32, Data = usize>> + Clone,
+    {
+        Forest::builder(builder)
+            .extend([
+                ("1", Orientation::Forward, Interval::new(0, 10).unwrap(), 0),
+                ("1", Orientation::Reverse, Interval::new(5, 15).unwrap(), 1),
+                ("1", Orientation::Dual, Interval::new(8, 20).unwrap(), 2),
+                ("1", Orientation::Forward, Interval::new(30, 40).unwrap(), 3),
+                ("2", Orientation::Reverse, Interval::new(0, 10).unwrap(), 4),
+            ])
+            .add("2", Orientation::Forward, Interval::new(5, 10).unwrap(), 5)
+            .build()
+    }
+
+    fn sorted(hits: &Hits<i32, usize>) -> Vec<(Interval<i32>, usize)> {
+        let mut hits = hits.iter().map(|(x, y)| (*x, *y)).collect::<Vec<_>>();
+        hits.sort();
+        hits
+    }
+
+    fn run_all<B>(builder: B)
+    where
+        B: Builder<Target: ITree<Idx = i32, Data = usize>> + Clone,
+    {
+        let forest = forest(builder);
+        check_intersect(&forest);
+        check_batch_intersect(&forest);
+        check_query_orientation(&forest);
+    }
+
+    fn check_intersect<T: ITree<Idx = i32, Data = usize>>(forest: &Forest<&str, T>) {
+        assert_eq!(forest.len(), 6);
+        assert!(!forest.is_empty());
+
+        let query = Interval::new(7, 9).unwrap();
+        let mut hits = Hits::new();
+        for (orientation, stranding, expected) in [
+            (Orientation::Forward, Stranding::Aware, vec![0, 2]),
+            (Orientation::Reverse, Stranding::Aware, vec![1, 2]),
+            (Orientation::Dual, Stranding::Aware, vec![0, 1, 2]),
+            (Orientation::Forward, Stranding::Agnostic, vec![0, 1, 2]),
+            (Orientation::Reverse, Stranding::Agnostic, vec![0, 1, 2]),
+        ] {
+            forest.intersect(&"1", orientation, &query, stranding, &mut hits);
+            let data = sorted(&hits).into_iter().map(|x| x.1).collect::<Vec<_>>();
+            assert_eq!(data, expected, "{orientation:?} {stranding:?}");
+        }
+
+        // Unknown contigs and empty regions yield no hits
+        forest.intersect(
+            &"3",
+            Orientation::Dual,
+            &query,
+            Stranding::Agnostic,
+            &mut hits,
+        );
+        assert!(hits.is_empty());
+
+        let query = Interval::new(20, 30).unwrap();
+        forest.intersect(
+            &"1",
+            Orientation::Dual,
+            &query,
+            Stranding::Agnostic,
+            &mut hits,
+        );
+        assert!(hits.is_empty());
+    }
+
+    fn check_batch_intersect<T: ITree<Idx = i32, Data = usize>>(forest: &Forest<&str, T>) {
+        let queries = [
+            Interval::new(0, 5).unwrap(),
+            Interval::new(9, 12).unwrap(),
+            Interval::new(100, 200).unwrap(),
+        ];
+
+        let mut batch = BatchHits::new();
+        for contig in ["1", "2", "3"] {
+            for orientation in [
+                Orientation::Forward,
+                Orientation::Reverse,
+                Orientation::Dual,
+            ] {
+                for stranding in [Stranding::Aware, Stranding::Agnostic] {
+                    forest.batch_intersect(&contig, orientation, &queries, stranding, &mut batch);
+                    assert_eq!(batch.len(), queries.len());
+
+                    let mut hits = Hits::new();
+                    for (i, query) in queries.iter().enumerate() {
+                        forest.intersect(&contig, orientation, query, stranding, &mut hits);
+                        assert_eq!(batch.intervals(i).unwrap(), hits.intervals());
+                        assert_eq!(batch.data(i).unwrap(), hits.data());
+                        assert!(hits.intervals().iter().all(|x| x.intersects(query)));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_query_orientation<T: ITree<Idx = i32, Data = usize>>(forest: &Forest<&str, T>) {
+        let query = Interval::new(0, 100).unwrap();
+
+        let mut hits = forest
+            .query(&"1", Orientation::Forward, query, Stranding::Aware)
+            .map(|(orientation, _, data)| (orientation, *data))
+            .collect::<Vec<_>>();
+        hits.sort();
+        assert_eq!(
+            hits,
+            vec![
+                (Orientation::Dual, 2),
+                (Orientation::Forward, 0),
+                (Orientation::Forward, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_forest_bits() {
+        run_all(Bits::builder());
+    }
+
+    #[test]
+    fn test_forest_iitree() {
+        run_all(IITree::builder());
+    }
+
+    #[test]
+    fn test_forest_dynamic() {
+        run_all(Dynamic::builder());
+    }
+
+    #[test]
+    #[cfg(feature = "bitcode")]
+    fn test_forest_bitcode() -> eyre::Result<()> {
+        use crate::interval_tree::BitsBuilder;
+
+        let forest = forest(IITree::builder());
+        let bytes = bitcode::encode(&forest);
+        let decoded: Forest<&str, IITree<i32, usize>> = bitcode::decode(&bytes)?;
+        assert_eq!(decoded.len(), forest.len());
+        check_intersect(&decoded);
+        check_query_orientation(&decoded);
+
+        let builder = ForestBuilder::new(BitsBuilder::<i32, usize>::default()).add(
+            "1",
+            Orientation::Forward,
+            Interval::new(0, 10)?,
+            0,
+        );
+        let bytes = bitcode::encode(&builder);
+        let decoded: ForestBuilder<&str, BitsBuilder<i32, usize>> = bitcode::decode(&bytes)?;
+        assert_eq!(decoded.build().len(), 1);
+        Ok(())
+    }
+}
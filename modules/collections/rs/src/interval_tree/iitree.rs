@@ -0,0 +1,473 @@
+//! Implementation of an implicit augmented interval tree (cgranges/IITree).
+//! Reference: https://github.com/lh3/cgranges
+//!
+//! Intervals are sorted by start and laid out as an implicit binary search tree, where each node
+//! stores the maximum end coordinate of its subtree. Unlike [`Bits`](super::Bits), the query time
+//! is `O(log n + k)` regardless of the lengths of stored intervals.
+
+use super::results::{BatchHits, Hits};
+use super::tree::{Builder, ITree};
+use biobit_core_rs::{
+    loc::{Interval, IntervalOp},
+    num::PrimInt,
+};
+use derive_getters::Dissolve;
+use derive_more::From;
+use itertools::Itertools;
+
+#[cfg(feature = "bitcode")]
+use bitcode::{Decode, Encode};
+
+// Subtrees at this level or below are scanned linearly
+const SCAN_LEVEL: u8 = 3;
+
+/// A builder for constructing [`IITree`] interval trees.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, From, Dissolve)]
+pub struct IITreeBuilder<Idx: PrimInt, Data> {
+    records: Vec<(Interval<Idx>, Data)>,
+}
+
+impl<Idx: PrimInt, Data> Default for IITreeBuilder<Idx, Data> {
+    fn default() -> Self {
+        Self {
+            records: Vec::new(),
+        }
+    }
+}
+
+impl<Idx: PrimInt, Data> Builder for IITreeBuilder<Idx, Data> {
+    type Target = IITree<Idx, Data>;
+
+    fn add(
+        mut self,
+        interval: Interval<<Self::Target as ITree>::Idx>,
+        data: <Self::Target as ITree>::Data,
+    ) -> Self {
+        self.records.push((interval, data));
+        self
+    }
+
+    fn extend(
+        mut self,
+        records: impl IntoIterator<
+            Item = (
+                Interval<<Self::Target as ITree>::Idx>,
+                <Self::Target as ITree>::Data,
+            ),
+        >,
+    ) -> Self {
+        self.records.extend(records);
+        self
+    }
+
+    fn build(self) -> Self::Target {
+        IITree::new(self.records)
+    }
+}
+
+/// A partially immutable implicit interval tree. The tree layout is constant after construction,
+/// but the data elements can be modified. Prefer it over [`Bits`](super::Bits) when the index
+/// contains a few very long intervals, e.g., long genes or whole-chromosome partitions.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, From, Dissolve)]
+pub struct IITree<Idx: PrimInt, Data> {
+    // Associated data elements, corresponding to intervals at the same index.
+    data: Vec<Data>,
+    // Interval start coordinates, sorted.
+    starts: Vec<Idx>,
+    // Interval end coordinates, corresponding to `starts`.
+    ends: Vec<Idx>,
+    // The maximum end coordinate in the subtree rooted at each node.
+    max_ends: Vec<Idx>,
+    // The level of the root node, i.e., the tree height - 1. Unused for empty trees.
+    max_level: u8,
+}
+
+impl<Idx: PrimInt, Data> IITree<Idx, Data> {
+    /// Creates a new `IITree` interval tree from an iterator of `(Interval, Data)` pairs.
+    /// All intervals are sorted internally by their start coordinates. Use `new_unchecked` for
+    /// data that is already sorted.
+    pub fn new(iter: impl IntoIterator<Item = (Interval<Idx>, Data)>) -> Self {
+        // SAFETY: The intervals are sorted by their start coordinates.
+        unsafe { Self::new_unchecked(iter.into_iter().sorted_by_key(|(it, _)| it.start())) }
+    }
+
+    /// Creates a new `IITree` interval tree from an iterator of `(Interval, Data)` pairs.
+    ///
+    /// # Safety
+    /// The intervals must be sorted by their start coordinates. Use `new` for unsorted data.
+    pub unsafe fn new_unchecked(iter: impl IntoIterator<Item = (Interval<Idx>, Data)>) -> Self {
+        let iter = iter.into_iter();
+
+        let explen = iter.size_hint().0;
+        let mut starts = Vec::with_capacity(explen);
+        let mut ends = Vec::with_capacity(explen);
+        let mut data = Vec::with_capacity(explen);
+
+        for (interval, idata) in iter {
+            starts.push(interval.start());
+            ends.push(interval.end());
+            data.push(idata);
+        }
+        debug_assert!(starts.is_sorted());
+
+        let mut tree = Self {
+            max_ends: ends.clone(),
+            data,
+            starts,
+            ends,
+            max_level: 0,
+        };
+        tree.index();
+        tree
+    }
+
+    // Compute the maximum end coordinates for all internal nodes (see cgranges `index_core`)
+    fn index(&mut self) {
+        let n = self.len();
+        if n == 0 {
+            return;
+        }
+
+        // Leaves are all even nodes, their max ends are already initialized.
+        let (mut last_i, mut last) = ((n - 1) & !1, self.ends[(n - 1) & !1]);
+        let mut level = 1;
+        while 1usize << level <= n {
+            let x = 1usize << (level - 1);
+            let step = x << 2;
+            let mut i = (x << 1) - 1;
+            while i < n {
+                let left = self.max_ends[i - x];
+                let right = if i + x < n {
+                    self.max_ends[i + x]
+                } else {
+                    last
+                };
+                self.max_ends[i] = self.ends[i].max(left).max(right);
+                i += step;
+            }
+
+            // The last node at this level might be outside the array, track its max end
+            last_i = if (last_i >> level) & 1 == 1 {
+                last_i - x
+            } else {
+                last_i + x
+            };
+            if last_i < n && self.max_ends[last_i] > last {
+                last = self.max_ends[last_i];
+            }
+            level += 1;
+        }
+        self.max_level = (level - 1) as u8;
+    }
+
+    /// Creates an iterator over entries overlapping the given interval.
+    ///
+    /// This is the primary low-level query mechanism used by the `ITree` trait methods.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval`: The interval to query for overlapping entries.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding `(Interval<Idx>, &'tree Data)` tuples for overlapping entries.
+    #[inline]
+    pub fn query(&self, interval: Interval<Idx>) -> Iter<'_, Idx, Data> {
+        Iter {
+            cursor: QueryCursor::new(interval, self),
+            tree: self,
+        }
+    }
+
+    /// Creates a mutable iterator over entries overlapping the given interval.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval`: The interval to query for overlapping entries.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding `(Interval<Idx>, &'tree mut Data)` tuples for overlapping entries.
+    pub fn query_mut(&mut self, interval: Interval<Idx>) -> IterMut<'_, Idx, Data> {
+        IterMut {
+            cursor: QueryCursor::new(interval, self),
+            tree: self,
+        }
+    }
+
+    /// Returns the number of intervals stored in the tree.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Returns `true` if the tree contains no intervals.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// Provides access to the raw data elements stored in the tree.
+    /// The order corresponds to the intervals sorted by start position.
+    pub fn data(&self) -> &[Data] {
+        &self.data
+    }
+
+    /// Provides mutable access to the raw data elements stored in the tree.
+    /// The order corresponds to the intervals sorted by start position.
+    pub fn data_mut(&mut self) -> &mut [Data] {
+        &mut self.data
+    }
+
+    /// Returns a builder for constructing a new `IITree` interval tree.
+    pub fn builder() -> IITreeBuilder<Idx, Data> {
+        IITreeBuilder::default()
+    }
+}
+
+/// A pending subtree in the query stack: (level, node index, whether the left child was visited).
+#[derive(Debug, Clone, Copy, Default)]
+struct StackCell {
+    level: u8,
+    node: usize,
+    visited: bool,
+}
+
+/// Helper struct to manage the state of an ongoing query.
+struct QueryCursor<Idx: PrimInt> {
+    /// The query interval.
+    query: Interval<Idx>,
+    /// Subtrees left to visit. The tree height never exceeds 64 levels.
+    stack: [StackCell; 65],
+    top: usize,
+    /// Current position and the end of the linear scan over a small subtree.
+    scan: (usize, usize),
+}
+
+impl<Idx: PrimInt> QueryCursor<Idx> {
+    fn new<Data>(query: Interval<Idx>, tree: &IITree<Idx, Data>) -> Self {
+        let mut cursor = Self {
+            query,
+            stack: [StackCell::default(); 65],
+            top: 0,
+            scan: (0, 0),
+        };
+        if !tree.is_empty() {
+            cursor.stack[0] = StackCell {
+                level: tree.max_level,
+                node: (1usize << tree.max_level) - 1,
+                visited: false,
+            };
+            cursor.top = 1;
+        }
+        cursor
+    }
+
+    #[inline(always)]
+    fn push(&mut self, level: u8, node: usize, visited: bool) {
+        self.stack[self.top] = StackCell {
+            level,
+            node,
+            visited,
+        };
+        self.top += 1;
+    }
+
+    /// Advances the cursor to the next overlapping interval. Returns `None` if no more
+    /// overlapping intervals are found.
+    #[inline]
+    fn next<Data>(&mut self, tree: &IITree<Idx, Data>) -> Option<(Interval<Idx>, usize)> {
+        let (start, end) = (self.query.start(), self.query.end());
+        let n = tree.len();
+        loop {
+            // Finish the linear scan over the current small subtree
+            while self.scan.0 < self.scan.1 && tree.starts[self.scan.0] < end {
+                let ind = self.scan.0;
+                self.scan.0 += 1;
+                if start < tree.ends[ind] {
+                    return Some((self.interval(tree, ind), ind));
+                }
+            }
+            self.scan = (0, 0);
+
+            if self.top == 0 {
+                return None;
+            }
+            self.top -= 1;
+            let cell = self.stack[self.top];
+
+            if cell.level <= SCAN_LEVEL {
+                let first = cell.node >> cell.level << cell.level;
+                let last = (first + (1usize << (cell.level + 1)) - 1).min(n);
+                self.scan = (first, last);
+            } else if !cell.visited {
+                let left = cell.node - (1usize << (cell.level - 1));
+                self.push(cell.level, cell.node, true);
+                if left >= n || tree.max_ends[left] > start {
+                    self.push(cell.level - 1, left, false);
+                }
+            } else if cell.node < n && tree.starts[cell.node] < end {
+                self.push(
+                    cell.level - 1,
+                    cell.node + (1usize << (cell.level - 1)),
+                    false,
+                );
+                if start < tree.ends[cell.node] {
+                    return Some((self.interval(tree, cell.node), cell.node));
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn interval<Data>(&self, tree: &IITree<Idx, Data>, ind: usize) -> Interval<Idx> {
+        let segment = unsafe { Interval::new(tree.starts[ind], tree.ends[ind]).unwrap_unchecked() };
+        debug_assert!(segment.intersects(&self.query));
+        segment
+    }
+}
+
+/// An iterator over overlapping intervals and data references produced by `IITree::query`.
+pub struct Iter<'tree, Idx: PrimInt, Data> {
+    cursor: QueryCursor<Idx>,
+    tree: &'tree IITree<Idx, Data>,
+}
+
+impl<'tree, Idx: PrimInt, Data> Iterator for Iter<'tree, Idx, Data> {
+    type Item = (Interval<Idx>, &'tree Data);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (segment, cursor) = self.cursor.next(self.tree)?;
+        Some((segment, &self.tree.data[cursor]))
+    }
+}
+
+/// An iterator over overlapping intervals and data references produced by `IITree::query_mut`.
+/// This iterator allows for mutable access to the data elements.
+pub struct IterMut<'tree, Idx: PrimInt, Data> {
+    cursor: QueryCursor<Idx>,
+    tree: &'tree mut IITree<Idx, Data>,
+}
+
+impl<'tree, Idx: PrimInt, Data> Iterator for IterMut<'tree, Idx, Data> {
+    type Item = (Interval<Idx>, &'tree mut Data);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (segment, cursor) = self.cursor.next(self.tree)?;
+        // SAFETY: The cursor is guaranteed to be within bounds of the data vector, and each
+        // element is yielded at most once. The 'tree lifetime is at least as long as the '_
+        // lifetime of the IterMut borrow.
+        unsafe {
+            let element = self.tree.data.get_mut(cursor).unwrap_unchecked() as *mut Data;
+            Some((segment, element.as_mut().unwrap_unchecked()))
+        }
+    }
+}
+
+impl<Idx: PrimInt, Data> ITree for IITree<Idx, Data> {
+    type Idx = Idx;
+    type Data = Data;
+
+    fn data(&self) -> impl Iterator<Item = &Self::Data> {
+        self.data.iter()
+    }
+
+    fn intervals(&self) -> impl Iterator<Item = Interval<Self::Idx>> {
+        self.starts
+            .iter()
+            .zip(self.ends.iter())
+            // SAFETY: Interval validity (start < end) is ensured by constructor logic.
+            .map(|(x, y)| unsafe { Interval::new(*x, *y).unwrap_unchecked() })
+    }
+
+    fn records(&self) -> impl Iterator<Item = (Interval<Self::Idx>, &Self::Data)> {
+        self.intervals().zip(self.data.iter())
+    }
+
+    fn intersect_interval<'hits, 'tree: 'hits>(
+        &'tree self,
+        interval: &Interval<Self::Idx>,
+        buffer: &mut Hits<'hits, Self::Idx, Self::Data>,
+    ) {
+        buffer.clear();
+        for (interval, element) in self.query(*interval) {
+            buffer.push(interval, element)
+        }
+    }
+
+    fn batch_intersect_intervals<'hits, 'tree: 'hits>(
+        &'tree self,
+        intervals: &[Interval<Self::Idx>],
+        buffer: &mut BatchHits<'hits, Self::Idx, Self::Data>,
+    ) {
+        buffer.clear();
+
+        for query in intervals {
+            let mut hits = buffer.add_hits();
+            for (interval, element) in self.query(*query) {
+                hits.add(interval, element);
+            }
+            hits.push();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interval_tree::{Bits, test_stand};
+    use crate::random::Lcg;
+
+    #[test]
+    fn test_iitree_interval_tree() {
+        test_stand::run_all(IITree::builder());
+    }
+
+    #[test]
+    fn test_iitree_matches_bits() {
+        // Pseudo-random intervals with a few very long ones
+        let mut lcg = Lcg::new(42);
+        let mut next = move || lcg.next_u64();
+        for size in [1, 2, 3, 7, 8, 9, 15, 16, 17, 100, 1_000, 1_023, 1_025] {
+            let mut records = Vec::with_capacity(size);
+            for ind in 0..size {
+                let start = (next() % 100_000) as i64;
+                let length = if ind % 97 == 0 {
+                    50_000
+                } else {
+                    (next() % 100 + 1) as i64
+                };
+                records.push((Interval::new(start, start + length).unwrap(), ind));
+            }
+            let iitree = IITree::new(records.clone());
+            let bits = Bits::new(records);
+
+            for _ in 0..200 {
+                let start = (next() % 160_000) as i64 - 10_000;
+                let query = Interval::new(start, start + (next() % 1_000 + 1) as i64).unwrap();
+                let expected = bits.query(query).map(|x| *x.1).sorted().collect_vec();
+                let result = iitree.query(query).map(|x| *x.1).sorted().collect_vec();
+                assert_eq!(result, expected, "Size: {size}, query: {query:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_iitree_query_mut() {
+        let mut tree = IITree::new([
+            (Interval::new(0, 100).unwrap(), 0),
+            (Interval::new(10, 20).unwrap(), 0),
+            (Interval::new(50, 60).unwrap(), 0),
+        ]);
+        for (_, data) in tree.query_mut(Interval::new(15, 55).unwrap()) {
+            *data += 1;
+        }
+        assert_eq!(tree.data(), &[1, 1, 1]);
+        for (_, data) in tree.query_mut(Interval::new(20, 50).unwrap()) {
+            *data += 1;
+        }
+        assert_eq!(tree.data(), &[2, 1, 1]);
+    }
+}
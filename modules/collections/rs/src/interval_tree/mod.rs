@@ -1,16 +1,22 @@
 pub use bits::{Bits, BitsBuilder};
-pub use results::{BatchHitSegments, BatchHits, HitSegments, Hits};
-pub use tree::{Builder, ITree};
+pub use dynamic::{Dynamic, DynamicBuilder, Handle};
+pub use forest::{Forest, ForestBuilder, Stranding};
+pub use iitree::{IITree, IITreeBuilder};
+pub use results::{
+    BatchHitSegments, BatchHits, BatchWeightedSegments, HitSegments, Hits, SegmentsView,
+    WeightedSegments,
+};
+pub use tree::{Builder, ITree, ITreeExt};
+
+#[cfg(feature = "bitcode")]
+pub use results::{RawBatchHitSegments, RawBatchHits, RawHitSegments, RawHits};
 
 mod bits;
+mod dynamic;
+mod forest;
+mod iitree;
 mod results;
 pub mod tree;
 
 #[cfg(test)]
 pub(crate) mod test_stand;
-
-// If I ever want to use a bundle for the IntervalTree, it should be called Forest.
-// Re-working the module will require:
-// * Support for caching allocations (required for hits/segments construction and trees)
-// * Clearly defined primitives for intervals/pairs/chains/locations/etc
-// * Guarantees about non-overlapping and sorted nature of input intervals.
@@ -0,0 +1,171 @@
+//! Bitcode support for query results.
+//!
+//! Results borrow their data from the tree and keep invariants that bitcode derives can't check.
+//! Instead, they are encoded as unchecked mirrors that are generic over the data references. For
+//! example, `RawHits<Idx, &str>` encodes the referenced strings themselves and decodes them back
+//! as borrows of the input buffer. Mirrors are converted back to results with [`TryFrom`], which
+//! validates them.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use bitcode::{Decode, Encode};
+use eyre::{Report, Result, ensure};
+
+use biobit_core_rs::loc::Interval;
+use biobit_core_rs::num::PrimInt;
+
+use super::hits::{BatchHits, Hits};
+use super::segments::{BatchHitSegments, HitSegments};
+
+/// Unchecked mirror of [`Hits`], where `D` is the reference to the data (e.g. `&'tree T`).
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct RawHits<Idx: PrimInt, D> {
+    pub intervals: Vec<Interval<Idx>>,
+    pub data: Vec<D>,
+}
+
+/// Unchecked mirror of [`BatchHits`], see [`RawHits`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct RawBatchHits<Idx: PrimInt, D> {
+    pub intervals: Vec<Interval<Idx>>,
+    pub data: Vec<D>,
+    pub index: Vec<usize>,
+}
+
+/// Unchecked mirror of [`HitSegments`], see [`RawHits`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct RawHitSegments<Idx: PrimInt, D> {
+    pub segments: Vec<Interval<Idx>>,
+    pub data: Vec<Vec<D>>,
+}
+
+/// Unchecked mirror of [`BatchHitSegments`], see [`RawHits`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct RawBatchHitSegments<Idx: PrimInt, D> {
+    pub segments: Vec<Interval<Idx>>,
+    pub data: Vec<Vec<D>>,
+    pub index: Vec<usize>,
+}
+
+fn validate(intervals: usize, data: usize, index: Option<&[usize]>) -> Result<()> {
+    ensure!(
+        intervals == data,
+        "Intervals and data must have the same length, got {intervals} and {data}"
+    );
+    if let Some(index) = index {
+        ensure!(
+            index.first() == Some(&0) && index.last() == Some(&intervals) && index.is_sorted(),
+            "Batch index must be a sorted list of boundaries from 0 to {intervals}"
+        );
+    }
+    Ok(())
+}
+
+impl<'tree, Idx: PrimInt, T: ?Sized> From<&Hits<'tree, Idx, T>> for RawHits<Idx, &'tree T> {
+    fn from(hits: &Hits<'tree, Idx, T>) -> Self {
+        Self {
+            intervals: hits.intervals.clone(),
+            data: hits.data.clone(),
+        }
+    }
+}
+
+impl<'tree, Idx: PrimInt, T: ?Sized> TryFrom<RawHits<Idx, &'tree T>> for Hits<'tree, Idx, T> {
+    type Error = Report;
+
+    fn try_from(raw: RawHits<Idx, &'tree T>) -> Result<Self> {
+        validate(raw.intervals.len(), raw.data.len(), None)?;
+        Ok(Self {
+            intervals: raw.intervals,
+            data: raw.data,
+        })
+    }
+}
+
+impl<'tree, Idx: PrimInt, T: ?Sized> From<&BatchHits<'tree, Idx, T>>
+    for RawBatchHits<Idx, &'tree T>
+{
+    fn from(batch: &BatchHits<'tree, Idx, T>) -> Self {
+        Self {
+            intervals: batch.intervals.clone(),
+            data: batch.data.clone(),
+            index: batch.index.clone(),
+        }
+    }
+}
+
+impl<'tree, Idx: PrimInt, T: ?Sized> TryFrom<RawBatchHits<Idx, &'tree T>>
+    for BatchHits<'tree, Idx, T>
+{
+    type Error = Report;
+
+    fn try_from(raw: RawBatchHits<Idx, &'tree T>) -> Result<Self> {
+        validate(raw.intervals.len(), raw.data.len(), Some(&raw.index))?;
+        Ok(Self {
+            intervals: raw.intervals,
+            data: raw.data,
+            index: raw.index,
+        })
+    }
+}
+
+impl<'tree, Idx: PrimInt, T: Eq + Hash + ?Sized> From<&HitSegments<'tree, Idx, T>>
+    for RawHitSegments<Idx, &'tree T>
+{
+    fn from(segments: &HitSegments<'tree, Idx, T>) -> Self {
+        Self {
+            segments: segments.segments.clone(),
+            data: segments
+                .data
+                .iter()
+                .map(|x| x.iter().copied().collect())
+                .collect(),
+        }
+    }
+}
+
+impl<'tree, Idx: PrimInt, T: Eq + Hash + ?Sized> TryFrom<RawHitSegments<Idx, &'tree T>>
+    for HitSegments<'tree, Idx, T>
+{
+    type Error = Report;
+
+    fn try_from(raw: RawHitSegments<Idx, &'tree T>) -> Result<Self> {
+        validate(raw.segments.len(), raw.data.len(), None)?;
+        Ok(Self {
+            segments: raw.segments,
+            data: raw.data.into_iter().map(HashSet::from_iter).collect(),
+        })
+    }
+}
+
+impl<'tree, Idx: PrimInt, T: Eq + Hash + ?Sized> From<&BatchHitSegments<'tree, Idx, T>>
+    for RawBatchHitSegments<Idx, &'tree T>
+{
+    fn from(batch: &BatchHitSegments<'tree, Idx, T>) -> Self {
+        Self {
+            segments: batch.segments.clone(),
+            data: batch
+                .data
+                .iter()
+                .map(|x| x.iter().copied().collect())
+                .collect(),
+            index: batch.index.clone(),
+        }
+    }
+}
+
+impl<'tree, Idx: PrimInt, T: Eq + Hash + ?Sized> TryFrom<RawBatchHitSegments<Idx, &'tree T>>
+    for BatchHitSegments<'tree, Idx, T>
+{
+    type Error = Report;
+
+    fn try_from(raw: RawBatchHitSegments<Idx, &'tree T>) -> Result<Self> {
+        validate(raw.segments.len(), raw.data.len(), Some(&raw.index))?;
+        Ok(Self {
+            segments: raw.segments,
+            data: raw.data.into_iter().map(HashSet::from_iter).collect(),
+            index: raw.index,
+        })
+    }
+}
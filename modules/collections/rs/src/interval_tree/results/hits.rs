@@ -17,12 +17,21 @@ use std::fmt::Debug;
 /// 2.  **Across different lifetime scopes and types:** Use the [`recycle`](#method.recycle)
 ///     method to consume the current `Hits` object and return a new one with
 ///     the same capacity, but with different types and a new lifetime.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Idx: serde::Serialize, T: serde::Serialize",
+        deserialize = "Idx: serde::Deserialize<'de>, &'tree T: serde::Deserialize<'de>"
+    ))
+)]
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Dissolve)]
 pub struct Hits<'tree, Idx: PrimInt, T: ?Sized> {
     /// The intervals found in the tree corresponding to the hits.
-    intervals: Vec<Interval<Idx>>,
+    pub(super) intervals: Vec<Interval<Idx>>,
     /// References to the data associated with the found intervals.
-    data: Vec<&'tree T>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub(super) data: Vec<&'tree T>,
 }
 
 // Manual Clone implementation: Required because derive(Clone) would incorrectly
@@ -113,6 +122,24 @@ impl<'tree, Idx: PrimInt, T: ?Sized> Hits<'tree, Idx, T> {
         }
     }
 
+    /// Retains only the hits for which the predicate returns `true`, preserving their order.
+    ///
+    /// # Arguments
+    /// * `f` – Predicate receiving the interval and the data reference of each hit.
+    #[inline]
+    pub fn retain(&mut self, mut f: impl FnMut(&Interval<Idx>, &'tree T) -> bool) {
+        let mut kept = 0;
+        for i in 0..self.intervals.len() {
+            if f(&self.intervals[i], self.data[i]) {
+                self.intervals.swap(kept, i);
+                self.data.swap(kept, i);
+                kept += 1;
+            }
+        }
+        self.intervals.truncate(kept);
+        self.data.truncate(kept);
+    }
+
     /// Returns `true` if the collection contains no hits.
     #[inline]
     pub fn is_empty(&self) -> bool {
@@ -155,15 +182,24 @@ impl<'tree, Idx: PrimInt, T: ?Sized> Hits<'tree, Idx, T> {
 /// This structure supports buffer reuse similar to [`Hits`]:
 /// 1.  **Within the same lifetime scope:** Use [`clear`](#method.clear).
 /// 2.  **Across different lifetime scopes and types:** Use [`recycle`](#method.recycle).
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Idx: serde::Serialize, T: serde::Serialize",
+        deserialize = "Idx: serde::Deserialize<'de>, &'tree T: serde::Deserialize<'de>"
+    ))
+)]
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Dissolve)]
 pub struct BatchHits<'tree, Idx: PrimInt, T: ?Sized> {
     // Flattened vector of all intervals found across all queries in the batch.
-    intervals: Vec<Interval<Idx>>,
+    pub(super) intervals: Vec<Interval<Idx>>,
     // Flattened vector of references to data associated with the found intervals.
-    data: Vec<&'tree T>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub(super) data: Vec<&'tree T>,
     // Stores boundaries for each query in the batch. All intervals and data records in the
     // index[i]..index[i+1] range belong to the i-th query.
-    index: Vec<usize>,
+    pub(super) index: Vec<usize>,
 }
 
 // Manual Clone implementation to circumvent the derive requirement for T: Clone
@@ -381,6 +417,7 @@ impl<'query, 'tree: 'query, Idx: PrimInt, T: ?Sized> Drop
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
+    use biobit_core_rs::loc::IntervalOp;
     use eyre::Result;
     use itertools::Itertools;
 
@@ -441,6 +478,26 @@ pub(crate) mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_hits_retain() -> Result<()> {
+        let mut hits = Hits::new();
+        hits.push(Interval::new(0, 10)?, "A");
+        hits.push(Interval::new(5, 8)?, "B");
+        hits.push(Interval::new(12, 15)?, "C");
+        hits.push(Interval::new(20, 22)?, "D");
+
+        hits.retain(|it, data| it.start() >= 5 && data != "D");
+        assert_eq!(
+            hits.iter().map(|(it, d)| (*it, d)).collect_vec(),
+            vec![(Interval::new(5, 8)?, "B"), (Interval::new(12, 15)?, "C")]
+        );
+
+        hits.retain(|_, _| false);
+        assert!(hits.is_empty());
+        assert_eq!(hits.data().len(), 0);
+        Ok(())
+    }
+
     #[test]
     fn test_hits_clone() -> Result<()> {
         let data = "D";
@@ -710,4 +767,70 @@ pub(crate) mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_hits_serde() -> Result<()> {
+        let data = ["A".to_string(), "B".to_string()];
+        let mut hits: Hits<i64, str> = Hits::new();
+        hits.push(Interval::new(-5, 10)?, &data[0]);
+        hits.push(Interval::new(0, 1)?, &data[1]);
+
+        let bytes = bitcode::serialize(&hits)?;
+        let decoded: Hits<i64, str> = bitcode::deserialize(&bytes)?;
+        assert_eq!(decoded, hits);
+
+        let mut batch: BatchHits<i64, str> = BatchHits::new();
+        batch.add_hits().add(Interval::new(0, 10)?, &data[0]);
+        batch.add_hits().push();
+        {
+            let mut hits = batch.add_hits();
+            hits.add(Interval::new(5, 15)?, &data[1]);
+            hits.add(Interval::new(7, 8)?, &data[0]);
+        }
+
+        let bytes = bitcode::serialize(&batch)?;
+        let decoded: BatchHits<i64, str> = bitcode::deserialize(&bytes)?;
+        assert_eq!(decoded, batch);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "bitcode")]
+    fn test_hits_bitcode() -> Result<()> {
+        use super::super::codec::{RawBatchHits, RawHits};
+
+        let mut hits: Hits<i64, str> = Hits::new();
+        hits.push(Interval::new(-5, 10)?, "A");
+        hits.push(Interval::new(0, 1)?, "B");
+
+        let bytes = bitcode::encode(&RawHits::from(&hits));
+        let decoded: Hits<i64, str> = bitcode::decode::<RawHits<i64, &str>>(&bytes)?.try_into()?;
+        assert_eq!(decoded, hits);
+
+        let mut batch: BatchHits<i64, str> = BatchHits::new();
+        batch.add_hits().add(Interval::new(0, 10)?, "A");
+        batch.add_hits().push();
+        {
+            let mut hits = batch.add_hits();
+            hits.add(Interval::new(5, 15)?, "B");
+            hits.add(Interval::new(7, 8)?, "A");
+        }
+
+        let bytes = bitcode::encode(&RawBatchHits::from(&batch));
+        let decoded: BatchHits<i64, str> =
+            bitcode::decode::<RawBatchHits<i64, &str>>(&bytes)?.try_into()?;
+        assert_eq!(decoded, batch);
+
+        // Mismatched columns and invalid batch boundaries are rejected
+        let mut raw = RawBatchHits::from(&batch);
+        raw.data.pop();
+        assert!(BatchHits::try_from(raw).is_err());
+        for index in [vec![], vec![1, 3], vec![0, 2, 1, 3], vec![0, 4]] {
+            let mut raw = RawBatchHits::from(&batch);
+            raw.index = index;
+            assert!(BatchHits::try_from(raw).is_err());
+        }
+        Ok(())
+    }
 }
@@ -1,5 +1,12 @@
+#[cfg(feature = "bitcode")]
+mod codec;
 mod hits;
 mod segments;
+mod weighted;
 
 pub use hits::{BatchHits, Hits};
 pub use segments::{BatchHitSegments, HitSegments};
+pub use weighted::{BatchWeightedSegments, SegmentsView, WeightedSegments};
+
+#[cfg(feature = "bitcode")]
+pub use codec::{RawBatchHitSegments, RawBatchHits, RawHitSegments, RawHits};
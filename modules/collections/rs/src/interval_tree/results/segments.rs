@@ -236,10 +236,19 @@ fn sweep_line_into<'tree, Idx: PrimInt, T: Eq + Hash + ?Sized>(
 ///   within the same lifetime (`'tree`).
 /// - `recycle()`: Consumes the object, returning a new empty one with the same allocated
 ///   capacity but potentially associated with different types and a new lifetime (`'new_tree`).
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Idx: serde::Serialize, T: serde::Serialize",
+        deserialize = "Idx: serde::Deserialize<'de>, &'tree T: serde::Deserialize<'de>"
+    ))
+)]
 #[derive(Clone, PartialEq, Eq, Debug, Dissolve)]
 pub struct HitSegments<'tree, Idx: PrimInt, T: Eq + Hash + ?Sized> {
-    segments: Vec<Interval<Idx>>,
-    data: Vec<HashSet<&'tree T>>,
+    pub(super) segments: Vec<Interval<Idx>>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub(super) data: Vec<HashSet<&'tree T>>,
 }
 
 impl<Idx: PrimInt, T: Eq + Hash + ?Sized> Default for HitSegments<'_, Idx, T> {
@@ -390,15 +399,24 @@ impl<'tree, Idx: PrimInt, T: Eq + Hash + ?Sized> HitSegments<'tree, Idx, T> {
 ///   within the same lifetime (`'tree`).
 /// - `recycle()`: Consumes the object, returning a new empty one with the same allocated
 ///   capacity but potentially associated with new types/lifetime.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Idx: serde::Serialize, T: serde::Serialize",
+        deserialize = "Idx: serde::Deserialize<'de>, &'tree T: serde::Deserialize<'de>"
+    ))
+)]
 #[derive(Clone, PartialEq, Eq, Debug, Dissolve)]
 pub struct BatchHitSegments<'tree, Idx: PrimInt, T: Eq + Hash + ?Sized> {
     // Flattened vector of all non-overlapping, sorted segments across all queries.
-    segments: Vec<Interval<Idx>>,
+    pub(super) segments: Vec<Interval<Idx>>,
     // Flattened vector of unique data sets corresponding to each segment.
-    data: Vec<HashSet<&'tree T>>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub(super) data: Vec<HashSet<&'tree T>>,
     // Stores boundaries for each query's results in the flattened vectors.
     // Results for query `i` are in the range `index[i]..index[i+1]`.
-    index: Vec<usize>,
+    pub(super) index: Vec<usize>,
 }
 
 impl<Idx: PrimInt, T: Eq + Hash + ?Sized> Default for BatchHitSegments<'_, Idx, T> {
@@ -911,6 +929,68 @@ mod tests {
         assert_eq!(segments.iter().count(), 0);
         Ok(())
     }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_segments_serde() -> Result<()> {
+        let mut hits = Hits::new();
+        hits.push(Interval::new(0, 10)?, "A");
+        hits.push(Interval::new(5, 15)?, "B");
+        let query = [Interval::new(0, 20)?];
+
+        let mut segments = HitSegments::new();
+        segments.build(query, &hits)?;
+        let bytes = bitcode::serialize(&segments)?;
+        let decoded: HitSegments<i32, str> = bitcode::deserialize(&bytes)?;
+        assert_eq!(decoded, segments);
+
+        let mut batch = BatchHits::new();
+        batch.add_hits().add(Interval::new(0, 10)?, "A");
+        batch.add_hits().add(Interval::new(20, 30)?, "C");
+        let mut segments = BatchHitSegments::new();
+        segments.build([&query, &query], &batch)?;
+        let bytes = bitcode::serialize(&segments)?;
+        let decoded: BatchHitSegments<i32, str> = bitcode::deserialize(&bytes)?;
+        assert_eq!(decoded, segments);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "bitcode")]
+    fn test_segments_bitcode() -> Result<()> {
+        use super::super::codec::{RawBatchHitSegments, RawHitSegments};
+
+        let mut hits = Hits::new();
+        hits.push(Interval::new(0, 10)?, "A");
+        hits.push(Interval::new(5, 15)?, "B");
+        let query = [Interval::new(0, 20)?];
+
+        let mut segments = HitSegments::new();
+        segments.build(query, &hits)?;
+        let bytes = bitcode::encode(&RawHitSegments::from(&segments));
+        let decoded: HitSegments<i32, str> =
+            bitcode::decode::<RawHitSegments<i32, &str>>(&bytes)?.try_into()?;
+        assert_eq!(decoded, segments);
+
+        let mut raw = RawHitSegments::from(&segments);
+        raw.segments.pop();
+        assert!(HitSegments::try_from(raw).is_err());
+
+        let mut batch = BatchHits::new();
+        batch.add_hits().add(Interval::new(0, 10)?, "A");
+        batch.add_hits().add(Interval::new(20, 30)?, "C");
+        let mut segments = BatchHitSegments::new();
+        segments.build([&query, &query], &batch)?;
+        let bytes = bitcode::encode(&RawBatchHitSegments::from(&segments));
+        let decoded: BatchHitSegments<i32, str> =
+            bitcode::decode::<RawBatchHitSegments<i32, &str>>(&bytes)?.try_into()?;
+        assert_eq!(decoded, segments);
+
+        let mut raw = RawBatchHitSegments::from(&segments);
+        raw.index.push(0);
+        assert!(BatchHitSegments::try_from(raw).is_err());
+        Ok(())
+    }
 }
 
 // fn _build_hashed(&mut self, query: &[Interval<Idx>], hits: &Hits<'tree, Idx, T>) {
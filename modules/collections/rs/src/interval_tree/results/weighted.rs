@@ -0,0 +1,919 @@
+use super::hits::{BatchHits, Hits};
+use ahash::RandomState;
+use biobit_core_rs::loc::{Interval, IntervalOp};
+use biobit_core_rs::num::{Float, PrimInt};
+use derive_getters::Dissolve;
+use eyre::{Result, bail, ensure};
+use itertools::izip;
+use std::borrow::Borrow;
+use std::hash::Hash;
+
+// Event kinds in the order they must be processed at the same position
+const HIT_START: u8 = 0;
+const QUERY_START: u8 = 1;
+const QUERY_END: u8 = 2;
+const HIT_END: u8 = 3;
+
+/// Reusable state of the sweep line. Kept between builds to avoid allocations. None of the buffers
+/// depend on the data type, so they also survive recycling.
+#[derive(Clone, Debug, Dissolve)]
+struct Sweep<Idx: PrimInt> {
+    // (position, kind, element index) triplets
+    events: Vec<(Idx, u8, u32)>,
+    // (data hash, hit index) pairs used to group hits with equal data references
+    keys: Vec<(u64, u32)>,
+    // Element index of each hit
+    elements: Vec<u32>,
+    // Number of active hit intervals for each element
+    depth: Vec<u32>,
+    // Bitset of elements with at least one active hit interval
+    active: Vec<u64>,
+    hasher: RandomState,
+}
+
+impl<Idx: PrimInt> Default for Sweep<Idx> {
+    fn default() -> Self {
+        Self {
+            events: Vec::new(),
+            keys: Vec::new(),
+            elements: Vec::new(),
+            depth: Vec::new(),
+            active: Vec::new(),
+            hasher: RandomState::new(),
+        }
+    }
+}
+
+impl<Idx: PrimInt> Sweep<Idx> {
+    fn recycle<NewIdx: PrimInt>(self) -> Sweep<NewIdx> {
+        let (mut events, keys, elements, depth, active, hasher) = self.dissolve();
+        events.clear();
+        Sweep {
+            events: events.into_iter().map(|_| unreachable!()).collect(),
+            keys,
+            elements,
+            depth,
+            active,
+            hasher,
+        }
+    }
+}
+
+/// Flattened output buffers shared by the single-query and batch segmentations.
+struct Output<'a, 'tree, Idx: PrimInt, T: ?Sized> {
+    segments: &'a mut Vec<Interval<Idx>>,
+    depth: &'a mut Vec<u32>,
+    offsets: &'a mut Vec<usize>,
+    members: &'a mut Vec<u32>,
+    elements: &'a mut Vec<&'tree T>,
+}
+
+/// Internal sweep-line implementation. Appends segments of a single query to the output buffers.
+/// Element indices stored in `members` are local to the query, i.e. they start from 0 at the
+/// first element appended by this call.
+fn sweep_into<'tree, Idx: PrimInt, T: Eq + Hash + ?Sized>(
+    query: impl Iterator<Item: Borrow<Interval<Idx>>>,
+    mut hits: impl Iterator<Item: Borrow<Interval<Idx>>>,
+    mut data: impl Iterator<Item: Borrow<&'tree T>>,
+    sweep: &mut Sweep<Idx>,
+    out: Output<'_, 'tree, Idx, T>,
+) -> Result<()> {
+    let events = &mut sweep.events;
+    events.clear();
+    for qit in query {
+        let qit = qit.borrow();
+        events.push((qit.start(), QUERY_START, 0));
+        events.push((qit.end(), QUERY_END, 0));
+    }
+    ensure!(
+        !events.is_empty(),
+        "No query intervals provided, cannot build segments."
+    );
+
+    // Every hit is temporarily stored as a separate element
+    let first_element = out.elements.len();
+    sweep.keys.clear();
+    loop {
+        let (it, hdata) = match (hits.next(), data.next()) {
+            (Some(it), Some(hdata)) => (it, hdata),
+            (None, None) => break,
+            _ => bail!("Mismatch between number of hits and data references."),
+        };
+        let (it, hdata) = (it.borrow(), *hdata.borrow());
+        let hit = (out.elements.len() - first_element) as u32;
+        sweep.keys.push((sweep.hasher.hash_one(hdata), hit));
+        out.elements.push(hdata);
+        events.push((it.start(), HIT_START, hit));
+        events.push((it.end(), HIT_END, hit));
+    }
+
+    // Map each hit to the first hit with equal data. Only hits with equal hashes are compared.
+    let hits = out.elements.len() - first_element;
+    let elements = &mut sweep.elements;
+    elements.clear();
+    elements.extend(0..hits as u32);
+    sweep.keys.sort_unstable();
+    for group in sweep.keys.chunk_by(|a, b| a.0 == b.0) {
+        for (i, &(_, first)) in group.iter().enumerate() {
+            if elements[first as usize] != first {
+                continue;
+            }
+            let hdata = out.elements[first_element + first as usize];
+            for &(_, hit) in &group[i + 1..] {
+                if elements[hit as usize] == hit
+                    && out.elements[first_element + hit as usize] == hdata
+                {
+                    elements[hit as usize] = first;
+                }
+            }
+        }
+    }
+
+    // Keep unique data references in the order of their first appearance
+    let mut unique = 0;
+    for hit in 0..hits {
+        let first = elements[hit] as usize;
+        if first == hit {
+            out.elements[first_element + unique] = out.elements[first_element + hit];
+            elements[hit] = unique as u32;
+            unique += 1;
+        } else {
+            elements[hit] = elements[first];
+        }
+    }
+    out.elements.truncate(first_element + unique);
+    for (_, kind, element) in events.iter_mut() {
+        if *kind == HIT_START || *kind == HIT_END {
+            *element = elements[*element as usize];
+        }
+    }
+
+    let elements = out.elements.len() - first_element;
+    sweep.depth.clear();
+    sweep.depth.resize(elements, 0);
+    sweep.active.clear();
+    sweep.active.resize(elements.div_ceil(64), 0);
+
+    events.sort_unstable();
+
+    let first_segment = out.segments.len();
+    let (mut active_queries, mut total_depth) = (0u32, 0u32);
+    let mut cursor = events[0].0;
+    for &(pos, kind, element) in events.iter() {
+        if pos != cursor && active_queries > 0 {
+            // Collect members of the [cursor, pos) segment
+            let start = out.members.len();
+            for (word, bits) in sweep.active.iter().enumerate() {
+                let mut bits = *bits;
+                while bits != 0 {
+                    out.members.push(word as u32 * 64 + bits.trailing_zeros());
+                    bits &= bits - 1;
+                }
+            }
+
+            // Merge with the previous segment if it's adjacent and identical
+            let last = out.segments.len().wrapping_sub(1);
+            let mergeable = out.segments.len() > first_segment
+                && out.segments[last].end() == cursor
+                && out.depth[last] == total_depth
+                && out.members[out.offsets[last]..start] == out.members[start..];
+            if mergeable {
+                out.members.truncate(start);
+                // SAFETY: Events are sorted by position and the cursor is always less than pos
+                unsafe { out.segments[last].set_end(pos) };
+            } else {
+                // SAFETY: Events are sorted by position and the cursor is always less than pos
+                out.segments
+                    .push(unsafe { Interval::new_unchecked(cursor, pos) });
+                out.depth.push(total_depth);
+                out.offsets.push(out.members.len());
+            }
+        }
+
+        match kind {
+            QUERY_START => active_queries += 1,
+            QUERY_END => active_queries -= 1,
+            HIT_START => {
+                let element = element as usize;
+                if sweep.depth[element] == 0 {
+                    sweep.active[element / 64] |= 1 << (element % 64);
+                }
+                sweep.depth[element] += 1;
+                total_depth += 1;
+            }
+            HIT_END => {
+                let element = element as usize;
+                debug_assert!(sweep.depth[element] > 0);
+                sweep.depth[element] -= 1;
+                if sweep.depth[element] == 0 {
+                    sweep.active[element / 64] &= !(1 << (element % 64));
+                }
+                total_depth -= 1;
+            }
+            _ => unreachable!(),
+        }
+        cursor = pos;
+    }
+
+    debug_assert!(total_depth == 0 && sweep.active.iter().all(|x| *x == 0));
+    debug_assert!(out.offsets.len() == out.segments.len() + 1);
+    Ok(())
+}
+
+/// A read-only view of segments generated for a single query.
+///
+/// Each segment is annotated with its coverage depth (the number of hit intervals overlapping
+/// it) and the sorted list of unique elements overlapping it. Elements are referenced by their
+/// index in the [`elements`](#method.elements) table, which lists each unique data reference
+/// of the query hits exactly once.
+#[derive(Debug)]
+pub struct SegmentsView<'a, 'tree, Idx: PrimInt, T: ?Sized> {
+    segments: &'a [Interval<Idx>],
+    depth: &'a [u32],
+    // Absolute offsets into `members`, one more than the number of segments
+    offsets: &'a [usize],
+    members: &'a [u32],
+    elements: &'a [&'tree T],
+}
+
+// Manual Clone and Copy implementations: derive would require T: Clone
+impl<Idx: PrimInt, T: ?Sized> Clone for SegmentsView<'_, '_, Idx, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Idx: PrimInt, T: ?Sized> Copy for SegmentsView<'_, '_, Idx, T> {}
+
+impl<'a, 'tree, Idx: PrimInt, T: ?Sized> SegmentsView<'a, 'tree, Idx, T> {
+    /// Non-overlapping segments sorted by coordinate.
+    #[inline]
+    pub fn segments(&self) -> &'a [Interval<Idx>] {
+        self.segments
+    }
+
+    /// Number of hit intervals overlapping each segment.
+    #[inline]
+    pub fn depth(&self) -> &'a [u32] {
+        self.depth
+    }
+
+    /// Unique data references of the query hits.
+    #[inline]
+    pub fn elements(&self) -> &'a [&'tree T] {
+        self.elements
+    }
+
+    /// Sorted indices of elements overlapping the `i`-th segment.
+    #[inline]
+    pub fn members(&self, i: usize) -> &'a [u32] {
+        &self.members[self.offsets[i]..self.offsets[i + 1]]
+    }
+
+    /// Number of unique elements overlapping each segment.
+    pub fn counts(&self) -> impl ExactSizeIterator<Item = usize> + 'a {
+        self.offsets.windows(2).map(|x| x[1] - x[0])
+    }
+
+    /// Returns an iterator over `(segment, depth, members)` triplets.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&'a Interval<Idx>, u32, &'a [u32])> + '_ {
+        izip!(self.segments, self.depth, 0..self.segments.len())
+            .map(|(segment, depth, i)| (segment, *depth, self.members(i)))
+    }
+
+    /// Returns `true` if there are no segments.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Number of segments.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Fraction of the total query length covered by each segment.
+    pub fn weights<F: Float>(&self) -> impl ExactSizeIterator<Item = F> {
+        let total = self
+            .segments
+            .iter()
+            .fold(Idx::zero(), |sum, x| sum + x.len());
+        let total = F::from(total).unwrap();
+        self.segments
+            .iter()
+            .map(move |x| F::from(x.len()).unwrap() / total)
+    }
+
+    /// Distributes each segment weight equally between its members and accumulates the result
+    /// per element. Weights are written to the `buffer` (one per element), overwriting its
+    /// previous contents.
+    ///
+    /// Returns the total weight of segments without members, i.e. the fraction of the query
+    /// that doesn't overlap any element.
+    pub fn element_weights<F: Float>(&self, buffer: &mut Vec<F>) -> F {
+        buffer.clear();
+        buffer.resize(self.elements.len(), F::zero());
+
+        let mut uncovered = F::zero();
+        for (i, weight) in self.weights::<F>().enumerate() {
+            let members = self.members(i);
+            if members.is_empty() {
+                uncovered = uncovered + weight;
+            } else {
+                let weight = weight / F::from(members.len()).unwrap();
+                for m in members {
+                    buffer[*m as usize] = buffer[*m as usize] + weight;
+                }
+            }
+        }
+        uncovered
+    }
+}
+
+/// Segmentation of query intervals annotated with per-segment coverage depth and members.
+///
+/// It's a compact alternative to [`HitSegments`](super::HitSegments): instead of a `HashSet` per
+/// segment, members of all segments are stored as indices into a table of unique elements in a
+/// single flat buffer. Together with the internal sweep-line state, all buffers are reused
+/// between builds and kept by `recycle()`, so repeated segmentation doesn't allocate once the
+/// buffers are warmed up.
+///
+/// **Key Guarantees:**
+/// - Segments cover *only* regions within the original `query` intervals passed to `build`.
+/// - Segments are non-overlapping and sorted by coordinate.
+/// - Adjacent segments always differ in their members or coverage depth. Hence, segments can be
+///   shorter than in `HitSegments` when the same element overlaps a region several times.
+///
+/// **Buffer Reuse:**
+/// - `clear()`: Removes all segments, keeping the allocated memory for reuse within the same
+///   lifetime (`'tree`).
+/// - `recycle()`: Consumes the object, returning a new empty one with the same allocated
+///   capacity but potentially associated with different types and a new lifetime (`'new_tree`).
+#[derive(Clone, Debug, Dissolve)]
+pub struct WeightedSegments<'tree, Idx: PrimInt, T: Eq + Hash + ?Sized> {
+    segments: Vec<Interval<Idx>>,
+    depth: Vec<u32>,
+    offsets: Vec<usize>,
+    members: Vec<u32>,
+    elements: Vec<&'tree T>,
+    sweep: Sweep<Idx>,
+}
+
+impl<Idx: PrimInt, T: Eq + Hash + ?Sized> Default for WeightedSegments<'_, Idx, T> {
+    fn default() -> Self {
+        Self {
+            segments: Vec::new(),
+            depth: Vec::new(),
+            offsets: vec![0],
+            members: Vec::new(),
+            elements: Vec::new(),
+            sweep: Sweep::default(),
+        }
+    }
+}
+
+impl<'tree, Idx: PrimInt, T: Eq + Hash + ?Sized> WeightedSegments<'tree, Idx, T> {
+    /// Creates a new, empty `WeightedSegments` collection with no pre-allocated capacity.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calculates the segments based on the query intervals and hits using a sweep-line approach.
+    ///
+    /// Overwrites any existing segments stored in this object.
+    pub fn build<Query>(&mut self, query: Query, hits: &Hits<'tree, Idx, T>) -> Result<()>
+    where
+        Query: IntoIterator<Item: Borrow<Interval<Idx>>>,
+    {
+        self.build_from_parts(query, hits.intervals(), hits.data())
+    }
+
+    /// Calculates the segments based on the query intervals and hit parts. Errs if the length
+    /// of intervals and data don't match.
+    pub fn build_from_parts<Query, Intervals, Data>(
+        &mut self,
+        query: Query,
+        intervals: Intervals,
+        data: Data,
+    ) -> Result<()>
+    where
+        Query: IntoIterator<Item: Borrow<Interval<Idx>>>,
+        Intervals: IntoIterator<Item: Borrow<Interval<Idx>>>,
+        Data: IntoIterator<Item: Borrow<&'tree T>>,
+    {
+        self.clear();
+        sweep_into(
+            query.into_iter(),
+            intervals.into_iter(),
+            data.into_iter(),
+            &mut self.sweep,
+            Output {
+                segments: &mut self.segments,
+                depth: &mut self.depth,
+                offsets: &mut self.offsets,
+                members: &mut self.members,
+                elements: &mut self.elements,
+            },
+        )
+    }
+
+    /// Returns a view of the generated segments.
+    #[inline]
+    pub fn view(&self) -> SegmentsView<'_, 'tree, Idx, T> {
+        SegmentsView {
+            segments: &self.segments,
+            depth: &self.depth,
+            offsets: &self.offsets,
+            members: &self.members,
+            elements: &self.elements,
+        }
+    }
+
+    /// Returns `true` if no segments were generated.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the number of segments.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Clears the segments, retaining allocated capacity.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.segments.clear();
+        self.depth.clear();
+        self.offsets.clear();
+        self.offsets.push(0);
+        self.members.clear();
+        self.elements.clear();
+    }
+
+    pub fn recycle<'new_tree, NewIdx: PrimInt, NewT: Eq + Hash + ?Sized>(
+        mut self,
+    ) -> WeightedSegments<'new_tree, NewIdx, NewT> {
+        self.clear();
+
+        let (segments, depth, offsets, members, elements, sweep) = self.dissolve();
+        WeightedSegments {
+            segments: segments.into_iter().map(|_| unreachable!()).collect(),
+            depth,
+            offsets,
+            members,
+            elements: elements.into_iter().map(|_| unreachable!()).collect(),
+            sweep: sweep.recycle(),
+        }
+    }
+}
+
+/// Batch version of [`WeightedSegments`] storing segmentations of many queries in shared flat
+/// buffers.
+///
+/// It's designed for hot loops (e.g. processing alignments one by one), where the same object is
+/// rebuilt for each batch of queries without allocating.
+#[derive(Clone, Debug, Dissolve)]
+pub struct BatchWeightedSegments<'tree, Idx: PrimInt, T: Eq + Hash + ?Sized> {
+    segments: Vec<Interval<Idx>>,
+    depth: Vec<u32>,
+    offsets: Vec<usize>,
+    members: Vec<u32>,
+    elements: Vec<&'tree T>,
+    // Results for query `i` are in the index[i]..index[i+1] range of segments
+    index: Vec<usize>,
+    // Elements of query `i` are in the elements_index[i]..elements_index[i+1] range
+    elements_index: Vec<usize>,
+    sweep: Sweep<Idx>,
+}
+
+impl<Idx: PrimInt, T: Eq + Hash + ?Sized> Default for BatchWeightedSegments<'_, Idx, T> {
+    fn default() -> Self {
+        Self {
+            segments: Vec::new(),
+            depth: Vec::new(),
+            offsets: vec![0],
+            members: Vec::new(),
+            elements: Vec::new(),
+            index: vec![0],
+            elements_index: vec![0],
+            sweep: Sweep::default(),
+        }
+    }
+}
+
+impl<'tree, Idx: PrimInt, T: Eq + Hash + ?Sized> BatchWeightedSegments<'tree, Idx, T> {
+    /// Creates a new, empty `BatchWeightedSegments` collection with no pre-allocated capacity.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calculates the segments for each query and its hits using a sweep-line approach.
+    ///
+    /// Overwrites any existing segments stored in this object.
+    pub fn build<Queries>(
+        &mut self,
+        queries: Queries,
+        hits: &BatchHits<'tree, Idx, T>,
+    ) -> Result<()>
+    where
+        Queries: IntoIterator<Item: IntoIterator<Item: Borrow<Interval<Idx>>>>,
+    {
+        self.build_from_parts(queries, hits.intervals_iter(), hits.data_iter())?;
+        ensure!(
+            self.len() == hits.len(),
+            "Mismatch between number of queries and hits."
+        );
+        Ok(())
+    }
+
+    /// Calculates the segments for each query and its hit parts. Errs if the number of queries,
+    /// hit intervals and data don't match.
+    pub fn build_from_parts<Queries, Intervals, Data>(
+        &mut self,
+        queries: Queries,
+        intervals: Intervals,
+        data: Data,
+    ) -> Result<()>
+    where
+        Queries: IntoIterator<Item: IntoIterator<Item: Borrow<Interval<Idx>>>>,
+        Intervals: IntoIterator<Item: IntoIterator<Item: Borrow<Interval<Idx>>>>,
+        Data: IntoIterator<Item: IntoIterator<Item: Borrow<&'tree T>>>,
+    {
+        self.clear();
+
+        let mut queries = queries.into_iter();
+        let mut intervals = intervals.into_iter();
+        let mut data = data.into_iter();
+
+        for (q, i, d) in izip!(queries.by_ref(), intervals.by_ref(), data.by_ref()) {
+            sweep_into(
+                q.into_iter(),
+                i.into_iter(),
+                d.into_iter(),
+                &mut self.sweep,
+                Output {
+                    segments: &mut self.segments,
+                    depth: &mut self.depth,
+                    offsets: &mut self.offsets,
+                    members: &mut self.members,
+                    elements: &mut self.elements,
+                },
+            )?;
+            self.index.push(self.segments.len());
+            self.elements_index.push(self.elements.len());
+        }
+        ensure!(
+            queries.next().is_none() && intervals.next().is_none() && data.next().is_none(),
+            "Mismatch between number of queries and hit intervals/data."
+        );
+        Ok(())
+    }
+
+    /// Returns a view of segments generated for the `i`-th query in the batch.
+    pub fn get(&self, i: usize) -> Option<SegmentsView<'_, 'tree, Idx, T>> {
+        if i >= self.len() {
+            return None;
+        }
+        let (start, end) = (self.index[i], self.index[i + 1]);
+        Some(SegmentsView {
+            segments: &self.segments[start..end],
+            depth: &self.depth[start..end],
+            offsets: &self.offsets[start..=end],
+            members: &self.members,
+            elements: &self.elements[self.elements_index[i]..self.elements_index[i + 1]],
+        })
+    }
+
+    /// Returns an iterator over views of each query in the batch.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = SegmentsView<'_, 'tree, Idx, T>> {
+        (0..self.len()).map(|i| self.get(i).unwrap())
+    }
+
+    /// Returns `true` if no query results have been added to the batch yet.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.index.len() == 1
+    }
+
+    /// Returns the number of queries in the batch.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.index.len() - 1
+    }
+
+    /// Returns the total number of segments across all queries in the batch.
+    #[inline]
+    pub fn total_segments(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Clears the collection, retaining the allocated capacity for reuse.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.segments.clear();
+        self.depth.clear();
+        self.offsets.clear();
+        self.offsets.push(0);
+        self.members.clear();
+        self.elements.clear();
+        self.index.clear();
+        self.index.push(0);
+        self.elements_index.clear();
+        self.elements_index.push(0);
+    }
+
+    pub fn recycle<'new_tree, NewIdx: PrimInt, NewT: Eq + Hash + ?Sized>(
+        mut self,
+    ) -> BatchWeightedSegments<'new_tree, NewIdx, NewT> {
+        self.clear();
+
+        let (segments, depth, offsets, members, elements, index, elements_index, sweep) =
+            self.dissolve();
+        BatchWeightedSegments {
+            segments: segments.into_iter().map(|_| unreachable!()).collect(),
+            depth,
+            offsets,
+            members,
+            elements: elements.into_iter().map(|_| unreachable!()).collect(),
+            index,
+            elements_index,
+            sweep: sweep.recycle(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interval_tree::{BatchHitSegments, HitSegments};
+    use std::collections::HashSet;
+
+    const HITS: [(i32, i32, &str); 6] = [
+        (0, 10, "A"),
+        (5, 15, "B"),
+        (8, 12, "A"),
+        (20, 30, "C"),
+        (25, 26, "D"),
+        (40, 50, "E"),
+    ];
+
+    fn hits(hits: &[(i32, i32, &'static str)]) -> Hits<'static, i32, str> {
+        let mut result = Hits::new();
+        for (start, end, data) in hits {
+            result.push(Interval::new(*start, *end).unwrap(), *data);
+        }
+        result
+    }
+
+    // Expand segments to per-position (members, depth) annotation
+    fn expand<'a>(view: &SegmentsView<'_, 'a, i32, str>) -> Vec<(i32, HashSet<&'a str>, u32)> {
+        view.iter()
+            .flat_map(|(segment, depth, members)| {
+                let members = members
+                    .iter()
+                    .map(|x| view.elements()[*x as usize])
+                    .collect::<HashSet<_>>();
+                (segment.start()..segment.end()).map(move |pos| (pos, members.clone(), depth))
+            })
+            .collect()
+    }
+
+    fn brute_force(
+        query: &[Interval<i32>],
+        hits: &[(i32, i32, &'static str)],
+    ) -> Vec<(i32, HashSet<&'static str>, u32)> {
+        let mut positions = query
+            .iter()
+            .flat_map(|x| x.start()..x.end())
+            .collect::<Vec<_>>();
+        positions.sort();
+        positions.dedup();
+        positions
+            .into_iter()
+            .map(|pos| {
+                let overlapping = hits
+                    .iter()
+                    .filter(|(start, end, _)| *start <= pos && pos < *end)
+                    .collect::<Vec<_>>();
+                let members = overlapping.iter().map(|x| x.2).collect();
+                (pos, members, overlapping.len() as u32)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_weighted_segments_build() -> Result<()> {
+        let queries = [
+            vec![Interval::new(0, 50)?],
+            vec![Interval::new(3, 9)?, Interval::new(11, 22)?],
+            vec![Interval::new(0, 5)?, Interval::new(2, 8)?],
+            vec![Interval::new(100, 200)?],
+        ];
+
+        let mut segments = WeightedSegments::new();
+        for query in &queries {
+            segments.build(query, &hits(&HITS))?;
+            let view = segments.view();
+            assert_eq!(expand(&view), brute_force(query, &HITS));
+
+            // Segments are maximal
+            for (i, pair) in view.segments().windows(2).enumerate() {
+                assert!(
+                    pair[0].end() != pair[1].start()
+                        || view.depth()[i] != view.depth()[i + 1]
+                        || view.members(i) != view.members(i + 1)
+                );
+            }
+            assert!(view.counts().zip(view.iter()).all(|(n, x)| n == x.2.len()));
+
+            // Elements are unique
+            let elements = view.elements().iter().collect::<HashSet<_>>();
+            assert_eq!(elements.len(), view.elements().len());
+        }
+
+        assert!(
+            segments
+                .build(&[] as &[Interval<i32>], &hits(&HITS))
+                .is_err()
+        );
+        assert!(
+            segments
+                .build_from_parts(
+                    [Interval::new(0, 1)?],
+                    [Interval::new(0, 1)?],
+                    [] as [&str; 0]
+                )
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_weighted_segments_weights() -> Result<()> {
+        let query = [Interval::new(0, 20)?];
+        let mut segments = WeightedSegments::new();
+        segments.build(query, &hits(&[(0, 10, "A"), (5, 15, "B")]))?;
+        let view = segments.view();
+
+        let weights = view.weights::<f64>().collect::<Vec<_>>();
+        assert_eq!(weights, vec![0.25, 0.25, 0.25, 0.25]);
+
+        let mut buffer = Vec::new();
+        let uncovered = view.element_weights::<f64>(&mut buffer);
+        assert_eq!(uncovered, 0.25);
+        let mut weights = view
+            .elements()
+            .iter()
+            .copied()
+            .zip(buffer)
+            .collect::<Vec<_>>();
+        weights.sort_by(|a, b| a.0.cmp(b.0));
+        assert_eq!(weights, vec![("A", 0.375), ("B", 0.375)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_weighted_segments() -> Result<()> {
+        let queries = [
+            vec![Interval::new(0, 50)?],
+            vec![Interval::new(100, 200)?],
+            vec![Interval::new(3, 9)?, Interval::new(11, 22)?],
+        ];
+        let hits = [&HITS[..], &[], &HITS[..3]];
+
+        let mut batch = BatchHits::new();
+        for h in hits {
+            let mut adder = batch.add_hits();
+            for (start, end, data) in h {
+                adder.add(Interval::new(*start, *end)?, *data);
+            }
+        }
+
+        let mut weighted = BatchWeightedSegments::new();
+        let mut reference = BatchHitSegments::new();
+        for _ in 0..2 {
+            weighted.build(&queries, &batch)?;
+            reference.build(&queries, &batch)?;
+            assert_eq!(weighted.len(), queries.len());
+            assert!(weighted.get(queries.len()).is_none());
+
+            for (i, view) in weighted.iter().enumerate() {
+                assert_eq!(expand(&view), brute_force(&queries[i], hits[i]));
+
+                // Same element weights as with HashSet-based segments
+                let mut buffer = Vec::new();
+                let uncovered = view.element_weights::<f64>(&mut buffer);
+                let total = queries[i].iter().map(|x| x.len()).sum::<i32>() as f64;
+                let (mut expected, mut discarded) = (Vec::new(), 0.0);
+                for (segment, data) in reference
+                    .iter()
+                    .nth(i)
+                    .map(|(s, d)| s.iter().zip(d))
+                    .unwrap()
+                {
+                    let weight = segment.len() as f64 / total;
+                    if data.is_empty() {
+                        discarded += weight;
+                    }
+                    for x in data {
+                        expected.push((*x, weight / data.len() as f64));
+                    }
+                }
+                assert!((uncovered - discarded).abs() < 1e-9);
+                for (element, weight) in view.elements().iter().zip(buffer) {
+                    let sum = expected
+                        .iter()
+                        .filter(|x| x.0 == *element)
+                        .map(|x| x.1)
+                        .sum::<f64>();
+                    assert!((sum - weight).abs() < 1e-9);
+                }
+            }
+        }
+
+        weighted.clear();
+        assert!(weighted.is_empty());
+        assert_eq!(weighted.total_segments(), 0);
+
+        let recycled: BatchWeightedSegments<u64, usize> = weighted.recycle();
+        assert!(recycled.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_weighted_segments_many_elements() -> Result<()> {
+        // More than 64 elements exercise multiple bitset words
+        let names = (0..150).map(|x| x.to_string()).collect::<Vec<_>>();
+        let mut hits = Hits::new();
+        for (i, name) in names.iter().enumerate() {
+            hits.push(Interval::new(i as u32, i as u32 + 10)?, name.as_str());
+        }
+
+        let mut segments = WeightedSegments::new();
+        segments.build([Interval::new(0, 200)?], &hits)?;
+        let view = segments.view();
+        for (segment, depth, members) in view.iter() {
+            let pos = segment.start();
+            let expected = (pos.saturating_sub(9)..(pos + 1).min(150)).count();
+            assert_eq!(depth as usize, expected);
+            assert_eq!(members.len(), expected);
+            for m in members {
+                let element: u32 = view.elements()[*m as usize].parse()?;
+                assert!(element <= pos && pos < element + 10);
+            }
+        }
+
+        let reference = {
+            let mut reference = HitSegments::new();
+            reference.build([Interval::new(0, 200)?], &hits)?;
+            reference.segments().to_vec()
+        };
+        assert_eq!(view.segments(), reference);
+        Ok(())
+    }
+
+    #[test]
+    fn test_weighted_segments_recycle() -> Result<()> {
+        // Equal data behind distinct references is reported as a single element
+        let (a1, a2, b) = ("A".to_string(), "A".to_string(), "B".to_string());
+        let mut hits = Hits::new();
+        hits.push(Interval::new(0, 10)?, a1.as_str());
+        hits.push(Interval::new(5, 15)?, b.as_str());
+        hits.push(Interval::new(10, 20)?, a2.as_str());
+
+        let mut segments = WeightedSegments::new();
+        segments.build([Interval::new(0, 20)?], &hits)?;
+        let view = segments.view();
+        assert_eq!(view.elements(), ["A", "B"]);
+        assert_eq!(
+            view.segments(),
+            [
+                Interval::new(0, 5)?,
+                Interval::new(5, 15)?,
+                Interval::new(15, 20)?
+            ]
+        );
+        assert_eq!(view.depth(), [1, 2, 1]);
+
+        // Recycling keeps the sweep-line buffers
+        let capacity = (
+            segments.sweep.keys.capacity(),
+            segments.sweep.elements.capacity(),
+        );
+        assert!(capacity.0 >= 3 && capacity.1 >= 3);
+        let recycled: WeightedSegments<i64, usize> = segments.recycle();
+        assert_eq!(
+            (
+                recycled.sweep.keys.capacity(),
+                recycled.sweep.elements.capacity()
+            ),
+            capacity
+        );
+        Ok(())
+    }
+}
@@ -2,7 +2,8 @@ use biobit_core_rs::num::PrimInt;
 
 use super::results::BatchHits;
 use super::*;
-use biobit_core_rs::loc::Interval;
+use crate::random::Lcg;
+use biobit_core_rs::loc::{Interval, IntervalOp, Orientation};
 use itertools::Itertools;
 use itertools::izip;
 use std::fmt::Debug;
@@ -17,6 +18,7 @@ where
     test_multi_interval_tree(builder.clone());
     test_deeply_nested_interval_tree(builder.clone());
     test_sparse_interval_tree(builder.clone());
+    test_proximity_queries(builder.clone());
 }
 
 fn normit<Idx: PrimInt>(input: impl Iterator<Item = (Idx, Idx)>) -> Vec<Interval<Idx>> {
@@ -217,3 +219,131 @@ where
         ],
     )
 }
+
+fn test_proximity_queries<T>(builder: T)
+where
+    T: Builder<Target: ITree<Idx = i64, Data = &'static str>> + Clone,
+{
+    // Pseudo-random intervals with duplicates, nested and touching entries
+    let mut lcg = Lcg::new(42);
+    let mut random = |max: u64| lcg.below(max) as i64;
+    let mut records = (0..200)
+        .map(|i| {
+            let start = random(10_000) - 5_000;
+            let end = start + 1 + random(if i % 10 == 0 { 2_000 } else { 50 });
+            let data: &'static str = Box::leak(i.to_string().into_boxed_str());
+            (Interval::new(start, end).unwrap(), data)
+        })
+        .collect_vec();
+    records.push((Interval::new(-5_000, -4_990).unwrap(), "dup-1"));
+    records.push((Interval::new(-5_000, -4_990).unwrap(), "dup-2"));
+
+    let mut queries = (0..200)
+        .map(|_| {
+            let start = random(14_000) - 7_000;
+            Interval::new(start, start + 1 + random(100)).unwrap()
+        })
+        .collect_vec();
+    queries.extend(records.iter().take(20).map(|x| x.0));
+    queries.push(Interval::new(i64::MIN, i64::MIN + 10).unwrap());
+    queries.push(Interval::new(i64::MAX - 10, i64::MAX).unwrap());
+    queries.push(Interval::new(i64::MIN, i64::MAX).unwrap());
+
+    let distance = |a: &Interval<i64>, b: &Interval<i64>| {
+        if b.end() <= a.start() {
+            a.start().saturating_sub(b.end())
+        } else if a.end() <= b.start() {
+            b.start().saturating_sub(a.end())
+        } else {
+            0
+        }
+    };
+    let sorted = |hits: &Hits<i64, &'static str>| {
+        hits.iter()
+            .map(|(x, data)| (*x, *data))
+            .sorted()
+            .collect_vec()
+    };
+
+    for tree in [
+        builder.clone().build(),
+        builder.clone().extend(records.iter().cloned()).build(),
+    ] {
+        // Brute-force oracle over all records stored in the tree
+        let stored = tree.records().map(|(x, y)| (x, *y)).sorted().collect_vec();
+        let select = |keep: &dyn Fn(&Interval<i64>) -> bool| {
+            stored
+                .iter()
+                .filter(|(x, _)| keep(x))
+                .copied()
+                .collect_vec()
+        };
+
+        let mut hits = Hits::new();
+        for query in &queries {
+            tree.containing(query, &mut hits);
+            assert_eq!(sorted(&hits), select(&|x| x.envelops(query)));
+
+            tree.within(query, &mut hits);
+            assert_eq!(sorted(&hits), select(&|x| query.envelops(x)));
+
+            // Upstream for the forward strand is downstream for the reverse one
+            let left = stored
+                .iter()
+                .filter(|(x, _)| x.end() <= query.start())
+                .map(|(x, _)| x.end())
+                .max();
+            for (orientation, upstream) in [
+                (Orientation::Forward, true),
+                (Orientation::Dual, true),
+                (Orientation::Reverse, false),
+            ] {
+                let distance = if upstream {
+                    tree.nearest_upstream(query, orientation, &mut hits)
+                } else {
+                    tree.nearest_downstream(query, orientation, &mut hits)
+                };
+                assert_eq!(distance, left.map(|end| query.start().saturating_sub(end)));
+                assert_eq!(sorted(&hits), select(&|x| Some(x.end()) == left));
+            }
+
+            let right = stored
+                .iter()
+                .filter(|(x, _)| x.start() >= query.end())
+                .map(|(x, _)| x.start())
+                .min();
+            for (orientation, upstream) in [
+                (Orientation::Forward, false),
+                (Orientation::Dual, false),
+                (Orientation::Reverse, true),
+            ] {
+                let distance = if upstream {
+                    tree.nearest_upstream(query, orientation, &mut hits)
+                } else {
+                    tree.nearest_downstream(query, orientation, &mut hits)
+                };
+                assert_eq!(
+                    distance,
+                    right.map(|start| start.saturating_sub(query.end()))
+                );
+                assert_eq!(sorted(&hits), select(&|x| Some(x.start()) == right));
+            }
+
+            for k in [0, 1, 5, 50, 1_000] {
+                tree.closest(query, k, &mut hits);
+                let closest = stored
+                    .iter()
+                    .map(|(x, _)| (distance(query, x), *x))
+                    .sorted()
+                    .take(k)
+                    .collect_vec();
+                let result = hits
+                    .intervals()
+                    .iter()
+                    .map(|x| (distance(query, x), *x))
+                    .collect_vec();
+                assert_eq!(result, closest);
+            }
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use super::results::{BatchHits, Hits};
-use biobit_core_rs::{loc::Interval, num::PrimInt};
+use biobit_core_rs::{
+    loc::{Interval, IntervalOp, Orientation},
+    num::PrimInt,
+};
 
 /// A builder for constructing interval tree data structures.
 ///
@@ -92,3 +95,232 @@ pub trait ITree {
         buffer: &mut BatchHits<'hits, Self::Idx, Self::Data>,
     );
 }
+
+/// Proximity and containment queries available for every [`ITree`] implementation.
+///
+/// All queries are built on top of [`ITree::intersect_interval`]: proximity searches probe
+/// exponentially growing windows around the query until the answer is guaranteed, so their cost
+/// depends on the distance to the closest entries rather than on the size of the tree.
+///
+/// Distances are measured between interval boundaries, i.e. overlapping and touching intervals
+/// are at distance zero. Distances exceeding the coordinate type range saturate at its maximum.
+pub trait ITreeExt: ITree {
+    /// Finds all entries whose intervals fully contain the query interval.
+    ///
+    /// Results are placed into the provided `buffer`, overwriting its previous contents.
+    fn containing<'hits, 'tree: 'hits>(
+        &'tree self,
+        interval: &Interval<Self::Idx>,
+        buffer: &mut Hits<'hits, Self::Idx, Self::Data>,
+    ) {
+        self.intersect_interval(interval, buffer);
+        buffer.retain(|x, _| x.envelops(interval));
+    }
+
+    /// Finds all entries whose intervals lie fully within the query interval.
+    ///
+    /// Results are placed into the provided `buffer`, overwriting its previous contents.
+    fn within<'hits, 'tree: 'hits>(
+        &'tree self,
+        interval: &Interval<Self::Idx>,
+        buffer: &mut Hits<'hits, Self::Idx, Self::Data>,
+    ) {
+        self.intersect_interval(interval, buffer);
+        buffer.retain(|x, _| interval.envelops(x));
+    }
+
+    /// Finds the closest entries located upstream of the query and not overlapping it.
+    ///
+    /// Upstream is defined relative to the query `orientation`: lower coordinates for the forward
+    /// (and dual) orientation, higher coordinates for the reverse one. All entries sharing the
+    /// minimal distance are placed into the `buffer`, overwriting its previous contents.
+    ///
+    /// # Returns
+    /// The distance to the reported entries or `None` if there are no upstream entries.
+    fn nearest_upstream<'hits, 'tree: 'hits>(
+        &'tree self,
+        interval: &Interval<Self::Idx>,
+        orientation: Orientation,
+        buffer: &mut Hits<'hits, Self::Idx, Self::Data>,
+    ) -> Option<Self::Idx> {
+        match orientation {
+            Orientation::Forward | Orientation::Dual => nearest_left(self, interval, buffer),
+            Orientation::Reverse => nearest_right(self, interval, buffer),
+        }
+    }
+
+    /// Finds the closest entries located downstream of the query and not overlapping it.
+    ///
+    /// Downstream is defined relative to the query `orientation`: higher coordinates for the
+    /// forward (and dual) orientation, lower coordinates for the reverse one. All entries sharing
+    /// the minimal distance are placed into the `buffer`, overwriting its previous contents.
+    ///
+    /// # Returns
+    /// The distance to the reported entries or `None` if there are no downstream entries.
+    fn nearest_downstream<'hits, 'tree: 'hits>(
+        &'tree self,
+        interval: &Interval<Self::Idx>,
+        orientation: Orientation,
+        buffer: &mut Hits<'hits, Self::Idx, Self::Data>,
+    ) -> Option<Self::Idx> {
+        match orientation {
+            Orientation::Forward | Orientation::Dual => nearest_right(self, interval, buffer),
+            Orientation::Reverse => nearest_left(self, interval, buffer),
+        }
+    }
+
+    /// Finds up to `k` entries closest to the query in either direction, overlapping entries
+    /// included.
+    ///
+    /// Results are placed into the provided `buffer`, overwriting its previous contents, and are
+    /// sorted by the distance to the query. Ties are broken by the entry interval, and entries
+    /// that tie with the `k`-th one beyond the limit are dropped.
+    fn closest<'hits, 'tree: 'hits>(
+        &'tree self,
+        interval: &Interval<Self::Idx>,
+        k: usize,
+        buffer: &mut Hits<'hits, Self::Idx, Self::Data>,
+    ) {
+        closest(self, interval, k, buffer)
+    }
+}
+
+impl<T: ITree + ?Sized> ITreeExt for T {}
+
+/// Distance between two intervals, zero if they overlap or touch.
+#[inline]
+fn distance<Idx: PrimInt>(a: &Interval<Idx>, b: &Interval<Idx>) -> Idx {
+    if b.end() <= a.start() {
+        a.start().saturating_sub(b.end())
+    } else if a.end() <= b.start() {
+        b.start().saturating_sub(a.end())
+    } else {
+        Idx::zero()
+    }
+}
+
+/// Finds up to `k` entries closest to the query, see [`ITreeExt::closest`].
+fn closest<'hits, 'tree: 'hits, Idx: PrimInt, Data, T: ITree<Idx = Idx, Data = Data> + ?Sized>(
+    tree: &'tree T,
+    interval: &Interval<Idx>,
+    k: usize,
+    buffer: &mut Hits<'hits, Idx, Data>,
+) {
+    buffer.clear();
+    if k == 0 {
+        return;
+    }
+
+    // Window half-width, None once it no longer fits into Idx and the whole axis must be covered
+    let mut width = Some(Idx::one());
+    loop {
+        let (start, end) = match width {
+            Some(width) => (
+                interval.start().saturating_sub(width),
+                interval.end().saturating_add(width),
+            ),
+            None => (Idx::min_value(), Idx::max_value()),
+        };
+        // SAFETY: The window is never shorter than the query interval.
+        let window = unsafe { Interval::new_unchecked(start, end) };
+        tree.intersect_interval(&window, buffer);
+
+        // Entries closer than the window half-width are guaranteed to be inside the window
+        let exhaustive = start == Idx::min_value() && end == Idx::max_value();
+        let confident = match width {
+            Some(width) if !exhaustive => buffer
+                .intervals()
+                .iter()
+                .filter(|x| distance(interval, x) < width)
+                .count(),
+            _ => buffer.len(),
+        };
+
+        if confident >= k || exhaustive {
+            let mut hits = buffer
+                .iter()
+                .map(|(x, data)| (distance(interval, x), *x, data))
+                .collect::<Vec<_>>();
+            hits.sort_by_key(|(distance, x, _)| (*distance, *x));
+
+            buffer.clear();
+            for (_, x, data) in hits.into_iter().take(k) {
+                buffer.push(x, data);
+            }
+            return;
+        }
+        width = width.and_then(|x| x.checked_add(&x));
+    }
+}
+
+/// Finds the closest entries ending at or before the query start.
+fn nearest_left<
+    'hits,
+    'tree: 'hits,
+    Idx: PrimInt,
+    Data,
+    T: ITree<Idx = Idx, Data = Data> + ?Sized,
+>(
+    tree: &'tree T,
+    interval: &Interval<Idx>,
+    buffer: &mut Hits<'hits, Idx, Data>,
+) -> Option<Idx> {
+    buffer.clear();
+
+    let mut width = Some(Idx::one());
+    while interval.start() > Idx::min_value() {
+        let start = match width {
+            Some(width) => interval.start().saturating_sub(width),
+            None => Idx::min_value(),
+        };
+        // SAFETY: start < interval.start() since the latter is above the minimum value.
+        let window = unsafe { Interval::new_unchecked(start, interval.start()) };
+        tree.intersect_interval(&window, buffer);
+        buffer.retain(|x, _| x.end() <= interval.start());
+
+        if let Some(end) = buffer.intervals().iter().map(|x| x.end()).max() {
+            buffer.retain(|x, _| x.end() == end);
+            return Some(interval.start().saturating_sub(end));
+        } else if start == Idx::min_value() {
+            break;
+        }
+        width = width.and_then(|x| x.checked_add(&x));
+    }
+    None
+}
+
+/// Finds the closest entries starting at or after the query end.
+fn nearest_right<
+    'hits,
+    'tree: 'hits,
+    Idx: PrimInt,
+    Data,
+    T: ITree<Idx = Idx, Data = Data> + ?Sized,
+>(
+    tree: &'tree T,
+    interval: &Interval<Idx>,
+    buffer: &mut Hits<'hits, Idx, Data>,
+) -> Option<Idx> {
+    buffer.clear();
+
+    let mut width = Some(Idx::one());
+    while interval.end() < Idx::max_value() {
+        let end = match width {
+            Some(width) => interval.end().saturating_add(width),
+            None => Idx::max_value(),
+        };
+        // SAFETY: interval.end() < end since the former is below the maximum value.
+        let window = unsafe { Interval::new_unchecked(interval.end(), end) };
+        tree.intersect_interval(&window, buffer);
+        buffer.retain(|x, _| x.start() >= interval.end());
+
+        if let Some(start) = buffer.intervals().iter().map(|x| x.start()).min() {
+            buffer.retain(|x, _| x.start() == start);
+            return Some(start.saturating_sub(interval.end()));
+        } else if end == Idx::max_value() {
+            break;
+        }
+        width = width.and_then(|x| x.checked_add(&x));
+    }
+    None
+}
@@ -1,2 +1,6 @@
+pub mod genomic_array;
 pub mod interval_tree;
 pub mod rle_vec;
+
+#[cfg(test)]
+pub(crate) mod random;
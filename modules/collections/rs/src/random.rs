@@ -0,0 +1,25 @@
+/// Deterministic pseudo-random numbers for test workloads (64-bit LCG, Knuth's MMIX constants)
+#[derive(Clone, Debug)]
+pub struct Lcg {
+    state: u64,
+}
+
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Next 31-bit number
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self
+            .state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.state >> 33
+    }
+
+    /// Next number in 0..max
+    pub fn below(&mut self, max: u64) -> u64 {
+        self.next_u64() % max
+    }
+}
@@ -0,0 +1,52 @@
+//! Serialization formats can't produce an `RleVec` with mismatched values and lengths: inputs are
+//! decoded into an unchecked mirror first and validated by the builder afterwards.
+
+#[cfg(feature = "bitcode")]
+use bitcode::{Decode, Encode};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use biobit_core_rs::num::PrimUInt;
+
+use super::identical::Identical;
+use super::rle_vec::RleVec;
+
+/// Unchecked mirror of [`RleVec`] used to serialize it.
+///
+/// Serde (de)serializes `RleVec` through this mirror transparently. Bitcode derives can't validate
+/// decoded values, so bitcode users encode the mirror itself and convert it back with
+/// [`TryFrom`]:
+///
+/// ```ignore
+/// let bytes = bitcode::encode(&RawRleVec::from(rle));
+/// let rle: RleVec<_, _, _> = bitcode::decode::<RawRleVec<_, _, _>>(&bytes)?.try_into()?;
+/// ```
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawRleVec<V, L: PrimUInt, I> {
+    pub values: Vec<V>,
+    #[cfg_attr(feature = "serde", serde(with = "super::compact"))]
+    pub lengths: Vec<L>,
+    pub identical: I,
+}
+
+impl<V, L: PrimUInt, I: Identical<V>> From<RleVec<V, L, I>> for RawRleVec<V, L, I> {
+    fn from(rle: RleVec<V, L, I>) -> Self {
+        Self {
+            values: rle.values,
+            lengths: rle.lengths,
+            identical: rle.identical,
+        }
+    }
+}
+
+impl<V, L: PrimUInt, I: Identical<V>> TryFrom<RawRleVec<V, L, I>> for RleVec<V, L, I> {
+    type Error = eyre::Report;
+
+    fn try_from(raw: RawRleVec<V, L, I>) -> Result<Self, Self::Error> {
+        RleVec::builder(raw.identical)
+            .with_rle_values(raw.values, raw.lengths)
+            .map(|builder| builder.build())
+    }
+}
@@ -0,0 +1,86 @@
+//! Compact serde representation of run lengths.
+//!
+//! Most runs are short, so storing each length as a fixed-width integer wastes space. Lengths are
+//! written as a single byte string of LEB128 varints instead: 7 bits per byte, with the high bit
+//! set on every byte except the last one of each length.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserializer, Serializer};
+
+use biobit_core_rs::num::PrimUInt;
+
+pub(super) fn serialize<L: PrimUInt, S: Serializer>(
+    lengths: &[L],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut bytes = Vec::with_capacity(lengths.len());
+    for length in lengths {
+        // Unsigned primitive integers always fit into u128
+        let mut length = length.to_u128().unwrap();
+        while length >= 0x80 {
+            bytes.push((length as u8) | 0x80);
+            length >>= 7;
+        }
+        bytes.push(length as u8);
+    }
+    serializer.serialize_bytes(&bytes)
+}
+
+pub(super) fn deserialize<'de, L: PrimUInt, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<L>, D::Error> {
+    deserializer.deserialize_bytes(LengthsVisitor(PhantomData))
+}
+
+fn decode<L: PrimUInt, E: de::Error>(bytes: impl IntoIterator<Item = u8>) -> Result<Vec<L>, E> {
+    let mut lengths = Vec::new();
+    let (mut length, mut shift) = (0u128, 0u32);
+    for byte in bytes {
+        let bits = (byte & 0x7F) as u128;
+        if shift >= u128::BITS || (bits << shift) >> shift != bits {
+            return Err(E::custom("run length varint overflows u128"));
+        }
+        length |= bits << shift;
+        if byte & 0x80 == 0 {
+            let value = L::from(length).ok_or_else(|| {
+                E::custom(format!(
+                    "run length {length} doesn't fit into the length type"
+                ))
+            })?;
+            lengths.push(value);
+            (length, shift) = (0, 0);
+        } else {
+            shift += 7;
+        }
+    }
+    if shift != 0 {
+        return Err(E::custom("truncated run length varint"));
+    }
+    Ok(lengths)
+}
+
+struct LengthsVisitor<L>(PhantomData<L>);
+
+impl<'de, L: PrimUInt> Visitor<'de> for LengthsVisitor<L> {
+    type Value = Vec<L>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte string of LEB128-encoded run lengths")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        decode(v.iter().copied())
+    }
+
+    // Self-describing formats without a native byte type (e.g. JSON) emit a sequence instead
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        decode(bytes)
+    }
+}
@@ -1,3 +1,6 @@
+#[cfg(feature = "bitcode")]
+use bitcode::{Decode, Encode};
+
 /// A trait representing the concept of identity for run-length encoding.
 /// This trait is used to determine whether two values should be considered as part of the same run.
 pub trait Identical<T> {
@@ -25,3 +28,16 @@ where
         self(first, second)
     }
 }
+
+/// Values are identical if they are equal according to `PartialEq`.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Equal;
+
+impl<T: PartialEq> Identical<T> for Equal {
+    #[inline]
+    fn identical(&self, first: &T, second: &T) -> bool {
+        first == second
+    }
+}
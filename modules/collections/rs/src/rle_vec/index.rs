@@ -0,0 +1,267 @@
+use eyre::{Result, ensure, eyre};
+use num::{NumCast, ToPrimitive};
+
+use biobit_core_rs::loc::{Interval, IntervalOp};
+use biobit_core_rs::num::{Num, PrimUInt};
+
+use super::{Identical, RleVec};
+
+/// A cumulative-length index over an [`RleVec`] enabling O(log n) random access.
+///
+/// Positions are 0-based and refer to the decoded (dense) vector. Runs of zero length are
+/// allowed in the underlying vector but never reported by the index.
+#[derive(Debug, Clone)]
+pub struct RleIndex<'a, V, L: PrimUInt, I: Identical<V>> {
+    rle: &'a RleVec<V, L, I>,
+    // Exclusive end position of each run in the dense coordinates
+    ends: Vec<u64>,
+}
+
+impl<'a, V, L: PrimUInt, I: Identical<V>> RleIndex<'a, V, L, I> {
+    /// Index the given vector in O(n) time, where n is the number of runs. The index borrows the
+    /// vector, so it can't be modified while the index is alive.
+    pub fn new(rle: &'a RleVec<V, L, I>) -> Self {
+        let mut end = 0u64;
+        let ends = rle
+            .runs()
+            .map(|(_, length)| {
+                end += length.to_u64().unwrap();
+                end
+            })
+            .collect();
+
+        Self { rle, ends }
+    }
+
+    /// The indexed vector.
+    pub fn rle(&self) -> &'a RleVec<V, L, I> {
+        self.rle
+    }
+
+    /// Total length of the decoded vector, i.e. the sum of all run lengths.
+    pub fn total_length(&self) -> u64 {
+        self.ends.last().copied().unwrap_or(0)
+    }
+
+    /// Index of the run covering the given position or `None` if the position is out of bounds.
+    pub fn run_at(&self, pos: u64) -> Option<usize> {
+        if pos >= self.total_length() {
+            return None;
+        }
+        Some(self.ends.partition_point(|end| *end <= pos))
+    }
+
+    /// Value at the given position or `None` if the position is out of bounds.
+    pub fn get(&self, pos: u64) -> Option<&'a V> {
+        self.run_at(pos).map(|run| &self.rle.values[run])
+    }
+
+    /// Iterate over runs overlapping the given range. Runs are clipped to the range boundaries.
+    pub fn range(
+        &self,
+        range: Interval<u64>,
+    ) -> Result<impl Iterator<Item = (Interval<u64>, &'a V)> + '_> {
+        ensure!(
+            range.end() <= self.total_length(),
+            "Range {range} is out of bounds for RleVec of length {}",
+            self.total_length()
+        );
+
+        let first = self.ends.partition_point(|end| *end <= range.start());
+        let values = &self.rle.values;
+        let iter = (first..self.ends.len())
+            .map(move |run| {
+                let start = if run == 0 { 0 } else { self.ends[run - 1] };
+                (
+                    start.max(range.start()),
+                    self.ends[run].min(range.end()),
+                    run,
+                )
+            })
+            .take_while(move |(start, _, _)| *start < range.end())
+            .filter(|(start, end, _)| start < end)
+            // SAFETY: start < end is checked above
+            .map(move |(start, end, run)| {
+                (unsafe { Interval::new_unchecked(start, end) }, &values[run])
+            });
+        Ok(iter)
+    }
+
+    /// Copy the given range into a new `RleVec` with the same identity rule.
+    pub fn slice(&self, range: Interval<u64>) -> Result<RleVec<V, L, I>>
+    where
+        V: Clone,
+        I: Clone,
+    {
+        let mut result = RleVec::builder(self.rle.identical.clone()).build();
+        for (interval, value) in self.range(range)? {
+            // Clipped runs are never longer than the original ones
+            let length = L::from(interval.len()).unwrap();
+            result.push(value.clone(), length);
+        }
+        Ok(result)
+    }
+
+    /// Sum of all values in the given range, each value is weighted by its run length.
+    ///
+    /// The sum is accumulated in the type `A`, which should be wider than `V` to avoid overflows
+    /// (e.g. `i64` or `i128` for `i32` values and `f64` for `f32` ones). Values that can't be
+    /// represented by `A` are reported as errors.
+    pub fn sum<A>(&self, range: Interval<u64>) -> Result<A>
+    where
+        V: ToPrimitive + Copy,
+        A: Num + NumCast,
+    {
+        let mut sum = A::zero();
+        for (interval, value) in self.range(range)? {
+            let value = A::from(*value)
+                .ok_or_else(|| eyre!("Value can't be represented by the accumulator type"))?;
+            let length = A::from(interval.len())
+                .ok_or_else(|| eyre!("Run length {} can't be represented", interval.len()))?;
+            sum = sum + value * length;
+        }
+        Ok(sum)
+    }
+
+    /// Mean of all values in the given range accumulated in the type `A` (see [`Self::sum`]).
+    /// Integer sums are divided with truncation.
+    pub fn mean<A>(&self, range: Interval<u64>) -> Result<A>
+    where
+        V: ToPrimitive + Copy,
+        A: Num + NumCast,
+    {
+        let length = A::from(range.len())
+            .ok_or_else(|| eyre!("Range length {} can't be represented", range.len()))?;
+        Ok(self.sum::<A>(range)? / length)
+    }
+
+    /// Maximum value in the given range. For incomparable values (e.g. NaN) the first one wins.
+    pub fn max(&self, range: Interval<u64>) -> Result<&'a V>
+    where
+        V: PartialOrd,
+    {
+        self.range(range)?
+            .map(|(_, value)| value)
+            .reduce(|max, value| if value > max { value } else { max })
+            .ok_or_else(|| eyre!("Empty range"))
+    }
+
+    /// Minimum value in the given range. For incomparable values (e.g. NaN) the first one wins.
+    pub fn min(&self, range: Interval<u64>) -> Result<&'a V>
+    where
+        V: PartialOrd,
+    {
+        self.range(range)?
+            .map(|(_, value)| value)
+            .reduce(|min, value| if value < min { value } else { min })
+            .ok_or_else(|| eyre!("Empty range"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type RleVector = RleVec<i32, u8, fn(&i32, &i32) -> bool>;
+
+    fn rle(dense: &[i32]) -> RleVector {
+        RleVector::builder(PartialEq::eq)
+            .with_dense_values(dense)
+            .unwrap()
+            .build()
+    }
+
+    #[test]
+    fn test_rle_index_get() {
+        let dense = [1, 1, 2, 3, 3, 3, 1, 5, 5];
+        let mut vec = rle(&dense);
+        // Zero-length runs must be skipped
+        vec.push(7, 0);
+        vec.push(8, 2);
+
+        let index = vec.index();
+        assert_eq!(index.total_length(), 11);
+        for (pos, expected) in dense.iter().chain([8, 8].iter()).enumerate() {
+            assert_eq!(index.get(pos as u64), Some(expected));
+        }
+        assert_eq!(index.run_at(9), Some(6));
+        assert_eq!(index.get(11), None);
+        assert_eq!(rle(&[]).index().get(0), None);
+    }
+
+    #[test]
+    fn test_rle_index_range() -> Result<()> {
+        let dense = [1, 1, 2, 3, 3, 3, 1, 5, 5];
+        let vec = rle(&dense);
+        let index = vec.index();
+
+        for start in 0..dense.len() {
+            for end in start + 1..=dense.len() {
+                let range = Interval::new(start as u64, end as u64)?;
+                let window = &dense[start..end];
+
+                let decoded = index
+                    .range(range)?
+                    .flat_map(|(it, val)| std::iter::repeat_n(*val, it.len() as usize))
+                    .collect::<Vec<_>>();
+                assert_eq!(decoded, window);
+
+                let slice = index.slice(range)?;
+                let decoded = slice
+                    .intervals()
+                    .flat_map(|(it, val)| std::iter::repeat_n(*val, it.len() as usize))
+                    .collect::<Vec<_>>();
+                assert_eq!(decoded, window);
+
+                assert_eq!(index.sum::<i64>(range)?, window.iter().sum::<i32>() as i64);
+                assert_eq!(
+                    index.mean::<i32>(range)?,
+                    window.iter().sum::<i32>() / window.len() as i32
+                );
+                assert_eq!(
+                    index.mean::<f64>(range)?,
+                    window.iter().sum::<i32>() as f64 / window.len() as f64
+                );
+                assert_eq!(index.max(range)?, window.iter().max().unwrap());
+                assert_eq!(index.min(range)?, window.iter().min().unwrap());
+            }
+        }
+
+        assert!(index.range(Interval::new(5, 10)?).is_err());
+        assert!(index.slice(Interval::new(9, 10)?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rle_index_sum_widened() -> Result<()> {
+        let mut vec = RleVec::<i32, u32, fn(&i32, &i32) -> bool>::builder(PartialEq::eq).build();
+        vec.push(i32::MAX, 1_000);
+        vec.push(-1, 10);
+
+        let index = vec.index();
+        let range = Interval::new(0, 1_010)?;
+        assert_eq!(index.sum::<i64>(range)?, i32::MAX as i64 * 1_000 - 10);
+        assert_eq!(
+            index.mean::<i64>(range)?,
+            (i32::MAX as i64 * 1_000 - 10) / 1_010
+        );
+        // The accumulator must be able to represent all values
+        assert!(index.sum::<u64>(range).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rle_intervals() -> Result<()> {
+        let vec = rle(&[1, 1, 2, 3, 3, 3]);
+        let intervals = vec.intervals().map(|(x, y)| (x, *y)).collect::<Vec<_>>();
+        assert_eq!(
+            intervals,
+            vec![
+                (Interval::new(0, 2)?, 1),
+                (Interval::new(2, 3)?, 2),
+                (Interval::new(3, 6)?, 3),
+            ]
+        );
+        Ok(())
+    }
+}
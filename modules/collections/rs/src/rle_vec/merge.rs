@@ -1,7 +1,8 @@
 use std::marker::PhantomData;
 
 use ::impl_tools::autoimpl;
-use eyre::{Result, eyre};
+use eyre::{Result, ensure, eyre};
+use rayon::prelude::*;
 
 use biobit_core_rs::num::PrimUInt;
 
@@ -37,6 +38,16 @@ where
     }
 }
 
+impl<T, Single, Multiple> Clone for MergeFn<T, Single, Multiple>
+where
+    Single: FnMut(&T) -> T + Clone,
+    Multiple: FnMut(&[&T]) -> T + Clone,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.single_fn.clone(), self.multiple_fn.clone())
+    }
+}
+
 impl<T, Single, Multiple> Merge<T> for MergeFn<T, Single, Multiple>
 where
     Single: FnMut(&T) -> T,
@@ -111,27 +122,145 @@ impl<V, L: PrimUInt, M: Merge<V>, IOriginal: Identical<V>, INew: Identical<V>>
             .with_buffers(rle.0, rle.1)
             .build();
 
-        merge_impl(&self.inputs, rle, merge_fn)
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|x| x.values.iter().zip(x.lengths.iter().copied()))
+            .collect();
+        merge_impl(inputs, rle, merge_fn)
+    }
+
+    /// Merge inputs in parallel. Inputs are split into chunks of `chunk_length` positions, runs
+    /// crossing chunk boundaries are split between chunks. Chunks are merged independently and
+    /// stitched back together, re-joining identical runs at chunk boundaries. The result is
+    /// identical to [`MergeSetup::run`].
+    pub fn run_parallel(mut self, chunk_length: u64) -> Result<RleVec<V, L, INew>>
+    where
+        V: Send + Sync,
+        M: Clone + Send,
+        IOriginal: Sync,
+        INew: Clone + Send,
+    {
+        ensure!(chunk_length > 0, "Chunk length must be positive");
+        let merge_fn = self
+            .merge
+            .take()
+            .ok_or_else(|| eyre!("Merge function is unspecified in rle_vec::merge."))?;
+        let identical = self
+            .identical
+            .take()
+            .ok_or_else(|| eyre!("Identical rule is unspecified in rle_vec::merge."))?;
+
+        let ends = self
+            .inputs
+            .par_iter()
+            .map(|x| run_ends(x))
+            .collect::<Vec<_>>();
+        let chunks = chunks(&self.inputs, &ends, chunk_length);
+
+        let merged = chunks
+            .into_par_iter()
+            .map_with(
+                (identical.clone(), merge_fn),
+                |(identical, merge_fn), inputs| {
+                    let rle = RleVec::builder(identical.clone()).build();
+                    merge_impl(inputs, rle, merge_fn.clone())
+                },
+            )
+            .collect::<Result<Vec<_>>>()?;
+
+        // Stitch chunks, runs at chunk boundaries might be identical
+        let rle = self.write_to.take().unwrap_or_default();
+        let mut result = RleVec::builder(identical)
+            .with_buffers(rle.0, rle.1)
+            .build();
+        for chunk in merged {
+            for (value, length) in chunk {
+                result.append(value, length);
+            }
+        }
+        Ok(result)
     }
 }
 
-fn merge_impl<V, L, IOriginal, INew>(
-    inputs: &[&RleVec<V, L, IOriginal>],
+// Exclusive end of each run in the input
+fn run_ends<V, L: PrimUInt, I: Identical<V>>(rle: &RleVec<V, L, I>) -> Vec<u64> {
+    let mut end = 0u64;
+    rle.lengths
+        .iter()
+        .map(|x| {
+            end += x.to_u64().unwrap();
+            end
+        })
+        .collect()
+}
+
+// Split inputs into consecutive chunks of `chunk_length` positions (the last one might be shorter)
+fn chunks<'a, V, L: PrimUInt, I: Identical<V>>(
+    inputs: &[&'a RleVec<V, L, I>],
+    ends: &'a [Vec<u64>],
+    chunk_length: u64,
+) -> Vec<Vec<impl Iterator<Item = (&'a V, L)> + Send>>
+where
+    V: Sync,
+{
+    let total = ends
+        .iter()
+        .filter_map(|x| x.last())
+        .max()
+        .copied()
+        .unwrap_or(0);
+    (0..total.div_ceil(chunk_length))
+        .map(|chunk| {
+            let start = chunk * chunk_length;
+            let end = start.saturating_add(chunk_length).min(total);
+            inputs
+                .iter()
+                .zip(ends)
+                .map(|(rle, ends)| clip(&rle.values, ends, start, end))
+                .collect()
+        })
+        .collect()
+}
+
+// Runs of the input clipped to the [start, end) range, `ends` are exclusive run ends
+fn clip<'a, V, L: PrimUInt>(
+    values: &'a [V],
+    ends: &'a [u64],
+    start: u64,
+    end: u64,
+) -> impl Iterator<Item = (&'a V, L)> + Send
+where
+    V: Sync,
+{
+    let first = ends.partition_point(|x| *x <= start);
+    let last = ends
+        .partition_point(|x| *x < end)
+        .min(ends.len().saturating_sub(1));
+    (first..=last).filter_map(move |run| {
+        let run_start = if run == 0 { 0 } else { ends[run - 1] };
+        let (run_start, run_end) = (run_start.max(start), ends.get(run)?.min(&end));
+        // Clipped runs are never longer than the original ones
+        (run_start < *run_end).then(|| (&values[run], L::from(run_end - run_start).unwrap()))
+    })
+}
+
+fn merge_impl<'a, V: 'a, L, INew, Runs>(
+    inputs: Vec<Runs>,
     mut append_to: RleVec<V, L, INew>,
     mut merge: impl Merge<V>,
 ) -> Result<RleVec<V, L, INew>>
 where
     L: PrimUInt,
-    IOriginal: Identical<V>,
     INew: Identical<V>,
+    Runs: Iterator<Item = (&'a V, L)>,
 {
     // Iterators + cached current values
     let mut iterators = Vec::with_capacity(inputs.len());
     let mut iter_ends = Vec::with_capacity(inputs.len());
     let mut iter_vals = Vec::with_capacity(inputs.len());
 
-    for rle in inputs {
-        let mut rle = rle.runs();
+    for mut rle in inputs {
         match rle.next() {
             None => continue,
             Some((val, length)) => {
@@ -230,12 +359,12 @@ where
             let val = merge.single(val);
 
             if append_to.identical(&current_value, &val) {
-                current_length = current_length.checked_add(length).unwrap();
+                current_length = current_length.checked_add(&length).unwrap();
             } else {
                 append_to.push(current_value, current_length);
 
                 current_value = val;
-                current_length = *length;
+                current_length = length;
             }
         }
     }
@@ -255,6 +384,7 @@ mod tests {
     use std::iter::zip;
 
     use super::*;
+    use crate::random::Lcg;
 
     type RleVector = RleVec<u8, u8, fn(&u8, &u8) -> bool>;
 
@@ -373,4 +503,52 @@ mod tests {
         assert_rle_eq(merged, vec![(123, 1)]);
         Ok(())
     }
+
+    #[test]
+    fn test_rle_merge_parallel() -> Result<()> {
+        // Pseudo-random vectors with different lengths and unrelated run boundaries
+        let mut lcg = Lcg::new(17);
+        let mut random = |max: u64| lcg.below(max);
+        let rles = (0..6)
+            .map(|_| {
+                let length = 200 + random(300) as usize;
+                let dense = (0..length).map(|_| random(4) as u8).collect();
+                construct_from_dense(dense)
+            })
+            .collect::<Vec<_>>();
+
+        // Boundaries are not aligned, yet the inputs are still split into many chunks
+        let refs = rles.iter().collect::<Vec<_>>();
+        let ends = refs.iter().map(|x| run_ends(x)).collect::<Vec<_>>();
+        let total = ends.iter().map(|x| *x.last().unwrap()).max().unwrap();
+        let split = chunks(&refs, &ends, 50);
+        assert_eq!(split.len() as u64, total.div_ceil(50));
+        for (ind, chunk) in split.into_iter().enumerate() {
+            for (runs, ends) in zip(chunk, &ends) {
+                let covered = runs.map(|(_, x)| x as u64).sum::<u64>();
+                let (start, end) = (ind as u64 * 50, (ind as u64 * 50 + 50).min(total));
+                let expected = end.min(*ends.last().unwrap()).saturating_sub(start);
+                assert_eq!(covered, expected);
+            }
+        }
+
+        let mut inputs = vec![vec![], vec![construct_from_dense(vec![])]];
+        for i in 1..=rles.len() {
+            inputs.push(rles[..i].to_vec());
+        }
+        for rles in inputs {
+            let expected = test_merge(&rles)?;
+            for chunk_length in [1, 2, 7, 50, 10_000] {
+                let parallel = merge(&rles)
+                    .with_merge(MergeFn::new(|x: &u8| *x, maximum))
+                    .with_identical(PartialEq::eq as fn(&u8, &u8) -> bool)
+                    .run_parallel(chunk_length)?;
+                assert_eq!(
+                    parallel.runs().collect::<Vec<_>>(),
+                    expected.runs().collect::<Vec<_>>()
+                );
+            }
+        }
+        Ok(())
+    }
 }
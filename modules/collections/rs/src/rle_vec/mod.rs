@@ -1,10 +1,20 @@
-pub use identical::Identical;
+pub use identical::{Equal, Identical};
+pub use index::RleIndex;
 pub use merge::{Merge, MergeFn, merge};
 pub use merge2::{Merge2, Merge2Fn, merge2};
 pub use rle_vec::RleVec;
 
+#[cfg(any(feature = "serde", feature = "bitcode"))]
+pub use codec::RawRleVec;
+
+#[cfg(any(feature = "serde", feature = "bitcode"))]
+mod codec;
+#[cfg(feature = "serde")]
+mod compact;
 mod identical;
+mod index;
 mod merge;
 mod merge2;
+mod ops;
 #[allow(clippy::module_inception)]
 mod rle_vec;
@@ -0,0 +1,604 @@
+use std::collections::VecDeque;
+
+use eyre::{Result, ensure, eyre};
+use num::NumCast;
+
+use biobit_core_rs::loc::{Interval, IntervalOp};
+use biobit_core_rs::num::{Float, Num, PrimUInt};
+
+use super::{Identical, RleVec};
+
+impl<V, L: PrimUInt, I: Identical<V> + Clone> RleVec<V, L, I> {
+    /// Apply the function to each run value. Resulting runs are re-compressed using the
+    /// vector's `Identical` policy.
+    pub fn map(&self, mut f: impl FnMut(&V) -> V) -> Self {
+        let mut result = RleVec::builder(self.identical.clone())
+            .with_capacity(self.len(), self.len())
+            .build();
+        for (value, length) in self.runs() {
+            result.append(f(value), *length);
+        }
+        result
+    }
+
+    /// Combine two vectors of the same total length position by position. Zero-length runs of
+    /// both vectors are ignored.
+    pub fn zip_with(&self, other: &Self, mut f: impl FnMut(&V, &V) -> V) -> Result<Self> {
+        let (first, second) = (self.total_length(), other.total_length());
+        ensure!(
+            first == second,
+            "RleVec lengths must match, got {first} and {second}"
+        );
+
+        let mut result = RleVec::builder(self.identical.clone())
+            .with_capacity(self.len().max(other.len()), self.len().max(other.len()))
+            .build();
+        let (mut first, mut second) = (Cursor::new(self), Cursor::new(other));
+        while let (Some((a, alen)), Some((b, blen))) = (first.peek(), second.peek()) {
+            let length = alen.min(blen);
+            // Both cursors are inside runs of type L, so the overlap fits into L as well
+            result.append(f(a, b), L::from(length).unwrap());
+            first.advance(length);
+            second.advance(length);
+        }
+        debug_assert!(first.peek().is_none() && second.peek().is_none());
+        Ok(result)
+    }
+
+    /// Add the scalar to each value.
+    pub fn add_scalar(&self, scalar: V) -> Self
+    where
+        V: Num,
+    {
+        self.map(|x| *x + scalar)
+    }
+
+    /// Subtract the scalar from each value.
+    pub fn sub_scalar(&self, scalar: V) -> Self
+    where
+        V: Num,
+    {
+        self.map(|x| *x - scalar)
+    }
+
+    /// Multiply each value by the scalar.
+    pub fn mul_scalar(&self, scalar: V) -> Self
+    where
+        V: Num,
+    {
+        self.map(|x| *x * scalar)
+    }
+
+    /// Divide each value by the scalar. Integer values are divided with truncation.
+    pub fn div_scalar(&self, scalar: V) -> Self
+    where
+        V: Num,
+    {
+        self.map(|x| *x / scalar)
+    }
+
+    /// Position-wise sum of two vectors with the same total length, see [`RleVec::zip_with`].
+    pub fn add_rle(&self, other: &Self) -> Result<Self>
+    where
+        V: Num,
+    {
+        self.zip_with(other, |a, b| *a + *b)
+    }
+
+    /// Position-wise difference of two vectors with the same total length.
+    pub fn sub_rle(&self, other: &Self) -> Result<Self>
+    where
+        V: Num,
+    {
+        self.zip_with(other, |a, b| *a - *b)
+    }
+
+    /// Position-wise product of two vectors with the same total length.
+    pub fn mul_rle(&self, other: &Self) -> Result<Self>
+    where
+        V: Num,
+    {
+        self.zip_with(other, |a, b| *a * *b)
+    }
+
+    /// Position-wise quotient of two vectors with the same total length. Integer values are
+    /// divided with truncation.
+    pub fn div_rle(&self, other: &Self) -> Result<Self>
+    where
+        V: Num,
+    {
+        self.zip_with(other, |a, b| *a / *b)
+    }
+
+    /// Logarithm with the given base after adding a pseudocount: log(x + pseudocount).
+    pub fn log(&self, base: V, pseudocount: V) -> Self
+    where
+        V: Float,
+    {
+        self.map(|x| (*x + pseudocount).log(base))
+    }
+
+    /// Restrict values to the [min, max] range.
+    pub fn clamp(&self, min: V, max: V) -> Self
+    where
+        V: PartialOrd + Clone,
+    {
+        self.map(|x| {
+            if *x < min {
+                min.clone()
+            } else if *x > max {
+                max.clone()
+            } else {
+                x.clone()
+            }
+        })
+    }
+
+    /// Intervals where values are greater or equal to the cutoff. Adjacent intervals are merged.
+    pub fn threshold(&self, cutoff: &V) -> Vec<Interval<u64>>
+    where
+        V: PartialOrd,
+    {
+        self.threshold_by(|x| x >= cutoff)
+    }
+
+    /// Intervals where the predicate holds. Adjacent intervals are merged.
+    pub fn threshold_by(&self, mut predicate: impl FnMut(&V) -> bool) -> Vec<Interval<u64>> {
+        let mut result: Vec<Interval<u64>> = Vec::new();
+        for (interval, value) in self.intervals() {
+            if !predicate(value) {
+                continue;
+            }
+            match result.last_mut() {
+                Some(last) if last.end() == interval.start() => {
+                    // SAFETY: the interval is extended to the right, start < end still holds
+                    unsafe { last.set_end(interval.end()) }
+                }
+                _ => result.push(interval),
+            }
+        }
+        result
+    }
+
+    /// Rolling sum over a window of the given width centered at each position. For even widths
+    /// the window extends one position further to the right. Windows are clipped at the vector
+    /// boundaries.
+    ///
+    /// The window slides over whole runs, so the time is proportional to the number of runs in
+    /// the input and the output rather than to the total length of the vector.
+    pub fn rolling_sum(&self, width: u64) -> Result<Self>
+    where
+        V: Num,
+    {
+        let mut result = RleVec::builder(self.identical.clone()).build();
+        self.rolling_sums(width, |sum, _, length| result.append_long(sum, length))?;
+        Ok(result)
+    }
+
+    /// Rolling mean over a window of the given width, see [`RleVec::rolling_sum`]. Clipped windows
+    /// are averaged over the positions inside the vector.
+    pub fn rolling_mean(&self, width: u64) -> Result<Self>
+    where
+        V: Num + NumCast,
+    {
+        let mut result = RleVec::builder(self.identical.clone()).build();
+        let mut error = None;
+        self.rolling_sums(width, |sum, count, length| match V::from(count) {
+            Some(count) => result.append_long(sum / count, length),
+            None => error = Some(count),
+        })?;
+        match error {
+            Some(count) => Err(eyre!("Window size {count} can't be represented")),
+            None => Ok(result),
+        }
+    }
+
+    /// Rolling maximum over a window of the given width, see [`RleVec::rolling_sum`].
+    pub fn rolling_max(&self, width: u64) -> Result<Self>
+    where
+        V: PartialOrd + Clone,
+    {
+        ensure!(width > 0, "Window width must be positive");
+        let (left, right) = ((width - 1) / 2, width / 2);
+        let total = self.total_length();
+
+        let mut result = RleVec::builder(self.identical.clone()).build();
+        let mut ahead = Cursor::new(self);
+        let mut next = 0;
+        // Runs inside the window as (exclusive end, value) with strictly decreasing values
+        let mut window: VecDeque<(u64, &V)> = VecDeque::new();
+        let mut pos = 0;
+        while pos < total {
+            // Push runs entering the window
+            let hi = (pos + right).min(total - 1);
+            while let Some((value, length)) = ahead.peek()
+                && next <= hi
+            {
+                while window.back().is_some_and(|(_, last)| *last <= value) {
+                    window.pop_back();
+                }
+                next += length;
+                window.push_back((next, value));
+                ahead.advance(length);
+            }
+            // Drop runs leaving the window
+            let lo = pos.saturating_sub(left);
+            while window.front().is_some_and(|(end, _)| *end <= lo) {
+                window.pop_front();
+            }
+
+            // The maximum holds until the next run enters or the current one leaves the window
+            let (end, max) = *window.front().unwrap();
+            let mut until = (end + left).min(total);
+            if ahead.peek().is_some() {
+                until = until.min(next - right);
+            }
+            result.append_long(max.clone(), until - pos);
+            pos = until;
+        }
+        Ok(result)
+    }
+
+    // Append a run of any length, filling up the last identical run and splitting the rest into
+    // runs that fit into L
+    fn append_long(&mut self, value: V, mut length: u64)
+    where
+        V: Clone,
+    {
+        if let (Some(last), Some(last_length)) = (self.values.last(), self.lengths.last_mut())
+            && self.identical.identical(last, &value)
+        {
+            let room = (L::max_value() - *last_length)
+                .to_u64()
+                .unwrap()
+                .min(length);
+            *last_length = *last_length + L::from(room).unwrap();
+            length -= room;
+        }
+
+        let max = L::max_value().to_u64().unwrap();
+        while length > 0 {
+            let chunk = length.min(max);
+            self.push(value.clone(), L::from(chunk).unwrap());
+            length -= chunk;
+        }
+    }
+
+    // Report the window sum, the number of positions inside the window and the number of
+    // consecutive positions sharing them. While the values entering and leaving the window are
+    // the same, the sum doesn't change and whole stretches are reported at once.
+    fn rolling_sums(&self, width: u64, mut emit: impl FnMut(V, u64, u64)) -> Result<()>
+    where
+        V: Num,
+    {
+        ensure!(width > 0, "Window width must be positive");
+        let (left, right) = ((width - 1) / 2, width / 2);
+        let total = self.total_length();
+
+        // Positions [0, right) are inside the window before the first step
+        let (mut ahead, mut behind) = (Cursor::new(self), Cursor::new(self));
+        let (mut sum, mut count) = (V::zero(), right.min(total));
+        let mut skip = count;
+        while let Some((value, length)) = ahead.peek()
+            && skip > 0
+        {
+            let length = length.min(skip);
+            sum = sum + repeat_add(*value, length);
+            ahead.advance(length);
+            skip -= length;
+        }
+
+        let mut pos = 0;
+        while pos < total {
+            // Value entering (pos + right) and leaving (pos - left - 1) the window at this step
+            let entering = ahead.peek();
+            let leaving = if pos > left { behind.peek() } else { None };
+
+            let mut length = total - pos;
+            if let Some((_, remaining)) = entering {
+                length = length.min(remaining);
+            }
+            match leaving {
+                Some((_, remaining)) => length = length.min(remaining),
+                None => length = length.min(left + 1 - pos),
+            }
+
+            let steady = match (entering, leaving) {
+                (Some((x, _)), Some((y, _))) => (*x - *y).is_zero(),
+                (None, None) => true,
+                _ => false,
+            };
+            if steady {
+                emit(sum, count, length);
+            } else {
+                for _ in 0..length {
+                    if let Some((x, _)) = entering {
+                        sum = sum + *x;
+                        count += 1;
+                    }
+                    if let Some((y, _)) = leaving {
+                        sum = sum - *y;
+                        count -= 1;
+                    }
+                    emit(sum, count, 1);
+                }
+            }
+
+            if entering.is_some() {
+                ahead.advance(length);
+            }
+            if leaving.is_some() {
+                behind.advance(length);
+            }
+            pos += length;
+        }
+        Ok(())
+    }
+}
+
+// Sum of n copies of the value with O(log n) additions
+fn repeat_add<V: Num>(value: V, mut n: u64) -> V {
+    let (mut sum, mut power) = (V::zero(), value);
+    while n > 0 {
+        if n & 1 == 1 {
+            sum = sum + power;
+        }
+        n >>= 1;
+        if n > 0 {
+            power = power + power;
+        }
+    }
+    sum
+}
+
+// Position in the decoded vector that skips zero-length runs
+struct Cursor<'a, V, L> {
+    values: &'a [V],
+    lengths: &'a [L],
+    run: usize,
+    // Positions left in the current run
+    remaining: u64,
+}
+
+impl<'a, V, L: PrimUInt> Cursor<'a, V, L> {
+    fn new<I: Identical<V>>(rle: &'a RleVec<V, L, I>) -> Self {
+        let mut cursor = Self {
+            values: &rle.values,
+            lengths: &rle.lengths,
+            run: 0,
+            remaining: 0,
+        };
+        cursor.settle();
+        cursor
+    }
+
+    // Current value and the number of positions left in its run, None past the end
+    fn peek(&self) -> Option<(&'a V, u64)> {
+        (self.run < self.values.len()).then(|| (&self.values[self.run], self.remaining))
+    }
+
+    // Move forward within the current run
+    fn advance(&mut self, positions: u64) {
+        debug_assert!(positions <= self.remaining);
+        self.remaining -= positions;
+        if self.remaining == 0 {
+            self.run += 1;
+            self.settle();
+        }
+    }
+
+    fn settle(&mut self) {
+        while self.run < self.lengths.len() {
+            self.remaining = self.lengths[self.run].to_u64().unwrap();
+            if self.remaining > 0 {
+                break;
+            }
+            self.run += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type RleVector = RleVec<i32, u8, fn(&i32, &i32) -> bool>;
+
+    fn rle(dense: &[i32]) -> RleVector {
+        RleVector::builder(PartialEq::eq)
+            .with_dense_values(dense)
+            .unwrap()
+            .build()
+    }
+
+    fn decode(vec: &RleVector) -> Vec<i32> {
+        vec.runs()
+            .flat_map(|(value, length)| std::iter::repeat_n(*value, *length as usize))
+            .collect()
+    }
+
+    fn assert_compressed(vec: &RleVector) {
+        let values = vec.values().collect::<Vec<_>>();
+        assert!(values.windows(2).all(|x| x[0] != x[1]), "{values:?}");
+        assert!(vec.runs().all(|(_, length)| *length > 0));
+    }
+
+    const DENSE: [i32; 12] = [1, 1, 2, 3, 3, 3, -1, 5, 5, 0, 0, 4];
+
+    #[test]
+    fn test_rle_vec_scalar_ops() {
+        let vec = rle(&DENSE);
+        for (result, f) in [
+            (vec.add_scalar(2), (|x| x + 2) as fn(i32) -> i32),
+            (vec.sub_scalar(2), |x| x - 2),
+            (vec.mul_scalar(0), |_| 0),
+            (vec.div_scalar(2), |x| x / 2),
+            (vec.clamp(0, 3), |x| x.clamp(0, 3)),
+        ] {
+            assert_eq!(decode(&result), DENSE.map(f));
+            assert_compressed(&result);
+        }
+
+        let vec = RleVec::<f64, u8, fn(&f64, &f64) -> bool>::builder(PartialEq::eq)
+            .with_dense_values(&[0.0, 0.0, 1.0, 3.0])
+            .unwrap()
+            .build();
+        let logged = vec.log(2.0, 1.0);
+        assert_eq!(
+            logged.runs().map(|(x, y)| (*x, *y)).collect::<Vec<_>>(),
+            vec![(0.0, 2), (1.0, 1), (2.0, 1)]
+        );
+    }
+
+    #[test]
+    fn test_rle_vec_vector_ops() -> Result<()> {
+        let (first, second) = (rle(&DENSE), rle(&DENSE.map(|x| 6 - x)));
+        for (result, f) in [
+            (
+                first.add_rle(&second)?,
+                (|x, y| x + y) as fn(i32, i32) -> i32,
+            ),
+            (first.sub_rle(&second)?, |x, y| x - y),
+            (first.mul_rle(&second)?, |x, y| x * y),
+            (first.div_rle(&second)?, |x, y| x / y),
+        ] {
+            let expected = DENSE
+                .iter()
+                .zip(DENSE.map(|x| 6 - x))
+                .map(|(x, y)| f(*x, y))
+                .collect::<Vec<_>>();
+            assert_eq!(decode(&result), expected);
+            assert_compressed(&result);
+        }
+        assert_eq!(decode(&first.add_rle(&second)?), [6; 12]);
+        assert_eq!(first.add_rle(&second)?.len(), 1);
+
+        assert!(first.add_rle(&rle(&DENSE[1..])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rle_vec_zip_with_empty_runs() -> Result<()> {
+        let mut first = RleVector::builder(PartialEq::eq).build();
+        first.extend([(1, 3), (7, 0)]);
+        let second = rle(&[2, 2, 2]);
+        assert_eq!(decode(&first.add_rle(&second)?), [3, 3, 3]);
+        assert_eq!(decode(&second.add_rle(&first)?), [3, 3, 3]);
+
+        let mut first = RleVector::builder(PartialEq::eq).build();
+        first.extend([(0, 0), (1, 2), (5, 0), (2, 1), (9, 0)]);
+        let mut second = RleVector::builder(PartialEq::eq).build();
+        second.extend([(1, 1), (3, 0), (1, 2), (0, 0)]);
+        let result = first.mul_rle(&second)?;
+        assert_eq!(decode(&result), [1, 1, 2]);
+        assert_compressed(&result);
+
+        let empty = RleVector::builder(PartialEq::eq).build();
+        first.clear();
+        first.push(3, 0);
+        assert!(first.add_rle(&empty)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rle_vec_threshold() -> Result<()> {
+        let vec = rle(&DENSE);
+        assert_eq!(
+            vec.threshold(&3),
+            vec![
+                Interval::new(3, 6)?,
+                Interval::new(7, 9)?,
+                Interval::new(11, 12)?
+            ]
+        );
+        assert_eq!(
+            vec.threshold_by(|x| *x > 0),
+            vec![
+                Interval::new(0, 6)?,
+                Interval::new(7, 9)?,
+                Interval::new(11, 12)?
+            ]
+        );
+        assert!(vec.threshold(&10).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rle_vec_rolling() -> Result<()> {
+        let vec = rle(&DENSE);
+        for width in 1..=15u64 {
+            let (left, right) = ((width as usize - 1) / 2, width as usize / 2);
+            let windows = (0..DENSE.len())
+                .map(|x| &DENSE[x.saturating_sub(left)..(x + right + 1).min(DENSE.len())])
+                .collect::<Vec<_>>();
+
+            let sum = vec.rolling_sum(width)?;
+            let expected = windows.iter().map(|x| x.iter().sum()).collect::<Vec<i32>>();
+            assert_eq!(decode(&sum), expected, "{width}");
+            assert_compressed(&sum);
+
+            let mean = vec.rolling_mean(width)?;
+            let expected = windows
+                .iter()
+                .map(|x| x.iter().sum::<i32>() / x.len() as i32)
+                .collect::<Vec<_>>();
+            assert_eq!(decode(&mean), expected, "{width}");
+
+            let max = vec.rolling_max(width)?;
+            let expected = windows
+                .iter()
+                .map(|x| *x.iter().max().unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(decode(&max), expected, "{width}");
+            assert_compressed(&max);
+        }
+
+        assert!(vec.rolling_sum(0).is_err());
+        assert!(rle(&[]).rolling_max(3)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rle_vec_rolling_long_runs() -> Result<()> {
+        // Windows slide over whole runs, decoding this vector would take billions of steps
+        let billion = 1_000_000_000;
+        let mut vec = RleVec::<i64, u32, fn(&i64, &i64) -> bool>::builder(PartialEq::eq).build();
+        vec.extend([(1, billion), (0, 0), (3, billion)]);
+
+        let runs =
+            |vec: &RleVec<i64, u32, _>| vec.runs().map(|(x, y)| (*x, *y)).collect::<Vec<_>>();
+        assert_eq!(
+            runs(&vec.rolling_sum(5)?),
+            vec![
+                (3, 1),
+                (4, 1),
+                (5, billion - 4),
+                (7, 1),
+                (9, 1),
+                (11, 1),
+                (13, 1),
+                (15, billion - 4),
+                (12, 1),
+                (9, 1)
+            ]
+        );
+        assert_eq!(
+            runs(&vec.rolling_max(5)?),
+            vec![(1, billion - 2), (3, billion + 2)]
+        );
+        assert_eq!(
+            runs(&vec.rolling_mean(5)?),
+            vec![(1, billion), (2, 2), (3, billion - 2)]
+        );
+
+        // Runs that don't fit into the length type are split
+        let mut vec = RleVector::builder(PartialEq::eq).build();
+        vec.extend([(1, 200), (1, 200)]);
+        assert_eq!(runs_u8(&vec.rolling_max(3)?), vec![(1, 255), (1, 145)]);
+        Ok(())
+    }
+
+    fn runs_u8(vec: &RleVector) -> Vec<(i32, u8)> {
+        vec.runs().map(|(x, y)| (*x, *y)).collect()
+    }
+}
@@ -4,9 +4,16 @@ use std::vec::IntoIter;
 use derive_getters::Dissolve;
 use eyre::Result;
 
+use biobit_core_rs::loc::Interval;
 use biobit_core_rs::num::PrimUInt;
 
 use super::identical::Identical;
+use super::index::RleIndex;
+
+#[cfg(any(feature = "serde", feature = "bitcode"))]
+use super::codec::RawRleVec;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 pub struct RleVecBuilder<V, L: PrimUInt, I: Identical<V>> {
     values: Option<Vec<V>>,
@@ -161,11 +168,26 @@ impl<V, L: PrimUInt, I: Identical<V>> RleVecBuilder<V, L, I> {
     }
 }
 
+/// Run-length encoded vector.
+///
+/// Serialized run lengths are compact in both supported formats: bitcode packs integer columns
+/// natively, while serde writes them as a byte string of varints. Bitcode encodes the
+/// [`RawRleVec`] mirror instead of the vector itself. Both formats reject inputs with mismatched
+/// numbers of values and lengths.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        try_from = "RawRleVec<V, L, I>",
+        bound(deserialize = "V: Deserialize<'de>, I: Identical<V> + Deserialize<'de>")
+    )
+)]
 #[derive(Debug, Clone, Default, Dissolve)]
 pub struct RleVec<V, L: PrimUInt, I: Identical<V>> {
-    values: Vec<V>,
-    lengths: Vec<L>,
-    identical: I,
+    pub(crate) values: Vec<V>,
+    #[cfg_attr(feature = "serde", serde(with = "super::compact"))]
+    pub(crate) lengths: Vec<L>,
+    pub(crate) identical: I,
 }
 
 impl<V, L: PrimUInt, I: Identical<V>> RleVec<V, L, I> {
@@ -195,6 +217,11 @@ impl<V, L: PrimUInt, I: Identical<V>> RleVec<V, L, I> {
         self.lengths.len()
     }
 
+    /// Total length of the decoded vector, i.e. the sum of all run lengths.
+    pub fn total_length(&self) -> u64 {
+        self.lengths.iter().map(|x| x.to_u64().unwrap()).sum()
+    }
+
     pub fn clear(&mut self) {
         self.values.clear();
         self.lengths.clear();
@@ -205,6 +232,21 @@ impl<V, L: PrimUInt, I: Identical<V>> RleVec<V, L, I> {
         self.lengths.push(length);
     }
 
+    // Append a run merging it with the last one if values are identical and the length fits into L
+    pub(crate) fn append(&mut self, value: V, length: L) {
+        if length.is_zero() {
+            return;
+        }
+        if let (Some(last), Some(last_length)) = (self.values.last(), self.lengths.last_mut())
+            && self.identical.identical(last, &value)
+            && let Some(merged) = last_length.checked_add(&length)
+        {
+            *last_length = merged;
+            return;
+        }
+        self.push(value, length);
+    }
+
     pub fn pop(&mut self) -> Option<(V, L)> {
         match (self.values.pop(), self.lengths.pop()) {
             (Some(value), Some(length)) => Some((value, length)),
@@ -233,6 +275,21 @@ impl<V, L: PrimUInt, I: Identical<V>> RleVec<V, L, I> {
     pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
         self.values.iter_mut()
     }
+
+    /// Iterate over runs with their positions in the decoded vector. Zero-length runs are skipped.
+    pub fn intervals(&self) -> impl Iterator<Item = (Interval<u64>, &V)> {
+        let mut cursor = 0u64;
+        self.runs().filter_map(move |(value, length)| {
+            let start = cursor;
+            cursor += length.to_u64().unwrap();
+            Interval::new(start, cursor).ok().map(|x| (x, value))
+        })
+    }
+
+    /// Build a cumulative-length index for O(log n) random access and range queries.
+    pub fn index(&self) -> RleIndex<'_, V, L, I> {
+        RleIndex::new(self)
+    }
 }
 
 impl<V, L: PrimUInt, I: Identical<V>> IntoIterator for RleVec<V, L, I> {
@@ -309,4 +366,83 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    #[cfg(feature = "bitcode")]
+    fn test_rle_vec_bitcode() -> Result<()> {
+        use crate::rle_vec::Equal;
+
+        type Raw = RawRleVec<u8, u32, Equal>;
+
+        let vec = RleVec::<u8, u32, Equal>::builder(Equal)
+            .with_rle_values(vec![1, 2, 3], vec![1, 1_000, u32::MAX])?
+            .build();
+        let bytes = bitcode::encode(&Raw::from(vec.clone()));
+        let decoded: RleVec<u8, u32, Equal> = bitcode::decode::<Raw>(&bytes)?.try_into()?;
+        assert_eq!(
+            decoded.runs().collect::<Vec<_>>(),
+            vec.runs().collect::<Vec<_>>()
+        );
+
+        // Nested vectors keep their own number of runs
+        let empty = RleVec::<u8, u32, Equal>::builder(Equal).build();
+        let nested: Vec<Raw> = vec![vec.clone(), empty, vec]
+            .into_iter()
+            .map(Raw::from)
+            .collect();
+        let bytes = bitcode::encode(&nested);
+        let decoded = bitcode::decode::<Vec<Raw>>(&bytes)?
+            .into_iter()
+            .map(RleVec::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            decoded.iter().map(|x| x.runs().count()).collect::<Vec<_>>(),
+            vec![3, 0, 3]
+        );
+
+        // Truncated input and mismatched values and lengths are rejected
+        assert!(bitcode::decode::<Vec<Raw>>(&bytes[..bytes.len() - 1]).is_err());
+        let mismatched = bitcode::encode(&Raw {
+            values: vec![1, 2, 3],
+            lengths: vec![1, 2],
+            identical: Equal,
+        });
+        assert!(RleVec::try_from(bitcode::decode::<Raw>(&mismatched)?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_rle_vec_serde() -> Result<()> {
+        use crate::rle_vec::Equal;
+
+        let lengths = vec![0, 1, 127, 128, 300, 16_384, u32::MAX];
+        let vec = RleVec::<u8, u32, Equal>::builder(Equal)
+            .with_rle_values(vec![1, 2, 3, 4, 5, 6, 7], lengths.clone())?
+            .build();
+        let bytes = bitcode::serialize(&vec)?;
+        let decoded: RleVec<u8, u32, Equal> = bitcode::deserialize(&bytes)?;
+        assert_eq!(
+            decoded.runs().collect::<Vec<_>>(),
+            vec.runs().collect::<Vec<_>>()
+        );
+
+        // Lengths that don't fit into the target type are rejected
+        assert!(bitcode::deserialize::<RleVec<u8, u8, Equal>>(&bytes).is_err());
+
+        // So are mismatched values and lengths
+        let mismatched = bitcode::serialize(&RawRleVec {
+            values: vec![1u8, 2, 3],
+            lengths: vec![1u32, 2],
+            identical: Equal,
+        })?;
+        assert!(bitcode::deserialize::<RleVec<u8, u32, Equal>>(&mismatched).is_err());
+
+        // Short runs take a single byte each
+        let short = RleVec::<u8, u64, Equal>::builder(Equal)
+            .with_rle_values(vec![0; 1_000], vec![100; 1_000])?
+            .build();
+        assert!(bitcode::serialize(&short)?.len() < 2 * 1_000 + 64);
+        Ok(())
+    }
 }
@@ -16,6 +16,11 @@ use num::{NumCast, Unsigned};
 /// - Prohibit 'empty' intervals (start == end) or intervals with negative length (start > end)
 /// - Implement custom traits (e.g. Dissolve) and methods (e.g. contains, intersects, touches).
 #[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "(Idx, Idx)", into = "(Idx, Idx)")
+)]
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Dissolve)]
 pub struct Interval<Idx: PrimInt> {
     start: Idx,
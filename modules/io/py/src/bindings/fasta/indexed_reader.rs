@@ -17,7 +17,8 @@ pub struct PyIndexedSources {
 #[pymethods]
 impl PyIndexedSources {
     #[new]
-    fn new(path: Bound<PyAny>) -> Result<Self> {
+    #[pyo3(signature = (path, autoindex = true))]
+    fn new(path: Bound<PyAny>, autoindex: bool) -> Result<Self> {
         let mut paths = Vec::new();
         if let Ok(path) = path.extract::<PathBuf>() {
             paths.push(path);
@@ -32,7 +33,7 @@ impl PyIndexedSources {
         let indexed: Vec<_> = paths.iter().zip(decoders).collect();
 
         Ok(Self {
-            rs: IndexedSources::from_paths(&indexed),
+            rs: IndexedSources::from_paths(&indexed).with_autoindex(autoindex),
         })
     }
 
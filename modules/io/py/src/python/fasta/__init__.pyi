@@ -28,7 +28,7 @@ class IndexedReader:
     __hash__ = None  # type: ignore
 
 class IndexedSources:
-    def __init__(self, path: str | Path | Iterable[str | Path]): ...
+    def __init__(self, path: str | Path | Iterable[str | Path], autoindex: bool = True): ...
     def open(self) -> IndexedReader: ...
 
     __hash__ = None  # type: ignore
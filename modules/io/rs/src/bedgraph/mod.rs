@@ -0,0 +1,16 @@
+// Format specification: https://genome.ucsc.edu/goldenPath/help/bedgraph.html
+
+// Fields:
+// 1. seqid: [^\s]+
+// 2. start: u64, 0-based
+// 3. end: u64, exclusive
+// 4. value: numeric
+
+// Track and browser lines are not emitted. Consecutive runs with identical values are merged
+// into a single record.
+
+mod writer;
+
+pub use writer::Writer;
+
+pub const EXTENSIONS: &[&str] = &["bedgraph", "bdg"];
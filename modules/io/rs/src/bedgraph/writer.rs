@@ -0,0 +1,127 @@
+use biobit_collections_rs::rle_vec::{Identical, RleVec};
+use biobit_core_rs::num::PrimUInt;
+use eyre::{OptionExt, Result, ensure};
+use num::Zero;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use substratum_compress::{Encoder, adapter::BoxedSync, encode::Encode};
+
+/// A bedGraph writer for run-length encoded signal tracks.
+pub struct Writer<W> {
+    writer: W,
+    skip_zeros: bool,
+}
+
+impl Writer<()> {
+    /// Create a new bedGraph writer for the given file path. Use the BGZF encoder to produce files
+    /// that can be indexed with tabix.
+    pub fn from_path(
+        path: impl AsRef<Path>,
+        encoder: &Encoder,
+    ) -> Result<Writer<Box<dyn Write + Send + Sync>>> {
+        let file = encoder.encode(File::create(path.as_ref())?, BoxedSync)?;
+        Ok(Writer::new(file))
+    }
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            skip_zeros: true,
+        }
+    }
+
+    /// Don't write runs with zero values (default: true).
+    pub fn with_skip_zeros(mut self, skip_zeros: bool) -> Self {
+        self.skip_zeros = skip_zeros;
+        self
+    }
+
+    /// Write the signal track starting at the given 0-based position of the sequence.
+    pub fn write_rle<V, L, I>(
+        &mut self,
+        seqid: &str,
+        start: u64,
+        rle: &RleVec<V, L, I>,
+    ) -> Result<()>
+    where
+        V: Display + Zero,
+        L: PrimUInt,
+        I: Identical<V>,
+    {
+        ensure!(
+            !seqid.is_empty() && !seqid.contains(|c: char| c.is_ascii_whitespace()),
+            "bedGraph seqid must be non-empty and can't contain whitespace: {seqid}"
+        );
+
+        let mut position = start;
+        let mut current: Option<(&V, u64)> = None;
+        for (value, length) in rle.runs() {
+            let length = length
+                .to_u64()
+                .ok_or_eyre("RLE run length can't be represented as u64")?;
+            match current {
+                Some((previous, _)) if rle.identical(previous, value) => {}
+                _ => {
+                    if let Some((previous, begin)) = current {
+                        self.write_run(seqid, begin, position, previous)?;
+                    }
+                    current = Some((value, position));
+                }
+            }
+            position += length;
+        }
+        if let Some((previous, begin)) = current {
+            self.write_run(seqid, begin, position, previous)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn write_run<V: Display + Zero>(
+        &mut self,
+        seqid: &str,
+        start: u64,
+        end: u64,
+        value: &V,
+    ) -> Result<()> {
+        if start < end && !(self.skip_zeros && value.is_zero()) {
+            writeln!(self.writer, "{seqid}\t{start}\t{end}\t{value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_rle() -> Result<()> {
+        let mut rle = RleVec::<u32, u8, fn(&u32, &u32) -> bool>::builder(PartialEq::eq).build();
+        rle.extend([(1, 5), (1, 5), (0, 10), (0, 0), (7, 255), (3, 1)]);
+
+        let mut writer = Writer::new(Vec::new());
+        writer.write_rle("chr1", 100, &rle)?;
+        assert!(writer.write_rle("chr 1", 0, &rle).is_err());
+        assert_eq!(
+            String::from_utf8(writer.writer.clone())?,
+            "chr1\t100\t110\t1\nchr1\t120\t375\t7\nchr1\t375\t376\t3\n"
+        );
+
+        let mut writer = Writer::new(Vec::new()).with_skip_zeros(false);
+        writer.write_rle("chr2", 0, &rle)?;
+        assert_eq!(
+            String::from_utf8(writer.writer)?,
+            "chr2\t0\t10\t1\nchr2\t10\t20\t0\nchr2\t20\t275\t7\nchr2\t275\t276\t3\n"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+// All BBI files written by this module are little-endian. Big-endian files are rejected upon
+// reading by checking the magic number.
+
+use std::io::{Read, Result, Write};
+
+macro_rules! impl_le {
+    ($(($T:ty, $read:ident, $write:ident)),+ $(,)?) => {
+        $(
+            #[inline(always)]
+            pub fn $write(writer: &mut impl Write, value: $T) -> Result<()> {
+                writer.write_all(&value.to_le_bytes())
+            }
+
+            #[inline(always)]
+            pub fn $read(reader: &mut impl Read) -> Result<$T> {
+                let mut buffer = [0u8; size_of::<$T>()];
+                reader.read_exact(&mut buffer)?;
+                Ok(<$T>::from_le_bytes(buffer))
+            }
+        )+
+    };
+}
+
+impl_le!(
+    (u8, read_u8, write_u8),
+    (u16, read_u16, write_u16),
+    (u32, read_u32, write_u32),
+    (u64, read_u64, write_u64),
+    (f32, read_f32, write_f32),
+    (f64, read_f64, write_f64),
+);
+
+#[inline(always)]
+pub fn write_zeros(writer: &mut impl Write, count: usize) -> Result<()> {
+    const ZEROS: [u8; 256] = [0; 256];
+    let mut left = count;
+    while left > 0 {
+        let chunk = left.min(ZEROS.len());
+        writer.write_all(&ZEROS[..chunk])?;
+        left -= chunk;
+    }
+    Ok(())
+}
@@ -0,0 +1,159 @@
+// Chromosome B+ tree mapping sequence names to their IDs and sizes.
+//
+// Header (32 bytes): magic, blockSize, keySize, valSize, itemCount, reserved
+// Node: isLeaf (u8), reserved (u8), count (u16), followed by `blockSize` slots:
+// - leaf: key[keySize], chromId (u32), chromSize (u32)
+// - non-leaf: key[keySize], childOffset (u64)
+//
+// All nodes are written top-down with full `blockSize` slots, unused slots are zero-padded.
+
+use super::binary::*;
+use eyre::{Result, ensure};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+pub const MAGIC: u32 = 0x78CA8C91;
+const MAX_BLOCK_SIZE: usize = 256;
+
+/// Write the chromosome tree for the given (name, size) pairs. Chromosome IDs are the positions of
+/// names in the input slice, which must be sorted and contain unique names.
+pub fn write(writer: &mut (impl Write + Seek), chroms: &[(String, u32)]) -> Result<()> {
+    ensure!(
+        chroms
+            .windows(2)
+            .all(|w| w[0].0.as_bytes() < w[1].0.as_bytes()),
+        "Chromosome names must be sorted and unique"
+    );
+
+    let start = writer.stream_position()?;
+    let items = chroms.len();
+    let block = items.clamp(1, MAX_BLOCK_SIZE);
+    let key_size = chroms.iter().map(|(name, _)| name.len()).max().unwrap_or(1);
+    let node_size = (4 + block * (key_size + 8)) as u64;
+
+    write_u32(writer, MAGIC)?;
+    write_u32(writer, block as u32)?;
+    write_u32(writer, key_size as u32)?;
+    write_u32(writer, 8)?;
+    write_u64(writer, items as u64)?;
+    write_u64(writer, 0)?;
+
+    // Number of nodes per level, from the root to the leaves
+    let mut levels = vec![items.div_ceil(block).max(1)];
+    while *levels.last().unwrap() > 1 {
+        levels.push(levels.last().unwrap().div_ceil(block));
+    }
+    levels.reverse();
+
+    let mut level_offset = start + 32;
+    for (level, &nodes) in levels.iter().enumerate() {
+        let is_leaf = level + 1 == levels.len();
+        // Number of items covered by a single child node
+        let span = block.pow((levels.len() - level - 1) as u32);
+        let next_level_offset = level_offset + nodes as u64 * node_size;
+
+        for node in 0..nodes {
+            let first = node * block * span;
+            let last = ((node + 1) * block * span).min(items);
+            let children = (first..last).step_by(span).collect::<Vec<_>>();
+
+            write_u8(writer, is_leaf as u8)?;
+            write_u8(writer, 0)?;
+            write_u16(writer, children.len() as u16)?;
+            for &child in &children {
+                let name = chroms[child].0.as_bytes();
+                writer.write_all(name)?;
+                write_zeros(writer, key_size - name.len())?;
+                if is_leaf {
+                    write_u32(writer, child as u32)?;
+                    write_u32(writer, chroms[child].1)?;
+                } else {
+                    let offset = next_level_offset + (child / span) as u64 * node_size;
+                    write_u64(writer, offset)?;
+                }
+            }
+            write_zeros(writer, (block - children.len()) * (key_size + 8))?;
+        }
+        level_offset = next_level_offset;
+    }
+    Ok(())
+}
+
+/// Read all (name, size) pairs from the chromosome tree. Names are ordered by their IDs.
+pub fn read(reader: &mut (impl Read + Seek), offset: u64) -> Result<Vec<(String, u32)>> {
+    reader.seek(SeekFrom::Start(offset))?;
+    ensure!(
+        read_u32(reader)? == MAGIC,
+        "Invalid magic number of the bigWig chromosome tree"
+    );
+    let _block = read_u32(reader)?;
+    let key_size = read_u32(reader)? as usize;
+    let val_size = read_u32(reader)?;
+    ensure!(
+        val_size == 8,
+        "Unsupported value size in the bigWig chromosome tree: {val_size}"
+    );
+    let items = read_u64(reader)? as usize;
+    let _reserved = read_u64(reader)?;
+
+    let mut result = vec![None; items];
+    let mut key = vec![0u8; key_size];
+    let mut stack = vec![offset + 32];
+    while let Some(node) = stack.pop() {
+        reader.seek(SeekFrom::Start(node))?;
+        let is_leaf = read_u8(reader)? != 0;
+        let _reserved = read_u8(reader)?;
+        let count = read_u16(reader)?;
+
+        for _ in 0..count {
+            reader.read_exact(&mut key)?;
+            let end = key.iter().position(|x| *x == 0).unwrap_or(key.len());
+            if is_leaf {
+                let id = read_u32(reader)? as usize;
+                let size = read_u32(reader)?;
+                ensure!(
+                    id < items,
+                    "Chromosome ID is out of bounds in the bigWig chromosome tree: {id}"
+                );
+                result[id] = Some((String::from_utf8(key[..end].to_vec())?, size));
+            } else {
+                stack.push(read_u64(reader)?);
+            }
+        }
+    }
+
+    result
+        .into_iter()
+        .enumerate()
+        .map(|(id, x)| {
+            x.ok_or_else(|| eyre::eyre!("Chromosome ID {id} is missing in the bigWig file"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_bptree_roundtrip() -> Result<()> {
+        for size in [0, 1, 2, 255, 256, 257, 1000, 70_000] {
+            let mut chroms = (0..size)
+                .map(|x| (format!("chr{x}"), x as u32 + 1))
+                .collect::<Vec<_>>();
+            chroms.sort();
+
+            let mut buffer = Cursor::new(vec![0u8; 13]);
+            buffer.seek(SeekFrom::End(0))?;
+            write(&mut buffer, &chroms)?;
+            assert_eq!(read(&mut buffer, 13)?, chroms);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_bptree_rejects_unsorted() {
+        let chroms = vec![("chr2".to_string(), 1), ("chr1".to_string(), 1)];
+        assert!(write(&mut Cursor::new(Vec::new()), &chroms).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+// Chromosome-ID R-tree (CIR tree) indexing data blocks by their genomic extents.
+//
+// Header (48 bytes): magic, blockSize, itemCount, startChromIx, startBase, endChromIx, endBase,
+//                    endFileOffset, itemsPerSlot, reserved
+// Node: isLeaf (u8), reserved (u8), count (u16), followed by `blockSize` slots:
+// - leaf: startChromIx, startBase, endChromIx, endBase (u32), dataOffset (u64), dataSize (u64)
+// - non-leaf: startChromIx, startBase, endChromIx, endBase (u32), childOffset (u64)
+//
+// All nodes are written top-down with full `blockSize` slots, unused slots are zero-padded.
+
+use super::binary::*;
+use eyre::{Result, ensure};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+pub const MAGIC: u32 = 0x2468ACE0;
+pub const BLOCK_SIZE: usize = 256;
+
+const HEADER_SIZE: u64 = 48;
+const LEAF_ITEM_SIZE: usize = 32;
+const NODE_ITEM_SIZE: usize = 24;
+
+/// A single indexed data block. Coordinates are (chromId, base) pairs, the end is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Item {
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Write the index for the given blocks sorted by their start coordinates.
+pub fn write(
+    writer: &mut (impl Write + Seek),
+    items: &[Item],
+    items_per_slot: u32,
+    end_file_offset: u64,
+) -> Result<()> {
+    ensure!(
+        items.windows(2).all(|w| w[0].start <= w[1].start),
+        "Indexed bigWig blocks must be sorted"
+    );
+
+    let start = writer.stream_position()?;
+    let (first, last) = match (items.first(), items.iter().map(|x| x.end).max()) {
+        (Some(first), Some(last)) => (first.start, last),
+        _ => ((0, 0), (0, 0)),
+    };
+
+    write_u32(writer, MAGIC)?;
+    write_u32(writer, BLOCK_SIZE as u32)?;
+    write_u64(writer, items.len() as u64)?;
+    write_u32(writer, first.0)?;
+    write_u32(writer, first.1)?;
+    write_u32(writer, last.0)?;
+    write_u32(writer, last.1)?;
+    write_u64(writer, end_file_offset)?;
+    write_u32(writer, items_per_slot)?;
+    write_u32(writer, 0)?;
+
+    // Number of nodes per level, from the root to the leaves
+    let mut levels = vec![items.len().div_ceil(BLOCK_SIZE).max(1)];
+    while *levels.last().unwrap() > 1 {
+        levels.push(levels.last().unwrap().div_ceil(BLOCK_SIZE));
+    }
+    levels.reverse();
+
+    let node_size = |level: usize| {
+        let item = if level + 1 == levels.len() {
+            LEAF_ITEM_SIZE
+        } else {
+            NODE_ITEM_SIZE
+        };
+        (4 + BLOCK_SIZE * item) as u64
+    };
+
+    let mut level_offset = start + HEADER_SIZE;
+    for (level, &nodes) in levels.iter().enumerate() {
+        let is_leaf = level + 1 == levels.len();
+        // Number of items covered by a single child node
+        let span = BLOCK_SIZE.pow((levels.len() - level - 1) as u32);
+        let next_level_offset = level_offset + nodes as u64 * node_size(level);
+
+        for node in 0..nodes {
+            let first = node * BLOCK_SIZE * span;
+            let last = ((node + 1) * BLOCK_SIZE * span).min(items.len());
+            let children = (first..last).step_by(span).collect::<Vec<_>>();
+
+            write_u8(writer, is_leaf as u8)?;
+            write_u8(writer, 0)?;
+            write_u16(writer, children.len() as u16)?;
+            for &child in &children {
+                let covered = &items[child..(child + span).min(items.len())];
+                let end = covered.iter().map(|x| x.end).max().unwrap();
+
+                write_u32(writer, items[child].start.0)?;
+                write_u32(writer, items[child].start.1)?;
+                write_u32(writer, end.0)?;
+                write_u32(writer, end.1)?;
+                if is_leaf {
+                    write_u64(writer, items[child].offset)?;
+                    write_u64(writer, items[child].size)?;
+                } else {
+                    let offset = next_level_offset + (child / span) as u64 * node_size(level + 1);
+                    write_u64(writer, offset)?;
+                }
+            }
+
+            let item = if is_leaf {
+                LEAF_ITEM_SIZE
+            } else {
+                NODE_ITEM_SIZE
+            };
+            write_zeros(writer, (BLOCK_SIZE - children.len()) * item)?;
+        }
+        level_offset = next_level_offset;
+    }
+    Ok(())
+}
+
+/// Find all blocks overlapping the [start, end) interval on the given chromosome.
+pub fn query(
+    reader: &mut (impl Read + Seek),
+    offset: u64,
+    chrom: u32,
+    start: u32,
+    end: u32,
+    into: &mut Vec<(u64, u64)>,
+) -> Result<()> {
+    into.clear();
+
+    reader.seek(SeekFrom::Start(offset))?;
+    ensure!(
+        read_u32(reader)? == MAGIC,
+        "Invalid magic number of the bigWig R-tree index"
+    );
+
+    let overlaps =
+        |first: (u32, u32), last: (u32, u32)| first < (chrom, end) && last > (chrom, start);
+
+    let mut stack = vec![offset + HEADER_SIZE];
+    while let Some(node) = stack.pop() {
+        reader.seek(SeekFrom::Start(node))?;
+        let is_leaf = read_u8(reader)? != 0;
+        let _reserved = read_u8(reader)?;
+        let count = read_u16(reader)?;
+
+        let mut children = Vec::new();
+        for _ in 0..count {
+            let first = (read_u32(reader)?, read_u32(reader)?);
+            let last = (read_u32(reader)?, read_u32(reader)?);
+            if is_leaf {
+                let data = (read_u64(reader)?, read_u64(reader)?);
+                if overlaps(first, last) {
+                    into.push(data);
+                }
+            } else {
+                let child = read_u64(reader)?;
+                if overlaps(first, last) {
+                    children.push(child);
+                }
+            }
+        }
+        // Preserve the on-disk order of the blocks
+        stack.extend(children.into_iter().rev());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn items(count: u32) -> Vec<Item> {
+        (0..count)
+            .map(|x| Item {
+                start: (x / 1000, (x % 1000) * 10),
+                end: (x / 1000, (x % 1000) * 10 + 5),
+                offset: x as u64 * 100,
+                size: 100,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cirtree_query() -> Result<()> {
+        for count in [0, 1, 255, 256, 257, 5_000, 70_000] {
+            let items = items(count);
+            let mut buffer = Cursor::new(vec![0u8; 7]);
+            buffer.seek(SeekFrom::End(0))?;
+            write(&mut buffer, &items, 1024, 7)?;
+
+            let mut hits = Vec::new();
+            for (chrom, start, end) in [(0, 0, 10), (0, 5, 10), (1, 15, 36), (2, 0, u32::MAX)] {
+                query(&mut buffer, 7, chrom, start, end, &mut hits)?;
+                let expected = items
+                    .iter()
+                    .filter(|x| x.start < (chrom, end) && x.end > (chrom, start))
+                    .map(|x| (x.offset, x.size))
+                    .collect::<Vec<_>>();
+                assert_eq!(hits, expected);
+            }
+        }
+        Ok(())
+    }
+}
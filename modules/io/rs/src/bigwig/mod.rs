@@ -0,0 +1,37 @@
+// Format specification: https://genome.ucsc.edu/goldenPath/help/bigWig.html
+// and Kent et al. 2010, "BigWig and BigBed: enabling browsing of large distributed datasets"
+
+// File layout (all offsets are absolute, little-endian):
+// 1. Header (64 bytes): magic, version, zoomLevels, chromTreeOffset, fullDataOffset,
+//    fullIndexOffset, fieldCount, definedFieldCount, autoSqlOffset, totalSummaryOffset,
+//    uncompressBufSize, reserved
+// 2. Zoom headers (24 bytes each): reductionLevel, reserved, dataOffset, indexOffset
+// 3. Total summary (40 bytes)
+// 4. Chromosome B+ tree
+// 5. Full resolution data: sectionCount (u64) followed by zlib-compressed sections
+// 6. Chromosome R-tree index over the full resolution data
+// 7. Zoom levels: recordCount (u32), zlib-compressed zoom records, R-tree index
+// 8. Magic number
+
+mod binary;
+mod bptree;
+mod cirtree;
+mod reader;
+mod summary;
+mod writer;
+
+pub use reader::Reader;
+pub use summary::Summary;
+pub use writer::Writer;
+
+pub const EXTENSIONS: &[&str] = &["bw", "bigwig", "bigWig"];
+
+const MAGIC: u32 = 0x888FFC26;
+const HEADER_SIZE: u64 = 64;
+const ZOOM_HEADER_SIZE: u64 = 24;
+const SUMMARY_SIZE: u64 = 40;
+
+// Section types of the full resolution data
+const BEDGRAPH: u8 = 1;
+const VARSTEP: u8 = 2;
+const FIXEDSTEP: u8 = 3;
@@ -0,0 +1,391 @@
+use super::binary::*;
+use super::summary::Summary;
+use super::{BEDGRAPH, FIXEDSTEP, HEADER_SIZE, MAGIC, SUMMARY_SIZE, VARSTEP, bptree, cirtree};
+use ahash::HashMap;
+use biobit_collections_rs::rle_vec::{Identical, RleVec};
+use biobit_core_rs::loc::{Interval, IntervalOp};
+use biobit_core_rs::num::PrimUInt;
+use eyre::{Result, bail, ensure, eyre};
+use flate2::read::ZlibDecoder;
+use num::NumCast;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// A bigWig reader that fetches full resolution records overlapping the requested regions.
+pub struct Reader<R: Read + Seek> {
+    reader: R,
+    chroms: Vec<(String, u32)>,
+    ids: HashMap<String, u32>,
+    // (reductionLevel, dataOffset, indexOffset)
+    zooms: Vec<(u32, u64, u64)>,
+    summary: Summary,
+    index_offset: u64,
+    uncompress_buf_size: u32,
+    // Reusable buffers
+    blocks: Vec<(u64, u64)>,
+    compressed: Vec<u8>,
+    buffer: Vec<u8>,
+}
+
+impl Reader<BufReader<File>> {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let file = BufReader::new(File::open(path.as_ref())?);
+        Reader::new(file)
+    }
+}
+
+impl<R: Read + Seek> Reader<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+        let magic = read_u32(&mut reader)?;
+        ensure!(
+            magic == MAGIC,
+            "Not a bigWig file or big-endian bigWig files which are not supported: {magic:#X}"
+        );
+        let version = read_u16(&mut reader)?;
+        let zoom_levels = read_u16(&mut reader)?;
+        let chrom_tree_offset = read_u64(&mut reader)?;
+        let _full_data_offset = read_u64(&mut reader)?;
+        let index_offset = read_u64(&mut reader)?;
+        let _field_count = read_u16(&mut reader)?;
+        let _defined_field_count = read_u16(&mut reader)?;
+        let _auto_sql_offset = read_u64(&mut reader)?;
+        let summary_offset = read_u64(&mut reader)?;
+        let uncompress_buf_size = read_u32(&mut reader)?;
+        let _reserved = read_u64(&mut reader)?;
+        debug_assert_eq!(reader.stream_position()?, HEADER_SIZE);
+
+        let mut zooms = Vec::with_capacity(zoom_levels as usize);
+        for _ in 0..zoom_levels {
+            let reduction = read_u32(&mut reader)?;
+            let _reserved = read_u32(&mut reader)?;
+            zooms.push((reduction, read_u64(&mut reader)?, read_u64(&mut reader)?));
+        }
+
+        // Total summary is available only since version 2
+        let summary = if version >= 2 && summary_offset != 0 {
+            reader.seek(SeekFrom::Start(summary_offset))?;
+            let summary = Summary::read_total(&mut reader)?;
+            debug_assert_eq!(reader.stream_position()?, summary_offset + SUMMARY_SIZE);
+            summary
+        } else {
+            Summary::new()
+        };
+
+        let chroms = bptree::read(&mut reader, chrom_tree_offset)?;
+        let ids = chroms
+            .iter()
+            .enumerate()
+            .map(|(id, (name, _))| (name.clone(), id as u32))
+            .collect();
+
+        Ok(Self {
+            reader,
+            chroms,
+            ids,
+            zooms,
+            summary,
+            index_offset,
+            uncompress_buf_size,
+            blocks: Vec::new(),
+            compressed: Vec::new(),
+            buffer: Vec::new(),
+        })
+    }
+
+    /// (name, size) of all chromosomes in the file ordered by their IDs.
+    pub fn chroms(&self) -> &[(String, u32)] {
+        &self.chroms
+    }
+
+    /// Reduction levels (bin sizes) of all zoom levels in the file.
+    pub fn zoom_levels(&self) -> Vec<u32> {
+        self.zooms.iter().map(|x| x.0).collect()
+    }
+
+    pub fn summary(&self) -> &Summary {
+        &self.summary
+    }
+
+    /// Read all records overlapping the given interval of the sequence. Records are returned in
+    /// the file order and are not clipped to the interval.
+    pub fn records(
+        &mut self,
+        seqid: &str,
+        interval: &Interval<u64>,
+        into: &mut Vec<(Interval<u64>, f32)>,
+    ) -> Result<()> {
+        into.clear();
+        let chrom = *self
+            .ids
+            .get(seqid)
+            .ok_or_else(|| eyre!("Unknown bigWig chromosome: {seqid}"))?;
+        let (start, end) = (
+            interval.start().min(u32::MAX as u64) as u32,
+            interval.end().min(u32::MAX as u64) as u32,
+        );
+        if start >= end {
+            return Ok(());
+        }
+
+        cirtree::query(
+            &mut self.reader,
+            self.index_offset,
+            chrom,
+            start,
+            end,
+            &mut self.blocks,
+        )?;
+
+        for &(offset, size) in &self.blocks {
+            self.reader.seek(SeekFrom::Start(offset))?;
+            self.compressed.resize(size as usize, 0);
+            self.reader.read_exact(&mut self.compressed)?;
+
+            self.buffer.clear();
+            if self.uncompress_buf_size > 0 {
+                ZlibDecoder::new(self.compressed.as_slice()).read_to_end(&mut self.buffer)?;
+            } else {
+                std::mem::swap(&mut self.buffer, &mut self.compressed);
+            }
+            parse_section(&self.buffer, chrom, start, end, into)?;
+        }
+        Ok(())
+    }
+
+    /// Read the signal over the given interval of the sequence as a run-length encoded vector.
+    /// Positions without records are filled with the `missing` value.
+    pub fn read_rle<L: PrimUInt, I: Identical<f32>>(
+        &mut self,
+        seqid: &str,
+        interval: &Interval<u64>,
+        missing: f32,
+        into: &mut RleVec<f32, L, I>,
+    ) -> Result<()> {
+        into.clear();
+
+        let mut records = Vec::new();
+        self.records(seqid, interval, &mut records)?;
+
+        let mut cursor = interval.start();
+        for (record, value) in records {
+            let (start, end) = (record.start().max(cursor), record.end().min(interval.end()));
+            if start >= end {
+                continue;
+            }
+            push_run(into, missing, start - cursor)?;
+            push_run(into, value, end - start)?;
+            cursor = end;
+        }
+        push_run(into, missing, interval.end() - cursor)?;
+        Ok(())
+    }
+}
+
+// Append the run merging it with the last one if they are identical. Runs longer than the
+// maximum value of L are split.
+fn push_run<L: PrimUInt, I: Identical<f32>>(
+    rle: &mut RleVec<f32, L, I>,
+    value: f32,
+    length: u64,
+) -> Result<()> {
+    if length == 0 {
+        return Ok(());
+    }
+    let maxlen = L::max_value()
+        .to_u64()
+        .ok_or_else(|| eyre!("RLE length type can't be represented as u64"))?;
+
+    let mut left = length;
+    if let Some((last, lastlen)) = rle.pop() {
+        if rle.identical(&last, &value) {
+            // Safe to unwrap: lastlen <= maxlen
+            left += lastlen.to_u64().unwrap();
+        } else {
+            rle.push(last, lastlen);
+        }
+    }
+
+    while left > 0 {
+        let chunk = left.min(maxlen);
+        // Safe to unwrap: chunk <= maxlen
+        rle.push(value, <L as NumCast>::from(chunk).unwrap());
+        left -= chunk;
+    }
+    Ok(())
+}
+
+// Parse records from a single uncompressed data section overlapping the [start, end) interval.
+fn parse_section(
+    section: &[u8],
+    chrom: u32,
+    start: u32,
+    end: u32,
+    into: &mut Vec<(Interval<u64>, f32)>,
+) -> Result<()> {
+    let mut reader = Cursor::new(section);
+    let section_chrom = read_u32(&mut reader)?;
+    let section_start = read_u32(&mut reader)?;
+    let _section_end = read_u32(&mut reader)?;
+    let step = read_u32(&mut reader)?;
+    let span = read_u32(&mut reader)?;
+    let kind = read_u8(&mut reader)?;
+    let _reserved = read_u8(&mut reader)?;
+    let count = read_u16(&mut reader)?;
+
+    if section_chrom != chrom {
+        return Ok(());
+    }
+
+    for i in 0..count as u32 {
+        let (first, last, value) = match kind {
+            BEDGRAPH => (
+                read_u32(&mut reader)?,
+                read_u32(&mut reader)?,
+                read_f32(&mut reader)?,
+            ),
+            VARSTEP => {
+                let first = read_u32(&mut reader)?;
+                (first, first.saturating_add(span), read_f32(&mut reader)?)
+            }
+            FIXEDSTEP => {
+                let first = section_start.saturating_add(i.saturating_mul(step));
+                (first, first.saturating_add(span), read_f32(&mut reader)?)
+            }
+            _ => bail!("Unknown bigWig section type: {kind}"),
+        };
+        if first < last && first < end && last > start {
+            into.push((Interval::new(first as u64, last as u64)?, value));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Writer;
+    use super::*;
+
+    type Rle = RleVec<f32, u8, fn(&f32, &f32) -> bool>;
+
+    fn rle(runs: &[(f32, u8)]) -> Rle {
+        let mut rle = Rle::builder(PartialEq::eq).build();
+        rle.extend(runs.iter().copied());
+        rle
+    }
+
+    fn runs(rle: &Rle) -> Vec<(f32, u8)> {
+        rle.runs().map(|(v, l)| (*v, *l)).collect()
+    }
+
+    #[test]
+    fn test_bigwig_roundtrip() -> Result<()> {
+        let chroms = vec![("chr2".to_string(), 10_000), ("chr1".to_string(), 1_000)];
+        let mut writer = Writer::new(Cursor::new(Vec::new()), chroms)?.with_items_per_slot(3)?;
+        writer.add_rle(
+            "chr1",
+            10,
+            &rle(&[(1.0, 5), (1.0, 5), (0.0, 10), (-2.5, 1)]),
+        )?;
+        writer.add_rle("chr1", 100, &rle(&[(3.0, 200), (4.0, 200)]))?;
+        for start in (0..10_000).step_by(100) {
+            writer.add_rle("chr2", start, &rle(&[(start as f32, 50)]))?;
+        }
+        assert!(writer.add_rle("chr1", 0, &rle(&[(1.0, 1)])).is_err());
+        assert!(writer.add_rle("chr3", 0, &rle(&[(1.0, 1)])).is_err());
+        assert!(writer.add_rle("chr1", 900, &rle(&[(1.0, 200)])).is_err());
+
+        let file = writer.finish()?;
+        let mut reader = Reader::new(file)?;
+        assert_eq!(
+            reader.chroms(),
+            &[("chr1".to_string(), 1_000), ("chr2".to_string(), 10_000)]
+        );
+        assert!(!reader.zoom_levels().is_empty());
+        // The first chr2 track is zero and skipped
+        assert_eq!(*reader.summary().bases(), 10 + 1 + 400 + 99 * 50);
+        assert_eq!(*reader.summary().min(), -2.5);
+        assert_eq!(*reader.summary().max(), 9_900.0);
+
+        let mut records = Vec::new();
+        reader.records("chr1", &Interval::new(0, 1_000)?, &mut records)?;
+        assert_eq!(
+            records,
+            vec![
+                (Interval::new(10, 20)?, 1.0),
+                (Interval::new(30, 31)?, -2.5),
+                (Interval::new(100, 300)?, 3.0),
+                (Interval::new(300, 500)?, 4.0),
+            ]
+        );
+
+        reader.records("chr2", &Interval::new(5_020, 5_130)?, &mut records)?;
+        assert_eq!(
+            records,
+            vec![
+                (Interval::new(5_000, 5_050)?, 5_000.0),
+                (Interval::new(5_100, 5_150)?, 5_100.0),
+            ]
+        );
+        assert!(
+            reader
+                .records("chr3", &Interval::new(0, 1)?, &mut records)
+                .is_err()
+        );
+
+        let mut signal = rle(&[]);
+        reader.read_rle("chr1", &Interval::new(15, 35)?, 0.0, &mut signal)?;
+        assert_eq!(
+            runs(&signal),
+            vec![(1.0, 5), (0.0, 10), (-2.5, 1), (0.0, 4)]
+        );
+
+        reader.read_rle("chr1", &Interval::new(0, 600)?, f32::NAN, &mut signal)?;
+        let expected = [
+            (f32::NAN, 10),
+            (1.0, 10),
+            (f32::NAN, 10),
+            (-2.5, 1),
+            (f32::NAN, 69),
+            (3.0, 200),
+            (4.0, 200),
+            (f32::NAN, 100),
+        ];
+        let mut total = 0;
+        for ((value, length), (exp_value, exp_length)) in signal
+            .runs()
+            .zip(expected.iter().flat_map(|(v, l)| split(*v, *l)))
+        {
+            assert!(*value == exp_value || (value.is_nan() && exp_value.is_nan()));
+            assert_eq!(*length, exp_length);
+            total += *length as u64;
+        }
+        assert_eq!(total, 600);
+        Ok(())
+    }
+
+    fn split(value: f32, length: u16) -> Vec<(f32, u8)> {
+        let mut result = Vec::new();
+        let mut left = length;
+        while left > 0 {
+            let chunk = left.min(u8::MAX as u16);
+            result.push((value, chunk as u8));
+            left -= chunk;
+        }
+        result
+    }
+
+    #[test]
+    fn test_bigwig_empty() -> Result<()> {
+        let writer = Writer::new(Cursor::new(Vec::new()), vec![("chr1".to_string(), 10)])?;
+        let mut reader = Reader::new(writer.finish()?)?;
+        assert!(reader.zoom_levels().is_empty());
+        assert!(reader.summary().is_empty());
+
+        let mut signal = rle(&[]);
+        reader.read_rle("chr1", &Interval::new(0, 10)?, 0.0, &mut signal)?;
+        assert_eq!(runs(&signal), vec![(0.0, 10)]);
+        Ok(())
+    }
+}
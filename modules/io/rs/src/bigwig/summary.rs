@@ -0,0 +1,196 @@
+use super::binary::*;
+use derive_getters::{Dissolve, Getters};
+use std::io::{Read, Result, Write};
+
+/// Summary statistics over covered bases of a bigWig file or one of its zoom bins.
+#[derive(Debug, Clone, Copy, PartialEq, Dissolve, Getters)]
+pub struct Summary {
+    bases: u64,
+    min: f64,
+    max: f64,
+    sum: f64,
+    sum_squares: f64,
+}
+
+impl Summary {
+    pub fn new() -> Self {
+        Self {
+            bases: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            sum: 0.0,
+            sum_squares: 0.0,
+        }
+    }
+
+    /// Account for `bases` positions covered by the `value`.
+    #[inline(always)]
+    pub fn add(&mut self, value: f64, bases: u64) {
+        if bases == 0 {
+            return;
+        }
+        self.bases += bases;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value * bases as f64;
+        self.sum_squares += value * value * bases as f64;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bases == 0
+    }
+
+    /// Mean value over covered bases or NaN if there are no covered bases.
+    pub fn mean(&self) -> f64 {
+        if self.bases == 0 {
+            f64::NAN
+        } else {
+            self.sum / self.bases as f64
+        }
+    }
+
+    // Total summary layout: basesCovered (u64), minVal, maxVal, sumData, sumSquares (f64)
+    pub(crate) fn write_total(&self, writer: &mut impl Write) -> Result<()> {
+        let (min, max) = if self.is_empty() {
+            (0.0, 0.0)
+        } else {
+            (self.min, self.max)
+        };
+        write_u64(writer, self.bases)?;
+        write_f64(writer, min)?;
+        write_f64(writer, max)?;
+        write_f64(writer, self.sum)?;
+        write_f64(writer, self.sum_squares)
+    }
+
+    pub(crate) fn read_total(reader: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            bases: read_u64(reader)?,
+            min: read_f64(reader)?,
+            max: read_f64(reader)?,
+            sum: read_f64(reader)?,
+            sum_squares: read_f64(reader)?,
+        })
+    }
+
+    // Zoom record layout: validCount (u32), minVal, maxVal, sumData, sumSquares (f32)
+    pub(crate) fn write_zoom(&self, writer: &mut impl Write) -> Result<()> {
+        write_u32(writer, self.bases as u32)?;
+        write_f32(writer, self.min as f32)?;
+        write_f32(writer, self.max as f32)?;
+        write_f32(writer, self.sum as f32)?;
+        write_f32(writer, self.sum_squares as f32)
+    }
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A summary of the [start, end) bin on the chromosome with the given ID.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ZoomRecord {
+    pub chrom: u32,
+    pub start: u32,
+    pub end: u32,
+    pub summary: Summary,
+}
+
+/// Summarise bedGraph-like records, (start, end, value) per chromosome, into non-overlapping bins
+/// of the given size. Bins without covered bases are omitted.
+pub(crate) fn reduce(
+    records: &[Vec<(u32, u32, f32)>],
+    sizes: &[u32],
+    reduction: u32,
+) -> Vec<ZoomRecord> {
+    debug_assert!(reduction > 0);
+    debug_assert_eq!(records.len(), sizes.len());
+
+    let mut result = Vec::new();
+    for (chrom, (records, &size)) in records.iter().zip(sizes).enumerate() {
+        let mut current: Option<ZoomRecord> = None;
+        for &(start, end, value) in records {
+            let mut pos = start;
+            while pos < end {
+                let bin = pos / reduction;
+                let bin_start = bin * reduction;
+                let bin_end = (bin_start as u64 + reduction as u64).min(size as u64) as u32;
+                let segment_end = end.min(bin_end);
+
+                match &mut current {
+                    Some(record) if record.start == bin_start => {}
+                    _ => {
+                        if let Some(record) = current.take() {
+                            result.push(record);
+                        }
+                        current = Some(ZoomRecord {
+                            chrom: chrom as u32,
+                            start: bin_start,
+                            end: bin_end,
+                            summary: Summary::new(),
+                        });
+                    }
+                }
+                current
+                    .as_mut()
+                    .unwrap()
+                    .summary
+                    .add(value as f64, (segment_end - pos) as u64);
+                pos = segment_end;
+            }
+        }
+        if let Some(record) = current.take() {
+            result.push(record);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reduce() {
+        let records = vec![
+            vec![(0, 5, 1.0), (5, 12, 2.0), (25, 26, -1.0)],
+            vec![],
+            vec![(3, 4, 0.5)],
+        ];
+        let sizes = vec![26, 10, 4];
+
+        let zoom = reduce(&records, &sizes, 10);
+        let summarised = zoom
+            .iter()
+            .map(|x| {
+                (
+                    x.chrom,
+                    x.start,
+                    x.end,
+                    x.summary.bases,
+                    x.summary.min,
+                    x.summary.max,
+                    x.summary.sum,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summarised,
+            vec![
+                (0, 0, 10, 10, 1.0, 2.0, 15.0),
+                (0, 10, 20, 2, 2.0, 2.0, 4.0),
+                (0, 20, 26, 1, -1.0, -1.0, -1.0),
+                (2, 0, 4, 1, 0.5, 0.5, 0.5),
+            ]
+        );
+
+        let mut total = Summary::new();
+        assert!(total.mean().is_nan());
+        total.add(1.0, 2);
+        total.add(4.0, 2);
+        assert_eq!(total.mean(), 2.5);
+        assert_eq!(*total.sum_squares(), 34.0);
+    }
+}
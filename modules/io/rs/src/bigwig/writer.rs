@@ -0,0 +1,331 @@
+use super::binary::*;
+use super::summary::{Summary, ZoomRecord, reduce};
+use super::{BEDGRAPH, HEADER_SIZE, MAGIC, SUMMARY_SIZE, ZOOM_HEADER_SIZE, bptree, cirtree};
+use ahash::HashMap;
+use biobit_collections_rs::rle_vec::{Identical, RleVec};
+use biobit_core_rs::num::PrimUInt;
+use eyre::{OptionExt, Result, ensure, eyre};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use num::ToPrimitive;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const VERSION: u16 = 4;
+const MAX_ZOOM_LEVELS: usize = 10;
+
+/// A bigWig writer. Signal tracks are buffered in memory and the file is laid out (data blocks,
+/// R-tree index, zoom levels, and summary) upon calling [`Writer::finish`].
+pub struct Writer<W: Write + Seek> {
+    writer: W,
+    // Sorted by name, chromosome ID is the position in the vector
+    chroms: Vec<(String, u32)>,
+    ids: HashMap<String, usize>,
+    // (start, end, value) records per chromosome
+    records: Vec<Vec<(u32, u32, f32)>>,
+    skip_zeros: bool,
+    items_per_slot: u32,
+}
+
+impl Writer<BufWriter<File>> {
+    pub fn from_path(path: impl AsRef<Path>, chroms: Vec<(String, u64)>) -> Result<Self> {
+        let file = BufWriter::new(File::create(path.as_ref())?);
+        Writer::new(file, chroms)
+    }
+}
+
+impl<W: Write + Seek> Writer<W> {
+    /// Create a new bigWig writer for the given (name, size) chromosomes.
+    pub fn new(writer: W, chroms: Vec<(String, u64)>) -> Result<Self> {
+        let mut sorted = Vec::with_capacity(chroms.len());
+        for (name, size) in chroms {
+            ensure!(
+                !name.is_empty() && !name.contains(|c: char| c.is_ascii_whitespace() || c == '\0'),
+                "bigWig chromosome name must be non-empty and can't contain whitespace: {name}"
+            );
+            let size = u32::try_from(size)
+                .map_err(|_| eyre!("bigWig chromosome is too large: {name} ({size} bp)"))?;
+            sorted.push((name, size));
+        }
+        sorted.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+        ensure!(
+            sorted.windows(2).all(|w| w[0].0 != w[1].0),
+            "bigWig chromosome names must be unique"
+        );
+
+        let ids = sorted
+            .iter()
+            .enumerate()
+            .map(|(id, (name, _))| (name.clone(), id))
+            .collect();
+        let records = vec![Vec::new(); sorted.len()];
+        Ok(Self {
+            writer,
+            chroms: sorted,
+            ids,
+            records,
+            skip_zeros: true,
+            items_per_slot: 1024,
+        })
+    }
+
+    /// Don't write runs with zero values (default: true).
+    pub fn with_skip_zeros(mut self, skip_zeros: bool) -> Self {
+        self.skip_zeros = skip_zeros;
+        self
+    }
+
+    /// Maximum number of records per data block (default: 1024).
+    pub fn with_items_per_slot(mut self, items_per_slot: u32) -> Result<Self> {
+        ensure!(
+            items_per_slot > 0 && items_per_slot <= u16::MAX as u32,
+            "bigWig items per slot must be in [1, {}]: {items_per_slot}",
+            u16::MAX
+        );
+        self.items_per_slot = items_per_slot;
+        Ok(self)
+    }
+
+    pub fn chroms(&self) -> &[(String, u32)] {
+        &self.chroms
+    }
+
+    /// Add the signal track starting at the given 0-based position of the sequence. Tracks for the
+    /// same sequence must be added in the coordinate order and can't overlap.
+    pub fn add_rle<V, L, I>(&mut self, seqid: &str, start: u64, rle: &RleVec<V, L, I>) -> Result<()>
+    where
+        V: ToPrimitive,
+        L: PrimUInt,
+        I: Identical<V>,
+    {
+        let id = *self
+            .ids
+            .get(seqid)
+            .ok_or_else(|| eyre!("Unknown bigWig chromosome: {seqid}"))?;
+        let size = self.chroms[id].1 as u64;
+        let records = &mut self.records[id];
+
+        if let Some(&(_, end, _)) = records.last() {
+            ensure!(
+                start >= end as u64,
+                "Signal tracks for {seqid} must be added in order and can't overlap: {start} < {end}"
+            );
+        }
+
+        let mut position = start;
+        for (value, length) in rle.runs() {
+            let length = length
+                .to_u64()
+                .ok_or_eyre("RLE run length can't be represented as u64")?;
+            let end = position + length;
+            ensure!(
+                end <= size,
+                "Signal track for {seqid} exceeds the chromosome size: {end} > {size}"
+            );
+
+            let value = value
+                .to_f32()
+                .ok_or_eyre("Signal value can't be represented as f32")?;
+            ensure!(value.is_finite(), "Signal value must be finite: {value}");
+
+            if length > 0 && !(self.skip_zeros && value == 0.0) {
+                match records.last_mut() {
+                    Some(last) if last.1 as u64 == position && last.2 == value => {
+                        last.1 = end as u32;
+                    }
+                    _ => records.push((position as u32, end as u32, value)),
+                }
+            }
+            position = end;
+        }
+        Ok(())
+    }
+
+    /// Write the bigWig file and return the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        let zooms = self.zoom_levels();
+
+        // Placeholders for the header, zoom headers, and the total summary. All offsets are
+        // absolute, hence the file must start at the beginning of the stream.
+        let start = self.writer.stream_position()?;
+        ensure!(
+            start == 0,
+            "bigWig file must start at the beginning of the stream"
+        );
+        write_zeros(
+            &mut self.writer,
+            (HEADER_SIZE + ZOOM_HEADER_SIZE * zooms.len() as u64 + SUMMARY_SIZE) as usize,
+        )?;
+
+        let chrom_tree_offset = self.writer.stream_position()?;
+        bptree::write(&mut self.writer, &self.chroms)?;
+
+        // Full resolution data
+        let full_data_offset = self.writer.stream_position()?;
+        let sections = self
+            .records
+            .iter()
+            .map(|x| x.len().div_ceil(self.items_per_slot as usize) as u64)
+            .sum();
+        write_u64(&mut self.writer, sections)?;
+
+        let mut max_block_size = 0;
+        let mut items = Vec::new();
+        let mut block = Vec::new();
+        for (chrom, records) in self.records.iter().enumerate() {
+            for chunk in records.chunks(self.items_per_slot as usize) {
+                block.clear();
+                let (first, last) = (chunk[0].0, chunk[chunk.len() - 1].1);
+                write_u32(&mut block, chrom as u32)?;
+                write_u32(&mut block, first)?;
+                write_u32(&mut block, last)?;
+                write_u32(&mut block, 0)?;
+                write_u32(&mut block, 0)?;
+                write_u8(&mut block, BEDGRAPH)?;
+                write_u8(&mut block, 0)?;
+                write_u16(&mut block, chunk.len() as u16)?;
+                for &(start, end, value) in chunk {
+                    write_u32(&mut block, start)?;
+                    write_u32(&mut block, end)?;
+                    write_f32(&mut block, value)?;
+                }
+                max_block_size = max_block_size.max(block.len());
+
+                let (offset, size) = write_block(&mut self.writer, &block)?;
+                items.push(cirtree::Item {
+                    start: (chrom as u32, first),
+                    end: (chrom as u32, last),
+                    offset,
+                    size,
+                });
+            }
+        }
+
+        let full_index_offset = self.writer.stream_position()?;
+        cirtree::write(
+            &mut self.writer,
+            &items,
+            self.items_per_slot,
+            full_index_offset,
+        )?;
+
+        // Zoom levels: (reduction, data offset, index offset)
+        let mut headers = Vec::with_capacity(zooms.len());
+        for (reduction, records) in zooms {
+            let data_offset = self.writer.stream_position()?;
+            write_u32(&mut self.writer, records.len() as u32)?;
+
+            items.clear();
+            for chunk in records.chunk_by(|a, b| a.chrom == b.chrom) {
+                for chunk in chunk.chunks(self.items_per_slot as usize) {
+                    block.clear();
+                    for record in chunk {
+                        write_u32(&mut block, record.chrom)?;
+                        write_u32(&mut block, record.start)?;
+                        write_u32(&mut block, record.end)?;
+                        record.summary.write_zoom(&mut block)?;
+                    }
+                    max_block_size = max_block_size.max(block.len());
+
+                    let (offset, size) = write_block(&mut self.writer, &block)?;
+                    items.push(cirtree::Item {
+                        start: (chunk[0].chrom, chunk[0].start),
+                        end: (chunk[0].chrom, chunk[chunk.len() - 1].end),
+                        offset,
+                        size,
+                    });
+                }
+            }
+
+            let index_offset = self.writer.stream_position()?;
+            cirtree::write(&mut self.writer, &items, self.items_per_slot, index_offset)?;
+            headers.push((reduction, data_offset, index_offset));
+        }
+        write_u32(&mut self.writer, MAGIC)?;
+        let eof = self.writer.stream_position()?;
+
+        // Header
+        let summary_offset = HEADER_SIZE + ZOOM_HEADER_SIZE * headers.len() as u64;
+        self.writer.seek(SeekFrom::Start(0))?;
+        write_u32(&mut self.writer, MAGIC)?;
+        write_u16(&mut self.writer, VERSION)?;
+        write_u16(&mut self.writer, headers.len() as u16)?;
+        write_u64(&mut self.writer, chrom_tree_offset)?;
+        write_u64(&mut self.writer, full_data_offset)?;
+        write_u64(&mut self.writer, full_index_offset)?;
+        write_u16(&mut self.writer, 0)?; // fieldCount
+        write_u16(&mut self.writer, 0)?; // definedFieldCount
+        write_u64(&mut self.writer, 0)?; // autoSqlOffset
+        write_u64(&mut self.writer, summary_offset)?;
+        write_u32(&mut self.writer, max_block_size as u32)?;
+        write_u64(&mut self.writer, 0)?; // reserved
+
+        for (reduction, data_offset, index_offset) in headers {
+            write_u32(&mut self.writer, reduction)?;
+            write_u32(&mut self.writer, 0)?;
+            write_u64(&mut self.writer, data_offset)?;
+            write_u64(&mut self.writer, index_offset)?;
+        }
+
+        let mut summary = Summary::new();
+        for &(start, end, value) in self.records.iter().flatten() {
+            summary.add(value as f64, (end - start) as u64);
+        }
+        summary.write_total(&mut self.writer)?;
+
+        self.writer.seek(SeekFrom::Start(eof))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    // Pre-compute zoom levels starting from 10x the average record length and increasing the
+    // reduction 4x at each level until the number of records stops decreasing.
+    fn zoom_levels(&self) -> Vec<(u32, Vec<ZoomRecord>)> {
+        let (count, bases) = self
+            .records
+            .iter()
+            .flatten()
+            .fold((0u64, 0u64), |(count, bases), (start, end, _)| {
+                (count + 1, bases + (end - start) as u64)
+            });
+        if count == 0 {
+            return Vec::new();
+        }
+
+        let sizes = self.chroms.iter().map(|x| x.1).collect::<Vec<_>>();
+        let mut reduction = (bases / count * 10).clamp(1, u32::MAX as u64) as u32;
+        let mut previous = count as usize;
+
+        let mut levels = Vec::new();
+        while levels.len() < MAX_ZOOM_LEVELS {
+            let zoom = reduce(&self.records, &sizes, reduction);
+            if zoom.len() >= previous {
+                break;
+            }
+            previous = zoom.len();
+            levels.push((reduction, zoom));
+
+            let chroms = self.records.iter().filter(|x| !x.is_empty()).count();
+            if previous <= chroms {
+                break;
+            }
+            match reduction.checked_mul(4) {
+                Some(next) => reduction = next,
+                None => break,
+            }
+        }
+        levels
+    }
+}
+
+// Compress and write the block, returning its (offset, size) in the file
+fn write_block(writer: &mut (impl Write + Seek), block: &[u8]) -> Result<(u64, u64)> {
+    let mut encoder = ZlibEncoder::new(Vec::with_capacity(block.len()), Compression::default());
+    encoder.write_all(block)?;
+    let compressed = encoder.finish()?;
+
+    let offset = writer.stream_position()?;
+    writer.write_all(&compressed)?;
+    Ok((offset, compressed.len() as u64))
+}
@@ -0,0 +1,539 @@
+use ahash::HashSet;
+use derive_getters::{Dissolve, Getters};
+use eyre::{Result, bail, ensure, eyre};
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use substratum_compress::Decoder;
+
+// Maximum number of uncompressed bytes in a single BGZF block (same as in htslib)
+const BGZF_BLOCK_SIZE: usize = 0xff00;
+
+// Empty BGZF block marking the end of the file
+const BGZF_EOF: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// A single line of the .fai index.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Dissolve, Getters)]
+pub struct FaiRecord {
+    name: String,
+    length: u64,
+    offset: u64,
+    bases_per_line: u64,
+    bytes_per_line: u64,
+}
+
+/// An index of a FASTA file: the .fai records and, for BGZF-compressed files, the .gzi block
+/// offsets as (compressed, uncompressed) pairs. The first block, starting at (0, 0), is implicit.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Dissolve, Getters)]
+pub struct Index {
+    fai: Vec<FaiRecord>,
+    gzi: Option<Vec<(u64, u64)>>,
+}
+
+impl Index {
+    /// Index the FASTA file at the given path. Only uncompressed and BGZF-compressed files can be
+    /// indexed, see [`bgzip`] to recompress gzip files.
+    pub fn from_path(path: impl AsRef<Path>, compression: &Decoder) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        match compression {
+            Decoder::Identity(_) => Self::from_fasta(file),
+            Decoder::Bgzf(_) => Self::from_bgzf(file),
+            _ => Err(eyre!(
+                "Unsupported compression {:?} for an indexed FASTA file (recompress it with BGZF): {}",
+                compression,
+                path.display()
+            )),
+        }
+    }
+
+    /// Index an uncompressed FASTA stream.
+    pub fn from_fasta(reader: impl Read) -> Result<Self> {
+        Ok(Self {
+            fai: fai(BufReader::new(reader))?,
+            gzi: None,
+        })
+    }
+
+    /// Index a BGZF-compressed FASTA stream. Offsets in the .fai are relative to the uncompressed
+    /// data, as expected by samtools and htslib.
+    pub fn from_bgzf(reader: impl Read) -> Result<Self> {
+        let mut blocks = BgzfBlocks::new(reader);
+        let fai = fai(BufReader::new(&mut blocks))?;
+        Ok(Self {
+            fai,
+            gzi: Some(blocks.gzi),
+        })
+    }
+
+    /// Write the .fai index in the samtools format.
+    pub fn write_fai(&self, writer: &mut impl Write) -> Result<()> {
+        for record in &self.fai {
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}\t{}",
+                record.name,
+                record.length,
+                record.offset,
+                record.bases_per_line,
+                record.bytes_per_line
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write the .gzi index in the htslib format. Fails if the index is not for a BGZF file.
+    pub fn write_gzi(&self, writer: &mut impl Write) -> Result<()> {
+        let gzi = self
+            .gzi
+            .as_ref()
+            .ok_or_else(|| eyre!("gzi index is available only for BGZF-compressed files"))?;
+        writer.write_all(&(gzi.len() as u64).to_le_bytes())?;
+        for (compressed, uncompressed) in gzi {
+            writer.write_all(&compressed.to_le_bytes())?;
+            writer.write_all(&uncompressed.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Save the index next to the FASTA file, i.e., as `{fasta}.fai` and `{fasta}.gzi`. Files are
+    /// written atomically, concurrent saves of the same index are safe.
+    pub fn save(&self, fasta: impl AsRef<Path>) -> Result<()> {
+        let fasta = fasta.as_ref();
+
+        let mut fai = Vec::new();
+        self.write_fai(&mut fai)?;
+        persist(&index_path(fasta, "fai"), &fai)?;
+
+        if self.gzi.is_some() {
+            let mut gzi = Vec::new();
+            self.write_gzi(&mut gzi)?;
+            persist(&index_path(fasta, "gzi"), &gzi)?;
+        }
+        Ok(())
+    }
+}
+
+/// Recompress the (decoded) FASTA stream with BGZF and index it on the fly.
+pub fn bgzip(reader: impl Read, writer: impl Write) -> Result<Index> {
+    let mut recompress = Recompress {
+        reader,
+        writer,
+        buffer: Vec::with_capacity(BGZF_BLOCK_SIZE),
+        coffset: 0,
+        uoffset: 0,
+        gzi: Vec::new(),
+    };
+    let fai = fai(BufReader::new(&mut recompress))?;
+    recompress.writer.write_all(&BGZF_EOF)?;
+    recompress.writer.flush()?;
+
+    Ok(Index {
+        fai,
+        gzi: Some(recompress.gzi),
+    })
+}
+
+/// Path of the index with the given extension for the FASTA file, e.g. `genome.fa.gz.fai`.
+pub(crate) fn index_path(fasta: &Path, extension: &str) -> PathBuf {
+    let mut name = fasta.as_os_str().to_os_string();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+// Write to a temporary file first and rename it to avoid exposing partially written indexes
+fn persist(path: &Path, content: &[u8]) -> Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let tmp = index_path(
+        path,
+        &format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ),
+    );
+    let result = std::fs::write(&tmp, content).and_then(|_| std::fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    Ok(result?)
+}
+
+// Build .fai records following the samtools conventions: sequence names end at the first
+// whitespace, and all lines of a sequence except the last one must have the same length.
+// Empty sequences are indexed with zero line lengths.
+fn fai(mut reader: impl BufRead) -> Result<Vec<FaiRecord>> {
+    let mut records: Vec<FaiRecord> = Vec::new();
+    let mut names = HashSet::default();
+
+    let mut line = Vec::new();
+    let mut offset = 0u64;
+    // The last line of the current sequence was observed (it was shorter than the others)
+    let mut finished = false;
+    loop {
+        line.clear();
+        let bytes = reader.read_until(b'\n', &mut line)? as u64;
+        if bytes == 0 {
+            break;
+        }
+        offset += bytes;
+
+        let terminated = line.last() == Some(&b'\n');
+        let content = line.strip_suffix(b"\n").unwrap_or(&line);
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+
+        if content.first() == Some(&b'>') {
+            let name = content[1..]
+                .split(|x| x.is_ascii_whitespace())
+                .next()
+                .unwrap_or_default();
+            let name = String::from_utf8(name.to_vec())?;
+            ensure!(
+                !name.is_empty(),
+                "Empty FASTA sequence name at offset {}",
+                offset - bytes
+            );
+            ensure!(
+                names.insert(name.clone()),
+                "Duplicate FASTA sequence name: {name}"
+            );
+
+            records.push(FaiRecord {
+                name,
+                length: 0,
+                offset,
+                bases_per_line: 0,
+                bytes_per_line: 0,
+            });
+            finished = false;
+            continue;
+        }
+
+        let record = match records.last_mut() {
+            Some(record) => record,
+            None if content.is_empty() => continue,
+            None => bail!("Expected '>' at the start of the FASTA file"),
+        };
+        let bases = content.len() as u64;
+        // Unterminated last line is indexed as if it had a single-byte line ending
+        let bytes = if terminated { bytes } else { bytes + 1 };
+
+        if record.length == 0 {
+            // Empty sequences (samtools indexes them with zero line lengths) can end with blank lines
+            if bases == 0 {
+                finished = true;
+                continue;
+            }
+            ensure!(
+                !finished,
+                "Empty line at the start of the FASTA sequence {}",
+                record.name
+            );
+            record.bases_per_line = bases;
+            record.bytes_per_line = bytes;
+        } else if (finished && bases > 0)
+            || bases > record.bases_per_line
+            || (bases == record.bases_per_line && bytes != record.bytes_per_line)
+        {
+            bail!(
+                "Different line lengths in the FASTA sequence {}, can't index it",
+                record.name
+            );
+        }
+        finished |= bases < record.bases_per_line;
+        record.length += bases;
+    }
+
+    Ok(records)
+}
+
+// Deflate the data and write it as a single BGZF block, returns the size of the block
+fn write_block(writer: &mut impl Write, data: &[u8]) -> Result<u64> {
+    let mut encoder = DeflateEncoder::new(Vec::with_capacity(data.len()), Compression::default());
+    encoder.write_all(data)?;
+    let cdata = encoder.finish()?;
+
+    let size = 18 + cdata.len() + 8;
+    ensure!(
+        size <= u16::MAX as usize + 1,
+        "BGZF block is too large: {size}"
+    );
+    let mut crc = flate2::Crc::new();
+    crc.update(data);
+
+    writer.write_all(&BGZF_EOF[..16])?;
+    writer.write_all(&((size - 1) as u16).to_le_bytes())?;
+    writer.write_all(&cdata)?;
+    writer.write_all(&crc.sum().to_le_bytes())?;
+    writer.write_all(&(data.len() as u32).to_le_bytes())?;
+    Ok(size as u64)
+}
+
+// Passes the data through while writing it as BGZF blocks and recording their offsets
+struct Recompress<R, W> {
+    reader: R,
+    writer: W,
+    buffer: Vec<u8>,
+    coffset: u64,
+    uoffset: u64,
+    gzi: Vec<(u64, u64)>,
+}
+
+impl<R: Read, W: Write> Recompress<R, W> {
+    fn flush_block(&mut self, size: usize) -> Result<()> {
+        if self.coffset > 0 {
+            self.gzi.push((self.coffset, self.uoffset));
+        }
+        self.coffset += write_block(&mut self.writer, &self.buffer[..size])?;
+        self.uoffset += size as u64;
+        self.buffer.drain(..size);
+        Ok(())
+    }
+}
+
+impl<R: Read, W: Write> Read for Recompress<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = self.reader.read(buf)?;
+        self.buffer.extend_from_slice(&buf[..size]);
+
+        let result = if size == 0 && !self.buffer.is_empty() {
+            self.flush_block(self.buffer.len())
+        } else if self.buffer.len() >= BGZF_BLOCK_SIZE {
+            self.flush_block(BGZF_BLOCK_SIZE)
+        } else {
+            Ok(())
+        };
+        result.map_err(std::io::Error::other)?;
+        Ok(size)
+    }
+}
+
+// Reads BGZF blocks one by one and records their offsets for the .gzi index
+struct BgzfBlocks<R> {
+    reader: R,
+    block: Vec<u8>,
+    cdata: Vec<u8>,
+    consumed: usize,
+    coffset: u64,
+    uoffset: u64,
+    gzi: Vec<(u64, u64)>,
+}
+
+impl<R: Read> BgzfBlocks<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            block: Vec::new(),
+            cdata: Vec::new(),
+            consumed: 0,
+            coffset: 0,
+            uoffset: 0,
+            gzi: Vec::new(),
+        }
+    }
+
+    // Load the next block, returns false at the end of the stream
+    fn next_block(&mut self) -> Result<bool> {
+        let mut header = [0u8; 12];
+        match self.reader.read(&mut header[..1])? {
+            0 => return Ok(false),
+            _ => self.reader.read_exact(&mut header[1..])?,
+        };
+        ensure!(
+            header[..4] == BGZF_EOF[..4],
+            "Invalid BGZF block header at offset {}",
+            self.coffset
+        );
+
+        // Find the BSIZE in the extra subfields
+        let xlen = u16::from_le_bytes([header[10], header[11]]) as usize;
+        let mut extra = vec![0u8; xlen];
+        self.reader.read_exact(&mut extra)?;
+        let mut bsize = None;
+        let mut fields = extra.as_slice();
+        while fields.len() >= 4 {
+            let length = u16::from_le_bytes([fields[2], fields[3]]) as usize;
+            if fields[..2] == *b"BC" && length == 2 && fields.len() >= 6 {
+                bsize = Some(u16::from_le_bytes([fields[4], fields[5]]) as usize + 1);
+            }
+            fields = &fields[(4 + length).min(fields.len())..];
+        }
+        let bsize = bsize.ok_or_else(|| {
+            eyre!(
+                "Missing BSIZE field in the BGZF block at offset {}",
+                self.coffset
+            )
+        })?;
+        ensure!(
+            bsize >= 12 + xlen + 8,
+            "Invalid BGZF block size at offset {}",
+            self.coffset
+        );
+
+        self.cdata.resize(bsize - 12 - xlen, 0);
+        self.reader.read_exact(&mut self.cdata)?;
+        let (cdata, footer) = self.cdata.split_at(self.cdata.len() - 8);
+        let isize = u32::from_le_bytes(footer[4..].try_into()?) as usize;
+
+        self.block.clear();
+        self.block.reserve(isize);
+        DeflateDecoder::new(cdata).read_to_end(&mut self.block)?;
+        ensure!(
+            self.block.len() == isize,
+            "Corrupted BGZF block at offset {}",
+            self.coffset
+        );
+        let mut crc = flate2::Crc::new();
+        crc.update(&self.block);
+        ensure!(
+            crc.sum().to_le_bytes() == footer[..4],
+            "CRC mismatch in the BGZF block at offset {}",
+            self.coffset
+        );
+
+        // Empty blocks (e.g. the EOF marker) are not included in the .gzi
+        if self.coffset > 0 && !self.block.is_empty() {
+            self.gzi.push((self.coffset, self.uoffset));
+        }
+        self.coffset += bsize as u64;
+        self.uoffset += self.block.len() as u64;
+        self.consumed = 0;
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for BgzfBlocks<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.consumed == self.block.len() {
+            if !self.next_block().map_err(std::io::Error::other)? {
+                return Ok(0);
+            }
+        }
+        let size = buf.len().min(self.block.len() - self.consumed);
+        buf[..size].copy_from_slice(&self.block[self.consumed..self.consumed + size]);
+        self.consumed += size;
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn resource(name: &str) -> PathBuf {
+        PathBuf::from(env!("BIOBIT_RESOURCES"))
+            .join("fasta")
+            .join(name)
+    }
+
+    #[test]
+    fn test_index_matches_samtools() -> Result<()> {
+        for (fasta, gzi) in [
+            ("indexed.fa", None),
+            ("indexed.fa.bgz", Some("indexed.fa.bgz.gzi")),
+        ] {
+            let path = resource(fasta);
+            let index =
+                Index::from_path(&path, &Decoder::from_path(&path, super::super::EXTENSIONS)?)?;
+
+            let mut fai = Vec::new();
+            index.write_fai(&mut fai)?;
+            assert_eq!(fai, std::fs::read(index_path(&path, "fai"))?, "{fasta}");
+
+            match gzi {
+                Some(gzi) => {
+                    let mut produced = Vec::new();
+                    index.write_gzi(&mut produced)?;
+                    assert_eq!(produced, std::fs::read(resource(gzi))?);
+                }
+                None => assert!(index.write_gzi(&mut Vec::new()).is_err()),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_fai_line_layout() -> Result<()> {
+        type Fai = Vec<(String, u64, u64, u64, u64)>;
+        let fai =
+            |x: &[u8]| -> Result<Fai> { Ok(fai(x)?.into_iter().map(|x| x.dissolve()).collect()) };
+
+        assert_eq!(
+            fai(b">a desc\nACGT\nAC\n>b\r\nAC\r\nA\r\n\n>c\nACG")?,
+            vec![
+                ("a".to_string(), 6, 8, 4, 5),
+                ("b".to_string(), 3, 20, 2, 4),
+                ("c".to_string(), 3, 31, 3, 4),
+            ]
+        );
+
+        // Empty sequences are accepted, as by samtools faidx
+        assert_eq!(
+            fai(b">a\n>b\nAC\n>c\n\n>d")?,
+            vec![
+                ("a".to_string(), 0, 3, 0, 0),
+                ("b".to_string(), 2, 6, 2, 3),
+                ("c".to_string(), 0, 12, 0, 0),
+                ("d".to_string(), 0, 15, 0, 0),
+            ]
+        );
+
+        for invalid in [
+            b">a\nAC\nACG\n".as_slice(),
+            b">a\nACG\nA\nACG\n",
+            b">a\nACG\r\nACG\n",
+            b">a\nA\n>a\nA\n",
+            b"ACGT\n>a\nA\n",
+            b">\nA\n",
+            b">a\n\nACG\n",
+        ] {
+            assert!(
+                fai(invalid).is_err(),
+                "{}",
+                String::from_utf8_lossy(invalid)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_bgzip() -> Result<()> {
+        let mut fasta = Vec::new();
+        for i in 0..2_000 {
+            writeln!(fasta, ">seq{i} description")?;
+            for j in 0..(i % 7 + 1) {
+                fasta.extend(b"ACGTNacgtn".iter().cycle().skip(i + j).take(60));
+                fasta.push(b'\n');
+            }
+        }
+        assert!(fasta.len() > 2 * BGZF_BLOCK_SIZE);
+
+        let mut compressed = Vec::new();
+        let index = bgzip(fasta.as_slice(), &mut compressed)?;
+        assert_eq!(index.fai, Index::from_fasta(fasta.as_slice())?.fai);
+
+        let gzi = index.gzi.as_ref().unwrap();
+        assert_eq!(gzi.len(), fasta.len() / BGZF_BLOCK_SIZE);
+        for (ind, (_, uncompressed)) in gzi.iter().enumerate() {
+            assert_eq!(*uncompressed, ((ind + 1) * BGZF_BLOCK_SIZE) as u64);
+        }
+
+        // Scanning the produced file must yield the same index and the original data
+        let mut blocks = BgzfBlocks::new(Cursor::new(&compressed));
+        let mut decompressed = Vec::new();
+        blocks.read_to_end(&mut decompressed)?;
+        assert_eq!(decompressed, fasta);
+        assert_eq!(&blocks.gzi, gzi);
+        assert_eq!(Index::from_bgzf(Cursor::new(&compressed))?, index);
+        Ok(())
+    }
+}
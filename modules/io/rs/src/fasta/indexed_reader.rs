@@ -1,3 +1,4 @@
+use super::normalize::{self, Normalization};
 use biobit_core_rs::loc::{Interval, IntervalOp};
 use biobit_core_rs::num::PrimInt;
 use derive_getters::{Dissolve, Getters};
@@ -17,6 +18,33 @@ pub trait IndexedReaderMutOp {
 
     /// Fetch the full reference sequence with the given ID.
     fn fetch_full_seq(&mut self, seqid: &str, buffer: &mut Vec<u8>) -> Result<()>;
+
+    /// Fetch the sequence for the given reference sequence ID and interval and normalize it.
+    fn fetch_normalized(
+        &mut self,
+        seqid: &str,
+        interval: Interval<u64>,
+        normalization: &Normalization,
+        buffer: &mut Vec<u8>,
+    ) -> Result<()> {
+        self.fetch(seqid, interval, buffer)?;
+        normalization.apply(buffer);
+        Ok(())
+    }
+
+    /// Fetch soft-masked (lowercase) repeats within the given interval of the reference sequence.
+    /// Returned intervals are in the reference sequence coordinates and clipped to the interval.
+    fn fetch_repeat_mask(
+        &mut self,
+        seqid: &str,
+        interval: Interval<u64>,
+        into: &mut Vec<Interval<u64>>,
+    ) -> Result<()> {
+        let mut buffer = Vec::with_capacity(interval.len() as usize);
+        self.fetch(seqid, interval, &mut buffer)?;
+        normalize::soft_masked(&buffer, interval.start(), into);
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Dissolve, Getters)]
@@ -79,11 +107,6 @@ impl<R: Read + Seek> IndexedReader<R> {
                     .ok_or_else(err)?
                     .parse::<u64>()
                     .wrap_err_with(err)?;
-                ensure!(
-                    length > 0,
-                    "Length of the reference sequence must be greater than zero, line: {}",
-                    buffer
-                );
                 lengths.push(length);
 
                 let offset = parts
@@ -103,8 +126,9 @@ impl<R: Read + Seek> IndexedReader<R> {
                     .ok_or_else(err)?
                     .parse::<u64>()
                     .wrap_err_with(err)?;
+                // Empty sequences are indexed with zero line lengths by samtools
                 ensure!(
-                    _bases_per_line > 0,
+                    _bases_per_line > 0 || length == 0,
                     "Bases per line must be greater than zero, line: {}",
                     buffer
                 );
@@ -117,7 +141,7 @@ impl<R: Read + Seek> IndexedReader<R> {
                     .parse::<u64>()
                     .wrap_err_with(err)?;
                 ensure!(
-                    _bytes_per_line > _bases_per_line,
+                    _bytes_per_line > _bases_per_line || length == 0,
                     "Bytes per line must be greater than bases per line, line: {}",
                     buffer
                 );
@@ -285,7 +309,12 @@ impl<R: Read + Seek> IndexedReader<R> {
             .get(seqid)
             .ok_or_else(|| eyre!("Reference sequence ID not found in the index: {}", seqid))?;
 
-        let interval = Interval::new(0, self.lengths[*index])?;
+        let length = self.lengths[*index];
+        if length == 0 {
+            buffer.clear();
+            return Ok(());
+        }
+        let interval = Interval::new(0, length)?;
         self.fetch_interval(seqid, &interval, buffer)
     }
 }
@@ -312,6 +341,7 @@ impl<R: Read + Seek> IndexedReaderMutOp for IndexedReader<R> {
 mod tests {
     use super::*;
     use crate::fasta::IndexedSources;
+    use std::io::Cursor;
     use std::path::PathBuf;
     use substratum_compress::Decoder;
 
@@ -524,6 +554,34 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_empty_sequences() -> Result<()> {
+        let fasta = b">empty\n>seq\nACGT\nAC\n>last\n".to_vec();
+        let mut fai = Vec::new();
+        crate::fasta::Index::from_fasta(fasta.as_slice())?.write_fai(&mut fai)?;
+        let mut reader = IndexedReader::new(vec![(Cursor::new(fasta), Cursor::new(fai))])?;
+
+        let lengths = IndexedReaderMutOp::lengths(&reader);
+        assert_eq!(
+            (lengths["empty"], lengths["seq"], lengths["last"]),
+            (0, 6, 0)
+        );
+
+        let mut buffer = b"N".to_vec();
+        reader.fetch_full_seq("empty", &mut buffer)?;
+        assert!(buffer.is_empty());
+        reader.fetch_full_seq("seq", &mut buffer)?;
+        assert_eq!(buffer, b"ACGTAC");
+        reader.fetch_full_seq("last", &mut buffer)?;
+        assert!(buffer.is_empty());
+        assert!(
+            reader
+                .fetch_interval("empty", &Interval::new(0, 1)?, &mut buffer)
+                .is_err()
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_multiple_indexed_fa() -> Result<()> {
         let mut indexed = Vec::new();
@@ -1,29 +1,38 @@
+use biobit_core_rs::loc::Interval;
 use eyre::{Result, ensure, eyre};
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
 use substratum_compress::Decoder;
 
+use super::index::{self, Index};
 use super::indexed_reader::{IndexedRead, IndexedReader, IndexedReaderMutOp};
+use crate::twobit;
 
 /// Reusable description of indexed FASTA input(s) that can open fresh readers on demand.
 ///
 /// Construction only records paths and compression metadata. File and index validation happens in
-/// [`IndexedSources::open`].
+/// [`IndexedSources::open`]. Besides indexed FASTA files, uncompressed .2bit files are supported.
+///
+/// By default, missing .fai/.gzi indexes are built on the first use and saved next to the FASTA
+/// file. If the directory is not writable, the index is kept in memory and rebuilt on each open.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct IndexedSources {
     sources: Vec<(PathBuf, Decoder)>,
+    autoindex: bool,
 }
 
 impl IndexedSources {
     pub fn new(sources: Vec<(PathBuf, Decoder)>) -> Self {
-        Self { sources }
+        Self {
+            sources,
+            autoindex: true,
+        }
     }
 
     pub fn from_path(fasta: impl AsRef<Path>, compression: Decoder) -> Self {
-        Self {
-            sources: vec![(fasta.as_ref().to_path_buf(), compression)],
-        }
+        Self::new(vec![(fasta.as_ref().to_path_buf(), compression)])
     }
 
     pub fn from_paths(indexed: &[(impl AsRef<Path>, Decoder)]) -> Self {
@@ -31,54 +40,172 @@ impl IndexedSources {
             .iter()
             .map(|(fasta, compression)| (fasta.as_ref().to_path_buf(), *compression))
             .collect();
-        Self { sources }
+        Self::new(sources)
+    }
+
+    /// Build missing FASTA indexes on the fly instead of failing.
+    pub fn with_autoindex(mut self, autoindex: bool) -> Self {
+        self.autoindex = autoindex;
+        self
     }
 
     pub fn sources(&self) -> &[(PathBuf, Decoder)] {
         &self.sources
     }
 
+    pub fn autoindex(&self) -> bool {
+        self.autoindex
+    }
+
+    /// Open a reader over all sources. Paths with the .2bit extension are read directly with the
+    /// [`twobit::Reader`], they must be uncompressed and don't need a FASTA index.
     pub fn open(&self) -> Result<Box<dyn IndexedReaderMutOp + Send + Sync + 'static>> {
         let mut parsed: Vec<(Box<dyn IndexedRead>, _)> = Vec::with_capacity(self.sources.len());
+        let mut twobits = Vec::new();
 
         for (fasta, compression) in &self.sources {
-            let mut path = fasta.clone();
-            let file = File::open(&path)?;
-
-            let fname = path
-                .file_name()
-                .and_then(|x| x.to_str())
-                .unwrap_or_default()
-                .to_string();
-            path.set_file_name(format!("{fname}.fai"));
-            ensure!(path.exists(), "fai index does not exist: {:?}", path);
-            let fai = BufReader::new(File::open(&path)?);
-
-            match compression {
-                Decoder::Identity(_) => {
-                    parsed.push((Box::new(file), fai));
-                }
-                Decoder::Bgzf(_) => {
-                    path.set_file_name(format!("{fname}.gzi"));
-                    ensure!(path.exists(), "gzi index does not exist: {:?}", path);
-                    let gzi = noodles::bgzf::gzi::fs::read(&path)?;
+            if is_twobit(fasta) {
+                ensure!(
+                    matches!(compression, Decoder::Identity(_)),
+                    "Unsupported compression {:?} for a .2bit file: {}",
+                    compression,
+                    fasta.display()
+                );
+                twobits.push(twobit::Reader::from_path(fasta)?);
+                continue;
+            }
 
+            let file = File::open(fasta)?;
+            let (fai, gzi) = self.load_index(fasta, compression)?;
+            match gzi {
+                None => parsed.push((Box::new(file), fai)),
+                Some(gzi) => {
                     let reader = Box::new(noodles::bgzf::io::indexed_reader::IndexedReader::new(
                         file, gzi,
                     ));
                     parsed.push((reader, fai));
                 }
-                _ => {
+            }
+        }
+
+        if twobits.is_empty() {
+            return Ok(Box::new(IndexedReader::new(parsed)?));
+        }
+
+        let mut readers: Vec<Box<dyn IndexedReaderMutOp + Send + Sync>> = Vec::new();
+        if !parsed.is_empty() {
+            readers.push(Box::new(IndexedReader::new(parsed)?));
+        }
+        for reader in twobits {
+            readers.push(Box::new(reader));
+        }
+        if readers.len() == 1 {
+            return Ok(readers.pop().unwrap());
+        }
+        Ok(Box::new(Chained::new(readers)?))
+    }
+
+    // Read the .fai (and .gzi for BGZF files) next to the FASTA file or build them if missing
+    fn load_index(
+        &self,
+        fasta: &Path,
+        compression: &Decoder,
+    ) -> Result<(Cursor<Vec<u8>>, Option<noodles::bgzf::gzi::Index>)> {
+        let bgzf = match compression {
+            Decoder::Identity(_) => false,
+            Decoder::Bgzf(_) => true,
+            _ => {
+                return Err(eyre!(
+                    "Unsupported compression {:?} for an Indexed FASTA file: {}",
+                    compression,
+                    fasta.display()
+                ));
+            }
+        };
+        let (fai, gzi) = (
+            index::index_path(fasta, "fai"),
+            index::index_path(fasta, "gzi"),
+        );
+
+        if fai.exists() && (!bgzf || gzi.exists()) {
+            let gzi = if bgzf {
+                Some(noodles::bgzf::gzi::fs::read(&gzi)?)
+            } else {
+                None
+            };
+            return Ok((Cursor::new(std::fs::read(&fai)?), gzi));
+        }
+
+        ensure!(
+            self.autoindex,
+            "{} index does not exist: {:?}",
+            if fai.exists() { "gzi" } else { "fai" },
+            if fai.exists() { gzi } else { fai }
+        );
+        let index = Index::from_path(fasta, compression)?;
+        if let Err(err) = index.save(fasta) {
+            log::warn!(
+                "Failed to save the index for {}, keeping it in memory: {}",
+                fasta.display(),
+                err
+            );
+        }
+
+        let mut fai = Vec::new();
+        index.write_fai(&mut fai)?;
+        let gzi = index.dissolve().1.map(|x| x.into());
+        Ok((Cursor::new(fai), gzi))
+    }
+}
+
+fn is_twobit(path: &Path) -> bool {
+    path.extension()
+        .and_then(|x| x.to_str())
+        .is_some_and(|x| twobit::EXTENSIONS.contains(&x))
+}
+
+// Dispatches fetches to one of several readers based on the reference sequence ID.
+struct Chained {
+    readers: Vec<Box<dyn IndexedReaderMutOp + Send + Sync>>,
+    index: HashMap<String, usize>,
+}
+
+impl Chained {
+    fn new(readers: Vec<Box<dyn IndexedReaderMutOp + Send + Sync>>) -> Result<Self> {
+        let mut index = HashMap::new();
+        for (ind, reader) in readers.iter().enumerate() {
+            for seqid in reader.lengths().into_keys() {
+                if index.insert(seqid.clone(), ind).is_some() {
                     return Err(eyre!(
-                        "Unsupported compression {:?} for an Indexed FASTA file: {}",
-                        compression,
-                        fasta.display()
+                        "Duplicate reference sequence ID in the indexed sources: {}",
+                        seqid
                     ));
                 }
-            };
+            }
         }
+        Ok(Self { readers, index })
+    }
+
+    fn reader(&mut self, seqid: &str) -> Result<&mut Box<dyn IndexedReaderMutOp + Send + Sync>> {
+        let ind = self
+            .index
+            .get(seqid)
+            .ok_or_else(|| eyre!("Reference sequence ID not found in the index: {}", seqid))?;
+        Ok(&mut self.readers[*ind])
+    }
+}
+
+impl IndexedReaderMutOp for Chained {
+    fn lengths(&self) -> HashMap<String, u64> {
+        self.readers.iter().flat_map(|x| x.lengths()).collect()
+    }
+
+    fn fetch(&mut self, seqid: &str, interval: Interval<u64>, buffer: &mut Vec<u8>) -> Result<()> {
+        self.reader(seqid)?.fetch(seqid, interval, buffer)
+    }
 
-        Ok(Box::new(IndexedReader::new(parsed)?))
+    fn fetch_full_seq(&mut self, seqid: &str, buffer: &mut Vec<u8>) -> Result<()> {
+        self.reader(seqid)?.fetch_full_seq(seqid, buffer)
     }
 }
 
@@ -98,4 +225,140 @@ mod tests {
         assert_eq!(sources.sources()[0].0.as_path(), Path::new("missing.fa"));
         assert!(sources.open().is_err());
     }
+
+    #[test]
+    fn open_fasta_and_twobit() -> Result<()> {
+        let fasta = PathBuf::from(env!("BIOBIT_RESOURCES"))
+            .join("fasta")
+            .join("indexed.fa");
+        let twobit = std::env::temp_dir().join(format!("biobit-{}.2bit", std::process::id()));
+
+        let mut writer = twobit::Writer::from_path(&twobit)?;
+        writer.add_record(&crate::fasta::Record::new(
+            "chrT".to_string(),
+            b"ACGTNNacgt".to_vec(),
+        )?)?;
+        writer.finish()?;
+
+        let sources = IndexedSources::from_paths(&[
+            (
+                fasta.clone(),
+                Decoder::from_path(&fasta, crate::fasta::EXTENSIONS)?,
+            ),
+            (twobit.clone(), Decoder::default()),
+        ]);
+        let result = (|| -> Result<()> {
+            let mut reader = sources.open()?;
+            assert_eq!(reader.lengths().len(), 9);
+
+            let mut buffer = Vec::new();
+            reader.fetch("chrT", Interval::new(3, 9)?, &mut buffer)?;
+            assert_eq!(buffer, b"TNNacg");
+            reader.fetch(
+                "sp|Q9Y572|RIPK3_HUMAN",
+                Interval::new(510, 518)?,
+                &mut buffer,
+            )?;
+            assert_eq!(buffer, b"GWYNHSGK");
+            assert!(
+                reader
+                    .fetch("chrX", Interval::new(0, 1)?, &mut buffer)
+                    .is_err()
+            );
+
+            let normalization = crate::fasta::Normalization::new().with_masked_repeats(true);
+            reader.fetch_normalized("chrT", Interval::new(0, 10)?, &normalization, &mut buffer)?;
+            assert_eq!(buffer, b"ACGTNNNNNN");
+
+            let mut repeats = Vec::new();
+            reader.fetch_repeat_mask("chrT", Interval::new(2, 9)?, &mut repeats)?;
+            assert_eq!(repeats, vec![Interval::new(6, 9)?]);
+            Ok(())
+        })();
+        std::fs::remove_file(&twobit)?;
+        result
+    }
+
+    #[test]
+    fn open_twobit() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("biobit-open-2bit-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+
+        let result = (|| -> Result<()> {
+            let twobit = dir.join("genome.2bit");
+            let mut writer = twobit::Writer::from_path(&twobit)?;
+            for (seqid, seq) in [
+                ("chr1", b"ACGTNNacgt".as_slice()),
+                ("chr2", b"TTAG".as_slice()),
+            ] {
+                writer.add_record(&crate::fasta::Record::new(seqid.to_string(), seq.to_vec())?)?;
+            }
+            writer.finish()?;
+
+            // .2bit files are opened without building a FASTA index
+            let mut reader = IndexedSources::from_path(&twobit, Decoder::default())
+                .with_autoindex(false)
+                .open()?;
+            assert_eq!(
+                reader.lengths(),
+                HashMap::from([("chr1".to_string(), 10), ("chr2".to_string(), 4)])
+            );
+
+            let mut buffer = Vec::new();
+            reader.fetch("chr1", Interval::new(2, 8)?, &mut buffer)?;
+            assert_eq!(buffer, b"GTNNac");
+            reader.fetch_full_seq("chr2", &mut buffer)?;
+            assert_eq!(buffer, b"TTAG");
+            assert!(!index::index_path(&twobit, "fai").exists());
+
+            // Compressed .2bit files are rejected
+            let bgzf = PathBuf::from(env!("BIOBIT_RESOURCES")).join("fasta/indexed.fa.bgz");
+            let bgzf = Decoder::from_path(&bgzf, crate::fasta::EXTENSIONS)?;
+            assert!(IndexedSources::from_path(&twobit, bgzf).open().is_err());
+            Ok(())
+        })();
+        std::fs::remove_dir_all(&dir)?;
+        result
+    }
+
+    #[test]
+    fn open_builds_missing_indexes() -> Result<()> {
+        let resources = PathBuf::from(env!("BIOBIT_RESOURCES")).join("fasta");
+        let dir = std::env::temp_dir().join(format!("biobit-autoindex-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+
+        let result = (|| -> Result<()> {
+            for fname in ["indexed.fa", "indexed.fa.bgz"] {
+                let fasta = dir.join(fname);
+                std::fs::copy(resources.join(fname), &fasta)?;
+                let compression = Decoder::from_path(&fasta, crate::fasta::EXTENSIONS)?;
+
+                let sources = IndexedSources::from_path(&fasta, compression).with_autoindex(false);
+                assert!(sources.open().is_err());
+
+                let mut reader = sources.with_autoindex(true).open()?;
+                let mut buffer = Vec::new();
+                reader.fetch(
+                    "sp|Q9Y572|RIPK3_HUMAN",
+                    Interval::new(510, 518)?,
+                    &mut buffer,
+                )?;
+                assert_eq!(buffer, b"GWYNHSGK");
+
+                // Saved indexes must match the ones produced by samtools
+                for extension in ["fai", "gzi"] {
+                    let expected = resources.join(format!("{fname}.{extension}"));
+                    if expected.exists() {
+                        assert_eq!(
+                            std::fs::read(index::index_path(&fasta, extension))?,
+                            std::fs::read(expected)?
+                        );
+                    }
+                }
+            }
+            Ok(())
+        })();
+        std::fs::remove_dir_all(&dir)?;
+        result
+    }
 }
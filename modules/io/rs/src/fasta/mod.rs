@@ -1,12 +1,16 @@
+mod index;
 mod indexed_reader;
 mod indexed_sources;
+mod normalize;
 mod reader;
 mod record;
 pub mod validate;
 mod writer;
 
+pub use index::{FaiRecord, Index, bgzip};
 pub use indexed_reader::{IndexedReader, IndexedReaderMutOp};
 pub use indexed_sources::IndexedSources;
+pub use normalize::{Normalization, soft_masked};
 pub use reader::Reader;
 pub use record::{Record, RecordMutOp, RecordOp};
 use std::num::NonZeroUsize;
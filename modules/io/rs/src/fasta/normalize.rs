@@ -0,0 +1,119 @@
+use biobit_core_rs::loc::Interval;
+
+/// Normalization applied to fetched nucleotide sequences. By default, sequences are converted to
+/// uppercase while preserving IUPAC ambiguity codes, and soft-masked (lowercase) repeats are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Normalization {
+    mask_repeats: bool,
+    collapse_iupac: bool,
+}
+
+impl Normalization {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace soft-masked (lowercase) bases with N.
+    pub fn with_masked_repeats(mut self, mask_repeats: bool) -> Self {
+        self.mask_repeats = mask_repeats;
+        self
+    }
+
+    /// Replace all symbols except A, C, G, and T with N.
+    pub fn with_collapsed_iupac(mut self, collapse_iupac: bool) -> Self {
+        self.collapse_iupac = collapse_iupac;
+        self
+    }
+
+    pub fn mask_repeats(&self) -> bool {
+        self.mask_repeats
+    }
+
+    pub fn collapse_iupac(&self) -> bool {
+        self.collapse_iupac
+    }
+
+    /// Normalize the sequence in place.
+    pub fn apply(&self, seq: &mut [u8]) {
+        for base in seq.iter_mut() {
+            if self.mask_repeats && base.is_ascii_lowercase() {
+                *base = b'N';
+                continue;
+            }
+            base.make_ascii_uppercase();
+            if self.collapse_iupac && !matches!(*base, b'A' | b'C' | b'G' | b'T') {
+                *base = b'N';
+            }
+        }
+    }
+}
+
+/// Collect runs of soft-masked (lowercase) bases as intervals. The `offset` is added to all
+/// coordinates, i.e., it is the position of the first base of the sequence.
+pub fn soft_masked(seq: &[u8], offset: u64, into: &mut Vec<Interval<u64>>) {
+    into.clear();
+
+    let mut start = None;
+    for (pos, base) in seq.iter().enumerate() {
+        match (base.is_ascii_lowercase(), start) {
+            (true, None) => start = Some(pos),
+            (false, Some(begin)) => {
+                // Safe: begin < pos
+                into.push(unsafe {
+                    Interval::new_unchecked(offset + begin as u64, offset + pos as u64)
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(begin) = start {
+        into.push(unsafe {
+            Interval::new_unchecked(offset + begin as u64, offset + seq.len() as u64)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalization() {
+        let raw = b"ACGTacgtNnRyswN".to_vec();
+        for (normalization, expected) in [
+            (Normalization::new(), b"ACGTACGTNNRYSWN"),
+            (
+                Normalization::new().with_masked_repeats(true),
+                b"ACGTNNNNNNRNNNN",
+            ),
+            (
+                Normalization::new().with_collapsed_iupac(true),
+                b"ACGTACGTNNNNNNN",
+            ),
+        ] {
+            let mut seq = raw.clone();
+            normalization.apply(&mut seq);
+            assert_eq!(&seq, expected);
+        }
+    }
+
+    #[test]
+    fn test_soft_masked() {
+        let mut mask = Vec::new();
+        soft_masked(b"acGTNnnA", 10, &mut mask);
+        assert_eq!(
+            mask,
+            vec![
+                Interval::new(10, 12).unwrap(),
+                Interval::new(15, 17).unwrap()
+            ]
+        );
+
+        soft_masked(b"ACGt", 0, &mut mask);
+        assert_eq!(mask, vec![Interval::new(3, 4).unwrap()]);
+
+        soft_masked(b"", 0, &mut mask);
+        assert!(mask.is_empty());
+    }
+}
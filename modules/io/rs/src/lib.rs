@@ -1,6 +1,10 @@
 pub mod bam;
 pub mod bed;
+pub mod bedgraph;
+pub mod bigwig;
 pub mod fasta;
 mod traits;
+pub mod twobit;
+pub mod vcf;
 
 pub use traits::{ReadRecord, WriteRecord};
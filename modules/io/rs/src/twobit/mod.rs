@@ -0,0 +1,24 @@
+// Format specification: https://genome.ucsc.edu/FAQ/FAQformat.html#format7
+
+// Header (16 bytes): signature (0x1A412743), version (0 or 1), sequenceCount, reserved
+// Index, for each sequence: nameSize (u8), name, offset (u32 in version 0, u64 in version 1)
+// Sequence record:
+// 1. dnaSize: u32
+// 2. nBlockCount: u32, followed by nBlockStarts[nBlockCount] and nBlockSizes[nBlockCount]
+// 3. maskBlockCount: u32, followed by maskBlockStarts[maskBlockCount] and maskBlockSizes[maskBlockCount]
+// 4. reserved: u32
+// 5. packedDna: 2 bits per base (T = 0, C = 1, A = 2, G = 3), the first base in the most
+//    significant bits
+
+// Only A, C, G, T, and N can be represented. Other IUPAC codes are stored as N. Lowercase
+// (soft-masked) bases are stored as mask blocks and restored upon reading.
+
+mod reader;
+mod writer;
+
+pub use reader::Reader;
+pub use writer::Writer;
+
+pub const EXTENSIONS: &[&str] = &["2bit"];
+
+const SIGNATURE: u32 = 0x1A412743;
@@ -0,0 +1,372 @@
+use super::SIGNATURE;
+use crate::fasta::IndexedReaderMutOp;
+use biobit_core_rs::loc::{Interval, IntervalOp};
+use eyre::{Result, ensure, eyre};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const BASES: [u8; 4] = [b'T', b'C', b'A', b'G'];
+
+// N-blocks and soft-masked blocks of a single sequence as [start, end) pairs
+#[derive(Debug, Clone, Default)]
+struct Blocks {
+    nblocks: Vec<(u32, u32)>,
+    masks: Vec<(u32, u32)>,
+    dna_offset: u64,
+}
+
+/// A .2bit reader implementing random access to the stored sequences. N-blocks are restored as
+/// `N` and soft-masked blocks are restored as lowercase bases.
+pub struct Reader<R> {
+    reader: R,
+    swapped: bool,
+    index: HashMap<String, usize>,
+    offsets: Vec<u64>,
+    lengths: Vec<u64>,
+    // Blocks of the last accessed sequence
+    cached: Option<(usize, Blocks)>,
+    packed: Vec<u8>,
+}
+
+impl Reader<BufReader<File>> {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let file = BufReader::new(File::open(path.as_ref())?);
+        Reader::new(file)
+    }
+}
+
+impl<R: Read + Seek> Reader<R> {
+    /// Create a new .2bit reader by parsing the file header and the sequence index.
+    pub fn new(mut reader: R) -> Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+        let swapped = match read_u32(&mut reader, false)? {
+            SIGNATURE => false,
+            x if x.swap_bytes() == SIGNATURE => true,
+            x => return Err(eyre!("Invalid .2bit signature: {x:#X}")),
+        };
+        let version = read_u32(&mut reader, swapped)?;
+        ensure!(version <= 1, "Unsupported .2bit version: {version}");
+        let count = read_u32(&mut reader, swapped)? as usize;
+        let _reserved = read_u32(&mut reader, swapped)?;
+
+        let mut index = HashMap::with_capacity(count);
+        let mut offsets = Vec::with_capacity(count);
+        let mut name = Vec::new();
+        for _ in 0..count {
+            let mut size = [0u8; 1];
+            reader.read_exact(&mut size)?;
+            name.resize(size[0] as usize, 0);
+            reader.read_exact(&mut name)?;
+
+            let name = String::from_utf8(name.clone())?;
+            ensure!(!name.is_empty(), ".2bit sequence name can't be empty");
+            ensure!(
+                index.insert(name.clone(), offsets.len()).is_none(),
+                "Duplicate sequence name in the .2bit file: {name}"
+            );
+
+            let offset = if version == 0 {
+                read_u32(&mut reader, swapped)? as u64
+            } else {
+                read_u64(&mut reader, swapped)?
+            };
+            offsets.push(offset);
+        }
+
+        let mut lengths = Vec::with_capacity(count);
+        for &offset in &offsets {
+            reader.seek(SeekFrom::Start(offset))?;
+            lengths.push(read_u32(&mut reader, swapped)? as u64);
+        }
+
+        Ok(Self {
+            reader,
+            swapped,
+            index,
+            offsets,
+            lengths,
+            cached: None,
+            packed: Vec::new(),
+        })
+    }
+
+    /// Fetch the sequence for the given reference sequence ID and interval.
+    pub fn fetch_interval(
+        &mut self,
+        seqid: &str,
+        interval: &Interval<u64>,
+        buffer: &mut Vec<u8>,
+    ) -> Result<()> {
+        let index = *self
+            .index
+            .get(seqid)
+            .ok_or_else(|| eyre!("Reference sequence ID not found in the index: {}", seqid))?;
+        let (start, end) = (interval.start(), interval.end());
+        let length = self.lengths[index];
+        ensure!(
+            start < length,
+            "Start coordinate for {} is out of bounds: {} >= {}",
+            seqid,
+            start,
+            length
+        );
+        ensure!(
+            end <= length,
+            "End coordinate for {} is out of bounds: {} > {}",
+            seqid,
+            end,
+            length
+        );
+
+        self.load_blocks(index)?;
+        let blocks = &self.cached.as_ref().unwrap().1;
+
+        // Unpack bases
+        let first = start / 4;
+        self.packed.resize((end.div_ceil(4) - first) as usize, 0);
+        self.reader
+            .seek(SeekFrom::Start(blocks.dna_offset + first))?;
+        self.reader.read_exact(&mut self.packed)?;
+
+        buffer.clear();
+        buffer.reserve((end - start) as usize);
+        for pos in start..end {
+            let byte = self.packed[(pos / 4 - first) as usize];
+            let shift = 6 - 2 * (pos % 4);
+            buffer.push(BASES[((byte >> shift) & 0b11) as usize]);
+        }
+
+        // Restore N-blocks and soft-masking
+        for (block_start, block_end) in overlapping(&blocks.nblocks, start, end) {
+            buffer[block_start..block_end].fill(b'N');
+        }
+        for (block_start, block_end) in overlapping(&blocks.masks, start, end) {
+            buffer[block_start..block_end].make_ascii_lowercase();
+        }
+        Ok(())
+    }
+
+    /// Fetch the full sequence for the given reference sequence ID.
+    pub fn fetch_full_seq(&mut self, seqid: &str, buffer: &mut Vec<u8>) -> Result<()> {
+        let index = self
+            .index
+            .get(seqid)
+            .ok_or_else(|| eyre!("Reference sequence ID not found in the index: {}", seqid))?;
+
+        // Empty sequences are valid in .2bit files, but not as intervals
+        let length = self.lengths[*index];
+        if length == 0 {
+            buffer.clear();
+            return Ok(());
+        }
+        let interval = Interval::new(0, length)?;
+        self.fetch_interval(seqid, &interval, buffer)
+    }
+
+    fn load_blocks(&mut self, index: usize) -> Result<()> {
+        if matches!(&self.cached, Some((cached, _)) if *cached == index) {
+            return Ok(());
+        }
+        let (mut blocks, swapped) = (self.cached.take().unwrap_or_default().1, self.swapped);
+        let reader = &mut self.reader;
+
+        reader.seek(SeekFrom::Start(self.offsets[index]))?;
+        let _length = read_u32(reader, swapped)?;
+        for target in [&mut blocks.nblocks, &mut blocks.masks] {
+            let count = read_u32(reader, swapped)? as usize;
+            target.clear();
+            for _ in 0..count {
+                let start = read_u32(reader, swapped)?;
+                target.push((start, start));
+            }
+            for block in target.iter_mut() {
+                let size = read_u32(reader, swapped)?;
+                block.1 = block.0.checked_add(size).ok_or_else(|| {
+                    eyre!("Invalid .2bit block: {} + {} overflows", block.0, size)
+                })?;
+            }
+            target.sort_unstable();
+        }
+        let _reserved = read_u32(reader, swapped)?;
+        blocks.dna_offset = reader.stream_position()?;
+
+        self.cached = Some((index, blocks));
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> IndexedReaderMutOp for Reader<R> {
+    fn lengths(&self) -> HashMap<String, u64> {
+        self.index
+            .iter()
+            .map(|(seqid, &index)| (seqid.clone(), self.lengths[index]))
+            .collect()
+    }
+
+    fn fetch(&mut self, seqid: &str, interval: Interval<u64>, buffer: &mut Vec<u8>) -> Result<()> {
+        self.fetch_interval(seqid, &interval, buffer)
+    }
+
+    fn fetch_full_seq(&mut self, seqid: &str, buffer: &mut Vec<u8>) -> Result<()> {
+        Self::fetch_full_seq(self, seqid, buffer)
+    }
+}
+
+// Parts of sorted blocks overlapping the [start, end) interval, relative to the interval start
+fn overlapping(
+    blocks: &[(u32, u32)],
+    start: u64,
+    end: u64,
+) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let first = blocks.partition_point(|x| (x.1 as u64) <= start);
+    blocks[first..]
+        .iter()
+        .take_while(move |x| (x.0 as u64) < end)
+        .map(move |x| {
+            let block_start = (x.0 as u64).max(start);
+            let block_end = (x.1 as u64).min(end);
+            ((block_start - start) as usize, (block_end - start) as usize)
+        })
+        .filter(|x| x.0 < x.1)
+}
+
+fn read_u32(reader: &mut impl Read, swapped: bool) -> Result<u32> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    let value = u32::from_le_bytes(buffer);
+    Ok(if swapped { value.swap_bytes() } else { value })
+}
+
+fn read_u64(reader: &mut impl Read, swapped: bool) -> Result<u64> {
+    let mut buffer = [0u8; 8];
+    reader.read_exact(&mut buffer)?;
+    let value = u64::from_le_bytes(buffer);
+    Ok(if swapped { value.swap_bytes() } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Writer;
+    use super::*;
+    use crate::fasta::Record;
+    use std::io::Cursor;
+
+    fn records() -> Result<Vec<Record>> {
+        let mut long = Vec::new();
+        for i in 0..10_000usize {
+            long.push(b"ACGTacgtNnRY"[(i * 7 + i / 13) % 12]);
+        }
+        Ok(vec![
+            Record::new("chr1".to_string(), b"ACGTNNNNacgtnnACGTA".to_vec())?,
+            Record::new("seq with spaces".to_string(), b"g".to_vec())?,
+            Record::new("chrLong".to_string(), long)?,
+            Record::new("chrN".to_string(), b"NNNNNNNNNN".to_vec())?,
+        ])
+    }
+
+    // Expected sequence after the round trip: non-ACGT symbols are stored as N
+    fn normalized(seq: &[u8]) -> Vec<u8> {
+        seq.iter()
+            .map(|&x| match x {
+                b'A' | b'C' | b'G' | b'T' | b'a' | b'c' | b'g' | b't' => x,
+                x if x.is_ascii_lowercase() => b'n',
+                _ => b'N',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_twobit_roundtrip() -> Result<()> {
+        let records = records()?;
+        let mut writer = Writer::new(Cursor::new(Vec::new()));
+        for record in &records {
+            writer.add_record(record)?;
+        }
+        assert!(writer.add_record(&records[0]).is_err());
+        let mut reader = Reader::new(writer.finish()?)?;
+
+        let lengths = reader.lengths();
+        assert_eq!(lengths.len(), records.len());
+
+        let mut buffer = Vec::new();
+        for record in &records {
+            let expected = normalized(record.seq());
+            assert_eq!(lengths[record.id()], expected.len() as u64);
+
+            reader.fetch_full_seq(record.id(), &mut buffer)?;
+            assert_eq!(buffer, expected, "ID: {}", record.id());
+
+            let length = expected.len() as u64;
+            for (start, end) in [
+                (0, 1),
+                (1, 3),
+                (3, 9),
+                (length / 3, length),
+                (length - 1, length),
+            ] {
+                if start >= end || end > length {
+                    continue;
+                }
+                // Alternate between sequences to invalidate the blocks cache
+                reader.fetch("chr1", Interval::new(0, 1)?, &mut buffer)?;
+                reader.fetch(record.id(), Interval::new(start, end)?, &mut buffer)?;
+                assert_eq!(
+                    buffer,
+                    &expected[start as usize..end as usize],
+                    "ID: {}, Interval: {start}..{end}",
+                    record.id()
+                );
+            }
+        }
+
+        for (seqid, start, end) in [("chr1", 0, 20), ("chr1", 19, 20), ("chr2", 0, 1)] {
+            assert!(
+                reader
+                    .fetch(seqid, Interval::new(start, end)?, &mut buffer)
+                    .is_err()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_twobit_empty_sequence() -> Result<()> {
+        let mut writer = Writer::new(Cursor::new(Vec::new()));
+        writer.add_record(&records()?[0])?;
+        writer.add_record(&unsafe { Record::new_unchecked("chrEmpty".to_string(), Vec::new()) })?;
+        let mut reader = Reader::new(writer.finish()?)?;
+        assert_eq!(reader.lengths()["chrEmpty"], 0);
+
+        let mut buffer = b"ACGT".to_vec();
+        reader.fetch_full_seq("chrEmpty", &mut buffer)?;
+        assert!(buffer.is_empty());
+        reader.fetch_full_seq("chr1", &mut buffer)?;
+        assert_eq!(buffer, b"ACGTNNNNacgtnnACGTA");
+        Ok(())
+    }
+
+    #[test]
+    fn test_twobit_byte_swapped() -> Result<()> {
+        // Big-endian header & index written by hand: one sequence "ACGt" with a single N-block
+        let mut file = Vec::new();
+        for x in [SIGNATURE, 0, 1, 0] {
+            file.extend(x.to_be_bytes());
+        }
+        file.push(3);
+        file.extend(b"seq");
+        file.extend(24u32.to_be_bytes());
+        for x in [5, 1, 4, 1, 1, 3, 1, 0] {
+            file.extend(u32::to_be_bytes(x));
+        }
+        // ACGT|A -> 10 01 11 00 | 10 00 00 00
+        file.extend([0b1001_1100, 0b1000_0000]);
+
+        let mut reader = Reader::new(Cursor::new(file))?;
+        let mut buffer = Vec::new();
+        reader.fetch_full_seq("seq", &mut buffer)?;
+        assert_eq!(buffer, b"ACGtN");
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use super::SIGNATURE;
+use crate::fasta::Record;
+use ahash::HashSet;
+use eyre::{Result, ensure};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// A single packed sequence waiting to be written
+struct Packed {
+    name: String,
+    length: u32,
+    nblocks: Vec<(u32, u32)>,
+    masks: Vec<(u32, u32)>,
+    dna: Vec<u8>,
+}
+
+impl Packed {
+    fn size(&self) -> u64 {
+        // dnaSize, nBlockCount, maskBlockCount, reserved + blocks + packed DNA
+        16 + 8 * (self.nblocks.len() + self.masks.len()) as u64 + self.dna.len() as u64
+    }
+}
+
+/// A .2bit writer. The index precedes the sequence data in the file, hence all records are packed
+/// in memory and written upon calling [`Writer::finish`].
+pub struct Writer<W: Write> {
+    writer: W,
+    records: Vec<Packed>,
+    names: HashSet<String>,
+}
+
+impl Writer<BufWriter<File>> {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let file = BufWriter::new(File::create(path.as_ref())?);
+        Ok(Writer::new(file))
+    }
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            records: Vec::new(),
+            names: HashSet::default(),
+        }
+    }
+
+    /// Pack the FASTA record. Lowercase bases are stored as soft-masked, and all symbols except
+    /// A, C, G, and T are stored as N.
+    pub fn add_record(&mut self, record: &Record) -> Result<()> {
+        let (name, seq) = (record.id(), record.seq());
+        ensure!(
+            name.len() <= u8::MAX as usize,
+            ".2bit sequence name can't be longer than 255 bytes: {name}"
+        );
+        ensure!(
+            seq.len() <= u32::MAX as usize,
+            ".2bit sequence can't be longer than {} bases: {name}",
+            u32::MAX
+        );
+        ensure!(
+            self.names.insert(name.to_string()),
+            "Duplicate .2bit sequence name: {name}"
+        );
+
+        let mut nblocks = Vec::new();
+        let mut masks = Vec::new();
+        let mut dna = vec![0u8; seq.len().div_ceil(4)];
+        for (pos, &base) in seq.iter().enumerate() {
+            let code = match base.to_ascii_uppercase() {
+                b'T' => 0,
+                b'C' => 1,
+                b'A' => 2,
+                b'G' => 3,
+                _ => {
+                    extend_blocks(&mut nblocks, pos as u32);
+                    0
+                }
+            };
+            if base.is_ascii_lowercase() {
+                extend_blocks(&mut masks, pos as u32);
+            }
+            dna[pos / 4] |= code << (6 - 2 * (pos % 4));
+        }
+
+        self.records.push(Packed {
+            name: name.to_string(),
+            length: seq.len() as u32,
+            nblocks,
+            masks,
+            dna,
+        });
+        Ok(())
+    }
+
+    /// Write the .2bit file and return the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        let index_size = |offset_size: u64| {
+            self.records
+                .iter()
+                .map(|x| 1 + x.name.len() as u64 + offset_size)
+                .sum::<u64>()
+        };
+        let data_size = self.records.iter().map(|x| x.size()).sum::<u64>();
+
+        // Switch to 64-bit offsets only when required
+        let last_offset =
+            16 + index_size(4) + data_size - self.records.last().map_or(0, |x| x.size());
+        let (version, offset_size) = if last_offset > u32::MAX as u64 {
+            (1, 8)
+        } else {
+            (0, 4)
+        };
+
+        let w = &mut self.writer;
+        w.write_all(&SIGNATURE.to_le_bytes())?;
+        w.write_all(&(version as u32).to_le_bytes())?;
+        w.write_all(&(self.records.len() as u32).to_le_bytes())?;
+        w.write_all(&0u32.to_le_bytes())?;
+
+        let mut offset = 16 + index_size(offset_size);
+        for record in &self.records {
+            w.write_all(&[record.name.len() as u8])?;
+            w.write_all(record.name.as_bytes())?;
+            if version == 0 {
+                w.write_all(&(offset as u32).to_le_bytes())?;
+            } else {
+                w.write_all(&offset.to_le_bytes())?;
+            }
+            offset += record.size();
+        }
+
+        for record in &self.records {
+            w.write_all(&record.length.to_le_bytes())?;
+            for blocks in [&record.nblocks, &record.masks] {
+                w.write_all(&(blocks.len() as u32).to_le_bytes())?;
+                for (start, _) in blocks {
+                    w.write_all(&start.to_le_bytes())?;
+                }
+                for (start, end) in blocks {
+                    w.write_all(&(end - start).to_le_bytes())?;
+                }
+            }
+            w.write_all(&0u32.to_le_bytes())?;
+            w.write_all(&record.dna)?;
+        }
+
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[inline(always)]
+fn extend_blocks(blocks: &mut Vec<(u32, u32)>, pos: u32) {
+    match blocks.last_mut() {
+        Some(last) if last.1 == pos => last.1 += 1,
+        _ => blocks.push((pos, pos + 1)),
+    }
+}
@@ -0,0 +1,297 @@
+use super::validate;
+#[cfg(feature = "bitcode")]
+use bitcode::{Decode, Encode};
+use derive_getters::{Dissolve, Getters};
+use eyre::{OptionExt, Result, ensure};
+use std::fmt::{Display, Formatter};
+
+pub const FILE_FORMAT: &str = "VCFv4.3";
+
+/// Number of values described by an INFO or FORMAT header line.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum Number {
+    Count(u32),
+    /// One value per ALT allele
+    A,
+    /// One value per allele, including REF
+    R,
+    /// One value per possible genotype
+    G,
+    /// Unknown or unbounded number of values
+    Unknown,
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Number::Count(n) => write!(f, "{n}"),
+            Number::A => write!(f, "A"),
+            Number::R => write!(f, "R"),
+            Number::G => write!(f, "G"),
+            Number::Unknown => write!(f, "."),
+        }
+    }
+}
+
+/// Type of values described by an INFO or FORMAT header line.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum ValueType {
+    Integer,
+    Float,
+    Flag,
+    Character,
+    String,
+}
+
+impl Display for ValueType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            ValueType::Integer => "Integer",
+            ValueType::Float => "Float",
+            ValueType::Flag => "Flag",
+            ValueType::Character => "Character",
+            ValueType::String => "String",
+        };
+        write!(f, "{value}")
+    }
+}
+
+/// VCF header: meta-information lines (without the leading '##') and sample names.
+///
+/// The `fileformat` line is always emitted first and is not stored among the meta lines. Contig
+/// lines are additionally parsed to allow for quick access to sequence names and lengths.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Dissolve, Getters)]
+pub struct Header {
+    meta: Vec<String>,
+    contigs: Vec<(String, Option<u64>)>,
+    samples: Vec<String>,
+}
+
+impl Header {
+    pub fn new(samples: Vec<String>) -> Result<Self> {
+        for sample in &samples {
+            ensure!(
+                !sample.is_empty() && !sample.contains(['\t', '\n', '\r']),
+                "Invalid VCF sample name: {sample}"
+            );
+        }
+        Ok(Self {
+            meta: Vec::new(),
+            contigs: Vec::new(),
+            samples,
+        })
+    }
+
+    /// Add an arbitrary meta-information line, e.g. `source=biobit`.
+    pub fn with_meta(mut self, line: impl Into<String>) -> Result<Self> {
+        let line = line.into();
+        let line = line.strip_prefix("##").unwrap_or(&line);
+        ensure!(
+            !line.is_empty() && !line.contains(['\n', '\r']),
+            "Invalid VCF meta-information line: {line}"
+        );
+        ensure!(
+            !line.starts_with("fileformat="),
+            "VCF fileformat line is always written automatically"
+        );
+
+        if let Some(contig) = line.strip_prefix("contig=") {
+            let fields = parse_structured(contig)?;
+            let id = fields
+                .iter()
+                .find(|(k, _)| *k == "ID")
+                .map(|(_, v)| v.to_string())
+                .ok_or_eyre(format!("VCF contig line is missing ID: {line}"))?;
+            let length = match fields.iter().find(|(k, _)| *k == "length") {
+                Some((_, v)) => Some(v.parse::<u64>()?),
+                None => None,
+            };
+            self.contigs.push((id, length));
+        }
+
+        self.meta.push(line.to_string());
+        Ok(self)
+    }
+
+    pub fn with_contig(self, id: &str, length: u64) -> Result<Self> {
+        validate::seqid(id)?;
+        self.with_meta(format!("contig=<ID={id},length={length}>"))
+    }
+
+    pub fn with_filter(self, id: &str, description: &str) -> Result<Self> {
+        self.with_meta(format!(
+            "FILTER=<ID={id},Description=\"{}\">",
+            escape(description)
+        ))
+    }
+
+    pub fn with_info(
+        self,
+        id: &str,
+        number: Number,
+        kind: ValueType,
+        description: &str,
+    ) -> Result<Self> {
+        validate::info(&[(id.to_string(), None)])?;
+        self.with_meta(format!(
+            "INFO=<ID={id},Number={number},Type={kind},Description=\"{}\">",
+            escape(description)
+        ))
+    }
+
+    pub fn with_format(
+        self,
+        id: &str,
+        number: Number,
+        kind: ValueType,
+        description: &str,
+    ) -> Result<Self> {
+        ensure!(
+            kind != ValueType::Flag,
+            "VCF FORMAT fields can't have the Flag type"
+        );
+        validate::samples(&[id.to_string()], &[])?;
+        self.with_meta(format!(
+            "FORMAT=<ID={id},Number={number},Type={kind},Description=\"{}\">",
+            escape(description)
+        ))
+    }
+
+    /// Parse the header line starting with '#CHROM' and return the sample names.
+    pub(crate) fn parse_samples(line: &str) -> Result<Vec<String>> {
+        const MANDATORY: [&str; 8] = [
+            "#CHROM", "POS", "ID", "REF", "ALT", "QUAL", "FILTER", "INFO",
+        ];
+
+        let mut parts = line.split('\t');
+        for expected in MANDATORY {
+            let found = parts.next().ok_or_eyre("Truncated VCF header line")?;
+            ensure!(
+                found == expected,
+                "Unexpected VCF header column: expected {expected}, found {found}"
+            );
+        }
+        match parts.next() {
+            None => Ok(Vec::new()),
+            Some("FORMAT") => Ok(parts.map(|x| x.to_string()).collect()),
+            Some(other) => Err(eyre::eyre!(
+                "Unexpected VCF header column: expected FORMAT, found {other}"
+            )),
+        }
+    }
+}
+
+fn escape(description: &str) -> String {
+    description.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Parse the content of structured meta lines: <KEY=VALUE,KEY="VALUE, with commas",...>
+fn parse_structured(line: &str) -> Result<Vec<(&str, &str)>> {
+    let content = line
+        .strip_prefix('<')
+        .and_then(|x| x.strip_suffix('>'))
+        .ok_or_eyre(format!("Invalid structured VCF meta line: {line}"))?;
+
+    let mut result = Vec::new();
+    let mut rest = content;
+    while !rest.is_empty() {
+        let (key, tail) = rest
+            .split_once('=')
+            .ok_or_eyre(format!("Invalid structured VCF meta line: {line}"))?;
+
+        let (value, tail) = if let Some(quoted) = tail.strip_prefix('"') {
+            let mut escaped = false;
+            let end = quoted
+                .char_indices()
+                .find(|(_, c)| {
+                    let stop = *c == '"' && !escaped;
+                    escaped = *c == '\\' && !escaped;
+                    stop
+                })
+                .map(|(i, _)| i)
+                .ok_or_eyre(format!("Unterminated quote in VCF meta line: {line}"))?;
+            (&quoted[..end], &quoted[end + 1..])
+        } else {
+            match tail.find(',') {
+                Some(end) => (&tail[..end], &tail[end..]),
+                None => (tail, ""),
+            }
+        };
+        result.push((key, value));
+
+        rest = match tail.strip_prefix(',') {
+            Some(x) => x,
+            None => {
+                ensure!(tail.is_empty(), "Invalid structured VCF meta line: {line}");
+                tail
+            }
+        };
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_builder() -> Result<()> {
+        let header = Header::new(vec!["S1".to_string()])?
+            .with_meta("##source=biobit")?
+            .with_contig("chr1", 1000)?
+            .with_meta("contig=<ID=chr2,assembly=\"T2T, v2\">")?
+            .with_info("DP", Number::Count(1), ValueType::Integer, "Total depth")?
+            .with_format("AD", Number::R, ValueType::Integer, "Allelic \"depths\"")?;
+
+        assert_eq!(
+            header.meta(),
+            &[
+                "source=biobit",
+                "contig=<ID=chr1,length=1000>",
+                "contig=<ID=chr2,assembly=\"T2T, v2\">",
+                "INFO=<ID=DP,Number=1,Type=Integer,Description=\"Total depth\">",
+                "FORMAT=<ID=AD,Number=R,Type=Integer,Description=\"Allelic \\\"depths\\\"\">",
+            ]
+        );
+        assert_eq!(
+            header.contigs(),
+            &[("chr1".to_string(), Some(1000)), ("chr2".to_string(), None)]
+        );
+
+        assert!(
+            Header::new(vec![])
+                .unwrap()
+                .with_meta("fileformat=VCFv4.2")
+                .is_err()
+        );
+        assert!(
+            Header::new(vec![])
+                .unwrap()
+                .with_meta("contig=<length=10>")
+                .is_err()
+        );
+        assert!(
+            Header::new(vec![])
+                .unwrap()
+                .with_format("FT", Number::Count(0), ValueType::Flag, "")
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_samples() -> Result<()> {
+        let line = "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO";
+        assert!(Header::parse_samples(line)?.is_empty());
+        assert_eq!(
+            Header::parse_samples(&format!("{line}\tFORMAT\tS1\tS2"))?,
+            vec!["S1", "S2"]
+        );
+        assert!(Header::parse_samples("#CHROM\tPOS").is_err());
+        assert!(Header::parse_samples(&format!("{line}\tS1")).is_err());
+        Ok(())
+    }
+}
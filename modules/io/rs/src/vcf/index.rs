@@ -0,0 +1,20 @@
+use eyre::{Context, Result, ensure};
+use std::path::{Path, PathBuf};
+
+/// Build a tabix index for the BGZF-compressed and coordinate-sorted VCF file and save it next to
+/// the file (`{path}.tbi`). Returns the path to the created index.
+pub fn index(path: impl AsRef<Path>) -> Result<PathBuf> {
+    let path = path.as_ref();
+    ensure!(path.is_file(), "VCF file doesn't exist: {}", path.display());
+
+    let index = noodles::vcf::fs::index(path)
+        .wrap_err_with(|| format!("Failed to index the VCF file: {}", path.display()))?;
+
+    let mut saveto = path.as_os_str().to_owned();
+    saveto.push(".tbi");
+    let saveto = PathBuf::from(saveto);
+
+    noodles::tabix::fs::write(&saveto, &index)
+        .wrap_err_with(|| format!("Failed to write the tabix index: {}", saveto.display()))?;
+    Ok(saveto)
+}
@@ -0,0 +1,29 @@
+// Format specification: https://samtools.github.io/hts-specs/VCFv4.3.pdf
+
+// Mandatory fields:
+// 1. CHROM: [^\s]+ (no whitespace, colons are tolerated)
+// 2. POS: u64, 1-based (stored 0-based in the Record)
+// 3. ID: semicolon-separated list of identifiers or '.'
+// 4. REF: [ACGTNacgtn]+
+// 5. ALT: comma-separated list of alleles or '.'
+// 6. QUAL: f32 or '.'
+// 7. FILTER: PASS, semicolon-separated list of filters or '.'
+// 8. INFO: semicolon-separated list of key[=value] pairs or '.'
+// Optional fields:
+// 9. FORMAT: colon-separated list of keys
+// 10+. Sample columns: colon-separated list of values matching FORMAT
+
+mod header;
+mod index;
+mod reader;
+mod record;
+pub mod validate;
+mod writer;
+
+pub use header::{Header, Number, ValueType};
+pub use index::index;
+pub use reader::Reader;
+pub use record::Record;
+pub use writer::Writer;
+
+pub const EXTENSIONS: &[&str] = &["vcf"];
@@ -0,0 +1,279 @@
+use super::{header::Header, record::Record, validate};
+use crate::ReadRecord;
+use eyre::{Context, OptionExt, Result, bail, ensure};
+use std::fs::File;
+use std::io::BufRead;
+use std::path::Path;
+use substratum_compress::{Decoder, adapter::BoxedSync, decode::DecodeReadIntoBufRead};
+
+pub mod parse {
+    use super::*;
+
+    fn missing_or<'a, T>(
+        field: &'a str,
+        parse: impl FnOnce(&'a str) -> Result<T>,
+    ) -> Result<Option<T>> {
+        if field == "." {
+            Ok(None)
+        } else {
+            parse(field).map(Some)
+        }
+    }
+
+    fn list(field: &str, separator: char, into: &mut Vec<String>) {
+        into.clear();
+        if field != "." {
+            into.extend(field.split(separator).map(|x| x.to_string()));
+        }
+    }
+
+    pub fn record<'a>(parts: &mut impl Iterator<Item = &'a str>, into: &mut Record) -> Result<()> {
+        // SAFETY: All fields are validated right after parsing
+        let (seqid, position, ids, reference, alternative, quality, filters, info, format, samples) =
+            unsafe { into.fields() };
+
+        seqid.clear();
+        seqid.push_str(parts.next().ok_or_eyre("Missing VCF CHROM")?);
+        validate::seqid(seqid)?;
+
+        let pos = parts
+            .next()
+            .ok_or_eyre("Missing VCF POS")?
+            .parse::<u64>()
+            .wrap_err("Invalid VCF POS")?;
+        ensure!(pos > 0, "VCF POS must be 1-based");
+        *position = pos - 1;
+
+        list(parts.next().ok_or_eyre("Missing VCF ID")?, ';', ids);
+        validate::ids(ids)?;
+
+        reference.clear();
+        reference.push_str(parts.next().ok_or_eyre("Missing VCF REF")?);
+        validate::reference(reference)?;
+
+        list(
+            parts.next().ok_or_eyre("Missing VCF ALT")?,
+            ',',
+            alternative,
+        );
+        validate::alternative(reference, alternative)?;
+
+        *quality = missing_or(parts.next().ok_or_eyre("Missing VCF QUAL")?, |x| {
+            x.parse::<f32>().wrap_err("Invalid VCF QUAL")
+        })?;
+        validate::quality(quality)?;
+
+        list(parts.next().ok_or_eyre("Missing VCF FILTER")?, ';', filters);
+        validate::filters(filters)?;
+
+        let field = parts.next().ok_or_eyre("Missing VCF INFO")?;
+        info.clear();
+        if field != "." {
+            info.extend(field.split(';').map(|x| match x.split_once('=') {
+                Some((k, v)) => (k.to_string(), Some(v.to_string())),
+                None => (x.to_string(), None),
+            }));
+        }
+        validate::info(info)?;
+
+        format.clear();
+        samples.clear();
+        if let Some(field) = parts.next() {
+            format.extend(field.split(':').map(|x| x.to_string()));
+            for sample in parts {
+                let mut values = Vec::with_capacity(format.len());
+                list(sample, ':', &mut values);
+                samples.push(values);
+            }
+        }
+        validate::samples(format, samples)?;
+
+        Ok(())
+    }
+}
+
+/// A VCF reader that parses the header upon creation and then reads one data line at a time.
+///
+/// Records must have exactly as many sample columns as declared in the header.
+pub struct Reader<R> {
+    reader: R,
+    header: Header,
+    buffer: String,
+}
+
+impl Reader<()> {
+    /// Create a new VCF reader from the given file path.
+    /// The compression is automatically detected based on the file extension and the internal file signature.
+    pub fn from_path(
+        path: impl AsRef<Path>,
+        decoder: &Decoder,
+    ) -> Result<Reader<Box<dyn BufRead + Send + Sync + 'static>>> {
+        let file = File::open(path.as_ref())?;
+        let src = decoder.decode_read_into_bufread(file, BoxedSync)?;
+        Reader::new(src)
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut buffer = String::new();
+
+        // The first line must declare the file format
+        reader.read_line(&mut buffer)?;
+        let line = buffer.trim_end_matches(['\n', '\r']);
+        ensure!(
+            line.starts_with("##fileformat=VCF"),
+            "Expected ##fileformat=VCF at the start of the VCF file"
+        );
+
+        let mut meta = Vec::new();
+        let samples = loop {
+            buffer.clear();
+            if reader.read_line(&mut buffer)? == 0 {
+                bail!("Unexpected EOF inside the VCF header");
+            }
+            let line = buffer.trim_end_matches(['\n', '\r']);
+            if let Some(line) = line.strip_prefix("##") {
+                meta.push(line.to_string());
+            } else {
+                break Header::parse_samples(line)?;
+            }
+        };
+
+        let header = meta
+            .into_iter()
+            .try_fold(Header::new(samples)?, |header, line| header.with_meta(line))?;
+
+        buffer.clear();
+        Ok(Self {
+            reader,
+            header,
+            buffer,
+        })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+}
+
+impl<R: BufRead> ReadRecord for Reader<R> {
+    type Record = Record;
+
+    fn read_record(&mut self, into: &mut Record) -> Result<bool> {
+        self.buffer.clear();
+        if self.reader.read_line(&mut self.buffer)? == 0 {
+            return Ok(false);
+        }
+
+        let mut parts = self.buffer.trim_end_matches(['\n', '\r']).split('\t');
+        parse::record(&mut parts, into)
+            .wrap_err_with(|| format!("Failed to parse VCF record: {}", self.buffer))?;
+        ensure!(
+            into.samples().len() == self.header.samples().len(),
+            "VCF record has {} sample columns, but the header declares {}: {}",
+            into.samples().len(),
+            self.header.samples().len(),
+            self.buffer
+        );
+        Ok(true)
+    }
+
+    fn read_records(&mut self, into: &mut [Record]) -> Result<usize> {
+        let mut total = 0;
+        for buf in into.iter_mut() {
+            if !self.read_record(buf)? {
+                return Ok(total);
+            }
+            total += 1;
+        }
+        Ok(total)
+    }
+
+    fn read_to_end(&mut self, into: &mut Vec<Record>) -> Result<usize> {
+        let mut total = 0;
+
+        // Read into the existing buffer
+        for record in into.iter_mut() {
+            if !self.read_record(record)? {
+                return Ok(total);
+            }
+            total += 1;
+        }
+
+        // Append to the buffer
+        loop {
+            let mut record = Record::default();
+            if !self.read_record(&mut record)? {
+                return Ok(total);
+            }
+            into.push(record);
+            total += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const CONTENT: &str = "\
+##fileformat=VCFv4.3
+##contig=<ID=chr1,length=100>
+#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tS1
+chr1\t10\trs1;rs2\tA\tG,T\t.\tPASS\tDP=10;SB\tAD:DP\t5,3,2:10
+chr1\t20\t.\tC\t.\t13.5\t.\t.\tAD\t.
+";
+
+    #[test]
+    fn test_read_vcf() -> Result<()> {
+        let mut reader = Reader::new(Cursor::new(CONTENT))?;
+        assert_eq!(reader.header().samples(), &["S1"]);
+        assert_eq!(
+            reader.header().contigs(),
+            &[("chr1".to_string(), Some(100))]
+        );
+
+        let mut records = Vec::new();
+        assert_eq!(reader.read_to_end(&mut records)?, 2);
+
+        let first = &records[0];
+        assert_eq!(first.seqid(), "chr1");
+        assert_eq!(*first.position(), 9);
+        assert_eq!(first.ids(), &["rs1", "rs2"]);
+        assert_eq!(first.alternative(), &["G", "T"]);
+        assert_eq!(first.filters(), &["PASS"]);
+        assert_eq!(first.info_value("SB"), Some(None));
+        assert_eq!(first.sample_value(0, "AD"), Some("5,3,2"));
+
+        let second = &records[1];
+        assert_eq!(*second.position(), 19);
+        assert!(second.ids().is_empty());
+        assert!(second.alternative().is_empty());
+        assert_eq!(*second.quality(), Some(13.5));
+        assert!(second.info().is_empty());
+        assert!(second.samples()[0].is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_vcf() {
+        assert!(Reader::new(Cursor::new("#CHROM\tPOS\n")).is_err());
+        assert!(Reader::new(Cursor::new("##fileformat=VCFv4.3\n")).is_err());
+
+        for line in [
+            "chr1\t0\t.\tA\tG\t.\t.\t.\tAD\t1",
+            "chr1\t10\t.\tA\tA\t.\t.\t.\tAD\t1",
+            "chr1\t10\t.\tA\tG\t.\t.\t.",
+            "chr1\t10\t.\tA\tG\t.\t.\t.\tAD\t1\t2",
+            "chr1\t10\t.\tA\tG\t.\t.",
+        ] {
+            let content = format!(
+                "##fileformat=VCFv4.3\n#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tS1\n{line}\n"
+            );
+            let mut reader = Reader::new(Cursor::new(content)).unwrap();
+            assert!(reader.read_record(&mut Record::default()).is_err());
+        }
+    }
+}
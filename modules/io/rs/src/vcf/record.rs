@@ -0,0 +1,215 @@
+use super::validate;
+#[cfg(feature = "bitcode")]
+use bitcode::{Decode, Encode};
+use derive_getters::{Dissolve, Getters};
+use eyre::Result;
+
+/// A single VCF data line with the following guarantees:
+/// - CHROM is non-empty and doesn't contain whitespace.
+/// - POS is stored as a 0-based coordinate and converted to the 1-based one on output.
+/// - REF is a non-empty sequence of A, C, G, T, or N (case-insensitive).
+/// - ALT alleles are non-empty, don't contain commas, and differ from REF.
+/// - INFO keys, FORMAT keys, and sample values are valid VCF tokens.
+/// - Each sample has at most as many values as there are FORMAT keys (trailing values may be
+///   dropped as permitted by the specification).
+///
+/// Empty collections correspond to missing values ('.') in the VCF line.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Debug, Clone, PartialEq, Dissolve, Getters)]
+pub struct Record {
+    seqid: String,
+    position: u64,
+    ids: Vec<String>,
+    reference: String,
+    alternative: Vec<String>,
+    quality: Option<f32>,
+    filters: Vec<String>,
+    info: Vec<(String, Option<String>)>,
+    format: Vec<String>,
+    samples: Vec<Vec<String>>,
+}
+
+impl Record {
+    /// Creates a new VCF record. The position is 0-based.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        seqid: String,
+        position: u64,
+        ids: Vec<String>,
+        reference: String,
+        alternative: Vec<String>,
+        quality: Option<f32>,
+        filters: Vec<String>,
+        info: Vec<(String, Option<String>)>,
+        format: Vec<String>,
+        samples: Vec<Vec<String>>,
+    ) -> Result<Self> {
+        validate::seqid(&seqid)?;
+        validate::position(position)?;
+        validate::ids(&ids)?;
+        validate::reference(&reference)?;
+        validate::alternative(&reference, &alternative)?;
+        validate::quality(&quality)?;
+        validate::filters(&filters)?;
+        validate::info(&info)?;
+        validate::samples(&format, &samples)?;
+
+        Ok(Self {
+            seqid,
+            position,
+            ids,
+            reference,
+            alternative,
+            quality,
+            filters,
+            info,
+            format,
+            samples,
+        })
+    }
+
+    /// Returns the value of the INFO key if it is present. Flags are reported as `Some(None)`.
+    pub fn info_value(&self, key: &str) -> Option<Option<&str>> {
+        self.info
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_deref())
+    }
+
+    /// Returns the value of the FORMAT key for the given sample if it is present.
+    pub fn sample_value(&self, sample: usize, key: &str) -> Option<&str> {
+        let ind = self.format.iter().position(|x| x == key)?;
+        self.samples
+            .get(sample)
+            .and_then(|x| x.get(ind))
+            .map(String::as_str)
+    }
+
+    /// Returns the 0-based exclusive end of the REF allele.
+    pub fn end(&self) -> u64 {
+        self.position + self.reference.len() as u64
+    }
+
+    /// # Safety
+    /// The caller must ensure that all fields remain valid after modifications.
+    #[allow(clippy::type_complexity)]
+    pub unsafe fn fields(
+        &mut self,
+    ) -> (
+        &mut String,
+        &mut u64,
+        &mut Vec<String>,
+        &mut String,
+        &mut Vec<String>,
+        &mut Option<f32>,
+        &mut Vec<String>,
+        &mut Vec<(String, Option<String>)>,
+        &mut Vec<String>,
+        &mut Vec<Vec<String>>,
+    ) {
+        (
+            &mut self.seqid,
+            &mut self.position,
+            &mut self.ids,
+            &mut self.reference,
+            &mut self.alternative,
+            &mut self.quality,
+            &mut self.filters,
+            &mut self.info,
+            &mut self.format,
+            &mut self.samples,
+        )
+    }
+}
+
+impl Default for Record {
+    fn default() -> Self {
+        Self {
+            seqid: "_".to_string(),
+            position: 0,
+            ids: Vec::new(),
+            reference: "N".to_string(),
+            alternative: Vec::new(),
+            quality: None,
+            filters: Vec::new(),
+            info: Vec::new(),
+            format: Vec::new(),
+            samples: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(reference: &str, alternative: &[&str]) -> Result<Record> {
+        Record::new(
+            "chr1".to_string(),
+            10,
+            vec![],
+            reference.to_string(),
+            alternative.iter().map(|x| x.to_string()).collect(),
+            None,
+            vec![],
+            vec![("DP".to_string(), Some("10".to_string()))],
+            vec!["AD".to_string(), "DP".to_string()],
+            vec![vec!["5,5".to_string(), "10".to_string()]],
+        )
+    }
+
+    #[test]
+    fn test_valid_record() -> Result<()> {
+        let record = record("A", &["G", "T"])?;
+        assert_eq!(record.end(), 11);
+        assert_eq!(record.info_value("DP"), Some(Some("10")));
+        assert_eq!(record.info_value("AD"), None);
+        assert_eq!(record.sample_value(0, "AD"), Some("5,5"));
+        assert_eq!(record.sample_value(0, "GT"), None);
+        assert_eq!(record.sample_value(1, "AD"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_records() {
+        assert!(record("", &["G"]).is_err());
+        assert!(record("AX", &["G"]).is_err());
+        assert!(record("A", &["A"]).is_err());
+        assert!(record("A", &["G,T"]).is_err());
+        assert!(record("A", &["."]).is_err());
+
+        let mut samples = vec![vec!["1".to_string(), "2".to_string(), "3".to_string()]];
+        assert!(
+            Record::new(
+                "chr1".to_string(),
+                0,
+                vec![],
+                "A".to_string(),
+                vec![],
+                None,
+                vec![],
+                vec![],
+                vec!["AD".to_string(), "DP".to_string()],
+                samples.clone(),
+            )
+            .is_err()
+        );
+
+        samples[0].pop();
+        assert!(
+            Record::new(
+                "chr 1".to_string(),
+                0,
+                vec![],
+                "A".to_string(),
+                vec![],
+                None,
+                vec![],
+                vec![],
+                vec!["AD".to_string(), "DP".to_string()],
+                samples,
+            )
+            .is_err()
+        );
+    }
+}
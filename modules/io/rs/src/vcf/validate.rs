@@ -0,0 +1,138 @@
+use eyre::{Result, ensure};
+
+#[inline(always)]
+fn is_field_safe(value: &str) -> bool {
+    !value.is_empty() && !value.contains(|c: char| c.is_ascii_whitespace())
+}
+
+pub fn seqid(seqid: &str) -> Result<()> {
+    ensure!(
+        is_field_safe(seqid),
+        "VCF CHROM must be non-empty and can't contain whitespace: {seqid}"
+    );
+    Ok(())
+}
+
+pub fn position(position: u64) -> Result<()> {
+    ensure!(
+        position < u64::MAX,
+        "VCF POS can't be represented as a 1-based coordinate: {position}"
+    );
+    Ok(())
+}
+
+pub fn ids(ids: &[String]) -> Result<()> {
+    for id in ids {
+        ensure!(
+            is_field_safe(id) && id != "." && !id.contains(';'),
+            "Invalid VCF ID: {id}"
+        );
+    }
+    Ok(())
+}
+
+pub fn reference(reference: &str) -> Result<()> {
+    ensure!(!reference.is_empty(), "VCF REF allele can't be empty");
+    ensure!(
+        reference.bytes().all(|x| matches!(
+            x,
+            b'A' | b'C' | b'G' | b'T' | b'N' | b'a' | b'c' | b'g' | b't' | b'n'
+        )),
+        "VCF REF allele must contain only A, C, G, T, or N: {reference}"
+    );
+    Ok(())
+}
+
+pub fn alternative(reference: &str, alternative: &[String]) -> Result<()> {
+    for allele in alternative {
+        ensure!(
+            is_field_safe(allele) && allele != "." && !allele.contains(','),
+            "Invalid VCF ALT allele: {allele}"
+        );
+        ensure!(
+            allele != reference,
+            "VCF ALT allele can't be identical to the REF allele: {allele}"
+        );
+    }
+    Ok(())
+}
+
+pub fn quality(quality: &Option<f32>) -> Result<()> {
+    if let Some(quality) = quality {
+        ensure!(
+            quality.is_finite(),
+            "VCF QUAL must be a finite number: {quality}"
+        );
+    }
+    Ok(())
+}
+
+pub fn filters(filters: &[String]) -> Result<()> {
+    for filter in filters {
+        ensure!(
+            is_field_safe(filter) && filter != "." && filter != "0" && !filter.contains(';'),
+            "Invalid VCF FILTER: {filter}"
+        );
+    }
+    ensure!(
+        filters.len() == 1 || !filters.iter().any(|x| x == "PASS"),
+        "VCF FILTER PASS can't be combined with other filters"
+    );
+    Ok(())
+}
+
+pub fn info(info: &[(String, Option<String>)]) -> Result<()> {
+    for (key, value) in info {
+        ensure!(
+            key == "1000G"
+                || (key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                    && key
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')),
+            "Invalid VCF INFO key: {key}"
+        );
+        if let Some(value) = value {
+            ensure!(
+                !value.is_empty()
+                    && !value.contains(|c: char| c == ';' || c == '=' || c.is_ascii_whitespace()),
+                "Invalid VCF INFO value for the key {key}: {value}"
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn samples(format: &[String], samples: &[Vec<String>]) -> Result<()> {
+    for key in format {
+        ensure!(
+            key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.'),
+            "Invalid VCF FORMAT key: {key}"
+        );
+    }
+    if let Some(position) = format.iter().position(|x| x == "GT") {
+        ensure!(position == 0, "VCF FORMAT key GT must be the first one");
+    }
+    ensure!(
+        !format.is_empty() || samples.is_empty(),
+        "VCF sample columns require the FORMAT field"
+    );
+
+    for sample in samples {
+        ensure!(
+            sample.len() <= format.len(),
+            "VCF sample has more values ({}) than FORMAT keys ({})",
+            sample.len(),
+            format.len()
+        );
+        for value in sample {
+            ensure!(
+                is_field_safe(value) && !value.contains(':'),
+                "Invalid VCF sample value: {value}"
+            );
+        }
+    }
+    Ok(())
+}
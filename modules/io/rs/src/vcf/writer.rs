@@ -0,0 +1,174 @@
+use super::header::{FILE_FORMAT, Header};
+use super::record::Record;
+use crate::traits::WriteRecord;
+use eyre::{Result, ensure};
+use itertools::Itertools;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use substratum_compress::{Encoder, adapter::BoxedSync, encode::Encode};
+
+/// A VCF writer that emits the header upon creation. Records must have exactly as many sample
+/// columns as declared in the header, and the FORMAT field if and only if there are samples.
+pub struct Writer<W> {
+    writer: W,
+    samples: usize,
+}
+
+impl Writer<()> {
+    /// Create a new VCF writer for the given file path. Use the BGZF encoder to produce files that
+    /// can be indexed with [`super::index`].
+    pub fn from_path(
+        path: impl AsRef<Path>,
+        encoder: &Encoder,
+        header: &Header,
+    ) -> Result<Box<dyn WriteRecord<Record = Record> + Send + Sync + 'static>> {
+        let file = encoder.encode(File::create(path.as_ref())?, BoxedSync)?;
+        let writer = Box::new(Writer::new(file, header)?);
+        Ok(writer)
+    }
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(mut writer: W, header: &Header) -> Result<Self> {
+        writeln!(writer, "##fileformat={FILE_FORMAT}")?;
+        for line in header.meta() {
+            writeln!(writer, "##{line}")?;
+        }
+
+        write!(writer, "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO")?;
+        if !header.samples().is_empty() {
+            write!(writer, "\tFORMAT\t{}", header.samples().iter().join("\t"))?;
+        }
+        writeln!(writer)?;
+
+        Ok(Self {
+            writer,
+            samples: header.samples().len(),
+        })
+    }
+}
+
+fn join_or_missing(values: &[String], separator: &str) -> String {
+    if values.is_empty() {
+        ".".to_string()
+    } else {
+        values.join(separator)
+    }
+}
+
+impl<W: Write> WriteRecord for Writer<W> {
+    type Record = Record;
+
+    fn write_record(&mut self, record: &Self::Record) -> Result<()> {
+        ensure!(
+            record.samples().len() == self.samples,
+            "VCF record has {} sample columns, but the header declares {}",
+            record.samples().len(),
+            self.samples
+        );
+        ensure!(
+            record.format().is_empty() == (self.samples == 0),
+            "VCF record must have the FORMAT field if and only if the header declares samples, \
+             got {} FORMAT keys and {} samples",
+            record.format().len(),
+            self.samples
+        );
+
+        let quality = match record.quality() {
+            Some(quality) => quality.to_string(),
+            None => ".".to_string(),
+        };
+        let info = if record.info().is_empty() {
+            ".".to_string()
+        } else {
+            record
+                .info()
+                .iter()
+                .map(|(k, v)| match v {
+                    Some(v) => format!("{k}={v}"),
+                    None => k.clone(),
+                })
+                .join(";")
+        };
+
+        write!(
+            self.writer,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            record.seqid(),
+            record.position() + 1,
+            join_or_missing(record.ids(), ";"),
+            record.reference(),
+            join_or_missing(record.alternative(), ","),
+            quality,
+            join_or_missing(record.filters(), ";"),
+            info,
+        )?;
+
+        if !record.format().is_empty() {
+            write!(self.writer, "\t{}", record.format().join(":"))?;
+            for sample in record.samples() {
+                write!(self.writer, "\t{}", join_or_missing(sample, ":"))?;
+            }
+        }
+        writeln!(self.writer)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ReadRecord;
+    use crate::vcf::Reader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_vcf_writer_preserves_content() -> Result<()> {
+        let expected = "\
+##fileformat=VCFv4.3
+##contig=<ID=chr1,length=100>
+#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tS1\tS2
+chr1\t10\trs1\tA\tG,T\t.\tPASS\tDP=10;SB\tAD:DP\t5,3,2:10\t.
+chr1\t20\t.\tC\t.\t13.5\t.\t.\tAD\t1\t2
+";
+        let mut reader = Reader::new(Cursor::new(expected))?;
+        let mut records = Vec::new();
+        reader.read_to_end(&mut records)?;
+
+        let mut produced = Vec::new();
+        let mut writer = Writer::new(Cursor::new(&mut produced), reader.header())?;
+        writer.write_records(&records)?;
+        assert!(writer.write_record(&Record::default()).is_err());
+
+        // Sample columns without the FORMAT field
+        let mut invalid = records[1].clone();
+        unsafe {
+            invalid.fields().8.clear();
+        }
+        assert!(writer.write_record(&invalid).is_err());
+        writer.flush()?;
+
+        assert_eq!(String::from_utf8(produced)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcf_writer_rejects_format_without_samples() -> Result<()> {
+        let mut produced = Vec::new();
+        let mut writer = Writer::new(Cursor::new(&mut produced), &Header::new(Vec::new())?)?;
+        writer.write_record(&Record::default())?;
+
+        let mut record = Record::default();
+        unsafe {
+            record.fields().8.push("DP".to_string());
+        }
+        assert!(writer.write_record(&record).is_err());
+        Ok(())
+    }
+}
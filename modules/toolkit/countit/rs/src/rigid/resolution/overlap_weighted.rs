@@ -9,7 +9,7 @@ use itertools::{Itertools, izip};
 
 #[derive(Clone, Debug, Default)]
 pub struct OverlapWeighted<Idx: PrimInt> {
-    steps: Option<interval_tree::BatchHitSegments<'static, Idx, usize>>,
+    steps: Option<interval_tree::BatchWeightedSegments<'static, Idx, usize>>,
 }
 
 impl<Idx: PrimInt> OverlapWeighted<Idx> {
@@ -29,7 +29,7 @@ impl<Idx: PrimInt, Cnts: Float, Elt> Resolution<Idx, Cnts, Elt> for OverlapWeigh
         let mut steps = self.steps.take().unwrap_or_default().recycle();
         steps.build(alignment.intervals.iter(), bhits)?;
 
-        for (query, n, (segments, data)) in izip!(
+        for (query, n, view) in izip!(
             alignment.intervals.iter(),
             alignment.total_hits.iter(),
             steps.iter(),
@@ -40,29 +40,29 @@ impl<Idx: PrimInt, Cnts: Float, Elt> Resolution<Idx, Cnts, Elt> for OverlapWeigh
                 .fold(Idx::zero(), |sum, x| sum + x);
             let weight = Cnts::one() / (Cnts::from(length).unwrap() * Cnts::from(*n).unwrap());
 
-            for (segment, hits) in izip!(segments, data) {
+            for (segment, _, members) in view.iter() {
                 let segweight = Cnts::from(segment.len()).unwrap() * weight;
-                if hits.is_empty() {
+                if members.is_empty() {
                     outcome.discarded = outcome.discarded + segweight;
                 } else {
                     outcome.resolved = outcome.resolved + segweight;
-                    let segweight = segweight / Cnts::from(hits.len()).unwrap();
-                    for x in hits {
-                        counts[**x] = counts[**x] + segweight;
+                    let segweight = segweight / Cnts::from(members.len()).unwrap();
+                    for m in members {
+                        let x = *view.elements()[*m as usize];
+                        counts[x] = counts[x] + segweight;
                     }
                 }
             }
 
             debug_assert!(
-                segments
+                view.segments()
                     .iter()
                     .map(|x| x.len())
                     .fold(Idx::zero(), |sum, x| sum + x)
                     == length,
-                "Query: {:?}\n{:?}\n{:?}",
+                "Query: {:?}\n{:?}",
                 query.iter().collect_vec(),
-                segments,
-                data
+                view
             );
         }
 
@@ -5,6 +5,7 @@ use biobit_core_rs::source::{DynSource, Source};
 use biobit_io_py::bam::IntoPyReader;
 use biobit_io_py::fasta::PyIndexedSources;
 use biobit_io_rs::bam::{strdeductor, transform};
+use biobit_io_rs::fasta::Normalization;
 use biobit_reat_rs::Reat;
 use biobit_reat_rs::worker::{SourceArgs, SourceItem};
 use eyre::{Result, eyre};
@@ -52,12 +53,17 @@ impl PyReat {
 #[pymethods]
 impl PyReat {
     #[new]
-    #[pyo3(signature = (reference, selector = None, min_phred = 20, threads = -1))]
+    #[pyo3(signature = (
+        reference, selector = None, min_phred = 20, threads = -1, mask_repeats = false,
+        exclude_repeats = false
+    ))]
     pub fn new(
         reference: Py<PyIndexedSources>,
         selector: Option<IntoPySelector>,
         min_phred: u8,
         threads: isize,
+        mask_repeats: bool,
+        exclude_repeats: bool,
         py: Python,
     ) -> Result<Self> {
         let pool = ThreadPoolBuilder::new()
@@ -70,7 +76,9 @@ impl PyReat {
 
         Ok(Self {
             samples: Vec::new(),
-            reat: Reat::new(pool, reference, min_phred, selector),
+            reat: Reat::new(pool, reference, min_phred, selector)
+                .with_normalization(Normalization::new().with_masked_repeats(mask_repeats))
+                .with_excluded_repeats(exclude_repeats),
         })
     }
 
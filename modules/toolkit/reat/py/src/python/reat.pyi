@@ -30,11 +30,15 @@ class Reat[T]:
         selector: Selector | None = None,
         min_phred: int = 20,
         threads: int = -1,
+        mask_repeats: bool = False,
+        exclude_repeats: bool = False,
     ) -> None:
         """
         Create a runner over indexed FASTA reference sources.
 
-        `min_phred` filters low-quality read bases before counting.
+        `min_phred` filters low-quality read bases before counting. `mask_repeats` reports
+        soft-masked (lowercase) reference bases as N, while `exclude_repeats` drops all sites within
+        soft-masked repeats from the results.
         """
         ...
 
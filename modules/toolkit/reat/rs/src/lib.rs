@@ -6,6 +6,7 @@ pub mod pileup;
 pub mod result;
 pub mod selection;
 pub mod task;
+pub mod vcf;
 pub mod worker;
 
 mod reat;
@@ -7,7 +7,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use biobit_core_rs::loc::{IntervalOp, Orientation, PerOrientation};
 use biobit_core_rs::num::PrimUInt;
 use biobit_core_rs::source::Source;
-use biobit_io_rs::fasta::IndexedSources;
+use biobit_io_rs::fasta::{IndexedSources, Normalization};
 use eyre::{Result, eyre};
 use rayon::ThreadPool;
 use thread_local::ThreadLocal;
@@ -157,6 +157,8 @@ where
 {
     pool: ThreadPool,
     reference: IndexedSources,
+    normalization: Normalization,
+    exclude_repeats: bool,
     selector: Arc<dyn Selector<SeqId, Idx, Cnts> + Send + Sync>,
     min_phred: u8,
     samples: BTreeMap<SmplTag, Vec<Src>>,
@@ -179,12 +181,27 @@ where
         Self {
             pool,
             reference,
+            normalization: Normalization::new(),
+            exclude_repeats: false,
             selector,
             min_phred,
             samples: BTreeMap::new(),
         }
     }
 
+    /// Normalization applied to the fetched reference sequence, e.g., to report soft-masked
+    /// repeats as N.
+    pub fn with_normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Don't report sites within soft-masked (lowercase) repeats of the reference.
+    pub fn with_excluded_repeats(mut self, exclude_repeats: bool) -> Self {
+        self.exclude_repeats = exclude_repeats;
+        self
+    }
+
     pub fn register<Sources>(&mut self, tag: SmplTag, sources: Sources) -> &mut Self
     where
         Sources: IntoIterator<Item = Src>,
@@ -232,6 +249,8 @@ where
                             let mut worker = cache.worker(|| {
                                 Ok(Worker::new(
                                     self.reference.open()?,
+                                    self.normalization,
+                                    self.exclude_repeats,
                                     Arc::clone(&self.selector),
                                     self.min_phred,
                                     size_hint,
@@ -0,0 +1,152 @@
+#[cfg(feature = "bitcode")]
+use bitcode::{Decode, Encode};
+use std::collections::BTreeMap;
+
+use biobit_collections_rs::interval_tree::Bits;
+use biobit_core_rs::loc::{Interval, IntervalOp, Orientation};
+use biobit_core_rs::num::PrimUInt;
+use eyre::{Result, bail};
+
+use crate::dna::Reference;
+use crate::pileup::DensePileup;
+use crate::selection::{Selection, Selector};
+
+/// Wraps another selector and removes all sites overlapping the excluded intervals from its
+/// selection. Exclusions are strand-agnostic, e.g. known SNPs loaded with `vcf::known_sites`.
+#[cfg_attr(feature = "bitcode", derive(Encode, Decode))]
+#[derive(Clone, PartialEq, Debug)]
+pub struct ExcludedSites<S, SeqId: Ord = String, Idx: PrimUInt = u64> {
+    inner: S,
+    index: BTreeMap<SeqId, Bits<Idx, ()>>,
+}
+
+impl<S, SeqId, Idx> ExcludedSites<S, SeqId, Idx>
+where
+    SeqId: Ord,
+    Idx: PrimUInt,
+{
+    pub fn new<Excluded, Intervals>(inner: S, excluded: Excluded) -> Self
+    where
+        Excluded: IntoIterator<Item = (SeqId, Intervals)>,
+        Intervals: IntoIterator<Item = Interval<Idx>>,
+    {
+        let mut grouped = BTreeMap::<SeqId, Vec<Interval<Idx>>>::new();
+        for (seqid, intervals) in excluded {
+            grouped.entry(seqid).or_default().extend(intervals);
+        }
+
+        let index = grouped
+            .into_iter()
+            .filter_map(|(key, mut intervals)| {
+                if intervals.is_empty() {
+                    return None;
+                }
+                let intervals = Interval::merge(&mut intervals);
+                let records = intervals.into_iter().map(|interval| (interval, ()));
+                Some((key, Bits::new(records)))
+            })
+            .collect();
+
+        Self { inner, index }
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn index(&self) -> &BTreeMap<SeqId, Bits<Idx, ()>> {
+        &self.index
+    }
+}
+
+impl<S, SeqId, Idx, Cnts> Selector<SeqId, Idx, Cnts> for ExcludedSites<S, SeqId, Idx>
+where
+    S: Selector<SeqId, Idx, Cnts>,
+    SeqId: Ord,
+    Idx: PrimUInt,
+    Cnts: PrimUInt,
+{
+    fn select(
+        &self,
+        seqid: &SeqId,
+        orientation: Orientation,
+        pileup: &DensePileup<Idx, Cnts>,
+        reference: &[Reference],
+        selection: &mut Selection,
+    ) -> Result<()> {
+        self.inner
+            .select(seqid, orientation, pileup, reference, selection)?;
+
+        let Some(excluded) = self.index.get(seqid) else {
+            return Ok(());
+        };
+        for (overlap, _) in excluded.query(*pileup.interval()) {
+            let Some(intersection) = overlap.intersection(pileup.interval()) else {
+                bail!("Overlap calculated by Bits should intersect with pileup interval");
+            };
+            let start = (intersection.start() - pileup.interval().start())
+                .to_usize()
+                .expect("intersection start should be within pileup interval");
+            let end = (intersection.end() - pileup.interval().start())
+                .to_usize()
+                .expect("intersection end should be within pileup interval");
+            for offset in start..end {
+                selection.exclude(offset);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pileup::Pileup;
+    use crate::selection::Mismatches;
+
+    use super::*;
+
+    #[test]
+    fn excludes_sites_regardless_of_orientation() -> Result<()> {
+        let selector = ExcludedSites::new(
+            Mismatches::new(1_u32, 0.0)?,
+            [
+                ("chr1", vec![Interval::new(11_u64, 13)?]),
+                ("chr2", vec![Interval::new(10_u64, 15)?]),
+            ],
+        );
+        let dense = DensePileup::new(
+            Interval::new(10_u64, 15)?,
+            Pileup::<u32>::new(
+                vec![0, 0, 0, 0, 0],
+                vec![1, 1, 1, 1, 1],
+                vec![0, 0, 0, 0, 0],
+                vec![0, 0, 0, 0, 0],
+                vec![0, 0, 0, 0, 0],
+                vec![0, 0, 0, 0, 0],
+            )?,
+        )?;
+        let reference = vec![Reference::A; dense.len()];
+
+        for orientation in [Orientation::Forward, Orientation::Reverse] {
+            let mut selection = Selection::zeros(dense.len());
+            selector.select(&"chr1", orientation, &dense, &reference, &mut selection)?;
+            assert_eq!(
+                selection.selected_offsets().collect::<Vec<_>>(),
+                vec![0, 3, 4]
+            );
+        }
+
+        let mut selection = Selection::zeros(dense.len());
+        selector.select(
+            &"chr3",
+            Orientation::Forward,
+            &dense,
+            &reference,
+            &mut selection,
+        )?;
+        assert_eq!(selection.selected_offsets().count(), 5);
+        Ok(())
+    }
+}
@@ -1,11 +1,13 @@
 #![allow(clippy::module_inception)]
 
+pub use excluded_sites::ExcludedSites;
 pub use mismatches::Mismatches;
 pub use required_or_mismatches::RequiredOrMismatches;
 pub use required_sites::RequiredSites;
 pub use selection::Selection;
 pub use selector::Selector;
 
+mod excluded_sites;
 mod mismatches;
 mod required_or_mismatches;
 mod required_sites;
@@ -0,0 +1,341 @@
+use std::collections::BTreeMap;
+
+use biobit_core_rs::loc::{Interval, Orientation};
+use biobit_core_rs::num::PrimUInt;
+use biobit_io_rs::ReadRecord;
+use biobit_io_rs::vcf::{Header, Number, Record, ValueType};
+use eyre::{OptionExt, Result, ensure};
+use itertools::Itertools;
+
+use crate::dna::Reference;
+use crate::pileup::Site;
+use crate::result::SamplePileup;
+use crate::task::TaskPileup;
+
+// Editing sites are exported as VCF records with the following layout:
+// - REF: reference nucleotide (N for ambiguous positions)
+// - ALT: all observed nucleotides (A, C, G, T) that differ from REF in at least one sample
+// - INFO/DP: total coverage across all samples
+// - INFO/STRAND: strand of the pileup (omitted for unstranded pileups)
+// - FORMAT/AD: per-sample counts of the REF and ALT alleles
+// - FORMAT/DP: per-sample coverage (including N and deletions)
+// - FORMAT/DEL: per-sample number of deletions
+// Samples without a pileup at the given site have all FORMAT values missing ('.').
+
+const ALLELES: [Reference; 4] = [Reference::A, Reference::C, Reference::G, Reference::T];
+
+type Counts = [u64; 6];
+
+/// Create a VCF header declaring all INFO and FORMAT fields used by the exported records.
+pub fn header(
+    samples: Vec<String>,
+    contigs: impl IntoIterator<Item = (String, u64)>,
+) -> Result<Header> {
+    let header = contigs
+        .into_iter()
+        .try_fold(Header::new(samples)?, |header, (contig, length)| {
+            header.with_contig(&contig, length)
+        })?
+        .with_meta("source=biobit-reat")?
+        .with_info(
+            "DP",
+            Number::Count(1),
+            ValueType::Integer,
+            "Total coverage across all samples",
+        )?
+        .with_info(
+            "STRAND",
+            Number::Count(1),
+            ValueType::Character,
+            "Strand of the pileup: + or -",
+        )?
+        .with_format(
+            "AD",
+            Number::R,
+            ValueType::Integer,
+            "Number of observed REF and ALT nucleotides",
+        )?
+        .with_format(
+            "DP",
+            Number::Count(1),
+            ValueType::Integer,
+            "Coverage, including N nucleotides and deletions",
+        )?
+        .with_format(
+            "DEL",
+            Number::Count(1),
+            ValueType::Integer,
+            "Number of deletions",
+        )?;
+    Ok(header)
+}
+
+fn counts<Cnts: PrimUInt>(site: &Site<'_, Cnts>) -> Result<Counts> {
+    let mut result = [0; 6];
+    for (value, count) in result.iter_mut().zip([
+        site.a(),
+        site.c(),
+        site.g(),
+        site.t(),
+        site.n(),
+        site.deletion(),
+    ]) {
+        *value = count
+            .to_u64()
+            .ok_or_eyre("Pileup counts can't be represented as u64")?;
+    }
+    Ok(result)
+}
+
+fn allele_count(counts: &Counts, allele: Reference) -> u64 {
+    match allele {
+        Reference::A => counts[0],
+        Reference::C => counts[1],
+        Reference::G => counts[2],
+        Reference::T => counts[3],
+        Reference::N => counts[4],
+    }
+}
+
+fn record(
+    seqid: &str,
+    position: u64,
+    orientation: Orientation,
+    reference: Reference,
+    samples: &[Option<Counts>],
+) -> Result<Record> {
+    let alternative = ALLELES
+        .into_iter()
+        .filter(|allele| {
+            *allele != reference
+                && samples
+                    .iter()
+                    .flatten()
+                    .any(|counts| allele_count(counts, *allele) > 0)
+        })
+        .collect_vec();
+
+    let total: u64 = samples.iter().flatten().flatten().sum();
+    let mut info = vec![("DP".to_string(), Some(total.to_string()))];
+    if orientation != Orientation::Dual {
+        info.push(("STRAND".to_string(), Some(orientation.symbol().to_string())));
+    }
+
+    let format = vec!["AD".to_string(), "DP".to_string(), "DEL".to_string()];
+    let samples = samples
+        .iter()
+        .map(|counts| match counts {
+            None => Vec::new(),
+            Some(counts) => vec![
+                std::iter::once(reference)
+                    .chain(alternative.iter().copied())
+                    .map(|allele| allele_count(counts, allele))
+                    .join(","),
+                counts.iter().sum::<u64>().to_string(),
+                counts[5].to_string(),
+            ],
+        })
+        .collect();
+
+    Record::new(
+        seqid.to_string(),
+        position,
+        Vec::new(),
+        reference.symbol().to_string(),
+        alternative.iter().map(|x| x.symbol().to_string()).collect(),
+        None,
+        Vec::new(),
+        info,
+        format,
+        samples,
+    )
+}
+
+/// Convert a single-sample pileup into VCF records, one per covered site.
+pub fn task_records<Idx, Cnts>(
+    seqid: &str,
+    orientation: Orientation,
+    pileup: &TaskPileup<Idx, Cnts>,
+) -> Result<Vec<Record>>
+where
+    Idx: PrimUInt,
+    Cnts: PrimUInt,
+{
+    pileup
+        .pileup()
+        .iter()
+        .zip(pileup.reference())
+        .map(|((position, site), reference)| {
+            let position = position
+                .to_u64()
+                .ok_or_eyre("Pileup position can't be represented as u64")?;
+            record(
+                seqid,
+                position,
+                orientation,
+                *reference,
+                &[Some(counts(&site)?)],
+            )
+        })
+        .collect()
+}
+
+/// Convert pileups for multiple samples into multi-sample VCF records. Records are sorted by
+/// sequence ID, position, and orientation. The order of sample columns matches the input order.
+pub fn sample_records<SeqId, Idx, Cnts, Tag>(
+    samples: &[SamplePileup<SeqId, Idx, Cnts, Tag>],
+) -> Result<Vec<Record>>
+where
+    SeqId: Ord + AsRef<str>,
+    Idx: PrimUInt,
+    Cnts: PrimUInt,
+{
+    let mut sites = BTreeMap::<(&str, Idx, Orientation), (Reference, Vec<Option<Counts>>)>::new();
+    for (ind, sample) in samples.iter().enumerate() {
+        for ((seqid, orientation), pileup) in &sample.pileups {
+            for ((position, site), reference) in pileup.pileup().iter().zip(pileup.reference()) {
+                let (expected, observed) = sites
+                    .entry((seqid.as_ref(), position, *orientation))
+                    .or_insert_with(|| (*reference, vec![None; samples.len()]));
+                ensure!(
+                    *expected == *reference,
+                    "Samples disagree on the reference nucleotide at {}:{:?} ({} vs {})",
+                    seqid.as_ref(),
+                    position,
+                    expected,
+                    reference
+                );
+                observed[ind] = Some(counts(&site)?);
+            }
+        }
+    }
+
+    sites
+        .into_iter()
+        .map(|((seqid, position, orientation), (reference, counts))| {
+            let position = position
+                .to_u64()
+                .ok_or_eyre("Pileup position can't be represented as u64")?;
+            record(seqid, position, orientation, reference, &counts)
+        })
+        .collect()
+}
+
+/// Load known sites (e.g., SNPs) from a VCF file to be used as an exclusion mask. Each record is
+/// converted into the interval covered by its REF allele. Intervals are grouped by sequence ID.
+pub fn known_sites(
+    reader: &mut (impl ReadRecord<Record = Record> + ?Sized),
+) -> Result<BTreeMap<String, Vec<Interval<u64>>>> {
+    let mut result = BTreeMap::<String, Vec<Interval<u64>>>::new();
+    let mut record = Record::default();
+    while reader.read_record(&mut record)? {
+        let interval = Interval::new(*record.position(), record.end())?;
+        match result.get_mut(record.seqid().as_str()) {
+            Some(intervals) => intervals.push(interval),
+            None => {
+                result.insert(record.seqid().clone(), vec![interval]);
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use biobit_io_rs::WriteRecord;
+    use biobit_io_rs::vcf::{Reader, Writer};
+
+    use super::*;
+    use crate::pileup::{Pileup, SparsePileup};
+
+    fn task_pileup(positions: Vec<u64>, a: Vec<u32>, g: Vec<u32>) -> Result<TaskPileup> {
+        let len = positions.len();
+        TaskPileup::new(
+            SparsePileup::new(
+                positions,
+                Pileup::new(a, vec![0; len], g, vec![0; len], vec![0; len], vec![1; len])?,
+            )?,
+            vec![Reference::A; len],
+        )
+    }
+
+    #[test]
+    fn converts_task_pileup_into_records() -> Result<()> {
+        let pileup = task_pileup(vec![10, 20], vec![5, 7], vec![3, 0])?;
+        let records = task_records("chr1", Orientation::Reverse, &pileup)?;
+        assert_eq!(records.len(), 2);
+
+        let first = &records[0];
+        assert_eq!(*first.position(), 10);
+        assert_eq!(first.reference(), "A");
+        assert_eq!(first.alternative(), &["G"]);
+        assert_eq!(first.info_value("DP"), Some(Some("9")));
+        assert_eq!(first.info_value("STRAND"), Some(Some("-")));
+        assert_eq!(first.sample_value(0, "AD"), Some("5,3"));
+        assert_eq!(first.sample_value(0, "DP"), Some("9"));
+        assert_eq!(first.sample_value(0, "DEL"), Some("1"));
+
+        let second = &records[1];
+        assert!(second.alternative().is_empty());
+        assert_eq!(second.sample_value(0, "AD"), Some("7"));
+        Ok(())
+    }
+
+    #[test]
+    fn merges_samples_into_multi_sample_records() -> Result<()> {
+        let first = SamplePileup {
+            tag: (),
+            pileups: BTreeMap::from([(
+                ("chr1".to_string(), Orientation::Forward),
+                task_pileup(vec![10], vec![5], vec![0])?,
+            )]),
+        };
+        let second = SamplePileup {
+            tag: (),
+            pileups: BTreeMap::from([(
+                ("chr1".to_string(), Orientation::Forward),
+                task_pileup(vec![10, 12], vec![1, 2], vec![4, 3])?,
+            )]),
+        };
+
+        let records = sample_records(&[first, second])?;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].alternative(), &["G"]);
+        assert_eq!(records[0].sample_value(0, "AD"), Some("5,0"));
+        assert_eq!(records[0].sample_value(1, "AD"), Some("1,4"));
+        assert_eq!(records[0].info_value("DP"), Some(Some("12")));
+        assert_eq!(records[1].sample_value(0, "AD"), None);
+        assert_eq!(records[1].sample_value(1, "AD"), Some("2,3"));
+
+        // Round-trip through the VCF writer and reader
+        let header = header(
+            vec!["S1".to_string(), "S2".to_string()],
+            [("chr1".to_string(), 100)],
+        )?;
+        let mut produced = Vec::new();
+        let mut writer = Writer::new(Cursor::new(&mut produced), &header)?;
+        writer.write_records(&records)?;
+        writer.flush()?;
+
+        let mut reader = Reader::new(Cursor::new(produced))?;
+        let mut parsed = Vec::new();
+        reader.read_to_end(&mut parsed)?;
+        assert_eq!(parsed, records);
+
+        let mut reader = Reader::new(Cursor::new(
+            "##fileformat=VCFv4.3\n#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+             chr1\t11\trs1\tA\tG\t.\t.\t.\nchr2\t5\t.\tAC\tA\t.\t.\t.\n",
+        ))?;
+        let known = known_sites(&mut reader)?;
+        assert_eq!(
+            known,
+            BTreeMap::from([
+                ("chr1".to_string(), vec![Interval::new(10, 11)?]),
+                ("chr2".to_string(), vec![Interval::new(4, 6)?]),
+            ])
+        );
+        Ok(())
+    }
+}
@@ -1,25 +1,33 @@
-use biobit_core_rs::loc::IntervalOp;
+use biobit_core_rs::loc::{Interval, IntervalOp};
 use biobit_core_rs::num::PrimUInt;
-use biobit_io_rs::fasta::IndexedReaderMutOp;
+use biobit_io_rs::fasta::{IndexedReaderMutOp, Normalization, soft_masked};
 use eyre::{Result, ensure};
 
 use crate::dna;
 
 pub struct RefReader {
     reader: Box<dyn IndexedReaderMutOp + Send + Sync>,
+    normalization: Normalization,
+    track_repeats: bool,
     raw: Vec<u8>,
     reference: Vec<dna::Reference>,
+    repeats: Vec<Interval<u64>>,
 }
 
 impl RefReader {
     pub fn with_capacity(
         reader: Box<dyn IndexedReaderMutOp + Send + Sync>,
+        normalization: Normalization,
+        track_repeats: bool,
         capacity: usize,
     ) -> Self {
         Self {
             reader,
+            normalization,
+            track_repeats,
             raw: Vec::with_capacity(capacity),
             reference: Vec::with_capacity(capacity),
+            repeats: Vec::new(),
         }
     }
 
@@ -43,6 +51,13 @@ impl RefReader {
             "fetched reference length does not match task envelope length"
         );
 
+        // Soft-masking must be collected before the normalization
+        self.repeats.clear();
+        if self.track_repeats {
+            soft_masked(&self.raw, 0, &mut self.repeats);
+        }
+        self.normalization.apply(&mut self.raw);
+
         self.reference.clear();
         for byte in &self.raw {
             let base = match dna::Reference::try_from(*byte) {
@@ -64,4 +79,71 @@ impl RefReader {
     pub fn reference(&self) -> &[dna::Reference] {
         &self.reference
     }
+
+    /// Soft-masked repeats in the last fetched interval, relative to its start. Empty unless
+    /// repeats are tracked.
+    pub fn repeats(&self) -> &[Interval<u64>] {
+        &self.repeats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct InMemory(Vec<u8>);
+
+    impl IndexedReaderMutOp for InMemory {
+        fn lengths(&self) -> HashMap<String, u64> {
+            HashMap::from([("chr1".to_string(), self.0.len() as u64)])
+        }
+
+        fn fetch(
+            &mut self,
+            _seqid: &str,
+            interval: Interval<u64>,
+            buffer: &mut Vec<u8>,
+        ) -> Result<()> {
+            buffer.clear();
+            buffer.extend_from_slice(&self.0[interval.start() as usize..interval.end() as usize]);
+            Ok(())
+        }
+
+        fn fetch_full_seq(&mut self, seqid: &str, buffer: &mut Vec<u8>) -> Result<()> {
+            self.fetch(seqid, Interval::new(0, self.0.len() as u64)?, buffer)
+        }
+    }
+
+    #[test]
+    fn normalizes_and_tracks_repeats() -> Result<()> {
+        use dna::Reference::{A, C, G, N, T};
+
+        let seq = b"ACgtRyNA".to_vec();
+        for (normalization, track, expected, repeats) in [
+            (
+                Normalization::new(),
+                false,
+                vec![A, C, G, T, N, N, N, A],
+                vec![],
+            ),
+            (
+                Normalization::new().with_masked_repeats(true),
+                true,
+                vec![A, C, N, N, N, N, N, A],
+                vec![Interval::new(2, 4)?, Interval::new(5, 6)?],
+            ),
+        ] {
+            let mut reader = RefReader::with_capacity(
+                Box::new(InMemory(seq.clone())),
+                normalization,
+                track,
+                seq.len(),
+            );
+            reader.fetch("chr1", Interval::new(0_u64, 8)?)?;
+            assert_eq!(reader.reference(), expected);
+            assert_eq!(reader.repeats(), repeats);
+        }
+        Ok(())
+    }
 }
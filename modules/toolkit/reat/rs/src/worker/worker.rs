@@ -3,7 +3,7 @@ use biobit_core_rs::LendingIterator;
 use biobit_core_rs::loc::{IntervalOp, Orientation, PerOrientation};
 use biobit_core_rs::num::PrimUInt;
 use biobit_core_rs::source::DynSource;
-use biobit_io_rs::fasta::IndexedReaderMutOp;
+use biobit_io_rs::fasta::{IndexedReaderMutOp, Normalization};
 use eyre::Result;
 use higher_kinded_types::prelude::*;
 use noodles::bam::Record;
@@ -34,13 +34,20 @@ where
 {
     pub fn new(
         reference: Box<dyn IndexedReaderMutOp + Send + Sync>,
+        normalization: Normalization,
+        exclude_repeats: bool,
         selector: Arc<dyn Selector<SeqId, Idx, Cnts> + Send + Sync>,
         min_phred: u8,
         size_hint: usize,
     ) -> Self {
         Self {
             selector,
-            reference: RefReader::with_capacity(reference, size_hint),
+            reference: RefReader::with_capacity(
+                reference,
+                normalization,
+                exclude_repeats,
+                size_hint,
+            ),
             pileups: PileupCache::with_capacity(size_hint),
             selection: Selection::zeros(size_hint),
             min_phred,
@@ -122,6 +129,11 @@ where
                 &mut self.selection,
             )?;
             task.exclude_outside_intervals(&mut self.selection)?;
+            for repeat in self.reference.repeats() {
+                for offset in repeat.start()..repeat.end() {
+                    self.selection.exclude(offset as usize);
+                }
+            }
 
             let offsets = self.selection.selected_offsets().collect::<Vec<_>>();
             if offsets.is_empty() {
@@ -82,9 +82,7 @@ pub fn run<S: scoring::Score>(
     // assumed that the overlap calculated over matches only. Therefore, we need to recalculate
     // the overlap and filter out the alignments that do not meet the criteria.
     let (rois, filter) = {
-        let (_, _, tracer) = aligner.dissolve();
-        let (allopt, _, _) = tracer.dissolve();
-        let filter = allopt.dissolve().0;
+        let filter = aligner.into_storage().dissolve().0;
 
         let mut index = BitsBuilder::default();
         for roi in filter.rois() {